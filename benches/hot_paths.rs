@@ -0,0 +1,97 @@
+//! Times the per-frame mesh rebuild and the collision checks, none of which need a gpu. Building a
+//! mesh into gpu buffers isn't covered, since that needs a device.
+
+use cgmath::{vec2, Vector2};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use wgpu_fun::collison;
+use wgpu_fun::config::Config;
+use wgpu_fun::game::Game;
+use wgpu_fun::highscore::HighScoreTables;
+use wgpu_fun::renderer::buffer::{MeshBuilder, Vertex};
+use wgpu_fun::renderer::shapes::SegmentCount;
+use wgpu_fun::Action;
+
+fn quads(count: usize) -> MeshBuilder {
+    let mut mesh = MeshBuilder::default();
+    for i in 0..count {
+        let x = i as f32 * 0.001;
+        let corners = [[x, 0.], [x + 0.001, 0.], [x + 0.001, 0.001], [x, 0.001]];
+        mesh.push(
+            corners.map(|position| Vertex {
+                position,
+                color: [1.; 4],
+            }),
+            [0, 1, 2, 0, 2, 3],
+        );
+    }
+    mesh
+}
+
+/// Two triangles for each brick of a 10 by 8 grid
+fn brick_triangles() -> Vec<[Vector2<f32>; 3]> {
+    (0..10)
+        .flat_map(|column| (0..8).map(move |row| (column, row)))
+        .flat_map(|(column, row)| {
+            let min = vec2(column as f32 * 0.2 - 1., row as f32 * 0.1);
+            let max = min + vec2(0.18, 0.08);
+            let (a, b, c, d) = (min, vec2(max.x, min.y), max, vec2(min.x, max.y));
+            [[a, b, c], [a, c, d]]
+        })
+        .collect()
+}
+
+fn playing_game() -> Game {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    for _ in 0..3 {
+        game.handle(Action::Serve);
+    }
+    game
+}
+
+fn hot_paths(c: &mut Criterion) {
+    c.bench_function("MeshBuilder::push 10k quads", |b| {
+        b.iter(|| quads(10_000));
+    });
+
+    c.bench_function("MeshBuilder::push_circle", |b| {
+        b.iter(|| {
+            let mut mesh = MeshBuilder::default();
+            mesh.push_circle(vec2(0., 0.), 0.05, SegmentCount::Fixed(32), [1.; 4]);
+            mesh
+        });
+    });
+
+    let triangles = brick_triangles();
+    c.bench_function("circle_intersects_triangle 10x8 bricks", |b| {
+        b.iter(|| {
+            let ball = black_box(vec2(0.35, 0.42));
+            triangles
+                .iter()
+                .filter(|[a, b, c]| collison::circle_intersects_triangle(ball, 0.05, *a, *b, *c))
+                .count()
+        });
+    });
+
+    // a fresh game each time, since one left running ends and then has little to do, made
+    // outside the timing so only the ticks count
+    c.bench_function("Game::tick x500 from the serve", |b| {
+        b.iter_batched(
+            playing_game,
+            |mut game| {
+                for _ in 0..500 {
+                    game.tick();
+                }
+                game
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    let game = playing_game();
+    c.bench_function("Game::mesh", |b| {
+        b.iter(|| game.mesh());
+    });
+}
+
+criterion_group!(benches, hot_paths);
+criterion_main!(benches);
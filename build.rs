@@ -0,0 +1,10 @@
+//! Runs the tests that need a gpu only when asked to, so a machine without one reports them as
+//! ignored rather than passing tests it never ran
+
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(gpu_tests)");
+    println!("cargo::rerun-if-env-changed=WGPU_FUN_GPU_TESTS");
+    if std::env::var_os("WGPU_FUN_GPU_TESTS").is_some() {
+        println!("cargo::rustc-cfg=gpu_tests");
+    }
+}
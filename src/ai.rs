@@ -0,0 +1,125 @@
+use crate::tunables::Tunables;
+use crate::{Ball, Paddle};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Difficulty {
+    /// Ticks between the ball moving and the ai noticing
+    pub reaction_delay: usize,
+    /// The fastest the ai may move its paddle, in -1..=1 like `Paddle::velocity`
+    pub max_speed: f32,
+    /// The largest error the ai makes guessing where the ball will land
+    pub prediction_noise: f32,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self {
+            reaction_delay: 15,
+            max_speed: 0.6,
+            prediction_noise: 0.15,
+        }
+    }
+}
+
+/// Steers a paddle towards where it predicts the ball will arrive
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Ai {
+    pub difficulty: Difficulty,
+    /// The ball as seen over the last `reaction_delay` ticks, oldest first
+    seen: VecDeque<Ball>,
+    /// The error in the current guess, redrawn whenever the ball changes vertical direction
+    noise: f32,
+    approaching: bool,
+}
+
+impl Ai {
+    /// How far ahead the ai looks for the ball reaching its paddle
+    const LOOKAHEAD: usize = 200;
+    const ACCELERATION: f32 = 0.05;
+
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self {
+            seen: VecDeque::with_capacity(difficulty.reaction_delay + 1),
+            difficulty,
+            noise: 0.,
+            approaching: false,
+        }
+    }
+
+    /// Forgets what has been seen, for when the ball is reset
+    pub fn clear(&mut self) {
+        self.seen.clear();
+    }
+
+    /// Where the ball will be when it reaches the paddle's face
+    fn arrival(paddle: &Paddle, ball: &Ball, tunables: &Tunables) -> Option<f32> {
+        ball.predict(Self::LOOKAHEAD, tunables)
+            .into_iter()
+            .find(|position| (position.y - paddle.y()) * paddle.facing <= paddle.height / 2.)
+            .map(|position| position.x)
+    }
+
+    pub fn update(
+        &mut self,
+        paddle: &mut Paddle,
+        ball: &Ball,
+        tunables: &Tunables,
+        rng: &mut impl Rng,
+    ) {
+        self.seen.push_back(ball.clone());
+        if self.seen.len() <= self.difficulty.reaction_delay {
+            return;
+        }
+        let ball = self.seen.pop_front().unwrap();
+
+        let approaching = ball.velocity.y * paddle.facing < 0.;
+        if approaching && !self.approaching {
+            let noise = self.difficulty.prediction_noise;
+            self.noise = if noise > 0. {
+                rng.gen_range(-noise..=noise)
+            } else {
+                0.
+            };
+        }
+        self.approaching = approaching;
+
+        // drift back to the middle while the ball is heading away
+        let target = approaching
+            .then(|| Self::arrival(paddle, &ball, tunables))
+            .flatten()
+            .map_or(0., |x| x + self.noise);
+
+        let max_speed = self.difficulty.max_speed;
+        let desired = ((target - paddle.x) * 4.).clamp(-max_speed, max_speed);
+        paddle.velocity +=
+            (desired - paddle.velocity).clamp(-Self::ACCELERATION, Self::ACCELERATION);
+    }
+}
+
+#[test]
+fn ai_moves_to_where_the_ball_arrives() {
+    let tunables = Tunables::default();
+    let mut rng = rand::thread_rng();
+    let mut paddle = Paddle::new(-1.);
+    let ball = Ball {
+        position: [1., 0.3].into(),
+        velocity: [0., 0.1].into(),
+        state: crate::BallState::Free,
+        ..Ball::default()
+    };
+
+    let mut ai = Ai::new(Difficulty {
+        reaction_delay: 5,
+        max_speed: 1.,
+        prediction_noise: 0.,
+    });
+    for _ in 0..200 {
+        ai.update(&mut paddle, &ball, &tunables, &mut rng);
+        paddle.advance(tunables.wall_x);
+    }
+
+    assert!((paddle.x - 1.).abs() < 0.05);
+}
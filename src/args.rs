@@ -0,0 +1,266 @@
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use wgpu_fun::config::Config;
+
+pub const USAGE: &str = "\
+usage: wgpu-fun [options]
+
+options:
+    --width <pixels>         initial window width
+    --height <pixels>        initial window height
+    --fullscreen             start fullscreen
+    --vsync <on|off>         wait for the display before presenting frames
+    --msaa <1|4>             samples per pixel for antialiasing
+    --seed <n>               seed the game's randomness
+    --level <path>           play a level file instead of the bundled levels
+    --record <path>          record the session's input to a file
+    --replay <path>          play back a recorded session
+    --load-bugreport <path>  replay a bug report from where it starts
+    --no-resume              start from the menu, leaving the saved game be
+    --require-hardware       refuse to fall back to software rendering
+    --mesh-particles         draw particles into the mesh rather than instanced, to compare
+    --transparent            show the desktop through the window where nothing's drawn
+    --print-diagnostics      print the adapter, window and settings that would be used, then exit
+    --bench-frames <n>       play and draw n frames offscreen, print timings, then exit
+    --budget-ms <ms>         with --bench-frames, fail if any frame takes longer than this
+    --help                   show this message";
+
+/// Command line options. Those also in the config file take priority over it.
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub help: bool,
+    /// Print the adapter and settings that would be used instead of starting
+    pub print_diagnostics: bool,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fullscreen: Option<bool>,
+    pub vsync: Option<bool>,
+    pub msaa: Option<u32>,
+    /// Where to record the session's events
+    pub record: Option<PathBuf>,
+    /// A recording to play back instead of reading the keyboard
+    pub replay: Option<PathBuf>,
+    /// A bug report to play back from the state it starts in
+    pub load_bugreport: Option<PathBuf>,
+    /// Seeds the game's randomness, which is otherwise seeded randomly
+    pub seed: Option<u64>,
+    /// A level file to play instead of the bundled levels
+    pub level: Option<PathBuf>,
+    /// Leave any saved game be rather than resuming it
+    pub no_resume: bool,
+    /// Fail rather than render in software, for benchmarking
+    pub require_hardware: bool,
+    /// Draw particles the old way, as triangles in the frame's mesh
+    pub mesh_particles: bool,
+    /// Float over the desktop, in a window with nothing behind the scene
+    pub transparent: bool,
+    /// Play and draw this many frames without a window, then print how long they took
+    pub bench_frames: Option<usize>,
+    /// The longest a benchmarked frame may take, in milliseconds
+    pub budget_ms: Option<f64>,
+}
+
+fn parse_value<T: FromStr>(arg: &str, value: &str) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|err| format!("invalid value {value} for {arg}: {err}"))
+}
+
+/// Overrides a config field with its command line value, if given
+fn set<T: PartialEq + Debug>(name: &str, field: &mut T, value: Option<T>) {
+    if let Some(value) = value {
+        if *field != value {
+            log::info!("--{name} overrides {field:?} from the config file with {value:?}");
+            *field = value;
+        }
+    }
+}
+
+impl Args {
+    /// Parses the arguments after the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+            match arg.as_str() {
+                "--help" | "-h" => parsed.help = true,
+                "--print-diagnostics" => parsed.print_diagnostics = true,
+                "--width" => parsed.width = Some(parse_value(&arg, &value()?)?),
+                "--height" => parsed.height = Some(parse_value(&arg, &value()?)?),
+                "--fullscreen" => parsed.fullscreen = Some(true),
+                "--vsync" => {
+                    parsed.vsync = Some(match value()?.as_str() {
+                        "on" => true,
+                        "off" => false,
+                        other => return Err(format!("--vsync takes on or off, not {other}")),
+                    })
+                }
+                "--msaa" => {
+                    parsed.msaa = Some(match parse_value(&arg, &value()?)? {
+                        count @ (1 | 4) => count,
+                        other => return Err(format!("--msaa takes 1 or 4, not {other}")),
+                    })
+                }
+                "--record" => parsed.record = Some(value()?.into()),
+                "--replay" => parsed.replay = Some(value()?.into()),
+                "--load-bugreport" => parsed.load_bugreport = Some(value()?.into()),
+                "--level" => parsed.level = Some(value()?.into()),
+                "--no-resume" => parsed.no_resume = true,
+                "--require-hardware" => parsed.require_hardware = true,
+                "--mesh-particles" => parsed.mesh_particles = true,
+                "--transparent" => parsed.transparent = true,
+                "--seed" => parsed.seed = Some(parse_value(&arg, &value()?)?),
+                "--bench-frames" => parsed.bench_frames = Some(parse_value(&arg, &value()?)?),
+                "--budget-ms" => {
+                    // as it's made a duration of, which can't be negative, nan or endless
+                    parsed.budget_ms = Some(match parse_value(&arg, &value()?)? {
+                        ms if ms > 0. && Duration::try_from_secs_f64(ms / 1000.).is_ok() => ms,
+                        other => {
+                            return Err(format!(
+                                "--budget-ms takes a positive number of milliseconds, not {other}"
+                            ))
+                        }
+                    })
+                }
+                _ => return Err(format!("unknown argument {arg}\n\n{USAGE}")),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// The config with any options given on the command line taking its place
+    pub fn apply(&self, mut config: Config) -> Config {
+        set("width", &mut config.width, self.width.map(Some));
+        set("height", &mut config.height, self.height.map(Some));
+        set("fullscreen", &mut config.fullscreen, self.fullscreen);
+        set("vsync", &mut config.vsync, self.vsync);
+        set("msaa", &mut config.msaa, self.msaa);
+        config
+    }
+
+    /// Whether the config field `name` was given on the command line
+    pub fn overrides(&self, name: &str) -> bool {
+        match name {
+            "width" => self.width.is_some(),
+            "height" => self.height.is_some(),
+            "fullscreen" => self.fullscreen.is_some(),
+            "vsync" => self.vsync.is_some(),
+            "msaa" => self.msaa.is_some(),
+            _ => false,
+        }
+    }
+}
+
+#[test]
+fn parses_arguments() {
+    let args = |args: &[&str]| Args::parse(args.iter().map(|arg| arg.to_string()));
+
+    assert_eq!(args(&[]), Ok(Args::default()));
+    assert_eq!(
+        args(&["--replay", "session.json"]),
+        Ok(Args {
+            replay: Some("session.json".into()),
+            ..Args::default()
+        })
+    );
+    assert_eq!(
+        args(&["--load-bugreport", "bugreport-1.ron"]),
+        Ok(Args {
+            load_bugreport: Some("bugreport-1.ron".into()),
+            ..Args::default()
+        })
+    );
+    assert_eq!(
+        args(&[
+            "--seed",
+            "42",
+            "--record",
+            "out.json",
+            "--level",
+            "mine.toml",
+            "--no-resume",
+            "--require-hardware",
+            "--mesh-particles",
+            "--transparent",
+            "--bench-frames",
+            "500",
+            "--budget-ms",
+            "16.7"
+        ]),
+        Ok(Args {
+            record: Some("out.json".into()),
+            seed: Some(42),
+            level: Some("mine.toml".into()),
+            no_resume: true,
+            require_hardware: true,
+            mesh_particles: true,
+            transparent: true,
+            bench_frames: Some(500),
+            budget_ms: Some(16.7),
+            ..Args::default()
+        })
+    );
+    assert!(args(&["--seed", "-1"]).is_err());
+    assert!(args(&["--record"]).is_err());
+    assert!(args(&["--fast"]).is_err());
+    assert!(args(&["--msaa", "2"]).is_err());
+    assert!(args(&["--vsync", "maybe"]).is_err());
+}
+
+#[test]
+fn frame_budgets_are_positive_times() {
+    let budget =
+        |ms: &str| Args::parse(["--budget-ms", ms].map(String::from)).map(|args| args.budget_ms);
+
+    assert_eq!(budget("0.5"), Ok(Some(0.5)));
+    for ms in ["0", "-16.7", "NaN", "inf", "1e300", "soon"] {
+        assert!(budget(ms).is_err(), "{ms}");
+    }
+}
+
+#[test]
+fn command_line_wins_over_the_config_file() {
+    let args = Args::parse(
+        [
+            "--width",
+            "1280",
+            "--height",
+            "720",
+            "--fullscreen",
+            "--vsync",
+            "off",
+            "--msaa",
+            "4",
+        ]
+        .map(String::from),
+    )
+    .unwrap();
+
+    let file = Config {
+        width: Some(640),
+        msaa: 1,
+        ..Config::default()
+    };
+    assert_eq!(
+        args.apply(file.clone()),
+        Config {
+            width: Some(1280),
+            height: Some(720),
+            fullscreen: true,
+            vsync: false,
+            msaa: 4,
+            ..file.clone()
+        }
+    );
+
+    // anything not given is left as the file has it
+    assert_eq!(Args::default().apply(file.clone()), file);
+}
@@ -0,0 +1,101 @@
+use crossbeam::channel::Sender;
+
+/// A sound effect the game can play
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sfx {
+    PaddleHit,
+    WallBounce,
+    BrickBreak,
+    Lose,
+}
+
+#[cfg(feature = "audio")]
+impl Sfx {
+    const ALL: [Self; 4] = [
+        Self::PaddleHit,
+        Self::WallBounce,
+        Self::BrickBreak,
+        Self::Lose,
+    ];
+
+    fn wav(self) -> &'static [u8] {
+        match self {
+            Self::PaddleHit => include_bytes!("../assets/sfx/paddle_hit.wav"),
+            Self::WallBounce => include_bytes!("../assets/sfx/wall_bounce.wav"),
+            Self::BrickBreak => include_bytes!("../assets/sfx/brick_break.wav"),
+            Self::Lose => include_bytes!("../assets/sfx/lose.wav"),
+        }
+    }
+}
+
+/// Sends sound effects to the audio thread, playing nothing when there is no audio
+#[derive(Debug, Clone, Default)]
+pub struct AudioHandle {
+    sender: Option<Sender<(Sfx, f32)>>,
+}
+
+impl AudioHandle {
+    /// Starts the audio thread, which stays silent if built without the `audio` feature
+    pub fn new(volume: f32) -> Self {
+        #[cfg(feature = "audio")]
+        {
+            let (sender, receiver) = crossbeam::channel::unbounded();
+            std::thread::spawn(move || run(receiver, volume));
+            Self {
+                sender: Some(sender),
+            }
+        }
+
+        #[cfg(not(feature = "audio"))]
+        {
+            let _ = volume;
+            Self::default()
+        }
+    }
+
+    /// Plays `sfx` with its pitch scaled by `pitch`
+    pub fn play(&self, sfx: Sfx, pitch: f32) {
+        if let Some(sender) = &self.sender {
+            // the audio thread is gone if there is no device, so this is silent
+            let _ = sender.send((sfx, pitch));
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+fn run(receiver: crossbeam::channel::Receiver<(Sfx, f32)>, volume: f32) {
+    use rodio::{Decoder, OutputStream, Source};
+    use std::io::Cursor;
+
+    let (_stream, handle) = match OutputStream::try_default() {
+        Ok(output) => output,
+        Err(err) => {
+            log::warn!("no audio device, so the game is silent: {err}");
+            return;
+        }
+    };
+
+    let sounds = match Sfx::ALL
+        .iter()
+        .map(|sfx| Decoder::new(Cursor::new(sfx.wav())).map(Source::buffered))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(sounds) => sounds,
+        Err(err) => {
+            log::warn!("couldn't decode sound effects, so the game is silent: {err}");
+            return;
+        }
+    };
+
+    for (sfx, pitch) in receiver {
+        let source = sounds[sfx as usize]
+            .clone()
+            .speed(pitch)
+            .amplify(volume)
+            .convert_samples::<f32>();
+        if let Err(err) = handle.play_raw(source) {
+            log::warn!("audio device failed, so the game is silent: {err}");
+            return;
+        }
+    }
+}
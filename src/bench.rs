@@ -0,0 +1,270 @@
+use cgmath::Vector2;
+use rand::{rngs::StdRng, SeedableRng};
+use std::time::{Duration, Instant};
+use wgpu_fun::config::Config;
+use wgpu_fun::game::Game;
+use wgpu_fun::highscore::HighScoreTables;
+use wgpu_fun::instrument::{self, Counts};
+use wgpu_fun::particles::ParticleSystem;
+use wgpu_fun::renderer::buffer::{Mesh, MeshBuilder, Vertex};
+use wgpu_fun::renderer::{describe_adapter, headless::Headless};
+use wgpu_fun::{Action, Player};
+
+/// How many draws the frame is cut into to compare issuing them one by one with all at once
+const DRAW_ITEMS: usize = 1000;
+/// How many particles are drawn to compare pushing them into the mesh with drawing instances
+const PARTICLES: usize = 10_000;
+
+/// What `--bench-frames` runs
+#[derive(Debug, Clone, Copy)]
+pub struct BenchOptions {
+    pub frames: usize,
+    /// A frame taking longer than this counts against the run
+    pub budget: Option<Duration>,
+    pub require_hardware: bool,
+}
+
+/// How long one stage of every frame took
+#[derive(Debug, Default)]
+struct Samples(Vec<Duration>);
+
+impl Samples {
+    fn mean(&self) -> Duration {
+        self.0.iter().sum::<Duration>() / self.0.len().max(1) as u32
+    }
+
+    /// The time `fraction` of the frames came in under
+    fn percentile(&self, fraction: f64) -> Duration {
+        let mut sorted = self.0.clone();
+        sorted.sort();
+        let index = ((sorted.len() as f64 * fraction).ceil() as usize).saturating_sub(1);
+        sorted.get(index).copied().unwrap_or_default()
+    }
+
+    fn line(&self, name: &str) -> String {
+        format!(
+            "{name:<12} mean {:>10.3?}  p95 {:>10.3?}",
+            self.mean(),
+            self.percentile(0.95)
+        )
+    }
+}
+
+/// Keeps player one's paddle under the ball with the stick, serving every so often so a game is
+/// always going
+fn bot(game: &mut Game, frame: usize) {
+    if frame.is_multiple_of(100) {
+        game.handle(Action::Serve);
+    }
+    let pose = game.pose();
+    let axis = ((pose.ball.x - pose.paddles[0]) * 4.).clamp(-1., 1.);
+    game.handle(Action::PaddleAxis(Player::One, axis));
+}
+
+/// Plays and draws `frames` frames offscreen, printing how long each stage took. Fails if there's
+/// nothing to draw with or any frame went over the budget.
+pub fn run(options: BenchOptions, seed: u64) -> Result<(), String> {
+    let mut headless = futures_lite::future::block_on(Headless::new(options.require_hardware))
+        .map_err(|err| err.to_string())?;
+    println!(
+        "{} frames on {}",
+        options.frames,
+        describe_adapter(&headless.adapter)
+    );
+
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), seed);
+    // allocated up front so recording them doesn't count against the frames
+    let samples = || Samples(Vec::with_capacity(options.frames));
+    let (mut ticks, mut builds, mut submits) = (samples(), samples(), samples());
+    let mut vertices = 0;
+    // summed over every frame, to be divided back down for the mean
+    let (mut draw_calls, mut indices, mut uploaded) = (0, 0, 0);
+    let mut over = 0;
+    // built over every frame, as the game does
+    let mut builder = MeshBuilder::default();
+    let mut particles = Vec::new();
+    let mut mesh = None;
+    let (mut tick_counts, mut frame_counts) = (Counts::default(), Counts::default());
+
+    for frame in 0..options.frames {
+        bot(&mut game, frame);
+
+        let before = instrument::counts();
+        let start = Instant::now();
+        game.tick();
+        let ticked = Instant::now();
+        let after_tick = instrument::counts();
+        let built_frame = game.frame_with(builder, particles);
+        headless.set_background(built_frame.background);
+        (builder, particles) = (built_frame.mesh, built_frame.particles);
+        vertices += builder.vertex_count();
+        let pose = game.pose();
+        let mesh = headless.write(&mut mesh, &builder);
+        let built = Instant::now();
+        let report = headless.draw(mesh, &particles, pose.camera_x, pose.zoom);
+        let submitted = Instant::now();
+        draw_calls += report.draw_calls as usize;
+        indices += report.indices as usize;
+        uploaded += report.bytes_uploaded as usize + builder.upload_size() as usize;
+        tick_counts += after_tick.since(&before);
+        frame_counts += instrument::counts().since(&after_tick);
+
+        ticks.0.push(ticked - start);
+        builds.0.push(built - ticked);
+        submits.0.push(submitted - built);
+        if options
+            .budget
+            .is_some_and(|budget| submitted - start > budget)
+        {
+            over += 1;
+        }
+    }
+
+    println!("{}", ticks.line("tick"));
+    println!("{}", builds.line("mesh build"));
+    println!("{}", submits.line("gpu submit"));
+    println!(
+        "{:<12} mean {:>10}",
+        "vertices",
+        vertices / options.frames.max(1)
+    );
+    let per_frame = |total: usize| total / options.frames.max(1);
+    println!(
+        "{:<12} mean {} calls, {} indices, {} bytes uploaded",
+        "draws",
+        per_frame(draw_calls),
+        per_frame(indices),
+        per_frame(uploaded)
+    );
+    println!(
+        "{:<12} {} builder reallocations, {} buffer recreations",
+        "growth",
+        tick_counts.builder_reallocations + frame_counts.builder_reallocations,
+        tick_counts.buffer_recreations + frame_counts.buffer_recreations,
+    );
+    println!(
+        "{:<12} {} hits, {} misses",
+        "gpu cache", frame_counts.cache_hits, frame_counts.cache_misses,
+    );
+    if let Some(mesh) = &mesh {
+        compare_draws(&mut headless, mesh, options.frames);
+    }
+    compare_particles(&mut headless, seed, options.frames);
+    if instrument::TRACKING_ALLOCATIONS {
+        let per_frame = |counts: Counts| counts.allocations as f64 / options.frames.max(1) as f64;
+        println!(
+            "{:<12} {:.1} per tick, {:.1} per frame",
+            "allocations",
+            per_frame(tick_counts),
+            per_frame(frame_counts)
+        );
+    }
+
+    match options.budget {
+        Some(budget) if over > 0 => Err(format!(
+            "{over} of {} frames went over the {budget:?} budget",
+            options.frames
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Draws the last frame as `DRAW_ITEMS` separate draws `frames` times over, a call each and then
+/// by one indirect call if the device can, printing how long each took to record
+fn compare_draws(headless: &mut Headless, mesh: &Mesh, frames: usize) {
+    // drawing some triangles twice over where there are fewer than that
+    let items: Vec<_> = mesh
+        .split(DRAW_ITEMS)
+        .into_iter()
+        .cycle()
+        .take(DRAW_ITEMS)
+        .collect();
+    let multi_draw_indirect = headless.capabilities.multi_draw_indirect;
+    let mut time = |indirect| {
+        let mut samples = Samples(Vec::with_capacity(frames));
+        for _ in 0..frames {
+            samples.0.push(
+                headless
+                    .draw_items(mesh, &items, indirect, 0., 1.)
+                    .encode_time,
+            );
+        }
+        samples
+    };
+
+    println!("{:<12} {}", "draw items", items.len());
+    println!("{}", time(false).line("encode loop"));
+    if multi_draw_indirect {
+        println!("{}", time(true).line("encode multi"));
+    } else {
+        println!("{:<12} unsupported on this adapter", "encode multi");
+    }
+}
+
+/// Draws `PARTICLES` particles `frames` times over, pushed into the mesh and then as instances,
+/// printing how long the cpu took over each, from building what's uploaded to recording the draw
+fn compare_particles(headless: &mut Headless, seed: u64, frames: usize) {
+    let mut system = ParticleSystem::with_limit(PARTICLES);
+    let mut rng = StdRng::seed_from_u64(seed);
+    system.spawn_burst(
+        Vector2::new(0., 0.),
+        Vector2::new(0., 1.),
+        PARTICLES,
+        &mut rng,
+    );
+    system.update(0.1);
+
+    // something underneath for the instances to be drawn over, as the game's frame would be
+    let mut background = MeshBuilder::default();
+    push_background(&mut background);
+    let background = background.build(&headless.device);
+
+    let mut builder = MeshBuilder::default();
+    let mut mesh = None;
+    let mut meshed = Samples(Vec::with_capacity(frames));
+    for _ in 0..frames {
+        let start = Instant::now();
+        builder.clear();
+        push_background(&mut builder);
+        system.push(&mut builder);
+        let mesh = headless.write(&mut mesh, &builder);
+        let built = start.elapsed();
+        meshed
+            .0
+            .push(built + headless.draw(mesh, &[], 0., 1.).encode_time);
+    }
+
+    let mut instances = Vec::with_capacity(PARTICLES);
+    let mut instanced = Samples(Vec::with_capacity(frames));
+    for _ in 0..frames {
+        let start = Instant::now();
+        instances.clear();
+        system.render_instances(&mut instances);
+        let built = start.elapsed();
+        instanced
+            .0
+            .push(built + headless.draw(&background, &instances, 0., 1.).encode_time);
+    }
+
+    println!("{:<12} {}", "particles", PARTICLES);
+    println!("{}", meshed.line("cpu mesh"));
+    println!("{}", instanced.line("cpu instance"));
+}
+
+fn push_background(builder: &mut MeshBuilder) {
+    let color = [0.1, 0.1, 0.1, 1.];
+    builder.push(
+        [[-1., -1.], [1., -1.], [1., 1.], [-1., 1.]].map(|position| Vertex { position, color }),
+        [0, 1, 2, 0, 2, 3],
+    );
+}
+
+#[test]
+fn percentiles_count_from_the_fastest() {
+    let samples = Samples((1..=100).map(Duration::from_millis).collect());
+    assert_eq!(samples.mean(), Duration::from_micros(50_500));
+    assert_eq!(samples.percentile(0.95), Duration::from_millis(95));
+    assert_eq!(samples.percentile(1.), Duration::from_millis(100));
+    assert_eq!(Samples::default().percentile(0.95), Duration::ZERO);
+    assert_eq!(Samples::default().mean(), Duration::ZERO);
+}
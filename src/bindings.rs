@@ -0,0 +1,211 @@
+use crate::Player;
+use serde::{Deserialize, Serialize};
+
+/// What a key types, named as the windowing names it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Key {
+    /// A key named for what it does rather than anything it types, like "Enter" or "F3"
+    Named(String),
+    Character(String),
+}
+
+/// A key as pressed, in the same terms as bindings
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyPress {
+    /// Where the key is on the keyboard, like "ArrowLeft" or "KeyA", if the platform can say
+    pub physical: Option<String>,
+    /// What it typed, if it was anything a binding can name
+    pub logical: Option<Key>,
+}
+
+/// A key as bound in the config
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+    /// Where the key is on the keyboard, the same whatever the layout, for movement
+    Physical(String),
+    /// What the key types under the current layout, for keys named after a letter
+    Logical(Key),
+}
+
+impl Binding {
+    fn physical(code: &str) -> Self {
+        Self::Physical(code.into())
+    }
+
+    fn named(name: &str) -> Self {
+        Self::Logical(Key::Named(name.into()))
+    }
+
+    fn character(c: &str) -> Self {
+        Self::Logical(Key::Character(c.into()))
+    }
+
+    pub fn matches(&self, key: &KeyPress) -> bool {
+        match (self, &key.logical) {
+            (Self::Physical(code), _) => key.physical.as_ref() == Some(code),
+            // the same binding with or without shift held
+            (Self::Logical(Key::Character(bound)), Some(Key::Character(typed))) => {
+                bound.eq_ignore_ascii_case(typed)
+            }
+            (Self::Logical(bound), logical) => logical.as_ref() == Some(bound),
+        }
+    }
+}
+
+/// What a bound key does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Left(Player),
+    Right(Player),
+    Harder,
+    /// Easier on the menu, and the magnet while playing
+    Easier,
+    Serve,
+    Pause,
+    Dash,
+    /// Slow motion while held along with shift
+    SlowMotion,
+    ToggleDebug,
+    NextPalette,
+    ToggleCrt,
+    /// Starts or stops capturing frames to share
+    ToggleCapture,
+    ReportBug,
+    /// Copies the result while held along with control
+    CopyResult,
+    Quit,
+}
+
+/// The keys bound to each command, any number to each
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bindings {
+    pub left: Vec<Binding>,
+    pub right: Vec<Binding>,
+    pub player_two_left: Vec<Binding>,
+    pub player_two_right: Vec<Binding>,
+    pub harder: Vec<Binding>,
+    pub easier: Vec<Binding>,
+    pub serve: Vec<Binding>,
+    pub pause: Vec<Binding>,
+    pub dash: Vec<Binding>,
+    pub slow_motion: Vec<Binding>,
+    pub toggle_debug: Vec<Binding>,
+    pub next_palette: Vec<Binding>,
+    pub toggle_crt: Vec<Binding>,
+    pub toggle_capture: Vec<Binding>,
+    pub report_bug: Vec<Binding>,
+    pub copy_result: Vec<Binding>,
+    pub quit: Vec<Binding>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            left: vec![Binding::physical("ArrowLeft")],
+            right: vec![Binding::physical("ArrowRight")],
+            player_two_left: vec![Binding::physical("KeyA")],
+            player_two_right: vec![Binding::physical("KeyD")],
+            harder: vec![Binding::physical("ArrowUp")],
+            easier: vec![Binding::physical("ArrowDown")],
+            serve: vec![Binding::physical("Space")],
+            pause: vec![Binding::named("Pause"), Binding::character("p")],
+            dash: vec![Binding::physical("ControlLeft")],
+            slow_motion: vec![Binding::character("s")],
+            toggle_debug: vec![Binding::named("F3")],
+            next_palette: vec![Binding::named("F2"), Binding::named("Tab")],
+            toggle_crt: vec![Binding::named("F4")],
+            toggle_capture: vec![Binding::named("F5")],
+            report_bug: vec![Binding::named("F9")],
+            copy_result: vec![Binding::character("c")],
+            quit: vec![Binding::named("Escape")],
+        }
+    }
+}
+
+impl Bindings {
+    /// The command bound to a key, the first listed if it's bound to several
+    pub fn command(&self, key: &KeyPress) -> Option<Command> {
+        [
+            (&self.left, Command::Left(Player::One)),
+            (&self.right, Command::Right(Player::One)),
+            (&self.player_two_left, Command::Left(Player::Two)),
+            (&self.player_two_right, Command::Right(Player::Two)),
+            (&self.harder, Command::Harder),
+            (&self.easier, Command::Easier),
+            (&self.serve, Command::Serve),
+            (&self.pause, Command::Pause),
+            (&self.dash, Command::Dash),
+            (&self.slow_motion, Command::SlowMotion),
+            (&self.toggle_debug, Command::ToggleDebug),
+            (&self.next_palette, Command::NextPalette),
+            (&self.toggle_crt, Command::ToggleCrt),
+            (&self.toggle_capture, Command::ToggleCapture),
+            (&self.report_bug, Command::ReportBug),
+            (&self.copy_result, Command::CopyResult),
+            (&self.quit, Command::Quit),
+        ]
+        .into_iter()
+        .find(|(bindings, _)| bindings.iter().any(|binding| binding.matches(key)))
+        .map(|(_, command)| command)
+    }
+}
+
+#[cfg(test)]
+fn press(physical: &str, logical: Key) -> KeyPress {
+    KeyPress {
+        physical: Some(physical.into()),
+        logical: Some(logical),
+    }
+}
+
+#[test]
+fn movement_follows_the_key_position_and_letters_what_they_type() {
+    let bindings = Bindings::default();
+    let character = |c: &str| Key::Character(c.into());
+
+    // the key left of S on azerty types q, but still steers player two
+    assert_eq!(
+        bindings.command(&press("KeyA", character("q"))),
+        Some(Command::Left(Player::Two))
+    );
+
+    // while pause follows the letter p wherever it is, in either case
+    assert_eq!(
+        bindings.command(&press("Semicolon", character("P"))),
+        Some(Command::Pause)
+    );
+    assert_eq!(bindings.command(&press("KeyP", character("o"))), None);
+    // and a key the platform can't place only matches by what it typed
+    let unplaced = KeyPress {
+        physical: None,
+        logical: Some(character("p")),
+    };
+    assert_eq!(bindings.command(&unplaced), Some(Command::Pause));
+    assert_eq!(bindings.command(&KeyPress::default()), None);
+
+    let config = r#"{ "serve": [{ "Logical": { "Named": "Enter" } }] }"#;
+    let bindings: Bindings = serde_json::from_str(config).unwrap();
+    let enter = Key::Named("Enter".into());
+    assert_eq!(
+        bindings.command(&press("Enter", enter.clone())),
+        Some(Command::Serve)
+    );
+    assert_eq!(
+        bindings.command(&press("ArrowLeft", enter)),
+        Some(Command::Left(Player::One))
+    );
+}
+
+#[test]
+fn bindings_are_written_as_before() {
+    let json = serde_json::to_value(Bindings::default()).unwrap();
+    assert_eq!(
+        json["left"],
+        serde_json::json!([{ "Physical": "ArrowLeft" }])
+    );
+    assert_eq!(
+        json["pause"],
+        serde_json::json!([{ "Logical": { "Named": "Pause" } }, { "Logical": { "Character": "p" } }])
+    );
+}
@@ -0,0 +1,231 @@
+use crate::collison::Shape;
+use crate::level::Level;
+use crate::palette::{Palette, Role};
+use crate::renderer::buffer::MeshBuilder;
+#[cfg(test)]
+use cgmath::InnerSpace;
+use cgmath::Vector2;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Brick {
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+    pub color: [f32; 4],
+    /// Hits left before the brick breaks
+    pub hit_points: u32,
+    pub max_hit_points: u32,
+    /// Counting up from the bottom row
+    pub row: usize,
+}
+
+impl Brick {
+    pub fn shape(&self) -> Shape {
+        Shape::Aabb {
+            min: self.min,
+            max: self.max,
+        }
+    }
+
+    fn center(&self) -> Vector2<f32> {
+        (self.min + self.max) / 2.
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BrickHit {
+    pub position: Vector2<f32>,
+    /// The outward normal of the face that was hit
+    pub normal: Vector2<f32>,
+    /// Whether that was the brick's last hit point
+    pub destroyed: bool,
+    /// Whether that was the last brick left in its row
+    pub row_cleared: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Bricks {
+    bricks: Vec<Brick>,
+}
+
+impl Bricks {
+    const SIZE: Vector2<f32> = Vector2::new(0.9, 0.08);
+    const GAP: f32 = 0.02;
+    const BOTTOM: f32 = 0.;
+
+    /// The level's brick grid, centred horizontally with its bottom row at `BOTTOM`
+    pub fn from_level(level: &Level) -> Self {
+        let columns = level.bricks.iter().map(|row| row.chars().count()).max();
+        let width = columns.unwrap_or(0) as f32 * (Self::SIZE.x + Self::GAP) - Self::GAP;
+
+        let bricks = level
+            .bricks
+            .iter()
+            .rev()
+            .enumerate()
+            .flat_map(|(row, cells)| {
+                cells
+                    .chars()
+                    .enumerate()
+                    .map(move |(column, cell)| (row, column, cell))
+            })
+            .filter_map(|(row, column, cell)| {
+                let brick = level.types.get(&cell)?;
+                let min = Vector2::new(
+                    -width / 2. + column as f32 * (Self::SIZE.x + Self::GAP),
+                    Self::BOTTOM + row as f32 * (Self::SIZE.y + Self::GAP),
+                );
+                let [r, g, b] = brick.color;
+
+                Some(Brick {
+                    min,
+                    max: min + Self::SIZE,
+                    color: [r, g, b, 1.],
+                    hit_points: brick.hit_points,
+                    max_hit_points: brick.hit_points,
+                    row,
+                })
+            })
+            .collect();
+
+        Self { bricks }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bricks.is_empty()
+    }
+
+    /// Hits the first brick the ball is moving into if any, destroying it on its last hit point
+    pub fn collide(
+        &mut self,
+        position: Vector2<f32>,
+        velocity: Vector2<f32>,
+        radius: f32,
+    ) -> Option<BrickHit> {
+        let (i, normal) = self.bricks.iter().enumerate().find_map(|(i, brick)| {
+            // clipping a corner glances off it rather than off either face
+            let normal = brick.shape().intersect_circle(position, radius)?.normal;
+            // ignore bricks the ball is already leaving
+            let entering = cgmath::dot(velocity, normal) < 0.;
+            entering.then_some((i, normal))
+        })?;
+
+        let brick = &mut self.bricks[i];
+        brick.hit_points -= 1;
+        let mut hit = BrickHit {
+            position: brick.center(),
+            normal,
+            destroyed: brick.hit_points == 0,
+            row_cleared: false,
+        };
+        if hit.destroyed {
+            let row = self.bricks.remove(i).row;
+            hit.row_cleared = self.bricks.iter().all(|brick| brick.row != row);
+        }
+
+        Some(hit)
+    }
+
+    /// Pushes each brick in its level's colour, or its row's if the palette colours bricks
+    pub fn push(&self, mesh: &mut MeshBuilder, palette: &Palette) {
+        mesh.reserve(self.bricks.len() * 4, self.bricks.len() * 6);
+        for brick in &self.bricks {
+            let [r, g, b, a] = if palette.level_bricks {
+                brick.color
+            } else {
+                palette.rgba(Role::BrickRow(brick.row), brick.color[3])
+            };
+            // damaged bricks fade towards breaking
+            let health = brick.hit_points as f32 / brick.max_hit_points as f32;
+            mesh.push_rect(brick.min, brick.max, [r, g, b, a * (0.4 + 0.6 * health)]);
+        }
+    }
+
+    pub fn get(&self, i: usize) -> Option<&Brick> {
+        self.bricks.get(i)
+    }
+
+    /// Every brick's collider
+    pub fn shapes(&self) -> impl Iterator<Item = Shape> + '_ {
+        self.bricks.iter().map(Brick::shape)
+    }
+}
+
+#[test]
+fn ball_from_below_breaks_brick_and_reflects_down() {
+    let mut bricks = Bricks {
+        bricks: vec![Brick {
+            min: Vector2::new(0., 0.),
+            max: Vector2::new(1., 0.1),
+            color: [1.; 4],
+            hit_points: 2,
+            max_hit_points: 2,
+            row: 0,
+        }],
+    };
+
+    // moving away, so no hit
+    assert!(bricks
+        .collide(Vector2::new(0.5, -0.02), Vector2::new(0., -0.1), 0.05)
+        .is_none());
+
+    // the first hit only damages it
+    let hit = bricks
+        .collide(Vector2::new(0.5, -0.02), Vector2::new(0., 0.1), 0.05)
+        .unwrap();
+    assert_eq!(hit.normal, Vector2::new(0., -1.));
+    assert!(!hit.destroyed);
+
+    let hit = bricks
+        .collide(Vector2::new(0.5, -0.02), Vector2::new(0., 0.1), 0.05)
+        .unwrap();
+    assert!(hit.destroyed);
+    assert!(hit.row_cleared);
+    assert!(bricks.is_empty());
+}
+
+#[test]
+fn ball_clipping_a_corner_glances_off_it() {
+    let mut bricks = Bricks {
+        bricks: vec![Brick {
+            min: Vector2::new(0., 0.),
+            max: Vector2::new(1., 0.1),
+            color: [1.; 4],
+            hit_points: 1,
+            max_hit_points: 1,
+            row: 0,
+        }],
+    };
+
+    let hit = bricks
+        .collide(Vector2::new(-0.03, -0.03), Vector2::new(0.1, 0.1), 0.05)
+        .unwrap();
+    let diagonal = Vector2::new(-1., -1.).normalize();
+    assert!((hit.normal - diagonal).magnitude() < 1e-6);
+}
+
+#[test]
+fn grid_comes_from_the_level() {
+    let level = Level::parse(
+        r#"
+        name = "test"
+        bricks = ["AB", ".A"]
+        types.A = { color = [1.0, 0.0, 0.0] }
+        types.B = { color = [0.0, 0.0, 1.0], hit_points = 3 }
+        "#,
+    )
+    .unwrap();
+    let bricks = Bricks::from_level(&level).bricks;
+
+    assert_eq!(bricks.len(), 3);
+    // the last row in the file is the bottom one
+    assert_eq!(bricks[0].min.y, Bricks::BOTTOM);
+    assert!(bricks[0].min.x > 0.);
+    assert_eq!(bricks[2].hit_points, 3);
+    assert_eq!(bricks[2].color, [0., 0., 1., 1.]);
+    assert!(bricks[2].min.y > bricks[0].min.y);
+    assert_eq!(
+        bricks.iter().map(|brick| brick.row).collect::<Vec<_>>(),
+        [0, 1, 1]
+    );
+}
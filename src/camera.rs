@@ -0,0 +1,412 @@
+use crate::renderer::buffer::ViewTransform;
+use cgmath::Vector2;
+
+/// The stretch of the world the view may show, from side to side. The camera only pans sideways,
+/// and the view always shows from -1 to 1 vertically at a zoom of 1, so only the sides are held,
+/// against a view as wide as the surface's aspect makes it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min_x: f32,
+    pub max_x: f32,
+}
+
+impl Bounds {
+    /// Room past each wall for a paddle pressed up against it to stay in view
+    const WALL_MARGIN: f32 = 0.5;
+
+    /// Out to a level's walls at `wall_x` either side, and a little past them
+    pub fn around_walls(wall_x: f32) -> Self {
+        let edge = wall_x.max(0.) + Self::WALL_MARGIN;
+        Self {
+            min_x: -edge,
+            max_x: edge,
+        }
+    }
+}
+
+/// The horizontally-following camera, with trauma-driven screen shake and zoom that frames the
+/// action
+#[derive(Debug, Clone)]
+pub struct Camera {
+    x: f32,
+    /// The view never shows past these, shake included
+    bounds: Bounds,
+    /// In 0..=1, decaying over time; the shake is proportional to its square
+    trauma: f32,
+    time: f32,
+    seed: u32,
+    /// Extra zoom on top of the framing, decaying over time
+    zoom: f32,
+    /// The zoom that keeps the action in view, eased towards `target_framing`
+    framing: f32,
+    target_framing: f32,
+    /// How quickly the framing eases towards its target, per second
+    pub framing_smoothing: f32,
+    pub reduced_motion: bool,
+    /// How many times wider than tall the surface is, which the view's as wide as
+    aspect: f32,
+}
+
+impl Camera {
+    const MAX_SHAKE: f32 = 0.15;
+    /// Trauma lost per second
+    const TRAUMA_DECAY: f32 = 1.5;
+    /// Noise lattice points sampled per second
+    const SHAKE_FREQUENCY: f32 = 20.;
+    /// Extra zoom lost per second
+    const ZOOM_DECAY: f32 = 0.3;
+    /// Framing while the ball is slow
+    const CALM_ZOOM: f32 = 1.05;
+    /// Framing while the ball is at full speed
+    const FAST_ZOOM: f32 = 0.85;
+    /// Space kept around the balls when fitting them all in view
+    const FIT_PADDING: f32 = 0.1;
+    const MIN_ZOOM: f32 = 0.8;
+    /// Any closer and the zones at the top and bottom leave the view
+    const MAX_ZOOM: f32 = 1. / 0.9;
+
+    pub fn new(bounds: Bounds, seed: u32) -> Self {
+        Self {
+            x: 0.,
+            bounds,
+            trauma: 0.,
+            time: 0.,
+            seed,
+            zoom: 0.,
+            framing: 1.,
+            target_framing: 1.,
+            framing_smoothing: 3.,
+            reduced_motion: false,
+            aspect: 1.,
+        }
+    }
+
+    /// Shows as much from side to side as a surface `aspect` times as wide as it's tall does
+    pub fn set_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
+        self.set_position(self.x);
+    }
+
+    /// Holds the view inside `bounds` from now on, as when a level with its walls elsewhere starts
+    pub fn set_bounds(&mut self, bounds: Bounds) {
+        self.bounds = bounds;
+        self.set_position(self.x);
+    }
+
+    /// Moves the camera to `x`, or as near as it gets with the view inside the bounds
+    pub fn set_position(&mut self, x: f32) {
+        self.x = self.clamp(x);
+    }
+
+    /// Eases the camera towards `target`
+    pub fn follow(&mut self, target: f32) {
+        self.set_position((self.x * 10. + target) / 11.);
+    }
+
+    /// The nearest position to `x` where the view, as wide as the aspect and zoom make it, stays
+    /// inside the bounds, or their middle if they're narrower than the view
+    fn clamp(&self, x: f32) -> f32 {
+        let half_width = self.half_width();
+        let (min, max) = (
+            self.bounds.min_x + half_width,
+            self.bounds.max_x - half_width,
+        );
+        if min > max {
+            (self.bounds.min_x + self.bounds.max_x) / 2.
+        } else {
+            x.clamp(min, max)
+        }
+    }
+
+    /// How far the view reaches either side of the camera, as `ViewTransform` draws it
+    fn half_width(&self) -> f32 {
+        self.aspect / self.zoom()
+    }
+
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.);
+    }
+
+    /// Punches the view in, easing back out over time
+    pub fn add_zoom(&mut self, amount: f32) {
+        self.zoom += amount;
+    }
+
+    /// Aims the framing in closer the calmer the play, where `pace` is the ball's speed as a
+    /// fraction of its maximum, but out far enough to fit every ball
+    pub fn frame(&mut self, pace: f32, balls: impl IntoIterator<Item = Vector2<f32>>) {
+        let pace = pace.clamp(0., 1.);
+        let calm = Self::CALM_ZOOM + (Self::FAST_ZOOM - Self::CALM_ZOOM) * pace;
+        // the view reaches the aspect times as far to the sides as up and down
+        let extent = balls
+            .into_iter()
+            .map(|ball| ((ball.x - self.x).abs() / self.aspect).max(ball.y.abs()))
+            .fold(0., f32::max);
+        let fit = 1. / (extent + Self::FIT_PADDING);
+
+        self.target_framing = calm.min(fit).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+        self.trauma = (self.trauma - Self::TRAUMA_DECAY * dt).max(0.);
+        self.zoom = (self.zoom - Self::ZOOM_DECAY * dt).max(0.);
+        let ease = 1. - (-self.framing_smoothing * dt).exp();
+        self.framing += (self.target_framing - self.framing) * ease;
+    }
+
+    fn shake(&self) -> f32 {
+        if self.reduced_motion {
+            return 0.;
+        }
+
+        let noise = value_noise(self.seed, self.time * Self::SHAKE_FREQUENCY);
+        noise * self.trauma.powi(2) * Self::MAX_SHAKE
+    }
+
+    /// The x position to render from
+    pub fn position(&self) -> f32 {
+        self.clamp(self.x + self.shake())
+    }
+
+    /// How much to scale the view by around the camera's position
+    pub fn zoom(&self) -> f32 {
+        if self.reduced_motion {
+            1.
+        } else {
+            self.framing * (1. + self.zoom)
+        }
+    }
+
+    /// What the renderer draws the world with from where the camera is now
+    pub fn view(&self) -> ViewTransform {
+        ViewTransform::new(self.position(), self.zoom(), self.aspect)
+    }
+
+    /// Where `world` shows on screen, from -1 to 1 across and up it
+    pub fn world_to_ndc(&self, world: Vector2<f32>) -> Vector2<f32> {
+        self.view().world_to_ndc(world)
+    }
+
+    /// Which pixel `world` shows on in a window `size` pixels across and down, counted from its
+    /// top left corner
+    pub fn world_to_pixels(&self, world: Vector2<f32>, size: Vector2<f32>) -> Vector2<f32> {
+        self.view().world_to_pixels(world, size)
+    }
+
+    /// What in the world shows at `pixel` in a window `size` pixels across and down, as from the
+    /// cursor
+    pub fn pixels_to_world(&self, pixel: Vector2<f32>, size: Vector2<f32>) -> Vector2<f32> {
+        self.view().pixels_to_world(pixel, size)
+    }
+}
+
+/// Hashes a lattice point to a value in -1..=1
+fn lattice(seed: u32, i: i32) -> f32 {
+    let mut h = (i as u32).wrapping_mul(0x9e37_79b9) ^ seed;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 16;
+    (h as f32 / u32::MAX as f32) * 2. - 1.
+}
+
+/// Smooth 1D noise in -1..=1, interpolating between hashed lattice points
+fn value_noise(seed: u32, t: f32) -> f32 {
+    let i = t.floor();
+    let frac = t - i;
+    let smooth = frac * frac * (3. - 2. * frac);
+    let (a, b) = (lattice(seed, i as i32), lattice(seed, i as i32 + 1));
+    a + (b - a) * smooth
+}
+
+#[test]
+fn shake_stays_within_bounds() {
+    for aspect in [1., 16. / 9., 9. / 16.] {
+        let mut camera = Camera::new(Bounds::around_walls(5.5), 1);
+        camera.set_aspect(aspect);
+        for _ in 0..100 {
+            camera.follow(100.);
+        }
+        camera.add_trauma(1.);
+
+        for _ in 0..100 {
+            camera.update(0.01);
+            assert!(camera.position() + camera.half_width() <= 6. + 1e-5);
+        }
+    }
+}
+
+#[test]
+fn the_view_never_shows_past_the_bounds() {
+    let bounds = Bounds {
+        min_x: -3.,
+        max_x: 5.,
+    };
+    // 4:3, 16:9 and a portrait phone's
+    for aspect in [4. / 3., 16. / 9., 9. / 19.5] {
+        let mut camera = Camera::new(bounds, 2);
+        camera.set_aspect(aspect);
+        for (pace, punch) in [(0., 0.), (1., 0.), (0., 0.5), (1., 2.)] {
+            camera.frame(pace, [Vector2::new(0., 0.)]);
+            camera.add_zoom(punch);
+            for _ in 0..200 {
+                camera.update(0.01);
+            }
+            // the edges as they're drawn, not just as the camera works them out
+            let view = camera.view();
+            let [left, right] = [-1., 1.].map(|x| view.ndc_to_world(Vector2::new(x, 0.)).x);
+            assert!((right - left - 2. * camera.half_width()).abs() < 1e-4);
+            for target in [-100., -3., 0., 1., 4.5, 100.] {
+                camera.set_position(target);
+                let view = camera.view();
+                let [left, right] = [-1., 1.].map(|x| view.ndc_to_world(Vector2::new(x, 0.)).x);
+                let zoom = camera.zoom();
+                assert!(
+                    left >= bounds.min_x - 1e-4,
+                    "{left} at {aspect} zoom {zoom}"
+                );
+                assert!(
+                    right <= bounds.max_x + 1e-4,
+                    "{right} at {aspect} zoom {zoom}"
+                );
+            }
+            // inside, it goes where it's put
+            camera.set_position(1.);
+            assert_eq!(camera.position(), 1.);
+        }
+    }
+}
+
+#[test]
+fn a_wider_surface_is_held_further_from_the_walls() {
+    let mut camera = Camera::new(Bounds::around_walls(5.5), 6);
+    camera.set_position(100.);
+    let square = camera.position();
+    // widening the window pulls the camera in from the wall straight away
+    camera.set_aspect(16. / 9.);
+    assert!(camera.position() < square);
+    assert!((camera.position() + camera.half_width() - 6.).abs() < 1e-5);
+}
+
+#[test]
+fn bounds_narrower_than_the_view_are_centred_on() {
+    let mut camera = Camera::new(Bounds::around_walls(5.5), 4);
+    camera.set_position(3.);
+    // a level's walls closer together than the view is wide
+    camera.set_bounds(Bounds {
+        min_x: 1.,
+        max_x: 2.,
+    });
+    assert_eq!(camera.position(), 1.5);
+    camera.set_position(-10.);
+    assert_eq!(camera.position(), 1.5);
+
+    // and exactly as wide, with nowhere to move
+    camera.set_bounds(Bounds::around_walls(0.5));
+    camera.follow(10.);
+    assert_eq!(camera.position(), 0.);
+}
+
+#[test]
+fn trauma_decays_and_reduced_motion_disables_shake() {
+    let mut camera = Camera::new(Bounds::around_walls(5.5), 7);
+    camera.add_trauma(0.4);
+    camera.update(0.05);
+    assert!(camera.shake() != 0.);
+
+    camera.add_zoom(0.1);
+    assert!(camera.zoom() > 1.);
+
+    camera.reduced_motion = true;
+    assert_eq!(camera.position(), 0.);
+    assert_eq!(camera.zoom(), 1.);
+    camera.reduced_motion = false;
+
+    camera.update(1.);
+    assert_eq!(camera.trauma, 0.);
+    assert_eq!(camera.zoom(), 1.);
+    assert_eq!(camera.position(), 0.);
+}
+
+#[test]
+fn value_noise_is_smooth_and_seeded() {
+    assert_eq!(value_noise(3, 1.25), value_noise(3, 1.25));
+    assert!(value_noise(3, 1.25) != value_noise(4, 1.25));
+
+    for i in 0..1000 {
+        let t = i as f32 * 0.01;
+        assert!((value_noise(3, t) - value_noise(3, t + 0.001)).abs() < 0.01);
+    }
+}
+
+#[test]
+fn framing_eases_in_when_calm_and_out_to_fit() {
+    let mut camera = Camera::new(Bounds::around_walls(5.5), 3);
+    camera.frame(0., [Vector2::new(0., 0.2)]);
+    for _ in 0..200 {
+        camera.update(0.01);
+    }
+    assert!((camera.zoom() - Camera::CALM_ZOOM).abs() < 0.01);
+
+    camera.frame(1., [Vector2::new(0., 0.2)]);
+    camera.update(0.01);
+    let zoom = camera.zoom();
+    assert!(zoom < Camera::CALM_ZOOM && zoom > Camera::FAST_ZOOM);
+
+    // a ball far off to the side is still framed, as far as the clamp allows
+    camera.frame(0., [Vector2::new(0., 0.), Vector2::new(3., 0.)]);
+    for _ in 0..200 {
+        camera.update(0.01);
+    }
+    assert!((camera.zoom() - Camera::MIN_ZOOM).abs() < 0.01);
+
+    // while a wide window has room for one off to the side without zooming out
+    camera.set_aspect(16. / 9.);
+    camera.frame(0., [Vector2::new(0., 0.), Vector2::new(1.5, 0.)]);
+    for _ in 0..200 {
+        camera.update(0.01);
+    }
+    assert!((camera.zoom() - Camera::CALM_ZOOM).abs() < 0.01);
+
+    camera.reduced_motion = true;
+    assert_eq!(camera.zoom(), 1.);
+}
+
+#[test]
+fn pixels_and_the_world_convert_both_ways() {
+    use cgmath::InnerSpace;
+
+    let mut camera = Camera::new(Bounds::around_walls(5.5), 5);
+    let sizes =
+        [(800., 600.), (600., 800.), (1920., 1080.), (256., 256.)].map(|(w, h)| Vector2::new(w, h));
+    for (x, punch) in [(0., 0.), (-3., 0.3), (4., 1.), (1.5, 0.05)] {
+        camera.set_position(x);
+        camera.add_zoom(punch);
+        for size in sizes {
+            for world in [
+                Vector2::new(0., 0.),
+                Vector2::new(-5.5, 1.),
+                Vector2::new(2.25, -0.7),
+                Vector2::new(x, 0.3),
+            ] {
+                let back = camera.pixels_to_world(camera.world_to_pixels(world, size), size);
+                assert!(
+                    (back - world).magnitude() < 1e-4,
+                    "{world:?} came back as {back:?}"
+                );
+            }
+        }
+    }
+
+    // the camera's own position is the middle of the window, and the top left corner is -1, 1
+    let size = Vector2::new(800., 600.);
+    let middle = Vector2::new(camera.position(), 0.);
+    assert_eq!(
+        camera.world_to_pixels(middle, size),
+        Vector2::new(400., 300.)
+    );
+    let corner = camera.pixels_to_world(Vector2::new(0., 0.), size);
+    assert!((camera.world_to_ndc(corner) - Vector2::new(-1., 1.)).magnitude() < 1e-5);
+}
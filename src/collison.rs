@@ -1,40 +1,37 @@
 #[cfg(test)]
 use cgmath::vec2;
 use cgmath::{InnerSpace, Vector2};
+#[cfg(test)]
+use proptest::prelude::*;
 
-/// checks if p is to the right of line ab
-fn is_right(p: Vector2<f32>, a: Vector2<f32>, b: Vector2<f32>) -> bool {
-    // line is vertical
-    if a.x == b.x {
-        return p.x > a.x;
-    }
-
-    // line is horizontal
-    if a.y == b.y {
-        return p.y > a.y;
+/// How far `p` is to the left of line ab, scaled by its length, by the cross product so steep lines
+/// are as exact as flat ones. Always worked out from the same end of the line, so going along it
+/// the other way rounds exactly the same and only flips the sign.
+fn side(p: Vector2<f32>, a: Vector2<f32>, b: Vector2<f32>) -> f32 {
+    if (a.x, a.y) > (b.x, b.y) {
+        return -side(p, b, a);
     }
-
-    // solve y = mx + c for line ab
-    let (dx, dy) = b.zip(a, std::ops::Sub::sub).into();
-    let m = dy / dx;
-    // c = y - mx
-    let c = a.y - m * a.x;
-
-    let ab_y = m * p.x + c;
-    p.y > ab_y
+    let (ab, ap) = (b - a, p - a);
+    ab.x * ap.y - ab.y * ap.x
 }
 
+/// Whether `p` is inside the triangle or on its outline. The same whichever order the corners
+/// are given in, so either winding, since it only asks that `p` is on the same side of every edge
+/// going round. A triangle with its corners in a line contains nothing.
 pub fn triangle_contains(
     p: Vector2<f32>,
     v1: Vector2<f32>,
     v2: Vector2<f32>,
     v3: Vector2<f32>,
 ) -> bool {
-    let s1 = is_right(p, v1, v2) ^ is_right(v3, v1, v2);
-    let s2 = is_right(p, v1, v3) ^ is_right(v2, v1, v3);
-    let s3 = is_right(p, v2, v3) ^ is_right(v1, v2, v3);
+    if side(v3, v1, v2) == 0. {
+        return false;
+    }
 
-    !s1 & !s2 & !s3
+    let sides = [side(p, v1, v2), side(p, v2, v3), side(p, v3, v1)];
+    let left = sides.iter().any(|&side| side > 0.);
+    let right = sides.iter().any(|&side| side < 0.);
+    !(left && right)
 }
 
 #[test]
@@ -61,6 +58,62 @@ fn triangle_contains_works() {
     ));
 }
 
+#[test]
+fn triangle_contains_ignores_the_order_of_the_corners() {
+    let triangles = [
+        [vec2(0., 0.), vec2(1., 0.), vec2(0., 1.)],
+        // thin and steep, as the paddle's halves are at full tilt
+        [vec2(0., 0.), vec2(0.05, 1.), vec2(0.1, 0.)],
+        [vec2(-0.5, -0.5), vec2(1., 0.25), vec2(0.25, 1.)],
+        // with a horizontal and a vertical edge
+        [vec2(0., 0.), vec2(1., 1.), vec2(0., 1.)],
+    ];
+    let orders = [
+        [0, 1, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [0, 2, 1],
+        [2, 1, 0],
+        [1, 0, 2],
+    ];
+    for triangle in triangles {
+        // a grid landing exactly on corners and along edges as well as either side of them
+        for i in -10..=30 {
+            for j in -10..=30 {
+                let p = vec2(i as f32, j as f32) / 20.;
+                let [first, rest @ ..] = orders
+                    .map(|[a, b, c]| triangle_contains(p, triangle[a], triangle[b], triangle[c]));
+                assert!(
+                    rest.iter().all(|&inside| inside == first),
+                    "{p:?} in {triangle:?}"
+                );
+            }
+        }
+    }
+
+    // the outline counts as inside for either winding
+    let [v1, v2, v3] = triangles[0];
+    for p in [vec2(0.5, 0.), vec2(0., 0.5), vec2(0.5, 0.5), v1, v2, v3] {
+        assert!(triangle_contains(p, v1, v2, v3));
+        assert!(triangle_contains(p, v1, v3, v2));
+    }
+
+    // nothing is inside a triangle flattened into a line, even on it
+    let flat = [vec2(0., 0.), vec2(0.5, 0.5), vec2(1., 1.)];
+    assert!(!triangle_contains(
+        vec2(0.25, 0.25),
+        flat[0],
+        flat[1],
+        flat[2]
+    ));
+    assert!(!triangle_contains(
+        vec2(0.25, 0.25),
+        flat[2],
+        flat[0],
+        flat[1]
+    ));
+}
+
 pub fn circle_intersects_line_segment(
     c: Vector2<f32>,
     r: f32,
@@ -69,15 +122,12 @@ pub fn circle_intersects_line_segment(
 ) -> bool {
     let closest_point = {
         let line = b - a;
-        let line_norm = line.normalize();
-        let ac = c - a;
-        let t = ac.dot(line_norm);
-        if t < 0.0 {
+        let length = line.magnitude2();
+        // a segment that's only a point has no direction to project onto
+        if length == 0. {
             a
-        } else if t > line.magnitude() {
-            b
         } else {
-            a + line_norm * t
+            a + line * ((c - a).dot(line) / length).clamp(0., 1.)
         }
     };
 
@@ -129,3 +179,1064 @@ pub fn circle_intersects_triangle(
         | circle_intersects_line_segment(c, r, v1, v3)
         | circle_intersects_line_segment(c, r, v2, v3)
 }
+
+/// The part of a shape's outline a circle touched, with edge `i` running from corner `i` to the
+/// next one round
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    Edge(usize),
+    Vertex(usize),
+    /// The circle's centre was inside the shape
+    Inside,
+}
+
+/// The nearest point on a polygon's outline to `c`, its squared distance, and whether it's on an
+/// edge or at a corner. A corner two edges share is reported as that corner whichever edge finds
+/// it first.
+fn nearest_on_outline(c: Vector2<f32>, points: &[Vector2<f32>]) -> (f32, Vector2<f32>, Feature) {
+    let mut nearest = (f32::INFINITY, c, Feature::Inside);
+    for (i, &a) in points.iter().enumerate() {
+        let next = (i + 1) % points.len();
+        let edge = points[next] - a;
+        let length = edge.magnitude2();
+        let t = if length == 0. {
+            0.
+        } else {
+            ((c - a).dot(edge) / length).clamp(0., 1.)
+        };
+        let feature = match t {
+            0. => Feature::Vertex(i),
+            1. => Feature::Vertex(next),
+            _ => Feature::Edge(i),
+        };
+        let point = a + edge * t;
+        let squared = (c - point).magnitude2();
+        if squared < nearest.0 {
+            nearest = (squared, point, feature);
+        }
+    }
+    nearest
+}
+
+/// Which part of the triangle a circle touches, if any, with its edges numbered from `v1` to
+/// `v2`, `v2` to `v3` and `v3` back to `v1`
+pub fn circle_triangle_feature(
+    c: Vector2<f32>,
+    r: f32,
+    v1: Vector2<f32>,
+    v2: Vector2<f32>,
+    v3: Vector2<f32>,
+) -> Option<Feature> {
+    if triangle_contains(c, v1, v2, v3) {
+        return Some(Feature::Inside);
+    }
+    let (squared, _, feature) = nearest_on_outline(c, &[v1, v2, v3]);
+    (squared <= r * r).then_some(feature)
+}
+
+#[test]
+fn circle_triangle_feature_works() {
+    let [v1, v2, v3] = [vec2(0., 0.), vec2(1., 0.), vec2(0., 1.)];
+    let feature = |c| circle_triangle_feature(c, 0.1, v1, v2, v3);
+    assert_eq!(feature(vec2(0.2, 0.2)), Some(Feature::Inside));
+    assert_eq!(feature(vec2(0.5, -0.05)), Some(Feature::Edge(0)));
+    assert_eq!(feature(vec2(0.55, 0.55)), Some(Feature::Edge(1)));
+    assert_eq!(feature(vec2(-0.05, 0.5)), Some(Feature::Edge(2)));
+    // grazing a corner, as near both edges meeting there
+    assert_eq!(feature(vec2(-0.05, -0.05)), Some(Feature::Vertex(0)));
+    assert_eq!(feature(vec2(1.05, -0.05)), Some(Feature::Vertex(1)));
+    assert_eq!(feature(vec2(0.5, -0.2)), None);
+}
+
+/// One of the two coordinate axes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+/// Which face of an axis-aligned rectangle a circle has pushed into least, which is the one to
+/// reflect it off
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AabbHit {
+    pub axis: Axis,
+    /// 1 for the face on the positive side of the axis, -1 for the negative
+    pub sign: f32,
+    /// How far the circle reaches past that face
+    pub depth: f32,
+    /// Numbering the corners anticlockwise from `min`, so edge 0 is the bottom face
+    pub feature: Feature,
+}
+
+impl AabbHit {
+    /// The face's outward normal
+    pub fn normal(&self) -> Vector2<f32> {
+        match self.axis {
+            Axis::X => Vector2::new(self.sign, 0.),
+            Axis::Y => Vector2::new(0., self.sign),
+        }
+    }
+}
+
+/// The face of the rectangle from `min` to `max` a circle has pushed into least, if it overlaps
+/// at all. Near a corner that's whichever face it's less deep past, favouring the top and bottom
+/// when they're level.
+pub fn circle_intersects_aabb(
+    c: Vector2<f32>,
+    r: f32,
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+) -> Option<AabbHit> {
+    let closest = Vector2::new(c.x.clamp(min.x, max.x), c.y.clamp(min.y, max.y));
+    if (c - closest).magnitude2() > r * r {
+        return None;
+    }
+    let feature = if closest == c {
+        Feature::Inside
+    } else {
+        let corners = [
+            min,
+            Vector2::new(max.x, min.y),
+            max,
+            Vector2::new(min.x, max.y),
+        ];
+        nearest_on_outline(c, &corners).2
+    };
+
+    // measured from the side of the centre the circle is on, so one larger than the rectangle
+    // still has a face to come out of
+    let center = (min + max) / 2.;
+    let face = |c: f32, min: f32, max: f32, center: f32| {
+        if c < center {
+            (-1., c + r - min)
+        } else {
+            (1., max - (c - r))
+        }
+    };
+    let (sign_x, depth_x) = face(c.x, min.x, max.x, center.x);
+    let (sign_y, depth_y) = face(c.y, min.y, max.y, center.y);
+
+    Some(if depth_x < depth_y {
+        AabbHit {
+            axis: Axis::X,
+            sign: sign_x,
+            depth: depth_x,
+            feature,
+        }
+    } else {
+        AabbHit {
+            axis: Axis::Y,
+            sign: sign_y,
+            depth: depth_y,
+            feature,
+        }
+    })
+}
+
+#[test]
+fn circle_intersects_aabb_works() {
+    let (min, max) = (vec2(0., 0.), vec2(1., 0.1));
+
+    // into the bottom face, well away from the corners
+    let hit = circle_intersects_aabb(vec2(0.5, -0.02), 0.05, min, max).unwrap();
+    assert_eq!((hit.axis, hit.sign), (Axis::Y, -1.));
+    assert_eq!(hit.feature, Feature::Edge(0));
+    assert!((hit.depth - 0.03).abs() < 1e-6);
+    assert_eq!(hit.normal(), vec2(0., -1.));
+
+    // into the right end
+    let hit = circle_intersects_aabb(vec2(1.04, 0.05), 0.05, min, max).unwrap();
+    assert_eq!((hit.axis, hit.sign), (Axis::X, 1.));
+    assert_eq!(hit.feature, Feature::Edge(1));
+    assert!((hit.depth - 0.01).abs() < 1e-6);
+
+    // past a corner on both axes, but not reaching it
+    assert_eq!(
+        circle_intersects_aabb(vec2(1.04, 0.14), 0.05, min, max),
+        None
+    );
+
+    // dead on a corner, off whichever face it's less far past
+    let hit = circle_intersects_aabb(vec2(1.03, 0.12), 0.05, min, max).unwrap();
+    assert_eq!((hit.axis, hit.sign), (Axis::X, 1.));
+    assert_eq!(hit.feature, Feature::Vertex(2));
+    let hit = circle_intersects_aabb(vec2(1.02, 0.13), 0.05, min, max).unwrap();
+    assert_eq!((hit.axis, hit.sign), (Axis::Y, 1.));
+    assert_eq!(hit.feature, Feature::Vertex(2));
+    // and exactly diagonal, where both faces are as near
+    let hit = circle_intersects_aabb(vec2(-0.03, -0.03), 0.05, min, max).unwrap();
+    assert_eq!(hit.feature, Feature::Vertex(0));
+
+    // a circle larger than the rectangle, centred just below and left of its middle
+    let hit = circle_intersects_aabb(vec2(0.45, 0.04), 1., min, max).unwrap();
+    assert_eq!((hit.axis, hit.sign), (Axis::Y, -1.));
+    assert_eq!(hit.feature, Feature::Inside);
+    assert!((hit.depth - 1.04).abs() < 1e-6);
+    assert_eq!(circle_intersects_aabb(vec2(3., 0.), 1., min, max), None);
+}
+
+/// A rectangle turned about its centre
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Obb {
+    pub center: Vector2<f32>,
+    /// Half the width and height before turning
+    pub half_extents: Vector2<f32>,
+    /// Radians anticlockwise
+    pub rotation: f32,
+}
+
+impl Obb {
+    /// The directions of the rectangle's width and height, turned with it
+    pub fn axes(&self) -> [Vector2<f32>; 2] {
+        let (s, c) = self.rotation.sin_cos();
+        [Vector2::new(c, s), Vector2::new(-s, c)]
+    }
+
+    /// Anticlockwise from the bottom left before turning
+    pub fn corners(&self) -> [Vector2<f32>; 4] {
+        let (s, c) = self.rotation.sin_cos();
+        let Vector2 { x: w, y: h } = self.half_extents;
+
+        [[-w, -h], [w, -h], [w, h], [-w, h]]
+            .map(|[x, y]| [x * c - y * s, x * s + y * c])
+            .map(|[x, y]| Vector2::new(self.center.x + x, self.center.y + y))
+    }
+
+    /// `p` in the rectangle's own frame, where it's axis-aligned about the origin
+    pub(crate) fn local(&self, p: Vector2<f32>) -> Vector2<f32> {
+        let [u, v] = self.axes();
+        let offset = p - self.center;
+        Vector2::new(offset.dot(u), offset.dot(v))
+    }
+
+    pub fn contains_point(&self, p: Vector2<f32>) -> bool {
+        let local = self.local(p);
+        local.x.abs() <= self.half_extents.x && local.y.abs() <= self.half_extents.y
+    }
+
+    pub fn intersects_circle(&self, c: Vector2<f32>, r: f32) -> bool {
+        self.circle_feature(c, r).is_some()
+    }
+
+    /// Which part of the rectangle a circle touches, if any, numbered as `corners` are
+    pub fn circle_feature(&self, c: Vector2<f32>, r: f32) -> Option<Feature> {
+        circle_intersects_aabb(self.local(c), r, -self.half_extents, self.half_extents)
+            .map(|hit| hit.feature)
+    }
+
+    /// By the separating axis theorem, with each rectangle's two axes the only ones to try
+    pub fn intersects_obb(&self, other: &Obb) -> bool {
+        let (ours, theirs) = (self.corners(), other.corners());
+        self.axes().into_iter().chain(other.axes()).all(|axis| {
+            let (our_min, our_max) = project(&ours, axis);
+            let (their_min, their_max) = project(&theirs, axis);
+            our_min <= their_max && their_min <= our_max
+        })
+    }
+}
+
+#[test]
+fn obb_works() {
+    use std::f32::consts::FRAC_PI_4;
+    let diamond = Obb {
+        center: vec2(1., 1.),
+        half_extents: vec2(1., 1.),
+        rotation: FRAC_PI_4,
+    };
+    let corners = diamond.corners();
+    assert!((corners[0] - vec2(1., 1. - 2f32.sqrt())).magnitude() < 1e-6);
+    assert!((corners[1] - vec2(1. + 2f32.sqrt(), 1.)).magnitude() < 1e-6);
+
+    assert!(diamond.contains_point(vec2(1., 2.3)));
+    // inside the unturned square, but outside the diamond
+    assert!(!diamond.contains_point(vec2(1.9, 1.9)));
+
+    assert!(diamond.intersects_circle(vec2(2.5, 1.), 0.1));
+    assert!(!diamond.intersects_circle(vec2(2., 2.), 0.3));
+    assert!(diamond.intersects_circle(vec2(2., 2.), 0.5));
+    // the corners are numbered the same turned or not
+    assert_eq!(
+        diamond.circle_feature(vec2(1. + 2f32.sqrt() + 0.05, 1.), 0.1),
+        Some(Feature::Vertex(1))
+    );
+    assert_eq!(
+        diamond.circle_feature(vec2(2., 2.), 0.5),
+        Some(Feature::Edge(1))
+    );
+
+    let square = |center, rotation| Obb {
+        center,
+        half_extents: vec2(0.5, 0.5),
+        rotation,
+    };
+    assert!(diamond.intersects_obb(&square(vec2(2.8, 1.), 0.)));
+    // overlapping on both of the square's axes, but apart along the diamond's
+    assert!(!diamond.intersects_obb(&square(vec2(2.5, 2.5), 0.)));
+    assert!(!square(vec2(2.5, 2.5), 0.).intersects_obb(&diamond));
+    assert!(diamond.intersects_obb(&square(vec2(1.9, 1.9), FRAC_PI_4)));
+}
+
+/// How far and which way to push a circle to separate it from a shape it overlaps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Manifold {
+    /// Unit length, pointing out of the shape towards the circle
+    pub normal: Vector2<f32>,
+    pub depth: f32,
+    pub feature: Feature,
+}
+
+/// Whether the polygon's corners turn the same way all the way round, in either winding order
+pub fn is_convex(points: &[Vector2<f32>]) -> bool {
+    if points.len() < 3 {
+        return false;
+    }
+
+    let crosses = (0..points.len()).map(|i| {
+        let [a, b, c] = [0, 1, 2].map(|offset| points[(i + offset) % points.len()]);
+        let (ab, bc) = (b - a, c - b);
+        ab.x * bc.y - ab.y * bc.x
+    });
+    let (mut positive, mut negative) = (false, false);
+    for cross in crosses {
+        positive |= cross > 0.;
+        negative |= cross < 0.;
+    }
+    positive != negative
+}
+
+/// The least and greatest distances of the points along `axis`
+fn project(points: &[Vector2<f32>], axis: Vector2<f32>) -> (f32, f32) {
+    points
+        .iter()
+        .map(|point| point.dot(axis))
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), d| {
+            (min.min(d), max.max(d))
+        })
+}
+
+/// The mean of a polygon's corners, always inside it when it's convex
+fn centroid(points: &[Vector2<f32>]) -> Vector2<f32> {
+    points.iter().fold(Vector2::new(0., 0.), |sum, p| sum + p) / points.len() as f32
+}
+
+/// Whether a polygon's corners go anticlockwise, by the sign of its area
+pub fn is_anticlockwise(points: &[Vector2<f32>]) -> bool {
+    let area = (0..points.len())
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f32>();
+    area > 0.
+}
+
+/// The overlap of a circle with a convex polygon, if any
+pub fn circle_convex_polygon(c: Vector2<f32>, r: f32, points: &[Vector2<f32>]) -> Option<Manifold> {
+    let centroid = centroid(points);
+
+    let mut inside = true;
+    // the nearest edge while inside
+    let mut nearest_edge = (f32::INFINITY, Vector2::new(0., 0.));
+    for (i, &a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        let edge = b - a;
+        let mut outward = Vector2::new(edge.y, -edge.x).normalize();
+        if outward.dot(a - centroid) < 0. {
+            outward = -outward;
+        }
+
+        let distance = (c - a).dot(outward);
+        if distance > 0. {
+            inside = false;
+        }
+        if -distance < nearest_edge.0 {
+            nearest_edge = (-distance, outward);
+        }
+    }
+
+    if inside {
+        let (distance, normal) = nearest_edge;
+        return Some(Manifold {
+            normal,
+            depth: distance + r,
+            feature: Feature::Inside,
+        });
+    }
+
+    let (squared, point, feature) = nearest_on_outline(c, points);
+    let distance = squared.sqrt();
+    (distance < r && distance > 0.).then(|| Manifold {
+        normal: (c - point) / distance,
+        depth: r - distance,
+        feature,
+    })
+}
+
+#[test]
+fn circle_convex_polygon_works() {
+    let square = [vec2(0., 0.), vec2(1., 0.), vec2(1., 1.), vec2(0., 1.)];
+    assert!(is_convex(&square));
+    assert!(is_anticlockwise(&square));
+    assert!(!is_convex(&[
+        vec2(0., 0.),
+        vec2(1., 0.),
+        vec2(0.2, 0.2),
+        vec2(0., 1.)
+    ]));
+
+    // resting into the top face
+    let manifold = circle_convex_polygon(vec2(0.5, 1.05), 0.1, &square).unwrap();
+    assert!((manifold.normal - vec2(0., 1.)).magnitude() < 1e-6);
+    assert!((manifold.depth - 0.05).abs() < 1e-6);
+    assert_eq!(manifold.feature, Feature::Edge(2));
+
+    // the same whichever way round the corners go
+    let mut reversed = square;
+    reversed.reverse();
+    assert_eq!(
+        circle_convex_polygon(vec2(0.5, 1.05), 0.1, &reversed),
+        Some(Manifold {
+            feature: Feature::Edge(0),
+            ..manifold
+        })
+    );
+
+    // centre inside, pushed out the nearest face
+    let manifold = circle_convex_polygon(vec2(0.9, 0.5), 0.1, &square).unwrap();
+    assert!((manifold.normal - vec2(1., 0.)).magnitude() < 1e-6);
+    assert!((manifold.depth - 0.2).abs() < 1e-6);
+    assert_eq!(manifold.feature, Feature::Inside);
+
+    // off a corner, diagonally
+    let manifold = circle_convex_polygon(vec2(1.05, 1.05), 0.1, &square).unwrap();
+    assert!((manifold.normal - vec2(1., 1.).normalize()).magnitude() < 1e-6);
+    // as near both edges meeting there, but the corner either way
+    assert_eq!(manifold.feature, Feature::Vertex(2));
+
+    assert_eq!(circle_convex_polygon(vec2(1.2, 0.5), 0.1, &square), None);
+}
+
+/// The shortest push separating two overlapping polygons
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mtv {
+    /// Unit length, pointing from the second polygon towards the first, so the way to push the
+    /// first out
+    pub axis: Vector2<f32>,
+    pub depth: f32,
+}
+
+/// How far and which way to push convex polygon `a` out of `b`, if they overlap. By the separating
+/// axis theorem, with the edge normals of both the only axes to try. Polygons that aren't convex,
+/// including those with every corner in a line, have no axes to speak of and never overlap.
+pub fn polygon_mtv(a: &[Vector2<f32>], b: &[Vector2<f32>]) -> Option<Mtv> {
+    if !is_convex(a) || !is_convex(b) {
+        return None;
+    }
+
+    let mut least: Option<Mtv> = None;
+    for points in [a, b] {
+        for (i, &start) in points.iter().enumerate() {
+            let edge = points[(i + 1) % points.len()] - start;
+            if edge.magnitude2() == 0. {
+                continue;
+            }
+            let axis = Vector2::new(edge.y, -edge.x).normalize();
+
+            let (a_min, a_max) = project(a, axis);
+            let (b_min, b_max) = project(b, axis);
+            let depth = a_max.min(b_max) - a_min.max(b_min);
+            // only touching is as good as apart
+            if depth <= 0. {
+                return None;
+            }
+            if least.is_none_or(|least| depth < least.depth) {
+                least = Some(Mtv { axis, depth });
+            }
+        }
+    }
+
+    least.map(|Mtv { axis, depth }| Mtv {
+        axis: if (centroid(a) - centroid(b)).dot(axis) < 0. {
+            -axis
+        } else {
+            axis
+        },
+        depth,
+    })
+}
+
+#[test]
+fn polygon_mtv_works() {
+    let rect =
+        |min: Vector2<f32>, max: Vector2<f32>| [min, vec2(max.x, min.y), max, vec2(min.x, max.y)];
+    let b = rect(vec2(0., 0.), vec2(1., 1.));
+
+    // overlapping least across x, so pushed out to the right
+    let a = rect(vec2(0.8, 0.2), vec2(1.8, 0.9));
+    let mtv = polygon_mtv(&a, &b).unwrap();
+    assert!((mtv.axis - vec2(1., 0.)).magnitude() < 1e-6);
+    assert!((mtv.depth - 0.2).abs() < 1e-6);
+    // and always from the second towards the first
+    let mtv = polygon_mtv(&b, &a).unwrap();
+    assert!((mtv.axis - vec2(-1., 0.)).magnitude() < 1e-6);
+    // whichever way round the corners go
+    let mut reversed = a;
+    reversed.reverse();
+    assert!((polygon_mtv(&reversed, &b).unwrap().axis - vec2(1., 0.)).magnitude() < 1e-6);
+
+    // overlapping on one axis alone isn't overlapping
+    assert_eq!(polygon_mtv(&rect(vec2(0.5, 1.5), vec2(1.5, 2.)), &b), None);
+    assert_eq!(polygon_mtv(&rect(vec2(1.5, 0.5), vec2(2.5, 0.6)), &b), None);
+    // nor is sharing an edge
+    assert_eq!(polygon_mtv(&rect(vec2(1., 0.), vec2(2., 1.)), &b), None);
+
+    // below the square, across a diagonal
+    let triangle = [vec2(0.5, -0.1), vec2(0.7, 0.1), vec2(0.3, 0.1)];
+    let mtv = polygon_mtv(&triangle, &b).unwrap();
+    assert!((mtv.axis - vec2(0., -1.)).magnitude() < 1e-6);
+    assert!((mtv.depth - 0.1).abs() < 1e-6);
+
+    // every corner in a line
+    let line = [vec2(0., 0.5), vec2(0.5, 0.5), vec2(1., 0.5)];
+    assert_eq!(polygon_mtv(&line, &b), None);
+    assert_eq!(polygon_mtv(&b, &line), None);
+}
+
+/// Anything the ball can collide with, so every kind of collider answers the same question
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Circle {
+        center: Vector2<f32>,
+        radius: f32,
+    },
+    Aabb {
+        min: Vector2<f32>,
+        max: Vector2<f32>,
+    },
+    Obb(Obb),
+    ConvexPolygon(Vec<Vector2<f32>>),
+    Segment {
+        a: Vector2<f32>,
+        b: Vector2<f32>,
+    },
+}
+
+impl Shape {
+    /// The overlap of a circle with the shape, if any, its normal always pointing out of the
+    /// shape towards the circle
+    pub fn intersect_circle(&self, c: Vector2<f32>, r: f32) -> Option<Manifold> {
+        match self {
+            Self::Circle { center, radius } => {
+                let offset = c - center;
+                let distance = offset.magnitude();
+                (distance < r + radius).then(|| Manifold {
+                    // either way will do from dead centre
+                    normal: if distance > 0. {
+                        offset / distance
+                    } else {
+                        Vector2::unit_y()
+                    },
+                    depth: r + radius - distance,
+                    feature: if distance < *radius {
+                        Feature::Inside
+                    } else {
+                        Feature::Edge(0)
+                    },
+                })
+            }
+            &Self::Aabb { min, max } => circle_aabb_manifold(c, r, min, max),
+            Self::Obb(obb) => {
+                let manifold =
+                    circle_aabb_manifold(obb.local(c), r, -obb.half_extents, obb.half_extents)?;
+                let [u, v] = obb.axes();
+                Some(Manifold {
+                    normal: u * manifold.normal.x + v * manifold.normal.y,
+                    ..manifold
+                })
+            }
+            Self::ConvexPolygon(points) => circle_convex_polygon(c, r, points),
+            &Self::Segment { a, b } => {
+                let (squared, point, feature) = nearest_on_outline(c, &[a, b]);
+                let distance = squared.sqrt();
+                // a circle centred on the segment is pushed off to its left
+                let normal = if distance > 0. {
+                    (c - point) / distance
+                } else {
+                    let along = (b - a).normalize();
+                    Vector2::new(-along.y, along.x)
+                };
+                (distance < r).then_some(Manifold {
+                    normal,
+                    depth: r - distance,
+                    feature: match feature {
+                        // going there and back, so the second edge is the first reversed
+                        Feature::Edge(_) => Feature::Edge(0),
+                        feature => feature,
+                    },
+                })
+            }
+        }
+    }
+
+    /// Whether `p` is inside the shape or on its outline. A segment has no inside, so contains
+    /// nothing.
+    pub fn contains_point(&self, p: Vector2<f32>) -> bool {
+        match self {
+            Self::Circle { center, radius } => (p - center).magnitude2() <= radius * radius,
+            Self::Aabb { min, max } => {
+                (min.x..=max.x).contains(&p.x) && (min.y..=max.y).contains(&p.y)
+            }
+            Self::Obb(obb) => obb.contains_point(p),
+            // on the same side of every edge going round, whichever way round that is
+            Self::ConvexPolygon(points) => {
+                let sides = points
+                    .iter()
+                    .zip(points.iter().cycle().skip(1))
+                    .map(|(&a, &b)| side(p, a, b));
+                let (mut left, mut right) = (false, false);
+                for side in sides {
+                    left |= side > 0.;
+                    right |= side < 0.;
+                }
+                points.len() >= 3 && !(left && right)
+            }
+            Self::Segment { .. } => false,
+        }
+    }
+}
+
+#[test]
+fn shapes_contain_what_they_cover() {
+    let square = vec![vec2(0., 0.), vec2(1., 0.), vec2(1., 1.), vec2(0., 1.)];
+    let clockwise: Vec<_> = square.iter().rev().copied().collect();
+    let shapes = [
+        Shape::Circle {
+            center: vec2(0.5, 0.5),
+            radius: 0.5,
+        },
+        Shape::Aabb {
+            min: vec2(0., 0.),
+            max: vec2(1., 1.),
+        },
+        Shape::Obb(Obb {
+            center: vec2(0.5, 0.5),
+            half_extents: vec2(0.5, 0.5),
+            rotation: 0.,
+        }),
+        Shape::ConvexPolygon(square),
+        Shape::ConvexPolygon(clockwise),
+    ];
+    for shape in &shapes {
+        assert!(shape.contains_point(vec2(0.5, 0.5)), "{shape:?}");
+        assert!(shape.contains_point(vec2(0.5, 0.99)), "{shape:?}");
+        assert!(!shape.contains_point(vec2(1.2, 0.5)), "{shape:?}");
+        assert!(!shape.contains_point(vec2(0.5, -0.01)), "{shape:?}");
+    }
+    let segment = Shape::Segment {
+        a: vec2(0., 0.),
+        b: vec2(1., 1.),
+    };
+    assert!(!segment.contains_point(vec2(0.5, 0.5)));
+}
+
+/// A circle's overlap with an axis-aligned rectangle, out of the face it's least deep past or
+/// straight out from the corner it's clipping
+fn circle_aabb_manifold(
+    c: Vector2<f32>,
+    r: f32,
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+) -> Option<Manifold> {
+    let hit = circle_intersects_aabb(c, r, min, max)?;
+    Some(match hit.feature {
+        Feature::Vertex(_) => {
+            let corner = Vector2::new(c.x.clamp(min.x, max.x), c.y.clamp(min.y, max.y));
+            let distance = (c - corner).magnitude();
+            Manifold {
+                normal: (c - corner) / distance,
+                depth: r - distance,
+                feature: hit.feature,
+            }
+        }
+        Feature::Edge(_) | Feature::Inside => Manifold {
+            normal: hit.normal(),
+            depth: hit.depth,
+            feature: hit.feature,
+        },
+    })
+}
+
+#[test]
+fn every_shape_points_its_normal_at_the_circle() {
+    let square = [vec2(0., 0.), vec2(1., 0.), vec2(1., 1.), vec2(0., 1.)];
+    let shapes = [
+        Shape::Circle {
+            center: vec2(0.5, 0.5),
+            radius: 0.5,
+        },
+        Shape::Aabb {
+            min: vec2(0., 0.),
+            max: vec2(1., 1.),
+        },
+        Shape::Obb(Obb {
+            center: vec2(0.5, 0.5),
+            half_extents: vec2(0.5, 0.5),
+            rotation: 0.,
+        }),
+        Shape::ConvexPolygon(square.to_vec()),
+        Shape::Segment {
+            a: vec2(0., 1.),
+            b: vec2(1., 1.),
+        },
+    ];
+
+    for shape in &shapes {
+        // resting into the top, from outside
+        let manifold = shape.intersect_circle(vec2(0.5, 1.05), 0.1).unwrap();
+        assert!(
+            (manifold.normal - vec2(0., 1.)).magnitude() < 1e-6,
+            "{shape:?}"
+        );
+        assert!((manifold.depth - 0.05).abs() < 1e-6, "{shape:?}");
+        assert_eq!(
+            manifold
+                .normal
+                .dot(vec2(0.5, 1.05) - vec2(0.5, 0.5))
+                .signum(),
+            1.,
+            "{shape:?}"
+        );
+
+        assert_eq!(
+            shape.intersect_circle(vec2(0.5, 1.2), 0.1),
+            None,
+            "{shape:?}"
+        );
+    }
+
+    // turned a quarter, the rectangle's normal turns back to the world's
+    let turned = Shape::Obb(Obb {
+        center: vec2(0.5, 0.5),
+        half_extents: vec2(0.5, 0.5),
+        rotation: std::f32::consts::FRAC_PI_2,
+    });
+    let manifold = turned.intersect_circle(vec2(1.05, 0.5), 0.1).unwrap();
+    assert!((manifold.normal - vec2(1., 0.)).magnitude() < 1e-6);
+
+    // clipping a corner pushes straight out from it, for rectangles as for polygons
+    for shape in &shapes[1..4] {
+        let manifold = shape.intersect_circle(vec2(1.05, 1.05), 0.1).unwrap();
+        assert!(
+            (manifold.normal - vec2(1., 1.).normalize()).magnitude() < 1e-6,
+            "{shape:?}"
+        );
+        assert_eq!(manifold.feature, Feature::Vertex(2), "{shape:?}");
+    }
+}
+
+/// When a swept circle first touches a shape
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Toi {
+    /// In the same units as the sweep's `dt`, 0 if they were touching from the start
+    pub time: f32,
+    /// Unit length, pointing out of the shape towards the circle as they touch
+    pub normal: Vector2<f32>,
+}
+
+/// The first time within `dt` that a circle moving at `c_vel` touches a convex polygon moving at
+/// `poly_vel`. Worked out in the polygon's frame, where only the circle moves, so two bodies whose
+/// paths cross at different times never meet. A circle already overlapping the polygon touches
+/// it at 0 if it's moving further in, and not at all if it's on its way out.
+pub fn swept_circle_vs_polygon(
+    c: Vector2<f32>,
+    r: f32,
+    c_vel: Vector2<f32>,
+    points: &[Vector2<f32>],
+    poly_vel: Vector2<f32>,
+    dt: f32,
+) -> Option<Toi> {
+    let motion = (c_vel - poly_vel) * dt;
+    if let Some(manifold) = circle_convex_polygon(c, r, points) {
+        return (motion.dot(manifold.normal) < 0.).then_some(Toi {
+            time: 0.,
+            normal: manifold.normal,
+        });
+    }
+    let length = motion.magnitude2();
+    if length == 0. {
+        return None;
+    }
+
+    // as the fraction of `motion` travelled, against the polygon grown by the radius: its edges
+    // pushed out flat and its corners rounded
+    let centroid = centroid(points);
+    let mut first: Option<(f32, Vector2<f32>)> = None;
+    let mut consider = |s: f32, normal: Vector2<f32>| {
+        if (0. ..=1.).contains(&s) && first.is_none_or(|(first, _)| s < first) {
+            first = Some((s, normal));
+        }
+    };
+    for (i, &a) in points.iter().enumerate() {
+        let edge = points[(i + 1) % points.len()] - a;
+        if edge.magnitude2() == 0. {
+            continue;
+        }
+        let mut outward = Vector2::new(edge.y, -edge.x).normalize();
+        if outward.dot(a - centroid) < 0. {
+            outward = -outward;
+        }
+
+        let into = motion.dot(outward);
+        if into < 0. {
+            let pushed = a + outward * r;
+            let s = (pushed - c).dot(outward) / into;
+            let along = (c + motion * s - pushed).dot(edge) / edge.magnitude2();
+            if (0. ..=1.).contains(&along) {
+                consider(s, outward);
+            }
+        }
+
+        // the nearer crossing of the circle of radius `r` round the corner
+        let offset = c - a;
+        let b = motion.dot(offset);
+        let discriminant = b * b - length * (offset.magnitude2() - r * r);
+        if discriminant >= 0. {
+            let s = (-b - discriminant.sqrt()) / length;
+            consider(s, (offset + motion * s) / r);
+        }
+    }
+
+    first.map(|(s, normal)| Toi {
+        time: s * dt,
+        normal,
+    })
+}
+
+#[test]
+fn swept_circle_vs_polygon_works() {
+    let square = [vec2(0., 0.), vec2(1., 0.), vec2(1., 1.), vec2(0., 1.)];
+    let still = vec2(0., 0.);
+
+    // the square sweeping over a ball in its way
+    let toi =
+        swept_circle_vs_polygon(vec2(2.5, 0.5), 0.25, still, &square, vec2(2., 0.), 1.).unwrap();
+    assert!((toi.time - 0.625).abs() < 1e-6);
+    assert!((toi.normal - vec2(1., 0.)).magnitude() < 1e-6);
+    // the same whether it's the ball or the square moving, and scaled by `dt`
+    let toi =
+        swept_circle_vs_polygon(vec2(2.5, 0.5), 0.25, vec2(-1., 0.), &square, still, 2.).unwrap();
+    assert!((toi.time - 1.25).abs() < 1e-6);
+
+    // meeting a corner, diagonally
+    let toi =
+        swept_circle_vs_polygon(vec2(2., 2.), 0.5, vec2(-1., -1.), &square, still, 1.).unwrap();
+    assert!((toi.normal - vec2(1., 1.).normalize()).magnitude() < 1e-6);
+    assert!((toi.time - (1. - 0.5 / 2f32.sqrt())).abs() < 1e-6);
+
+    // too far to reach within `dt`
+    assert_eq!(
+        swept_circle_vs_polygon(vec2(2.5, 0.5), 0.25, still, &square, vec2(2., 0.), 0.5),
+        None
+    );
+    // moving apart
+    assert_eq!(
+        swept_circle_vs_polygon(
+            vec2(2., 0.5),
+            0.25,
+            vec2(1., 0.),
+            &square,
+            vec2(-1., 0.),
+            1.
+        ),
+        None
+    );
+    // the ball crosses where the square ends up well before the square gets there
+    let far_left = square.map(|p| p - vec2(3., 0.));
+    assert_eq!(
+        swept_circle_vs_polygon(
+            vec2(0.5, 1.5),
+            0.1,
+            vec2(0., -3.),
+            &far_left,
+            vec2(3., 0.),
+            1.
+        ),
+        None
+    );
+
+    // already overlapping counts from the start, unless on the way out
+    let toi =
+        swept_circle_vs_polygon(vec2(1.1, 0.5), 0.25, vec2(-1., 0.), &square, still, 1.).unwrap();
+    assert_eq!(toi.time, 0.);
+    assert_eq!(
+        swept_circle_vs_polygon(vec2(1.1, 0.5), 0.25, vec2(1., 0.), &square, still, 1.),
+        None
+    );
+}
+
+/// Runs `count` cases, skipping as many again where the answer's too close to call
+#[cfg(test)]
+fn cases(count: u32) -> ProptestConfig {
+    ProptestConfig {
+        cases: count,
+        max_global_rejects: count,
+        ..ProptestConfig::default()
+    }
+}
+
+/// Points for the property tests, mostly small but some near-degenerate or far out
+#[cfg(test)]
+fn point() -> impl Strategy<Value = Vector2<f32>> {
+    let scale = prop_oneof![1 => Just(0.01f32), 2 => Just(1.), 1 => Just(1000.)];
+    (scale, -1f32..1., -1f32..1.).prop_map(|(scale, x, y)| vec2(x, y) * scale)
+}
+
+/// Barycentric coordinates of `p`, worked out in double precision as a reference
+#[cfg(test)]
+fn barycentric(
+    p: Vector2<f32>,
+    v1: Vector2<f32>,
+    v2: Vector2<f32>,
+    v3: Vector2<f32>,
+) -> Option<[f64; 3]> {
+    let [p, v1, v2, v3] = [p, v1, v2, v3].map(|v| v.cast::<f64>().unwrap());
+    let det = (v2.y - v3.y) * (v1.x - v3.x) + (v3.x - v2.x) * (v1.y - v3.y);
+    let scale = (v2 - v1).magnitude2().max((v3 - v1).magnitude2());
+    // too thin to say what's inside
+    if det.abs() < 1e-6 * scale {
+        return None;
+    }
+    let a = ((v2.y - v3.y) * (p.x - v3.x) + (v3.x - v2.x) * (p.y - v3.y)) / det;
+    let b = ((v3.y - v1.y) * (p.x - v3.x) + (v1.x - v3.x) * (p.y - v3.y)) / det;
+    Some([a, b, 1. - a - b])
+}
+
+/// The distance from `p` to the line through `a` and `b`, in double precision as a reference
+#[cfg(test)]
+fn line_distance(p: Vector2<f32>, a: Vector2<f32>, b: Vector2<f32>) -> f64 {
+    let [p, a, b] = [p, a, b].map(|v| v.cast::<f64>().unwrap());
+    let along = b - a;
+    let offset = p - a;
+    (along.x * offset.y - along.y * offset.x).abs() / along.magnitude().max(f64::MIN_POSITIVE)
+}
+
+/// The distance from `c` to the segment by sampling points along it, as a reference
+#[cfg(test)]
+fn sampled_distance(c: Vector2<f32>, a: Vector2<f32>, b: Vector2<f32>) -> f32 {
+    const SAMPLES: usize = 2_000;
+    (0..=SAMPLES)
+        .map(|i| (c - (a + (b - a) * (i as f32 / SAMPLES as f32))).magnitude())
+        .fold(f32::INFINITY, f32::min)
+}
+
+#[cfg(test)]
+proptest! {
+    #![proptest_config(cases(20_000))]
+
+    #[test]
+    fn triangle_contains_agrees_with_barycentric_coordinates(
+        [v1, v2, v3] in [point(), point(), point()],
+        // points from across the triangle and just around it
+        weights in [-0.5f32..1.5, -0.5f32..1.5, -0.5f32..1.5],
+    ) {
+        let sum = weights.iter().sum::<f32>();
+        let p = (v1 * weights[0] + v2 * weights[1] + v3 * weights[2]) / sum;
+
+        let coordinates = barycentric(p, v1, v2, v3);
+        prop_assume!(coordinates.is_some());
+        // too close to an edge for single precision to be sure of
+        let margin = coordinates.unwrap().iter().copied().fold(f64::INFINITY, f64::min);
+        prop_assume!(margin.abs() >= 1e-3);
+        // or to an edge's line by what rounding to the size of the coordinates could move it
+        let size = [p, v1, v2, v3].iter().map(|v| v.x.abs().max(v.y.abs())).fold(0., f32::max);
+        let nearest = [(v1, v2), (v2, v3), (v3, v1)]
+            .map(|(a, b)| line_distance(p, a, b))
+            .into_iter()
+            .fold(f64::INFINITY, f64::min);
+        prop_assume!(nearest >= size as f64 * 1e-4);
+
+        let expected = margin > 0.;
+        for [a, b, c] in [[v1, v2, v3], [v2, v3, v1], [v3, v1, v2], [v2, v1, v3]] {
+            prop_assert_eq!(
+                triangle_contains(p, a, b, c),
+                expected,
+                "{:?} in {:?}",
+                p,
+                [a, b, c]
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+proptest! {
+    #![proptest_config(cases(5_000))]
+
+    #[test]
+    fn circle_intersects_line_segment_agrees_with_sampling(
+        a in point(),
+        b in point(),
+        // every so often a segment that's only a point
+        point_only in proptest::bool::weighted(0.1),
+        along in -0.5f32..1.5,
+        off in point(),
+        scale in 0.5f32..1.5,
+    ) {
+        let b = if point_only { a } else { b };
+        let c = a + (b - a) * along + off * 0.1;
+        let distance = sampled_distance(c, a, b);
+        let r = distance * scale;
+
+        // sampling only finds the distance to within the gap between samples
+        let tolerance = (b - a).magnitude() / 1_000. + r * 1e-4;
+        prop_assume!((distance - r).abs() >= tolerance);
+
+        let expected = distance < r;
+        prop_assert_eq!(
+            circle_intersects_line_segment(c, r, a, b),
+            expected,
+            "circle at {:?}, radius {} against {:?} to {:?}",
+            c,
+            r,
+            a,
+            b
+        );
+        prop_assert_eq!(circle_intersects_line_segment(c, r, b, a), expected, "reversed");
+    }
+
+    #[test]
+    fn collisions_are_unchanged_by_moving_and_turning(
+        [v1, v2, v3] in [point(), point(), point()],
+        off in point(),
+        r in 0.01f32..1.,
+        angle in 0. ..std::f32::consts::TAU,
+        offset in (-10f32..10., -10f32..10.),
+    ) {
+        use cgmath::{Basis2, Rad, Rotation, Rotation2};
+
+        let c = (v1 + v2 + v3) / 3. + off;
+        let turn: Basis2<f32> = Rotation2::from_angle(Rad(angle));
+        let offset = vec2(offset.0, offset.1);
+        let moved = |v: Vector2<f32>| turn.rotate_vector(v) + offset;
+
+        // only where nothing is close to an edge, since moving rounds every coordinate, allowing
+        // for how far the sampled distance can miss between samples on a long edge
+        let near = |a: Vector2<f32>, b: Vector2<f32>| {
+            (sampled_distance(c, a, b) - r).abs() < 1e-2 + (b - a).magnitude() / 1_000.
+        };
+        prop_assume!(!(near(v1, v2) || near(v2, v3) || near(v3, v1)));
+        let coordinates = barycentric(c, v1, v2, v3);
+        prop_assume!(coordinates.is_some_and(|coordinates| {
+            coordinates.iter().all(|coordinate| coordinate.abs() >= 1e-2)
+        }));
+
+        prop_assert_eq!(
+            triangle_contains(moved(c), moved(v1), moved(v2), moved(v3)),
+            triangle_contains(c, v1, v2, v3)
+        );
+        prop_assert_eq!(
+            circle_intersects_triangle(moved(c), r, moved(v1), moved(v2), moved(v3)),
+            circle_intersects_triangle(c, r, v1, v2, v3)
+        );
+    }
+}
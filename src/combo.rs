@@ -0,0 +1,121 @@
+use crate::digits;
+use crate::renderer::buffer::MeshBuilder;
+use crate::renderer::shapes::SegmentCount;
+use cgmath::Vector2;
+use serde::{Deserialize, Serialize};
+
+/// Consecutive paddle hits without losing the ball, which multiply brick scores
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Combo {
+    hits: u32,
+}
+
+/// What happened during a tick that counts towards the combo
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ComboTick {
+    pub paddle_hit: bool,
+    pub bricks: u32,
+    pub ball_lost: bool,
+}
+
+impl Combo {
+    /// Hits needed for each doubling of the multiplier
+    const HITS_PER_STEP: u32 = 3;
+    const MAX_MULTIPLIER: u32 = 8;
+
+    pub fn multiplier(self) -> u32 {
+        2u32.saturating_pow(self.hits / Self::HITS_PER_STEP)
+            .min(Self::MAX_MULTIPLIER)
+    }
+
+    /// Hits made towards the next doubling, full once the multiplier is capped
+    fn progress(self) -> u32 {
+        if self.multiplier() == Self::MAX_MULTIPLIER {
+            Self::HITS_PER_STEP
+        } else {
+            self.hits % Self::HITS_PER_STEP
+        }
+    }
+
+    /// Scores the tick's bricks at the multiplier they were broken at, returning the points along
+    /// with the combo after the tick's hit or loss. Bricks broken in the tick the ball is lost
+    /// still score, and a loss cancels a hit in the same tick.
+    pub fn update(self, tick: ComboTick, brick_score: u32) -> (Self, u32) {
+        let points = tick.bricks * brick_score * self.multiplier();
+        let combo = if tick.ball_lost {
+            Self::default()
+        } else if tick.paddle_hit {
+            Self {
+                hits: self.hits + 1,
+            }
+        } else {
+            self
+        };
+
+        (combo, points)
+    }
+
+    /// Pushes the multiplier over a stack of pips, one lit per hit towards the next doubling
+    pub fn push(self, mesh: &mut MeshBuilder, bottom: Vector2<f32>) {
+        const SPACING: f32 = 0.06;
+
+        for i in 0..Self::HITS_PER_STEP {
+            let alpha = if i < self.progress() { 1. } else { 0.3 };
+            let center = bottom + Vector2::new(0., i as f32 * SPACING);
+            mesh.push_circle(center, 0.02, SegmentCount::Fixed(8), [1., 0.8, 0.2, alpha]);
+        }
+
+        let top = bottom + Vector2::new(0., Self::HITS_PER_STEP as f32 * SPACING + 0.04);
+        digits::push_digit(mesh, self.multiplier() as u8, top, 0.1, [1., 0.8, 0.2, 1.]);
+    }
+}
+
+#[test]
+fn multiplier_doubles_every_few_hits_up_to_the_cap() {
+    let hit = ComboTick {
+        paddle_hit: true,
+        ..ComboTick::default()
+    };
+
+    let mut combo = Combo::default();
+    let mut multipliers = vec![combo.multiplier()];
+    for _ in 0..15 {
+        combo = combo.update(hit, 5).0;
+        multipliers.push(combo.multiplier());
+    }
+
+    assert_eq!(
+        multipliers,
+        [1, 1, 1, 2, 2, 2, 4, 4, 4, 8, 8, 8, 8, 8, 8, 8]
+    );
+    assert_eq!(combo.progress(), Combo::HITS_PER_STEP);
+}
+
+#[test]
+fn bricks_score_at_the_multiplier_before_the_tick() {
+    let combo = Combo { hits: 5 };
+
+    // the hit that reaches 4x doesn't apply to the brick broken alongside it
+    let (after, points) = combo.update(
+        ComboTick {
+            paddle_hit: true,
+            bricks: 1,
+            ball_lost: false,
+        },
+        5,
+    );
+    assert_eq!(points, 10);
+    assert_eq!(after.multiplier(), 4);
+
+    // losing the ball as a brick breaks still scores the brick, then resets
+    let (after, points) = combo.update(
+        ComboTick {
+            paddle_hit: true,
+            bricks: 2,
+            ball_lost: true,
+        },
+        5,
+    );
+    assert_eq!(points, 20);
+    assert_eq!(after, Combo::default());
+}
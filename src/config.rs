@@ -0,0 +1,77 @@
+use crate::bindings::Bindings;
+use crate::difficulty::Preset;
+use crate::palette::PaletteName;
+use crate::renderer::post::{Crt, Tonemap};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Settings remembered between launches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// The difficulty selected on the menu when the game starts
+    pub difficulty: Preset,
+    /// The window's initial width in logical pixels, left to the platform if unset
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    /// Samples per pixel for antialiasing, either 1 or 4
+    pub msaa: u32,
+    /// Frames drawn per second, following the display's refresh rate if unset
+    pub target_fps: Option<u32>,
+    /// Pause when the window loses focus rather than playing on unseen
+    pub pause_on_focus_loss: bool,
+    /// Master volume for sound effects, from 0 to 1
+    pub volume: f32,
+    /// The colours the game is drawn in
+    pub palette: PaletteName,
+    /// Drawn like an old television, which the debug key turns on and off too
+    pub crt: bool,
+    /// How far the crt's screen bulges, from 0 to `Crt::MAX_DISTORTION`
+    pub crt_distortion: f32,
+    /// Let highlights past white through on displays that can show them, drawing as usual on
+    /// those that can't
+    pub hdr: bool,
+    /// How much brighter than usual the scene's drawn with hdr, up to `Tonemap::MAX_BRIGHTNESS`
+    pub hdr_brightness: f32,
+    /// The keys for each control, by position on the keyboard or by what they type
+    pub bindings: Bindings,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            difficulty: Preset::default(),
+            width: None,
+            height: None,
+            fullscreen: false,
+            vsync: true,
+            msaa: 1,
+            target_fps: None,
+            pause_on_focus_loss: true,
+            volume: 1.,
+            palette: PaletteName::default(),
+            crt: false,
+            crt_distortion: Crt::DEFAULT_DISTORTION,
+            hdr: false,
+            hdr_brightness: Tonemap::DEFAULT_BRIGHTNESS,
+            bindings: Bindings::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn default_path() -> Option<PathBuf> {
+        storage::config_path("config.json")
+    }
+
+    pub fn load(path: &Path) -> Self {
+        storage::load(path)
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        storage::save(path, self)
+    }
+}
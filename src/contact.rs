@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// A moving body, like a ball, and something it can touch, like a paddle, each numbered by the
+/// caller
+pub type Pair = (usize, usize);
+
+/// How a pair's overlap changed since the last tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Contact {
+    /// Touching now but not before, when bounces, sounds and the like happen
+    Enter,
+    /// Still touching
+    Stay,
+    /// Touching before but not now
+    Exit,
+}
+
+/// The pairs touching as of the last tick, turning each tick's overlaps into edges so something
+/// that stays in contact for several ticks only reacts once
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContactTracker {
+    touching: BTreeSet<Pair>,
+}
+
+impl ContactTracker {
+    /// Records whether the pair overlaps this tick, returning how that's changed, if it was or
+    /// is touching at all
+    pub fn update(&mut self, pair: Pair, overlapping: bool) -> Option<Contact> {
+        match (overlapping, self.touching.contains(&pair)) {
+            (true, false) => {
+                self.touching.insert(pair);
+                Some(Contact::Enter)
+            }
+            (true, true) => Some(Contact::Stay),
+            (false, true) => {
+                self.touching.remove(&pair);
+                Some(Contact::Exit)
+            }
+            (false, false) => None,
+        }
+    }
+
+    pub fn is_touching(&self, pair: Pair) -> bool {
+        self.touching.contains(&pair)
+    }
+
+    /// Forgets the body's contacts, as when it's gone, so it enters afresh if it comes back
+    pub fn forget_body(&mut self, body: usize) {
+        self.touching.retain(|&(touching, _)| touching != body);
+    }
+}
+
+#[test]
+fn overlaps_become_enter_stay_and_exit() {
+    let mut contacts = ContactTracker::default();
+    let pair = (0, 1);
+
+    assert_eq!(contacts.update(pair, false), None);
+    let ticks = [true, true, true, false, false, true]
+        .map(|overlapping| contacts.update(pair, overlapping));
+    assert_eq!(
+        ticks,
+        [
+            Some(Contact::Enter),
+            Some(Contact::Stay),
+            Some(Contact::Stay),
+            Some(Contact::Exit),
+            None,
+            Some(Contact::Enter),
+        ]
+    );
+
+    // pairs are kept apart
+    assert_eq!(contacts.update((0, 2), true), Some(Contact::Enter));
+    assert_eq!(contacts.update((1, 1), true), Some(Contact::Enter));
+    assert!(contacts.is_touching(pair));
+
+    // a body that's gone leaves nothing behind, so touches anew when it's back
+    contacts.forget_body(0);
+    assert!(!contacts.is_touching(pair) && !contacts.is_touching((0, 2)));
+    assert!(contacts.is_touching((1, 1)));
+    assert_eq!(contacts.update(pair, false), None);
+    assert_eq!(contacts.update(pair, true), Some(Contact::Enter));
+}
@@ -0,0 +1,70 @@
+use crate::renderer::buffer::MeshBuilder;
+use crate::TICK;
+use cgmath::Vector2;
+use serde::{Deserialize, Serialize};
+
+/// A quick lurch of a paddle the way it's moving, which has to recharge before the next
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Dash {
+    /// -1 or 1
+    direction: f32,
+    /// Ticks left of the current dash
+    remaining: u32,
+    /// Ticks until the next dash can start
+    cooldown: u32,
+}
+
+impl Dash {
+    /// Seconds
+    const DURATION: f32 = 0.15;
+    const COOLDOWN: f32 = 2.;
+    /// How many times its usual top speed the paddle moves at while dashing
+    pub const SPEED: f32 = 2.;
+
+    fn ticks(seconds: f32) -> u32 {
+        (seconds / TICK.as_secs_f32()).round() as u32
+    }
+
+    /// Starts a dash towards the sign of `velocity`, unless it's still recharging or the paddle
+    /// isn't moving. Returns whether it started.
+    pub fn start(&mut self, velocity: f32) -> bool {
+        if self.cooldown > 0 || velocity.abs() < f32::EPSILON {
+            return false;
+        }
+
+        self.direction = velocity.signum();
+        self.remaining = Self::ticks(Self::DURATION);
+        self.cooldown = Self::ticks(Self::COOLDOWN);
+        true
+    }
+
+    /// Runs down the dash and its cooldown by a tick
+    pub fn update(&mut self) {
+        self.remaining = self.remaining.saturating_sub(1);
+        self.cooldown = self.cooldown.saturating_sub(1);
+    }
+
+    /// The direction of the dash in progress, if any
+    pub fn direction(&self) -> Option<f32> {
+        (self.remaining > 0).then_some(self.direction)
+    }
+
+    /// Ends the dash early, for when the paddle reaches a wall
+    pub fn stop(&mut self) {
+        self.remaining = 0;
+    }
+
+    /// Pushes a bar `width` wide centred on `center`, filling up as the dash recharges
+    pub fn push(&self, mesh: &mut MeshBuilder, center: Vector2<f32>, width: f32) {
+        if self.cooldown == 0 {
+            return;
+        }
+
+        let half = Vector2::new(width / 2., 0.008);
+        let min = center - half;
+        mesh.push_rect(min, center + half, [1., 1., 1., 0.2]);
+        let filled = 1. - self.cooldown as f32 / Self::ticks(Self::COOLDOWN) as f32;
+        let max = Vector2::new(min.x + width * filled, center.y + half.y);
+        mesh.push_rect(min, max, [1., 1., 1., 0.7]);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::args::Args;
+use std::path::Path;
+use wgpu_fun::config::Config;
+
+/// The names of the fields the config file at `path` sets, none when it's missing or too corrupt
+/// to load, since the defaults are used then
+pub fn file_fields(path: &Path) -> Vec<String> {
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    if serde_json::from_str::<Config>(&contents).is_err() {
+        return Vec::new();
+    }
+    match serde_json::from_str(&contents) {
+        Ok(serde_json::Value::Object(fields)) => fields.into_iter().map(|(name, _)| name).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Each config value with where it came from, one per line, where `in_file` names the fields the
+/// file set, even to their defaults
+pub fn config_lines(file: &Config, in_file: &[String], args: &Args) -> Vec<String> {
+    let settings = match serde_json::to_value(args.apply(file.clone())) {
+        Ok(serde_json::Value::Object(fields)) => fields,
+        _ => unreachable!("the config serializes as a map"),
+    };
+
+    settings
+        .iter()
+        .map(|(name, value)| {
+            let source = if args.overrides(name) {
+                "command line"
+            } else if in_file.contains(name) {
+                "file"
+            } else {
+                "default"
+            };
+            format!("{name} = {value} ({source})")
+        })
+        .collect()
+}
+
+/// The window and multisampling asked for, before there's a window or surface to say what they
+/// came to
+pub fn display_lines(settings: &Config) -> [String; 2] {
+    let window = match (settings.width, settings.height) {
+        (Some(width), Some(height)) => format!("{width}x{height} logical pixels"),
+        _ => "sized by the platform".to_string(),
+    };
+    let fullscreen = if settings.fullscreen {
+        ", fullscreen"
+    } else {
+        ""
+    };
+    [
+        format!("window: {window}{fullscreen}"),
+        format!("msaa: {}x, if the surface supports it", settings.msaa),
+    ]
+}
+
+/// The adapter the renderer would pick, without needing a window for it
+pub fn adapter_line(require_hardware: bool) -> String {
+    match futures_lite::future::block_on(wgpu_fun::renderer::probe_adapter(require_hardware)) {
+        Ok(info) => format!("adapter: {}", wgpu_fun::renderer::describe_adapter(&info)),
+        Err(err) => format!("adapter: {err}"),
+    }
+}
+
+#[test]
+fn config_values_say_where_they_came_from() {
+    let file = Config {
+        vsync: false,
+        width: Some(640),
+        ..Config::default()
+    };
+    let args = Args::parse(["--width", "800"].map(String::from)).unwrap();
+
+    // the file sets msaa to what it'd be anyway
+    let in_file = ["vsync", "width", "msaa"].map(String::from);
+
+    let lines = config_lines(&file, &in_file, &args);
+    assert!(lines.contains(&"width = 800 (command line)".to_string()));
+    assert!(lines.contains(&"vsync = false (file)".to_string()));
+    assert!(lines.contains(&"msaa = 1 (file)".to_string()));
+    assert!(lines.contains(&"fullscreen = false (default)".to_string()));
+}
+
+#[test]
+fn only_fields_a_loadable_file_sets_are_from_it() {
+    let dir = std::env::temp_dir().join(format!("wgpu-fun-{}-diagnostics", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.json");
+
+    std::fs::write(&path, r#"{ "msaa": 1, "vsync": false }"#).unwrap();
+    let mut fields = file_fields(&path);
+    fields.sort();
+    assert_eq!(fields, ["msaa", "vsync"]);
+
+    std::fs::write(&path, r#"{ "msaa": "lots" }"#).unwrap();
+    assert!(file_fields(&path).is_empty());
+    assert!(file_fields(&dir.join("missing.json")).is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn display_lines_say_what_was_asked_for() {
+    let settings = Config {
+        width: Some(800),
+        height: Some(600),
+        msaa: 4,
+        ..Config::default()
+    };
+    assert_eq!(
+        display_lines(&settings),
+        [
+            "window: 800x600 logical pixels",
+            "msaa: 4x, if the surface supports it"
+        ]
+    );
+    assert_eq!(
+        display_lines(&Config::default())[0],
+        "window: sized by the platform"
+    );
+}
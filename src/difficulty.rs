@@ -0,0 +1,107 @@
+use crate::ai;
+use crate::modifiers::WidthModifier;
+use crate::tunables::Tunables;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Preset {
+    pub const ALL: [Self; 3] = [Self::Easy, Self::Normal, Self::Hard];
+
+    pub fn harder(self) -> Self {
+        match self {
+            Self::Easy => Self::Normal,
+            Self::Normal | Self::Hard => Self::Hard,
+        }
+    }
+
+    pub fn easier(self) -> Self {
+        match self {
+            Self::Easy | Self::Normal => Self::Easy,
+            Self::Hard => Self::Normal,
+        }
+    }
+
+    pub fn tunables(self) -> Tunables {
+        let normal = Tunables::default();
+        match self {
+            Self::Easy => Tunables {
+                gravity: normal.gravity * 0.7,
+                max_speed: 0.08,
+                paddle_width: 0.55,
+                power_up_chance: 0.2,
+                ..normal
+            },
+            Self::Normal => normal,
+            // narrowed by its width modifier instead
+            Self::Hard => Tunables {
+                gravity: normal.gravity * 1.3,
+                max_speed: 0.12,
+                power_up_chance: 0.1,
+                ..normal
+            },
+        }
+    }
+
+    /// Put on each paddle for the whole of a game at this preset
+    pub fn width_modifier(self) -> Option<WidthModifier> {
+        match self {
+            Self::Easy | Self::Normal => None,
+            Self::Hard => Some(WidthModifier {
+                factor: 0.8,
+                remaining: None,
+            }),
+        }
+    }
+
+    /// How wide paddles are at this preset, before any power-ups
+    pub fn paddle_width(self) -> f32 {
+        let width = self.tunables().paddle_width;
+        self.width_modifier()
+            .map_or(width, |modifier| width * modifier.factor)
+    }
+
+    pub fn ai(self) -> ai::Difficulty {
+        match self {
+            Self::Easy => ai::Difficulty {
+                reaction_delay: 25,
+                max_speed: 0.45,
+                prediction_noise: 0.3,
+            },
+            Self::Normal => ai::Difficulty::default(),
+            Self::Hard => ai::Difficulty {
+                reaction_delay: 8,
+                max_speed: 0.8,
+                prediction_noise: 0.05,
+            },
+        }
+    }
+}
+
+#[test]
+fn presets_get_harder() {
+    let [easy, normal, hard] = Preset::ALL.map(Preset::tunables);
+    let [easy_gravity, normal_gravity, hard_gravity] =
+        [&easy, &normal, &hard].map(|tunables| cgmath::InnerSpace::magnitude(tunables.gravity));
+    assert!(easy_gravity < normal_gravity && normal_gravity < hard_gravity);
+    assert!(easy.max_speed < normal.max_speed && normal.max_speed < hard.max_speed);
+    let [easy_width, normal_width, hard_width] = Preset::ALL.map(Preset::paddle_width);
+    assert!(easy_width > normal_width && normal_width > hard_width);
+    assert_eq!(easy.paddle_width, easy_width);
+    assert!(easy.power_up_chance > normal.power_up_chance);
+    assert!(normal.power_up_chance > hard.power_up_chance);
+
+    let [easy, normal, hard] = Preset::ALL.map(Preset::ai);
+    assert!(easy.reaction_delay > normal.reaction_delay);
+    assert!(normal.reaction_delay > hard.reaction_delay);
+
+    assert_eq!(Preset::Easy.easier(), Preset::Easy);
+    assert_eq!(Preset::Easy.harder().harder().harder(), Preset::Hard);
+}
@@ -0,0 +1,75 @@
+use crate::renderer::buffer::MeshBuilder;
+use cgmath::Vector2;
+
+/// Segments lit for each digit, in the order top, top right, bottom right, bottom, bottom left,
+/// top left, middle
+const SEGMENTS: [[bool; 7]; 10] = [
+    [true, true, true, true, true, true, false],
+    [false, true, true, false, false, false, false],
+    [true, true, false, true, true, false, true],
+    [true, true, true, true, false, false, true],
+    [false, true, true, false, false, true, true],
+    [true, false, true, true, false, true, true],
+    [true, false, true, true, true, true, true],
+    [true, true, true, false, false, false, false],
+    [true, true, true, true, true, true, true],
+    [true, true, true, true, false, true, true],
+];
+
+/// Pushes a seven-segment digit, `height` tall and half as wide, centred on `center`
+pub fn push_digit(
+    mesh: &mut MeshBuilder,
+    digit: u8,
+    center: Vector2<f32>,
+    height: f32,
+    color: [f32; 4],
+) {
+    let (w, h) = (height / 4., height / 2.);
+    let t = height / 10.;
+
+    // each segment as a min and max corner relative to the centre
+    let rects = [
+        ([-w, h - t], [w, h]),
+        ([w - t, 0.], [w, h]),
+        ([w - t, -h], [w, 0.]),
+        ([-w, -h], [w, -h + t]),
+        ([-w, -h], [-w + t, 0.]),
+        ([-w, 0.], [-w + t, h]),
+        ([-w, -t / 2.], [w, t / 2.]),
+    ];
+
+    for (lit, (min, max)) in SEGMENTS[digit as usize % 10].iter().zip(rects) {
+        if *lit {
+            mesh.push_rect(
+                center + Vector2::from(min),
+                center + Vector2::from(max),
+                color,
+            );
+        }
+    }
+}
+
+/// Pushes a whole number right aligned to `right`, each digit `height` tall
+pub fn push_number(
+    mesh: &mut MeshBuilder,
+    number: u32,
+    right: Vector2<f32>,
+    height: f32,
+    color: [f32; 4],
+) {
+    let width = height * 0.7;
+    for (i, digit) in number.to_string().bytes().rev().enumerate() {
+        let center = right - Vector2::new(width * (i as f32 + 0.5), 0.);
+        push_digit(mesh, digit - b'0', center, height, color);
+    }
+}
+
+#[test]
+fn digits_light_the_right_number_of_segments() {
+    let counts = [6, 2, 5, 5, 4, 5, 6, 3, 7, 6];
+    for (digit, count) in counts.into_iter().enumerate() {
+        let mut mesh = MeshBuilder::default();
+        push_digit(&mut mesh, digit as u8, Vector2::new(0., 0.), 1., [1.; 4]);
+        assert_eq!(mesh.vertex_count(), count * 4);
+    }
+}
@@ -0,0 +1,2087 @@
+use crate::ai::Ai;
+use crate::audio::{AudioHandle, Sfx};
+use crate::bricks::Bricks;
+use crate::camera::{Bounds, Camera};
+use crate::collison::Shape;
+use crate::combo::{Combo, ComboTick};
+use crate::config::Config;
+use crate::contact::ContactTracker;
+use crate::difficulty::Preset;
+use crate::ghost::Ghost;
+use crate::highscore::HighScoreTables;
+use crate::instrument::Counts;
+use crate::interpolation::{Frame, Pose};
+use crate::level::{Ceiling, Level};
+use crate::magnet::Magnet;
+use crate::menu::{self, Mode};
+use crate::modifiers::WidthModifier;
+use crate::obstacles::Obstacles;
+use crate::palette::Role;
+use crate::particles::ParticleSystem;
+use crate::picking::{EntityId, PickSet};
+use crate::portals::Portals;
+use crate::powerups::{PowerUpKind, PowerUps};
+use crate::renderer::background::{BackgroundKind, STARFIELD};
+use crate::renderer::buffer::{Mesh, MeshBuilder, ParticleInstance};
+use crate::renderer::debug::GIZMO_THICKNESS;
+use crate::renderer::shapes::LineStyle;
+use crate::renderer::RenderReport;
+use crate::scheduler::FALLBACK_REFRESH_RATE;
+use crate::shield::Shield;
+use crate::speedrun::Speedrun;
+use crate::stats::SessionStats;
+use crate::trail::Trail;
+use crate::tunables::Tunables;
+use crate::tween::Tween;
+use crate::zones::ForceZone;
+use crate::{
+    colliders, complete_level, digits, share, tick_ball, window_title, Action, Ball, BallState,
+    ButtonState, Controls, InputEvent, LoseZone, Paddle, Phase, Player, Serve, BALL, BRICK_SCORE,
+    COPY_FLASH, COUNTDOWN, DESPAWN, FLASH, HARD_HIT_SPEED, TICK, WALL_X, WINNING_SCORE,
+};
+use bugreport::Capture;
+use cgmath::prelude::*;
+use cgmath::Vector2;
+use crossbeam::channel::{Receiver, TryRecvError};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How much wider the wide power-up makes the player's paddle
+const WIDE_FACTOR: f32 = 1.5;
+
+pub mod bugreport;
+pub mod save;
+
+/// Assumed until the window says how tall it is
+const FALLBACK_SURFACE_HEIGHT: u32 = 600;
+
+/// Everything the game thread simulates, advanced one tick at a time
+pub struct Game {
+    lose_zone: LoseZone,
+    goal_zone: LoseZone,
+    paddles: [Paddle; 2],
+    /// Which paddles the ball is touching
+    contacts: ContactTracker,
+    ai: Ai,
+    pub(crate) ball: Ball,
+
+    phase: Phase,
+    mode: Mode,
+    server: Player,
+    serve: Serve,
+    /// Flipped every serve so they alternate sides
+    serve_bias: f32,
+    controls: [Controls; 2],
+    /// Player one's
+    magnet: Magnet,
+
+    /// The preset selected on the menu
+    preset: Preset,
+    /// The preset the current game is played at
+    active_preset: Preset,
+    tunables: Tunables,
+    trail: Trail,
+    particles: ParticleSystem,
+    ghost: Ghost,
+    speedrun: Speedrun,
+
+    scores: [u32; 2],
+    combo: Combo,
+    bricks: Bricks,
+    obstacles: Obstacles,
+    portals: Portals,
+    power_ups: PowerUps,
+    /// Up while the shield power-up runs, until it's used
+    shield: Option<Shield>,
+    /// The level's gravity, before any power-up turns it around
+    gravity: Vector2<f32>,
+    /// The levels played in order, the last repeating once they run out
+    pub levels: Vec<Level>,
+    /// Counting from 1
+    level: usize,
+    /// Whether the level came from a file dropped onto the window, naming it in the title
+    dropped_level: bool,
+    level_ticks: u32,
+    /// Seconds until the ball moves again
+    countdown: f32,
+    /// The ball shrinking away while `BallState::Despawning`
+    despawn: Tween,
+    lose_flash: Tween,
+    goal_flash: Tween,
+    /// Flashing the screen's edges after the result is copied
+    copy_flash: Tween,
+    /// The result line being copied, and where whether it was will arrive
+    copying: Option<(String, Receiver<Result<(), arboard::Error>>)>,
+    /// Flashing the lose zone red after a file dropped onto the window turned out not to be a level
+    reject_flash: Tween,
+
+    config: Config,
+    pub config_path: Option<PathBuf>,
+    high_scores: HighScoreTables,
+    pub high_scores_path: Option<PathBuf>,
+    stats: SessionStats,
+    /// Where a file of stats is written at the end of each game
+    pub stats_dir: Option<PathBuf>,
+    /// Where a game in progress is saved when the game is closed
+    pub save_path: Option<PathBuf>,
+    /// Whether the game carried on from the save, which is only cleared out once that's over
+    resumed: bool,
+    /// Where bug reports are written when asked for
+    pub bug_report_dir: Option<PathBuf>,
+    /// The last stretch of play, for bug reports
+    capture: Capture,
+
+    pub audio: AudioHandle,
+    /// Kept apart from `rng` so sounds don't change how the game plays
+    sfx_rng: StdRng,
+    /// The same generator as `StdRng`, but one that can be saved
+    rng: ChaCha12Rng,
+    /// What `rng` was seeded with
+    seed: u64,
+    /// Ticks since the game began
+    pub ticks: u64,
+    /// Ticks run per step, below 1 for slow motion
+    time_scale: f32,
+    /// Ticks left of the freeze after a hard hit, which pass without anything moving
+    hitstop_ticks_remaining: u32,
+    /// Ticks owed by the steps so far
+    accumulator: f32,
+    pub camera: Camera,
+    pub title: String,
+    /// Whether to draw the physics debug overlay
+    debug: bool,
+    /// Where the ball last touched something, for the debug overlay
+    contact: Option<Vector2<f32>>,
+    /// What the cursor can pick out, only filled each tick while debugging
+    picks: PickSet,
+    /// Where in the world the cursor last pointed
+    cursor: Option<Vector2<f32>>,
+    /// Ticks per second the game is really being run at, for the debug overlay
+    pub tick_rate: f32,
+    /// The refresh rate of the display the window is on, for the debug overlay
+    pub display_rate: f32,
+    /// How many pixels tall the window's surface is, for drawing circles as finely as they show
+    pub surface_height: u32,
+    /// What the game thread's last loop did, for the debug overlay
+    pub tick_counts: Counts,
+    /// What the last frame drawn did, for the debug overlay
+    pub frame_counts: Counts,
+    /// What the last frame drawn drew, for the debug overlay
+    pub frame_report: RenderReport,
+    /// Draw particles as quads in the mesh rather than as instances, for comparing the two
+    pub mesh_particles: bool,
+    /// The phase to go back to if quitting is called off
+    quit_from: Phase,
+    /// Whether quitting has been asked for, and confirmed if it needed to be
+    quitting: bool,
+}
+
+impl Game {
+    pub fn new(config: Config, high_scores: HighScoreTables, seed: u64) -> Self {
+        let mut rng = ChaCha12Rng::seed_from_u64(seed);
+        let paddles = [Paddle::new(1.), Paddle::new(-1.)];
+        let ball = Ball::default();
+        let preset = config.difficulty;
+
+        let mut game = Self {
+            lose_zone: LoseZone { side: -1. },
+            goal_zone: LoseZone { side: 1. },
+            ai: Ai::new(preset.ai()),
+            ball: Ball {
+                position: paddles[0].attach_point(ball.radius, 0., WALL_X),
+                ..ball
+            },
+            paddles,
+            contacts: ContactTracker::default(),
+
+            phase: Phase::Menu,
+            mode: Mode::OnePlayer,
+            server: Player::One,
+            serve: Serve::default(),
+            serve_bias: 1.,
+            controls: [Controls::RELEASED; 2],
+            magnet: Magnet::default(),
+
+            preset,
+            active_preset: preset,
+            tunables: preset.tunables(),
+            trail: Trail::new(30, 0.5),
+            particles: ParticleSystem::new(),
+            ghost: Ghost::default(),
+            speedrun: Speedrun::default(),
+
+            scores: [0; 2],
+            combo: Combo::default(),
+            bricks: Bricks::default(),
+            obstacles: Obstacles::default(),
+            portals: Portals::default(),
+            power_ups: PowerUps::default(),
+            shield: None,
+            gravity: Tunables::default().gravity,
+            levels: Level::bundled(),
+            level: 1,
+            dropped_level: false,
+            level_ticks: 0,
+            countdown: 0.,
+            despawn: Tween::default(),
+            lose_flash: Tween::default(),
+            goal_flash: Tween::default(),
+            reject_flash: Tween::default(),
+            copy_flash: Tween::default(),
+            copying: None,
+
+            title: window_title(&high_scores, preset, Mode::OnePlayer, [0, 0]),
+            config,
+            config_path: None,
+            high_scores,
+            high_scores_path: None,
+            stats: SessionStats::new(seed, preset, Mode::OnePlayer),
+            stats_dir: None,
+            save_path: None,
+            resumed: false,
+            bug_report_dir: None,
+            capture: Capture::default(),
+
+            camera: Camera::new(Bounds::around_walls(WALL_X), rng.gen()),
+            audio: AudioHandle::default(),
+            sfx_rng: StdRng::seed_from_u64(seed),
+            rng,
+            seed,
+            ticks: 0,
+            time_scale: 1.,
+            hitstop_ticks_remaining: 0,
+            accumulator: 0.,
+            debug: false,
+            quit_from: Phase::Menu,
+            quitting: false,
+            contact: None,
+            picks: PickSet::default(),
+            cursor: None,
+            tick_rate: 1. / TICK.as_secs_f32(),
+            display_rate: FALLBACK_REFRESH_RATE,
+            surface_height: FALLBACK_SURFACE_HEIGHT,
+            tick_counts: Counts::default(),
+            frame_counts: Counts::default(),
+            frame_report: RenderReport::default(),
+            mesh_particles: false,
+        };
+        game.restart_capture();
+        game
+    }
+
+    /// Plays `sfx` slightly detuned so repeated hits don't sound identical
+    fn play(&mut self, sfx: Sfx) {
+        let pitch = self.sfx_rng.gen_range(0.95..1.05);
+        self.audio.play(sfx, pitch);
+    }
+
+    fn update_title(&mut self, preset: Preset) {
+        self.title = window_title(&self.high_scores, preset, self.mode, self.scores);
+        if self.dropped_level {
+            self.title = format!("{} - {}", self.title, self.current_level().name);
+        }
+    }
+
+    /// Holds the ball on the serving paddle until it is served again
+    fn reset_ball(&mut self) {
+        self.ball.state = BallState::Attached;
+        self.contacts.forget_body(BALL);
+        self.portals.reset();
+        self.serve_bias = -self.serve_bias;
+        self.serve = Serve::random(&mut self.rng, self.serve_bias);
+        self.serve.offset += self.current_level().serve_offset;
+        self.countdown = COUNTDOWN;
+        self.trail.clear();
+        self.ai.clear();
+    }
+
+    fn current_level(&self) -> &Level {
+        &self.levels[(self.level - 1).min(self.levels.len() - 1)]
+    }
+
+    /// Sets up the playfield for the current level
+    fn start_level(&mut self) {
+        let level = self.current_level();
+        let strength = self.active_preset.tunables().gravity.magnitude();
+        let gravity = Vector2::from(level.gravity) * strength;
+        let wall_x = level.wall_x;
+        let ball_radius = level.ball_radius;
+        let bricks = match self.mode {
+            Mode::OnePlayer | Mode::Speedrun => Bricks::from_level(level),
+            Mode::TwoPlayer | Mode::Practice => Bricks::default(),
+        };
+        let obstacles = Obstacles::from_level(level);
+        let portals = Portals::from_level(level);
+        let force_zones = ForceZone::from_level(level);
+
+        self.gravity = gravity;
+        self.tunables.gravity = gravity;
+        self.tunables.wall_x = wall_x;
+        self.tunables.force_zones = force_zones;
+        self.magnet.refill();
+        self.ball.resize(ball_radius, &self.paddles, wall_x);
+        self.camera.set_bounds(Bounds::around_walls(wall_x));
+        self.bricks = bricks;
+        self.obstacles = obstacles;
+        self.portals = portals;
+        self.power_ups.clear();
+        self.shield = None;
+        for paddle in &mut self.paddles {
+            paddle.modifiers.end_timed();
+        }
+        self.level_ticks = 0;
+        self.speedrun.reset();
+        self.reset_ball();
+    }
+
+    /// Starts a game of the selected mode and preset from the first level
+    fn start_game(&mut self) {
+        self.phase = Phase::Playing;
+        self.apply_preset();
+
+        self.level = 1;
+        self.server = Player::One;
+        self.scores = [0, 0];
+        self.combo = Combo::default();
+        self.ghost.clear();
+        self.stats = SessionStats::new(self.seed, self.active_preset, self.mode);
+        self.start_level();
+        self.update_title(self.active_preset);
+    }
+
+    /// Plays the level in a file dropped onto the window straight away, abandoning any game in
+    /// progress. A file that isn't a level only flashes the lose zone, leaving everything as it was.
+    pub fn load_level_file(&mut self, path: &Path) {
+        let level = match Level::load(path) {
+            Ok(level) => level,
+            Err(err) => {
+                log::warn!("failed to load level {}: {err}", path.display());
+                self.reject_flash = Tween::new(FLASH);
+                return;
+            }
+        };
+        log::info!("loaded level {:?} from {}", level.name, path.display());
+
+        if matches!(
+            self.settled_phase(),
+            Phase::Playing | Phase::Paused | Phase::Victory
+        ) {
+            self.end_session();
+        }
+        self.levels = vec![level];
+        self.dropped_level = true;
+        // nothing in flight on the old level carries over, the rest going with `start_level`
+        self.particles.clear();
+        self.hitstop_ticks_remaining = 0;
+        self.contact = None;
+        self.start_game();
+        self.restart_capture();
+    }
+
+    /// Swaps in the whole selected preset at once, before anything uses it
+    fn apply_preset(&mut self) {
+        self.active_preset = self.preset;
+        self.tunables = self.preset.tunables();
+        self.ai = Ai::new(self.preset.ai());
+        for paddle in &mut self.paddles {
+            paddle.modifiers.clear();
+            if let Some(modifier) = self.preset.width_modifier() {
+                paddle.modifiers.add(modifier);
+            }
+            paddle.width = paddle.modifiers.apply(self.tunables.paddle_width);
+        }
+
+        if self.config.difficulty != self.preset {
+            self.config.difficulty = self.preset;
+            self.save_config();
+        }
+    }
+
+    fn save_config(&self) {
+        if let Some(path) = &self.config_path {
+            if let Err(err) = self.config.save(path) {
+                log::warn!("failed to save config: {err}");
+            }
+        }
+    }
+
+    pub fn handle(&mut self, event: impl Into<InputEvent>) {
+        let event = event.into().action;
+        if event.changes_play() {
+            self.capture_event(event);
+        }
+        if self.phase == Phase::ConfirmQuit && event != Action::Quit && event.is_press() {
+            self.phase = self.quit_from;
+            if self.phase == Phase::Playing {
+                self.countdown = COUNTDOWN;
+            }
+            // held controls still count as held, but nothing else happens for the key that
+            // called quitting off
+            if !matches!(
+                event,
+                Action::Left(..) | Action::Right(..) | Action::Magnet(_)
+            ) {
+                return;
+            }
+        }
+        match (self.phase, event) {
+            (Phase::Menu, Action::Left(Player::One, ButtonState::Pressed)) => {
+                self.mode = self.mode.previous();
+                self.update_title(self.preset);
+            }
+            (Phase::Menu, Action::Right(Player::One, ButtonState::Pressed)) => {
+                self.mode = self.mode.next();
+                self.update_title(self.preset);
+            }
+            (phase, Action::FocusLost) => {
+                self.controls = [Controls::RELEASED; 2];
+                if phase == Phase::Playing && self.config.pause_on_focus_loss {
+                    self.phase = Phase::Paused;
+                }
+            }
+            (_, Action::ToggleDebug) => self.debug = !self.debug,
+            // only the colours change, and the next frame is drawn in them
+            (_, Action::NextPalette) => {
+                self.config.palette = self.config.palette.next();
+                self.save_config();
+            }
+            (_, Action::ReportBug) => self.report_bug(),
+            (_, Action::PointAt(point)) => self.cursor = Some(point),
+            (_, Action::Pick) if self.debug => self.log_pick(),
+            (_, Action::Pick) => {}
+            (_, Action::CopyResult) => self.copy_result(),
+            // there's nothing to lose on the menu or once a game has ended
+            (Phase::Menu | Phase::GameOver | Phase::ConfirmQuit, Action::Quit) => {
+                self.quitting = true;
+            }
+            (phase, Action::Quit) => {
+                self.quit_from = phase;
+                self.phase = Phase::ConfirmQuit;
+            }
+            (_, Action::SetTimeScale(scale)) => self.time_scale = scale.max(0.),
+            (_, Action::Left(player, state)) => self.controls[player.index()].left.update(state),
+            (_, Action::Right(player, state)) => {
+                self.controls[player.index()].right.update(state);
+            }
+            (_, Action::PaddleAxis(player, axis)) => {
+                self.controls[player.index()].axis = axis.clamp(-1., 1.);
+            }
+            (_, Action::Magnet(state)) => self.controls[0].magnet = state,
+            (Phase::Playing, Action::Dash) => {
+                self.paddles[0].dash();
+            }
+            (_, Action::Dash) => {}
+            (Phase::Menu, Action::Harder | Action::Easier) => {
+                self.preset = match event {
+                    Action::Harder => self.preset.harder(),
+                    _ => self.preset.easier(),
+                };
+                self.update_title(self.preset);
+            }
+            // the active game keeps the parameters it started with
+            (_, Action::Harder | Action::Easier) => {}
+            (Phase::Menu, Action::Serve) => self.start_game(),
+            (Phase::Playing, Action::Serve) if self.countdown > 0. => {
+                self.countdown = 0.;
+            }
+            (Phase::Playing, Action::Serve) => {
+                if self.ball.state == BallState::Attached {
+                    if self.mode == Mode::Speedrun {
+                        self.speedrun.start();
+                    }
+                    self.ball.state = BallState::Free;
+                    self.ball.velocity =
+                        self.paddles[self.server.index()].serve_velocity(self.serve.angle);
+                }
+            }
+            (Phase::GameOver, Action::Serve) => {
+                self.phase = Phase::Menu;
+            }
+            // practice and speedruns never end, so are left from the pause screen
+            (Phase::Paused, Action::Serve)
+                if matches!(self.mode, Mode::Practice | Mode::Speedrun) =>
+            {
+                self.end_session();
+                self.phase = Phase::Menu;
+                self.ghost.clear();
+                self.update_title(self.preset);
+            }
+            (Phase::Paused, Action::Serve) => {}
+            // called off before getting here
+            (Phase::ConfirmQuit, Action::Serve) => {}
+            (Phase::Playing, Action::Pause) => {
+                self.phase = Phase::Paused;
+            }
+            (Phase::Paused, Action::Pause) => {
+                self.phase = Phase::Playing;
+                self.countdown = COUNTDOWN;
+            }
+            (_, Action::Pause) => {}
+            (Phase::Victory, Action::Serve) => {
+                self.phase = Phase::Playing;
+                self.level += 1;
+                self.start_level();
+                self.update_title(self.active_preset);
+            }
+        }
+    }
+
+    /// Called once per loop of the game thread, running whole ticks as the time scale allows. Slow
+    /// motion spreads the same ticks out rather than shortening them, so it plays out identically.
+    pub fn step(&mut self) {
+        self.update(TICK);
+    }
+
+    /// Runs as many whole ticks as `dt` of time covers at the time scale, carrying what's left of
+    /// a tick over to the next call
+    pub fn update(&mut self, dt: Duration) {
+        self.accumulator += dt.as_secs_f32() / TICK.as_secs_f32() * self.time_scale;
+        while self.accumulator >= 1. {
+            self.accumulator -= 1.;
+            self.tick();
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+        self.advance();
+        self.capture_tick();
+        if self.debug {
+            self.fill_picks();
+        }
+    }
+
+    /// Registers each entity the cursor can pick out where it is now
+    fn fill_picks(&mut self) {
+        let picks = &mut self.picks;
+        picks.clear();
+        for (i, shape) in self.obstacles.shapes().enumerate() {
+            picks.add(EntityId::Obstacle(i), shape);
+        }
+        for (i, shape) in self.bricks.shapes().enumerate() {
+            picks.add(EntityId::Brick(i), shape);
+        }
+        for (i, paddle) in self.paddles.iter().enumerate() {
+            picks.add(EntityId::Paddle(i), paddle.shape());
+        }
+        picks.add(
+            EntityId::Ball,
+            Shape::Circle {
+                center: self.ball.position,
+                radius: self.ball.radius,
+            },
+        );
+    }
+
+    /// What's under the cursor, as of the last tick while debugging
+    fn hovered(&self) -> Option<EntityId> {
+        self.picks.query(self.cursor?)
+    }
+
+    /// Logs the fields of whatever's under the cursor
+    fn log_pick(&self) {
+        let Some(id) = self.hovered() else {
+            return;
+        };
+        let fields = match id {
+            EntityId::Ball => format!("{:?}", self.ball),
+            EntityId::Paddle(i) => format!("{:?}", self.paddles[i]),
+            EntityId::Brick(i) => format!("{:?}", self.bricks.get(i)),
+            EntityId::Obstacle(_) => format!("{:?}", self.picks.shape(id)),
+        };
+        log::info!("picked {id:?}: {fields}");
+    }
+
+    /// Everything a tick does besides counting and capturing it
+    fn advance(&mut self) {
+        // the frozen ticks are spent rather than made up afterwards, with only the camera moving
+        // so its shake plays through the freeze
+        if self.hitstop_ticks_remaining > 0 {
+            self.hitstop_ticks_remaining -= 1;
+            self.camera.update(TICK.as_secs_f32());
+            return;
+        }
+
+        if !matches!(self.phase, Phase::Paused | Phase::ConfirmQuit) {
+            // outside two player games, player two's keys steer the player's paddle too
+            let controls = match self.mode {
+                Mode::TwoPlayer => self.controls[0],
+                _ => self.controls[0].combine(&self.controls[1]),
+            };
+            self.paddles[0].steer(&controls);
+            match self.mode {
+                Mode::OnePlayer | Mode::Practice | Mode::Speedrun => self.ai.update(
+                    &mut self.paddles[1],
+                    &self.ball,
+                    &self.tunables,
+                    &mut self.rng,
+                ),
+                Mode::TwoPlayer => self.paddles[1].steer(&self.controls[1]),
+            }
+            self.obstacles.update(TICK.as_secs_f32());
+            self.portals.update(TICK.as_secs_f32());
+            for paddle in &mut self.paddles {
+                paddle.advance(self.tunables.wall_x);
+                paddle.resize(self.tunables.paddle_width, &self.ball);
+            }
+
+            // only a ball in play is pulled, not one waiting to be served
+            let held = self.phase == Phase::Playing
+                && self.ball.state == BallState::Free
+                && self.controls[0].magnet.is_pressed();
+            self.magnet.update(held, TICK.as_secs_f32());
+            let paddle = &self.paddles[0];
+            self.tunables.attractor = self.magnet.attractor(Vector2::new(paddle.x, paddle.y()));
+
+            for kind in self.power_ups.update(TICK.as_secs_f32(), &self.paddles[0]) {
+                self.stats.power_ups.push(kind.name().to_owned());
+                match kind {
+                    PowerUpKind::Shield => {
+                        self.shield = Some(Shield::new(self.tunables.wall_x));
+                    }
+                    PowerUpKind::Wide => self.paddles[0].modifiers.restart(WidthModifier {
+                        factor: WIDE_FACTOR,
+                        remaining: Some(kind.duration()),
+                    }),
+                    PowerUpKind::GravityFlip => {}
+                }
+            }
+            if !self.power_ups.is_active(PowerUpKind::Shield) {
+                self.shield = None;
+            }
+            let flip = if self.power_ups.is_active(PowerUpKind::GravityFlip) {
+                -1.
+            } else {
+                1.
+            };
+            self.tunables.gravity = self.gravity * flip;
+        }
+
+        // counted in ticks rather than from the clock, and only while playing
+        if self.phase == Phase::Playing {
+            self.speedrun.tick();
+            self.stats.ticks += 1;
+        }
+
+        if self.phase == Phase::Playing && self.countdown > 0. {
+            self.countdown -= TICK.as_secs_f32();
+        }
+
+        match (self.phase, self.ball.state) {
+            (_, BallState::Attached) => {
+                self.ball.position = self.paddles[self.server.index()].attach_point(
+                    self.ball.radius,
+                    self.serve.offset,
+                    self.tunables.wall_x,
+                );
+                self.ball.velocity = Vector2::zero();
+                self.ball.spin = 0.;
+            }
+            (Phase::Playing, BallState::Free) if self.countdown <= 0. => self.tick_free_ball(),
+            (Phase::Playing, BallState::Despawning(scorer)) => {
+                self.despawn.update(TICK.as_secs_f32());
+                if self.despawn.is_finished() {
+                    self.score(scorer);
+                }
+            }
+            (_, BallState::Free | BallState::Despawning(_)) => {}
+        }
+
+        self.particles.update(TICK.as_secs_f32());
+        self.lose_flash.update(TICK.as_secs_f32());
+        self.goal_flash.update(TICK.as_secs_f32());
+        self.reject_flash.update(TICK.as_secs_f32());
+        self.copy_flash.update(TICK.as_secs_f32());
+        self.poll_copy();
+
+        self.camera.follow(self.paddles[0].x);
+        self.camera.frame(
+            self.ball.velocity.magnitude() / self.tunables.max_speed,
+            [self.ball.position],
+        );
+        self.camera.update(TICK.as_secs_f32());
+    }
+
+    fn tick_free_ball(&mut self) {
+        self.level_ticks += 1;
+
+        let from = self.ball.position;
+        let tick = tick_ball(
+            &mut self.ball,
+            &mut self.paddles,
+            &mut self.contacts,
+            &mut self.bricks,
+            &self.obstacles,
+            &mut self.portals,
+            &self.tunables,
+            None,
+        );
+
+        // with several balls, the first to reach it would use it up. Swept from where the ball
+        // started the tick, unless it went through a portal and didn't get here by moving.
+        let (ball, tunables) = (&mut self.ball, &self.tunables);
+        let from = if tick.teleported.is_some() {
+            ball.position
+        } else {
+            from
+        };
+        if let Some(shield) = self
+            .shield
+            .take_if(|shield| shield.collide(ball, from, tunables))
+        {
+            self.play(Sfx::WallBounce);
+            for point in shield.points(12) {
+                let count = self.rng.gen_range(5..=10);
+                self.particles
+                    .spawn_burst(point, Vector2::unit_y(), count, &mut self.rng);
+            }
+            self.power_ups.end(PowerUpKind::Shield);
+        }
+
+        let speed = self.ball.velocity.magnitude() / TICK.as_secs_f32();
+        self.stats.max_ball_speed = self.stats.max_ball_speed.max(speed);
+
+        if let Some(i) = tick.paddle_hit {
+            self.stats.paddle_hits += 1;
+            if self.mode == Mode::OnePlayer && i == 0 {
+                self.scores[0] += 1;
+            }
+
+            let speed = self.ball.velocity.magnitude();
+            if speed > HARD_HIT_SPEED {
+                self.camera.add_trauma(0.4);
+            }
+            if speed > self.tunables.hitstop_speed && !self.camera.reduced_motion {
+                self.hitstop_ticks_remaining = self.tunables.hitstop_ticks;
+            }
+
+            self.play(Sfx::PaddleHit);
+            let normal = self.paddles[i].normal();
+            let contact = self.ball.position - normal * self.ball.radius;
+            self.contact = Some(contact);
+            let count = self.rng.gen_range(10..=20);
+            self.particles
+                .spawn_burst(contact, normal, count, &mut self.rng);
+        }
+
+        if let Some(side) = tick.wall_hit {
+            self.play(Sfx::WallBounce);
+            self.contact = Some(self.ball.position + Vector2::new(side, 0.) * self.ball.radius);
+            let count = self.rng.gen_range(10..=20);
+            self.particles.spawn_burst(
+                self.ball.position,
+                [-side, 0.].into(),
+                count,
+                &mut self.rng,
+            );
+        }
+        if let Some(exit) = tick.teleported {
+            // so the trail doesn't streak across between the portals
+            self.trail.clear();
+            let count = self.rng.gen_range(10..=20);
+            let direction = self.ball.velocity.normalize();
+            self.particles
+                .spawn_burst(exit, direction, count, &mut self.rng);
+        }
+        if let Some(contact) = tick.obstacle_hit {
+            self.play(Sfx::WallBounce);
+            self.contact = Some(contact);
+            let count = self.rng.gen_range(10..=20);
+            let normal = (self.ball.position - contact).normalize();
+            self.particles
+                .spawn_burst(contact, normal, count, &mut self.rng);
+        }
+        for hit in &tick.brick_hits {
+            if hit.destroyed {
+                self.stats.bricks_destroyed += 1;
+                self.power_ups
+                    .maybe_drop(hit.position, &self.tunables, &mut self.rng);
+                self.play(Sfx::BrickBreak);
+            }
+            if hit.row_cleared {
+                self.speedrun.split();
+            }
+            self.contact = Some(hit.position);
+            let count = self.rng.gen_range(10..=20);
+            self.particles
+                .spawn_burst(hit.position, -hit.normal, count, &mut self.rng);
+        }
+
+        // the top zone can lose the ball too, though never the second player's
+        let ceiling_loses =
+            self.mode != Mode::TwoPlayer && self.current_level().ceiling == Ceiling::Lose;
+        let ball_lost =
+            self.lose_zone.holds(&self.ball) || (ceiling_loses && self.goal_zone.holds(&self.ball));
+
+        let (combo, points) = self.combo.update(
+            ComboTick {
+                paddle_hit: tick.paddle_hit == Some(0),
+                bricks: tick.brick_hits.iter().filter(|hit| hit.destroyed).count() as u32,
+                ball_lost,
+            },
+            BRICK_SCORE,
+        );
+        if combo.multiplier() > self.combo.multiplier() {
+            self.camera.add_zoom(0.05);
+        }
+        self.combo = combo;
+        self.scores[0] += points;
+
+        self.trail.record(self.ball.position);
+        if self.mode == Mode::Practice {
+            self.ghost.record(self.ball.position);
+        }
+
+        if matches!(self.mode, Mode::OnePlayer | Mode::Speedrun)
+            && complete_level(&mut self.phase, &self.bricks)
+        {
+            for i in 0..8 {
+                let x = self.paddles[0].x + (i as f32 - 3.5) * 0.25;
+                self.particles.spawn_burst(
+                    Vector2::new(x, -0.5),
+                    Vector2::unit_y(),
+                    60,
+                    &mut self.rng,
+                );
+            }
+
+            if self.mode == Mode::Speedrun {
+                self.finish_speedrun();
+            } else {
+                let seconds = self.level_ticks as f32 * TICK.as_secs_f32();
+                self.title = format!(
+                    "WGPU fun - {} cleared! score: {}, time: {seconds:.1}s",
+                    self.current_level().name,
+                    self.scores[0]
+                );
+            }
+        }
+
+        let scorer = if self.goal_zone.holds(&self.ball) {
+            self.goal_flash = Tween::new(FLASH);
+            if ceiling_loses {
+                self.play(Sfx::Lose);
+                Player::Two
+            } else {
+                Player::One
+            }
+        } else if self.lose_zone.holds(&self.ball) {
+            self.lose_flash = Tween::new(FLASH);
+            self.play(Sfx::Lose);
+            Player::Two
+        } else {
+            return;
+        };
+
+        // the point is scored once the ball has gone
+        self.ball.state = BallState::Despawning(scorer);
+        self.despawn = Tween::new(DESPAWN);
+    }
+
+    /// Stops the timer on a cleared level, saving the time if it's the best yet
+    fn finish_speedrun(&mut self) {
+        let Some(ticks) = self.speedrun.finish() else {
+            return;
+        };
+        let name = self.current_level().name.clone();
+        let best_is_new = self
+            .high_scores
+            .insert_time(self.active_preset, &name, ticks);
+        if best_is_new {
+            if let Some(path) = &self.high_scores_path {
+                if let Err(err) = self.high_scores.save(path) {
+                    log::warn!("failed to save high scores: {err}");
+                }
+            }
+        }
+
+        let seconds = self.speedrun.seconds();
+        self.title = match self.high_scores.best_time(self.active_preset, &name) {
+            Some(best) if !best_is_new => {
+                let best = best as f32 * TICK.as_secs_f32();
+                format!("WGPU fun - {name} cleared in {seconds:.2}s, best: {best:.2}s")
+            }
+            _ => format!("WGPU fun - {name} cleared in {seconds:.2}s, a new best!"),
+        };
+    }
+
+    /// Whether a game has ended and is waiting to go back to the menu
+    /// Whether the physics debug overlay is showing, the only time the cursor matters
+    pub fn is_debugging(&self) -> bool {
+        self.debug
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.phase == Phase::GameOver
+    }
+
+    /// Whether the player has asked to quit, and confirmed it if a game was in progress
+    pub fn wants_to_quit(&self) -> bool {
+        self.quitting
+    }
+
+    /// The phase, or the one quitting would go back to while it's being confirmed
+    fn settled_phase(&self) -> Phase {
+        match self.phase {
+            Phase::ConfirmQuit => self.quit_from,
+            phase => phase,
+        }
+    }
+
+    /// Copies the seed, score and time of the game being played, or the last one played, to the
+    /// clipboard. Without a clipboard the line is only logged, to be copied from there.
+    fn copy_result(&mut self) {
+        let line = share::result_line(
+            &self.stats.mode,
+            self.seed,
+            self.scores[0],
+            self.stats.ticks,
+        );
+        let copied = share::copy_to_clipboard(line.clone());
+        self.copying = Some((line, copied));
+    }
+
+    /// Flashes once the result's been copied, or logs it for copying by hand if it couldn't be
+    fn poll_copy(&mut self) {
+        let Some((line, copied)) = &self.copying else {
+            return;
+        };
+        match copied.try_recv() {
+            Ok(Ok(())) => {
+                log::info!("copied to the clipboard: {line}");
+                self.copy_flash = Tween::new(COPY_FLASH);
+            }
+            Ok(Err(err)) => log::warn!("failed to copy to the clipboard ({err}): {line}"),
+            Err(TryRecvError::Disconnected) => {
+                log::warn!("failed to copy to the clipboard: {line}")
+            }
+            Err(TryRecvError::Empty) => return,
+        }
+        self.copying = None;
+    }
+
+    /// Player one's score then player two's
+    pub fn scores(&self) -> [u32; 2] {
+        self.scores
+    }
+
+    /// Writes out anything left unsaved when the game is closed, saving a game in progress to
+    /// be resumed or clearing out the save of one resumed from it that's since ended
+    pub fn shutdown(&mut self) {
+        self.phase = self.settled_phase();
+        if !matches!(self.phase, Phase::Playing | Phase::Paused) {
+            if let Some(path) = self.save_path.as_ref().filter(|_| self.resumed) {
+                match std::fs::remove_file(path) {
+                    Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                        log::warn!("failed to remove the old save: {err}");
+                    }
+                    _ => {}
+                }
+            }
+            return;
+        }
+
+        // a saved session carries on when it's resumed, so its stats are written once it ends
+        let saved = self.save_path.as_deref().is_some_and(|path| {
+            self.save(path)
+                .inspect_err(|err| log::warn!("failed to save the game: {err}"))
+                .is_ok()
+        });
+        if !saved {
+            self.end_session();
+        }
+    }
+
+    /// Writes out the stats of the game just ended
+    fn end_session(&mut self) {
+        self.stats.score = self.scores[0];
+        if let Some(dir) = &self.stats_dir {
+            if let Err(err) = self.stats.save(dir) {
+                log::warn!("failed to save stats to {}: {err}", dir.display());
+            }
+        }
+    }
+
+    fn score(&mut self, scorer: Player) {
+        if scorer == Player::Two {
+            self.stats.lives_used += 1;
+        }
+
+        match (self.mode, scorer) {
+            (Mode::OnePlayer, Player::One) => {
+                // past the ai, so serve again
+                self.scores[0] += 1;
+                self.reset_ball();
+            }
+            (Mode::OnePlayer, Player::Two) => {
+                self.phase = Phase::GameOver;
+                self.end_session();
+
+                if self
+                    .high_scores
+                    .get_mut(self.active_preset)
+                    .insert(self.scores[0])
+                    .is_some()
+                {
+                    if let Some(path) = &self.high_scores_path {
+                        if let Err(err) = self.high_scores.save(path) {
+                            log::warn!("failed to save high scores: {err}");
+                        }
+                    }
+                    self.update_title(self.active_preset);
+                }
+
+                self.camera.add_trauma(0.6);
+            }
+            (Mode::Practice, Player::One) => {
+                self.ghost.restart();
+                self.reset_ball();
+            }
+            (Mode::Practice, Player::Two) => {
+                self.ghost.finish();
+                self.reset_ball();
+            }
+            // losing the ball only costs time
+            (Mode::Speedrun, _) => self.reset_ball(),
+            (Mode::TwoPlayer, player) => {
+                self.scores[player.index()] += 1;
+                self.update_title(self.active_preset);
+                self.camera.add_trauma(0.6);
+
+                if self.scores[player.index()] >= WINNING_SCORE {
+                    self.phase = Phase::GameOver;
+                    self.end_session();
+                } else {
+                    // serves alternate between the players
+                    self.server = self.server.other();
+                    self.reset_ball();
+                }
+            }
+        }
+    }
+
+    pub fn mesh(&self) -> MeshBuilder {
+        self.frame().mesh
+    }
+
+    /// Frames the camera for a surface `aspect` times as wide as it's tall
+    pub fn set_surface_aspect(&mut self, aspect: f32) {
+        self.camera.set_aspect(aspect);
+    }
+
+    /// Where the ball, paddles and camera are now
+    pub fn pose(&self) -> Pose {
+        Pose {
+            ball: self.ball.position,
+            paddles: self.paddles.each_ref().map(|paddle| paddle.x),
+            camera_x: self.camera.position(),
+            zoom: self.camera.zoom(),
+        }
+    }
+
+    /// The mesh along with which vertices draw the parts that move between ticks
+    pub fn frame(&self) -> Frame {
+        self.frame_with(Mesh::builder(), Vec::new())
+    }
+
+    /// Like `frame`, but built over `mesh` and `particles` to reuse their capacity
+    pub fn frame_with(&self, mut mesh: MeshBuilder, mut particles: Vec<ParticleInstance>) -> Frame {
+        mesh.clear();
+        particles.clear();
+        mesh.set_screen_scale(self.camera.zoom(), self.surface_height);
+        let palette = self.config.palette.palette();
+        let camera_x = self.camera.position();
+        // a rejected level file shows over the ball's own flash
+        let (lose_flash, lose_color) = if self.reject_flash.is_finished() {
+            (self.lose_flash, LoseZone::FLASH_COLOR)
+        } else {
+            (self.reject_flash, palette.rgba(Role::RejectFlash, 1.))
+        };
+        self.lose_zone
+            .push(&mut mesh, 1. - lose_flash.progress(), lose_color, palette);
+        self.goal_zone.push(
+            &mut mesh,
+            1. - self.goal_flash.progress(),
+            LoseZone::FLASH_COLOR,
+            palette,
+        );
+        let paddles = self.paddles.each_ref().map(|paddle| {
+            let start = mesh.vertex_count();
+            paddle.push(&mut mesh, palette);
+            start..mesh.vertex_count()
+        });
+        if self.phase == Phase::Playing {
+            let paddle = &self.paddles[0];
+            self.magnet
+                .push(&mut mesh, Vector2::new(paddle.x, paddle.y()), paddle.facing);
+        }
+        self.trail.push(&mut mesh, self.ball.radius, [1., 1., 1.]);
+        if self.phase == Phase::Playing && self.ball.state == BallState::Attached {
+            let aim = Ball {
+                velocity: self.paddles[self.server.index()].serve_velocity(self.serve.angle),
+                ..self.ball.clone()
+            };
+            // marching out from the paddle towards where the ball will go
+            let style = LineStyle::dashed(0.04, 0.04).with_phase(-(self.ticks as f32) * 0.002);
+            mesh.push_dashed_polyline(&aim.predict(40, &self.tunables), 0.015, style, |t| {
+                [1., 1., 1., 0.6 * (1. - t)]
+            });
+        }
+        let size = match self.ball.state {
+            BallState::Despawning(_) => 1. - self.despawn.progress(),
+            BallState::Attached | BallState::Free => 1.,
+        };
+        if self.ball.state == BallState::Free {
+            self.ghost.push(&mut mesh, self.ball.radius, Ball::SEGMENTS);
+        }
+        let start = mesh.vertex_count();
+        self.ball.push(&mut mesh, size, palette);
+        let ball = start..mesh.vertex_count();
+        if self.mesh_particles {
+            self.particles.push(&mut mesh);
+        } else {
+            self.particles.render_instances(&mut particles);
+        }
+        self.bricks.push(&mut mesh, palette);
+        self.power_ups.push(&mut mesh, palette);
+        if let Some(shield) = &self.shield {
+            shield.push(&mut mesh, palette);
+        }
+        self.obstacles.push(&mut mesh);
+        self.portals.push(&mut mesh, palette);
+        let time = self.level_ticks as f32 * TICK.as_secs_f32();
+        for zone in &self.tunables.force_zones {
+            zone.push(&mut mesh, time);
+        }
+        if self.mode == Mode::OnePlayer && self.phase == Phase::Playing {
+            let paddle = &self.paddles[0];
+            let bottom = Vector2::new(paddle.x + paddle.width / 2. + 0.1, paddle.y());
+            self.combo.push(&mut mesh, bottom);
+        }
+
+        // menus, numbers and the rest of the hud go over the particles
+        let hud = mesh.index_count();
+        let center_x = self.paddles[0].x;
+        match self.phase {
+            Phase::Menu => menu::push(
+                &mut mesh,
+                center_x,
+                self.mode,
+                self.preset,
+                self.config.palette,
+            ),
+            Phase::GameOver => menu::push_game_over(&mut mesh, center_x),
+            Phase::Victory => menu::push_victory(&mut mesh, center_x),
+            Phase::Paused => menu::push_paused(&mut mesh, center_x),
+            Phase::ConfirmQuit => menu::push_confirm_quit(&mut mesh, center_x),
+            Phase::Playing if self.countdown > 0. => {
+                // each number shrinks away over its third of the countdown
+                let third = COUNTDOWN / 3.;
+                let number = (self.countdown / third).ceil();
+                let fraction = 1. - (number - self.countdown / third);
+                digits::push_digit(
+                    &mut mesh,
+                    number as u8,
+                    Vector2::new(center_x, 0.2),
+                    0.2 + 0.2 * fraction,
+                    [1., 1., 1., 0.5 + 0.5 * fraction],
+                );
+            }
+            Phase::Playing => {}
+        }
+        if self.mode == Mode::Speedrun && self.phase != Phase::Menu {
+            self.speedrun
+                .push(&mut mesh, Vector2::new(center_x + 0.95, 0.95));
+        }
+        if self.time_scale < 1. {
+            menu::push_slow_motion(&mut mesh, center_x);
+        }
+        if !self.copy_flash.is_finished() {
+            let alpha = 1. - self.copy_flash.progress();
+            mesh.push_rect_outline(
+                Vector2::new(camera_x - 1., -1.),
+                Vector2::new(camera_x + 1., 1.),
+                0.03,
+                [1., 1., 1., alpha],
+            );
+        }
+        if self.debug {
+            self.debug_draw(&mut mesh, center_x);
+        }
+
+        Frame {
+            mesh,
+            hud,
+            particles,
+            background: BackgroundKind::Procedural(STARFIELD, self.current_level().background),
+            pose: self.pose(),
+            ball,
+            paddles,
+        }
+    }
+
+    /// Everything the ball can touch, each as the shape it collides as
+    fn colliders(&self) -> Vec<Shape> {
+        [self.lose_zone.shape(), self.goal_zone.shape()]
+            .into_iter()
+            .chain(colliders(&self.paddles, self.tunables.wall_x).map(|(_, shape)| shape))
+            .chain(self.bricks.shapes())
+            .chain(self.obstacles.shapes())
+            .collect()
+    }
+
+    /// Draws colliders, velocities and predictions over the scene for tuning the physics
+    fn debug_draw(&self, mesh: &mut MeshBuilder, center_x: f32) {
+        const THICKNESS: f32 = GIZMO_THICKNESS;
+        const OUTLINE: [f32; 4] = [0., 1., 0., 1.];
+        const ARROW: [f32; 4] = [1., 0., 1., 1.];
+
+        for collider in self.colliders() {
+            mesh.push_shape_outline(&collider, THICKNESS, OUTLINE);
+        }
+        for paddle in &self.paddles {
+            let center = Vector2::new(paddle.x, paddle.y());
+            mesh.push_arrow(
+                center,
+                center + paddle.normal() * 0.3,
+                THICKNESS,
+                THICKNESS * 3.,
+                ARROW,
+            );
+        }
+        mesh.push_outline(&self.ball.outline(), THICKNESS, OUTLINE);
+
+        // velocity is per tick, so scaled up to be visible
+        let position = self.ball.position;
+        mesh.push_arrow(
+            position,
+            position + self.ball.velocity * 5.,
+            THICKNESS,
+            THICKNESS * 3.,
+            ARROW,
+        );
+        mesh.push_polyline(
+            &self.ball.predict(60, &self.tunables),
+            THICKNESS / 2.,
+            [1., 1., 0., 0.8],
+        );
+        if let Some(contact) = self.contact {
+            mesh.push_cross(contact, 0.04, [1., 0., 0., 1.]);
+        }
+        if let Some(shape) = self.hovered().and_then(|id| self.picks.shape(id)) {
+            mesh.push_shape_outline(shape, THICKNESS * 3., [1., 1., 1., 1.]);
+        }
+
+        digits::push_number(
+            mesh,
+            self.tick_rate.round() as u32,
+            Vector2::new(center_x - 0.6, 0.9),
+            0.08,
+            OUTLINE,
+        );
+        digits::push_number(
+            mesh,
+            self.display_rate.round() as u32,
+            Vector2::new(center_x - 0.6, 0.78),
+            0.08,
+            OUTLINE,
+        );
+        // allocations, then reallocations and buffers made again, then pipelines and bind
+        // groups made, for ticks then frames
+        let counts = [self.tick_counts, self.frame_counts];
+        let rows = [
+            counts.map(|counts| counts.allocations),
+            counts.map(|counts| counts.builder_reallocations + counts.buffer_recreations),
+            counts.map(|counts| counts.cache_misses),
+        ];
+        for (row, numbers) in rows.iter().enumerate() {
+            for (column, number) in numbers.iter().enumerate() {
+                digits::push_number(
+                    mesh,
+                    *number as u32,
+                    Vector2::new(
+                        center_x - 0.6 + column as f32 * 0.3,
+                        0.66 - row as f32 * 0.12,
+                    ),
+                    0.08,
+                    OUTLINE,
+                );
+            }
+        }
+        // draw calls, indices drawn, kilobytes uploaded and microseconds spent encoding
+        let report = self.frame_report;
+        let numbers = [
+            report.draw_calls,
+            report.indices,
+            (report.bytes_uploaded / 1024) as u32,
+            report.encode_time.as_micros() as u32,
+        ];
+        for (column, number) in numbers.into_iter().enumerate() {
+            digits::push_number(
+                mesh,
+                number,
+                Vector2::new(
+                    center_x - 0.6 + column as f32 * 0.3,
+                    0.66 - rows.len() as f32 * 0.12,
+                ),
+                0.08,
+                OUTLINE,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+fn trajectory(seed: u64) -> Vec<Vector2<f32>> {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), seed);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.tick();
+    game.handle(Action::Serve);
+
+    (0..300)
+        .map(|_| {
+            game.tick();
+            game.ball.position
+        })
+        .collect()
+}
+
+#[test]
+fn same_seed_plays_the_same() {
+    assert_eq!(trajectory(3), trajectory(3));
+    assert_ne!(trajectory(3), trajectory(4));
+}
+
+#[test]
+fn palettes_swap_while_playing_and_come_back_round() {
+    use crate::palette::PaletteName;
+
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.handle(Action::Serve);
+    let before = game.mesh().checksum();
+
+    game.handle(Action::NextPalette);
+    assert_eq!(game.config.palette, PaletteName::Deuteranopia);
+    assert_ne!(game.mesh().checksum(), before);
+
+    game.handle(Action::NextPalette);
+    game.handle(Action::NextPalette);
+    assert_eq!(game.config.palette, PaletteName::Default);
+    assert_eq!(game.mesh().checksum(), before);
+}
+
+#[test]
+fn the_menu_shows_which_palette_is_picked() {
+    use crate::palette::PaletteName;
+
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    assert_eq!(game.phase, Phase::Menu);
+    let mut seen = vec![game.mesh().checksum()];
+    for _ in 1..PaletteName::ALL.len() {
+        game.handle(Action::NextPalette);
+        seen.push(game.mesh().checksum());
+    }
+    seen.sort_unstable();
+    seen.dedup();
+    assert_eq!(seen.len(), PaletteName::ALL.len());
+}
+
+#[test]
+fn default_scene_renders_unchanged() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    // with the particles in the mesh, so they're checked too
+    game.mesh_particles = true;
+    let mut checksums = vec![game.mesh().checksum()];
+
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.handle(Action::Right(Player::One, ButtonState::Pressed));
+    for _ in 0..20 {
+        game.tick();
+    }
+    checksums.push(game.mesh().checksum());
+
+    game.handle(Action::Serve);
+    for _ in 0..20 {
+        game.tick();
+    }
+    checksums.push(game.mesh().checksum());
+
+    // only update these when the scene is meant to look different
+    assert_eq!(
+        checksums,
+        [0xa5f7e654cbbe8144, 0xd10a5f376a997acf, 0x90691c8b6c75c627]
+    );
+}
+
+#[test]
+fn slow_motion_plays_the_same_ticks() {
+    let mut slow = Game::new(Config::default(), HighScoreTables::default(), 3);
+    slow.handle(Action::SetTimeScale(0.25));
+    slow.handle(Action::Serve);
+    slow.handle(Action::Serve);
+    for _ in 0..4 {
+        slow.step();
+    }
+    slow.handle(Action::Serve);
+    for _ in 0..1200 {
+        slow.step();
+    }
+
+    assert_eq!(slow.ticks, 301);
+    assert_eq!(slow.ball.position, trajectory(3)[299]);
+}
+
+#[test]
+fn updates_run_the_ticks_their_time_covers() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 3);
+    game.update(TICK * 5 / 2);
+    assert_eq!(game.ticks, 2);
+    // the half tick left over is made up by the next
+    game.update(TICK / 2);
+    assert_eq!(game.ticks, 3);
+
+    game.handle(Action::SetTimeScale(0.5));
+    game.update(TICK * 4);
+    assert_eq!(game.ticks, 5);
+    game.update(Duration::ZERO);
+    assert_eq!(game.ticks, 5);
+}
+
+#[test]
+fn practice_restarts_with_a_ghost_of_the_lost_rally() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.handle(Action::Right(Player::One, ButtonState::Pressed));
+    game.handle(Action::Right(Player::One, ButtonState::Pressed));
+    assert_eq!(game.mode, Mode::Practice);
+    game.handle(Action::Right(Player::One, ButtonState::Released));
+    // starting, skipping the countdown, then serving
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+
+    // lost off to the side, away from the paddle
+    game.ball.position = Vector2::new(1., -0.85);
+    game.ball.velocity = Vector2::new(0., -0.02);
+    while game.ball.state != BallState::Attached {
+        game.tick();
+    }
+    assert_eq!(game.phase, Phase::Playing);
+
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.tick();
+    let ghost = game.ghost.position().unwrap();
+    assert!(ghost.x > 0.9 && ghost.y < -0.85);
+
+    // leaving from the pause screen drops the ghost
+    game.handle(Action::Pause);
+    game.handle(Action::Serve);
+    assert_eq!(game.phase, Phase::Menu);
+    assert_eq!(game.ghost.position(), None);
+}
+
+#[test]
+fn debug_overlay_draws_only_when_toggled() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    let plain = game.mesh();
+
+    game.handle(Action::ToggleDebug);
+    assert!(game.mesh().vertex_count() > plain.vertex_count());
+
+    game.handle(Action::ToggleDebug);
+    assert_eq!(game.mesh().checksum(), plain.checksum());
+}
+
+#[test]
+fn the_cursor_picks_out_what_it_points_at_while_debugging() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.handle(Action::PointAt(game.ball.position));
+    game.tick();
+    // nothing's registered until debugging
+    assert_eq!(game.hovered(), None);
+
+    game.handle(Action::ToggleDebug);
+    game.tick();
+    assert_eq!(game.hovered(), Some(EntityId::Ball));
+    let outlined = game.mesh();
+    game.handle(Action::PointAt(Vector2::new(0., 0.5)));
+    assert_eq!(game.hovered(), None);
+    assert!(game.mesh().vertex_count() < outlined.vertex_count());
+
+    let paddle = &game.paddles[1];
+    game.handle(Action::PointAt(Vector2::new(paddle.x, paddle.y())));
+    assert_eq!(game.hovered(), Some(EntityId::Paddle(1)));
+}
+
+#[test]
+fn lost_ball_despawns_before_game_over() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+
+    game.ball.position = Vector2::new(0., -0.95);
+    game.ball.velocity = Vector2::new(0., -0.01);
+    game.tick();
+    assert_eq!(game.ball.state, BallState::Despawning(Player::Two));
+    assert!(!game.lose_flash.is_finished());
+
+    let ticks = (DESPAWN / TICK.as_secs_f32()).round() as usize;
+    for _ in 0..ticks - 1 {
+        game.tick();
+    }
+    assert_eq!(game.phase, Phase::Playing);
+
+    // allowing a tick for the durations not adding up exactly
+    game.tick();
+    game.tick();
+    assert_eq!(game.phase, Phase::GameOver);
+}
+
+#[test]
+fn overlapping_keys_hold_until_the_last_is_released() {
+    use crate::bindings::{Binding, Command, KeyPress};
+
+    let keys = ["ArrowLeft", "KeyJ"];
+    // released in the order pressed, then the other way round
+    for order in [[0, 1], [1, 0]] {
+        let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+        game.config.bindings.left = keys.map(|key| Binding::Physical(key.into())).to_vec();
+        // starting, skipping the countdown, then serving
+        for _ in 0..3 {
+            game.handle(Action::Serve);
+        }
+
+        let press = |game: &mut Game, key: &str, state| {
+            let key = KeyPress {
+                physical: Some(key.into()),
+                logical: None,
+            };
+            let command = game.config.bindings.command(&key);
+            assert_eq!(command, Some(Command::Left(Player::One)));
+            game.handle(Action::Left(Player::One, state));
+        };
+        let moved = |game: &mut Game| {
+            let before = game.paddles[0].x;
+            for _ in 0..10 {
+                game.tick();
+            }
+            game.paddles[0].x - before
+        };
+
+        press(&mut game, keys[0], ButtonState::Pressed);
+        press(&mut game, keys[1], ButtonState::Pressed);
+        moved(&mut game);
+        press(&mut game, keys[order[0]], ButtonState::Released);
+        assert!(game.controls[0].left.is_held());
+        let held = moved(&mut game);
+        assert!(held < 0., "{:?} let go first", keys[order[0]]);
+
+        // gliding to a stop once both are let go
+        press(&mut game, keys[order[1]], ButtonState::Released);
+        assert!(!game.controls[0].left.is_held());
+        let [first, second] = [(); 2].map(|_| moved(&mut game));
+        assert!(held < first && first < second && second < 0.);
+    }
+}
+
+#[test]
+fn losing_focus_pauses_and_releases_keys() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.handle(Action::Serve);
+    game.handle(Action::Right(Player::One, ButtonState::Pressed));
+
+    game.handle(Action::FocusLost);
+    assert_eq!(game.phase, Phase::Paused);
+    assert!(!game.controls[0].right.is_held());
+
+    game.handle(Action::Pause);
+    assert_eq!(game.phase, Phase::Playing);
+
+    game.config.pause_on_focus_loss = false;
+    game.handle(Action::FocusLost);
+    assert_eq!(game.phase, Phase::Playing);
+}
+
+#[test]
+fn magnet_only_pulls_a_ball_in_play() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+
+    // held before the serve, the attached ball isn't pulled
+    game.handle(Action::Magnet(ButtonState::Pressed));
+    game.tick();
+    assert_eq!(game.tunables.attractor, None);
+
+    game.handle(Action::Serve);
+    game.ball.position = Vector2::new(0.5, 0.);
+    game.ball.velocity = Vector2::zero();
+    game.tick();
+    assert!(game.tunables.attractor.is_some());
+    assert!(game.ball.velocity.x < 0.);
+
+    game.handle(Action::Magnet(ButtonState::Released));
+    game.tick();
+    assert_eq!(game.tunables.attractor, None);
+}
+
+#[test]
+fn speedrun_times_only_played_ticks() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.levels = vec![Level::parse(
+        r#"
+        name = "sprint"
+        bricks = ["a", "a"]
+        types.a = { color = [1.0, 1.0, 1.0] }
+        "#,
+    )
+    .unwrap()];
+    for _ in 0..3 {
+        game.handle(Action::Right(Player::One, ButtonState::Pressed));
+    }
+    assert_eq!(game.mode, Mode::Speedrun);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+
+    // waiting to serve doesn't count
+    for _ in 0..50 {
+        game.tick();
+    }
+    assert_eq!(game.speedrun.ticks(), 0);
+
+    game.handle(Action::Serve);
+    game.ball.position = Vector2::new(3., 0.5);
+    game.ball.velocity = Vector2::zero();
+    let mut played = 0;
+    for _ in 0..30 {
+        game.tick();
+        played += 1;
+    }
+
+    // nor does being paused
+    game.handle(Action::Pause);
+    for _ in 0..100 {
+        game.tick();
+    }
+    game.handle(Action::Pause);
+    game.handle(Action::Serve);
+    assert_eq!(game.speedrun.ticks(), played);
+
+    // knocking out each row from below in turn
+    for y in [-0.06, 0.04] {
+        game.ball.position = Vector2::new(0., y);
+        game.ball.velocity = Vector2::new(0., 0.03);
+        game.tick();
+        played += 1;
+    }
+    assert_eq!(game.phase, Phase::Victory);
+    assert_eq!(game.speedrun.splits(), [played - 1, played]);
+    assert_eq!(game.speedrun.ticks(), played);
+    assert_eq!(game.speedrun.seconds(), played as f32 * TICK.as_secs_f32());
+    assert_eq!(
+        game.high_scores.best_time(game.active_preset, "sprint"),
+        Some(played)
+    );
+
+    game.tick();
+    assert_eq!(game.speedrun.ticks(), played);
+}
+
+#[test]
+fn game_over_writes_the_session_stats() {
+    let dir = std::env::temp_dir().join(format!("wgpu-fun-{}-stats", std::process::id()));
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 7);
+    game.stats_dir = Some(dir.clone());
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+
+    game.ball.position = Vector2::new(0., -0.95);
+    game.ball.velocity = Vector2::new(0., -0.01);
+    let mut ticks = 0;
+    while game.phase == Phase::Playing {
+        game.tick();
+        ticks += 1;
+    }
+    assert_eq!(game.phase, Phase::GameOver);
+
+    let files = std::fs::read_dir(&dir).unwrap().collect::<Vec<_>>();
+    assert_eq!(files.len(), 1);
+    let path = files[0].as_ref().unwrap().path();
+    let stats: SessionStats =
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    assert_eq!(stats.seed, 7);
+    assert_eq!(stats.difficulty, game.active_preset);
+    assert_eq!(stats.lives_used, 1);
+    assert_eq!(stats.ticks, ticks);
+    assert!(stats.max_ball_speed > 0.);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn flipped_gravity_floats_the_ball_into_a_losing_ceiling() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.levels = vec![Level::parse(
+        r#"
+        name = "upside down"
+        ceiling = "lose"
+        bricks = ["a"]
+        types.a = { color = [1.0, 1.0, 1.0] }
+        "#,
+    )
+    .unwrap()];
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    let down = game.tunables.gravity;
+
+    // above the ai's paddle, so nothing stops it rising
+    game.power_ups.activate(PowerUpKind::GravityFlip);
+    game.ball.position = Vector2::new(2., 0.8);
+    game.ball.velocity = Vector2::zero();
+    game.tick();
+    assert_eq!(game.tunables.gravity, -down);
+    assert!(game.ball.velocity.y > 0.);
+
+    for _ in 0..100 {
+        game.tick();
+    }
+    assert_eq!(game.ball.state, BallState::Despawning(Player::Two));
+
+    let ticks = (5. / TICK.as_secs_f32()) as usize;
+    for _ in 0..ticks {
+        game.tick();
+    }
+    assert_eq!(game.tunables.gravity, down);
+}
+
+#[test]
+fn shield_saves_the_ball_once() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.power_ups.activate(PowerUpKind::Shield);
+    game.shield = Some(Shield::new(game.tunables.wall_x));
+
+    let drop = |game: &mut Game| {
+        game.ball.position = Vector2::new(2., -0.75);
+        game.ball.velocity = Vector2::new(0., -0.05);
+        for _ in 0..5 {
+            game.tick();
+        }
+    };
+
+    drop(&mut game);
+    assert_eq!(game.ball.state, BallState::Free);
+    assert!(game.ball.velocity.y > 0.);
+    assert!(game.shield.is_none());
+    assert!(!game.power_ups.is_active(PowerUpKind::Shield));
+
+    drop(&mut game);
+    assert_eq!(game.ball.state, BallState::Despawning(Player::Two));
+}
+
+#[test]
+fn paddles_widen_for_the_power_up_and_narrow_on_hard() {
+    use crate::difficulty::Preset;
+
+    let start = |difficulty: Preset| {
+        let config = Config {
+            difficulty,
+            ..Config::default()
+        };
+        let mut game = Game::new(config, HighScoreTables::default(), 1);
+        game.handle(Action::Serve);
+        game
+    };
+    let normal = Preset::Normal.tunables().paddle_width;
+
+    let hard = start(Preset::Hard);
+    assert!((hard.paddles[0].width - normal * 0.8).abs() < 1e-6);
+    assert!((hard.paddles[1].width - normal * 0.8).abs() < 1e-6);
+
+    let mut game = start(Preset::Normal);
+    assert_eq!(game.paddles[0].width, normal);
+    let paddle = &game.paddles[0];
+    let above = Vector2::new(paddle.x, paddle.y() + 0.1);
+    game.power_ups.drop_at(PowerUpKind::Wide, above);
+    for _ in 0..200 {
+        game.tick();
+    }
+    assert!(game.power_ups.is_active(PowerUpKind::Wide));
+    assert!((game.paddles[0].width - normal * 1.5).abs() < 1e-3);
+    assert_eq!(game.paddles[1].width, normal);
+
+    let ticks = (10. / TICK.as_secs_f32()) as usize;
+    for _ in 0..ticks {
+        game.tick();
+    }
+    assert!(!game.power_ups.is_active(PowerUpKind::Wide));
+    assert!((game.paddles[0].width - normal).abs() < 1e-3);
+}
+
+#[test]
+fn a_ball_too_fast_to_touch_the_shield_in_a_tick_is_still_saved() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.power_ups.activate(PowerUpKind::Shield);
+    let shield = Shield::new(game.tunables.wall_x);
+    let bar = shield.points(1).next().unwrap().y;
+    game.shield = Some(shield);
+
+    // a small ball moving further in a tick than it is wide, just above the bar and clear of
+    // the paddle
+    game.ball.resize(0.01, &game.paddles, game.tunables.wall_x);
+    game.ball.position = Vector2::new(2., bar + 0.03);
+    game.ball.velocity = Vector2::new(0., -0.08);
+    game.tick();
+
+    assert_eq!(game.ball.state, BallState::Free);
+    assert!(game.ball.velocity.y > 0.);
+    assert!(game.ball.position.y > bar);
+    assert!(game.shield.is_none());
+}
+
+#[test]
+fn hard_hit_freezes_the_ball_unless_motion_is_reduced() {
+    let hit = |reduced_motion: bool| {
+        let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+        game.camera.reduced_motion = reduced_motion;
+        game.handle(Action::Serve);
+        game.handle(Action::Serve);
+        game.handle(Action::Serve);
+
+        let paddle = game.paddles[0].attach_point(game.ball.radius, 0., game.tunables.wall_x);
+        game.ball.position = paddle + Vector2::unit_y() * 0.05;
+        game.ball.velocity = Vector2::new(0., -0.1);
+        let mut ticks = 0;
+        while game.ball.velocity.y < 0. && ticks < 10 {
+            game.tick();
+            ticks += 1;
+        }
+        assert!(game.ball.velocity.y > 0.);
+
+        let position = game.ball.position;
+        game.tick();
+        game.ball.position == position
+    };
+
+    assert!(hit(false));
+    assert!(!hit(true));
+}
+
+#[test]
+fn shutdown_writes_the_stats_of_a_game_in_progress() {
+    let dir = std::env::temp_dir().join(format!("wgpu-fun-{}-shutdown", std::process::id()));
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.stats_dir = Some(dir.clone());
+    game.shutdown();
+    assert!(!dir.exists());
+
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.shutdown();
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn scripted_play_keeps_the_ball_and_paddles_in_bounds() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 3);
+    let mut input = StdRng::seed_from_u64(3);
+    for _ in 0..1000 {
+        let event = match input.gen_range(0..8) {
+            0 => Action::Left(Player::One, ButtonState::Pressed),
+            1 => Action::Left(Player::One, ButtonState::Released),
+            2 => Action::Right(Player::One, ButtonState::Pressed),
+            3 => Action::Right(Player::One, ButtonState::Released),
+            4 => Action::Dash,
+            5 => Action::Serve,
+            _ => Action::Magnet(ButtonState::Pressed),
+        };
+        game.handle(event);
+        game.tick();
+
+        let wall_x = game.tunables.wall_x;
+        assert!(game.ball.position.x.abs() <= wall_x - game.ball.radius + 1e-4);
+        for paddle in &game.paddles {
+            assert!(paddle.x.abs() <= wall_x);
+        }
+    }
+    assert!(game.ticks >= 1000);
+}
+
+#[test]
+fn pausing_and_hitstop_hold_the_pose_still() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.handle(Action::Right(Player::One, ButtonState::Pressed));
+    game.ball.position = Vector2::new(1., 0.);
+    game.ball.velocity = Vector2::new(0.02, 0.02);
+
+    let moved = |game: &mut Game| {
+        let before = game.pose();
+        game.tick();
+        let after = game.pose();
+        (before.ball, before.paddles) != (after.ball, after.paddles)
+    };
+    assert!(moved(&mut game));
+
+    game.handle(Action::Pause);
+    assert!(!moved(&mut game));
+    game.handle(Action::Pause);
+    game.handle(Action::Serve);
+    assert!(moved(&mut game));
+
+    game.hitstop_ticks_remaining = 2;
+    assert!(!moved(&mut game));
+    assert!(!moved(&mut game));
+    assert!(moved(&mut game));
+}
+
+#[test]
+fn either_set_of_keys_steers_alone_but_only_their_own_with_two() {
+    let steer = |game: &mut Game, player: Player, state: ButtonState| {
+        game.handle(Action::Left(player, state));
+        game.tick();
+        game.paddles[0].velocity
+    };
+
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.handle(Action::Serve);
+    assert!(steer(&mut game, Player::Two, ButtonState::Pressed) < 0.);
+    steer(&mut game, Player::One, ButtonState::Pressed);
+    // letting go of the arrow leaves the held A steering
+    let before = game.paddles[0].velocity;
+    assert!(steer(&mut game, Player::One, ButtonState::Released) < before);
+    let before = game.paddles[0].velocity;
+    assert!(steer(&mut game, Player::Two, ButtonState::Released) > before);
+
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.handle(Action::Right(Player::One, ButtonState::Pressed));
+    assert_eq!(game.mode, Mode::TwoPlayer);
+    game.handle(Action::Right(Player::One, ButtonState::Released));
+    game.handle(Action::Serve);
+    assert_eq!(steer(&mut game, Player::Two, ButtonState::Pressed), 0.);
+}
+
+#[test]
+fn escape_asks_before_leaving_a_game_in_progress() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.handle(Action::Quit);
+    assert!(game.wants_to_quit());
+
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.ball.velocity = Vector2::new(0.02, 0.02);
+
+    game.handle(Action::Quit);
+    assert_eq!(game.phase, Phase::ConfirmQuit);
+    let before = game.pose();
+    game.tick();
+    assert_eq!(game.pose().ball, before.ball);
+    // letting go of a key or a stick drifting doesn't count as an answer
+    game.handle(Action::Right(Player::One, ButtonState::Released));
+    game.handle(Action::PaddleAxis(Player::One, 0.1));
+    assert_eq!(game.phase, Phase::ConfirmQuit);
+
+    // any other key resumes, still doing what it does if it's held
+    game.handle(Action::Left(Player::One, ButtonState::Pressed));
+    assert_eq!(game.phase, Phase::Playing);
+    assert!(game.countdown > 0.);
+    assert!(game.controls[0].left.is_held());
+    assert!(!game.wants_to_quit());
+
+    // pause resumes too rather than pausing on top
+    game.handle(Action::Quit);
+    game.handle(Action::Pause);
+    assert_eq!(game.phase, Phase::Playing);
+
+    game.handle(Action::Pause);
+    game.handle(Action::Quit);
+    game.handle(Action::Serve);
+    assert_eq!(game.phase, Phase::Paused);
+
+    game.handle(Action::Quit);
+    game.handle(Action::Quit);
+    assert!(game.wants_to_quit());
+
+    game.shutdown();
+    assert_eq!(game.phase, Phase::Paused);
+}
+
+#[test]
+fn dropping_a_level_file_plays_it_and_anything_else_flashes() {
+    let path = std::env::temp_dir().join(format!("wgpu-fun-{}-drop.toml", std::process::id()));
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+
+    std::fs::write(&path, "name = ").unwrap();
+    game.load_level_file(&path);
+    assert!(!game.reject_flash.is_finished());
+    assert_eq!(game.levels, Level::bundled());
+    assert_eq!(game.ball.state, BallState::Free);
+
+    std::fs::write(
+        &path,
+        "name = \"dropped\"\nbricks = [\"a\"]\n[types.a]\ncolor = [1, 0, 0]\n",
+    )
+    .unwrap();
+    game.load_level_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(game.phase, Phase::Playing);
+    assert_eq!(game.current_level().name, "dropped");
+    assert_eq!(game.ball.state, BallState::Attached);
+    assert!(!game.bricks.is_empty());
+    assert!(game.title.ends_with("dropped"));
+}
+
+#[test]
+fn plain_scene_reuses_its_buffers_once_warmed_up() {
+    // the gpu buffers are only checked along with the other gpu tests
+    let headless = cfg!(gpu_tests).then(|| crate::renderer::headless::for_test("buffer reuse"));
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    // built over every tick, as the render thread does
+    let (mut builder, mut particles) = (MeshBuilder::default(), Vec::new());
+    let mut mesh = None;
+
+    let start = crate::instrument::counts();
+    let mut warmed_up = start;
+    for tick in 0..200 {
+        if tick == 100 {
+            warmed_up = crate::instrument::counts();
+        }
+        game.tick();
+        let frame = game.frame_with(builder, particles);
+        (builder, particles) = (frame.mesh, frame.particles);
+        if let Some(headless) = &headless {
+            headless.write(&mut mesh, &builder);
+        }
+    }
+
+    assert!(warmed_up.since(&start).builder_reallocations > 0);
+    let steady = crate::instrument::counts().since(&warmed_up);
+    assert_eq!(steady.builder_reallocations, 0);
+    assert_eq!(steady.buffer_recreations, 0);
+}
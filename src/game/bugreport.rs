@@ -0,0 +1,320 @@
+use super::save::{Header, SaveError, SavedGame, VERSION};
+use super::Game;
+use crate::config::Config;
+use crate::difficulty::Preset;
+use crate::replay::Recording;
+use crate::storage;
+use crate::{Action, Controls, InputEvent, Phase};
+use cgmath::Vector2;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A checkpoint is taken every this many ticks, 30 seconds' worth, so the older of the two kept
+/// is always at least this far back
+const WINDOW: u64 = 3000;
+/// Far more events than anyone sends in twice the window
+const MAX_EVENTS: usize = 8192;
+/// Enough ticks to reach back to the older checkpoint
+const MAX_STATES: usize = 2 * WINDOW as usize;
+
+/// Where the ball and paddles were after a tick, to check a replay against
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TickState {
+    pub tick: u64,
+    pub ball: Vector2<f32>,
+    pub ball_velocity: Vector2<f32>,
+    /// The paddles' x positions
+    pub paddles: [f32; 2],
+}
+
+/// Everything a replay starts from, being a save along with what resuming a save starts afresh
+#[derive(Clone, Serialize, Deserialize)]
+struct Checkpoint {
+    tick: u64,
+    game: SavedGame,
+    phase: Phase,
+    preset: Preset,
+    controls: [Controls; 2],
+}
+
+/// The last stretch of play, in buffers allocated up front so capturing a tick never allocates.
+/// Only the checkpoint taken every window does.
+pub(super) struct Capture {
+    /// Oldest first, and never more than two
+    checkpoints: VecDeque<Checkpoint>,
+    events: VecDeque<InputEvent>,
+    states: VecDeque<TickState>,
+    /// The tick of the newest event dropped to make room, up to which the events are incomplete
+    dropped: Option<u64>,
+}
+
+impl Default for Capture {
+    fn default() -> Self {
+        Self {
+            checkpoints: VecDeque::with_capacity(2),
+            events: VecDeque::with_capacity(MAX_EVENTS),
+            states: VecDeque::with_capacity(MAX_STATES),
+            dropped: None,
+        }
+    }
+}
+
+/// A stretch of play that can be replayed exactly: a checkpoint, followed by every event since
+/// and where the ball and paddles were after each tick
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BugReport {
+    /// Of the save inside, so checked the same way
+    version: u32,
+    pub seed: u64,
+    /// Of the config the game was played with, as a replay under another may play differently
+    pub config_hash: u64,
+    start: Checkpoint,
+    pub events: Vec<InputEvent>,
+    pub ticks: Vec<TickState>,
+}
+
+impl BugReport {
+    pub fn parse(source: &str) -> Result<Self, SaveError> {
+        let header: Header = ron::from_str(source).map_err(SaveError::Report)?;
+        if header.version != VERSION {
+            return Err(SaveError::Version(header.version));
+        }
+        ron::from_str(source).map_err(SaveError::Report)
+    }
+
+    /// The report as ron, which is as readable as json but keeps its enums and tuples looking
+    /// like the code's
+    pub fn to_ron(&self) -> String {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .expect("bug reports serialize")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SaveError> {
+        Self::parse(&std::fs::read_to_string(path).map_err(SaveError::Io)?)
+    }
+
+    /// The report's events, to be played back from its start
+    pub fn recording(&self) -> Recording {
+        Recording {
+            seed: self.seed,
+            events: self.events.clone(),
+        }
+    }
+}
+
+/// Tells configs apart without including them, as an fnv-1a hash of the json
+pub fn config_hash(config: &Config) -> u64 {
+    let json = serde_json::to_vec(config).expect("configs serialize");
+    json.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+impl Game {
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            tick: self.ticks,
+            game: SavedGame::new(self),
+            phase: self.settled_phase(),
+            preset: self.preset,
+            controls: self.controls,
+        }
+    }
+
+    /// Forgets the play so far, as when a game is loaded over it
+    pub(super) fn restart_capture(&mut self) {
+        let checkpoint = self.checkpoint();
+        let capture = &mut self.capture;
+        capture.checkpoints.clear();
+        capture.checkpoints.push_back(checkpoint);
+        capture.events.clear();
+        capture.states.clear();
+        capture.dropped = None;
+    }
+
+    pub(super) fn capture_event(&mut self, action: Action) {
+        let capture = &mut self.capture;
+        if capture.events.len() == MAX_EVENTS {
+            capture.dropped = capture.events.pop_front().and_then(|event| event.tick_hint);
+        }
+        capture.events.push_back(InputEvent::at(self.ticks, action));
+    }
+
+    /// Called at the end of each tick
+    pub(super) fn capture_tick(&mut self) {
+        if self.capture.states.len() == MAX_STATES {
+            self.capture.states.pop_front();
+        }
+        self.capture.states.push_back(TickState {
+            tick: self.ticks,
+            ball: self.ball.position,
+            ball_velocity: self.ball.velocity,
+            paddles: [self.paddles[0].x, self.paddles[1].x],
+        });
+
+        if self.ticks.is_multiple_of(WINDOW) {
+            let checkpoint = self.checkpoint();
+            let capture = &mut self.capture;
+            if capture.checkpoints.len() == 2 {
+                capture.checkpoints.pop_front();
+            }
+            capture.checkpoints.push_back(checkpoint);
+
+            // nothing before the oldest checkpoint is replayed
+            let start = capture.checkpoints[0].tick;
+            while capture
+                .events
+                .front()
+                .is_some_and(|event| event.tick_hint.unwrap_or(0) < start)
+            {
+                capture.events.pop_front();
+            }
+        }
+    }
+
+    /// A report from the oldest checkpoint with every event since, which is none at all if more
+    /// events than can be kept were sent since the newest
+    pub fn bug_report(&self) -> Option<BugReport> {
+        let capture = &self.capture;
+        let start = capture
+            .checkpoints
+            .iter()
+            .find(|checkpoint| capture.dropped.is_none_or(|tick| tick < checkpoint.tick))?;
+
+        Some(BugReport {
+            version: VERSION,
+            seed: self.seed,
+            config_hash: config_hash(&self.config),
+            start: start.clone(),
+            events: capture
+                .events
+                .iter()
+                .filter(|event| event.tick_hint.unwrap_or(0) >= start.tick)
+                .copied()
+                .collect(),
+            ticks: capture
+                .states
+                .iter()
+                .filter(|state| state.tick > start.tick)
+                .copied()
+                .collect(),
+        })
+    }
+
+    /// Writes a bug report to `bug_report_dir` named after the time, logging rather than failing
+    pub(super) fn report_bug(&self) {
+        let Some(dir) = &self.bug_report_dir else {
+            return;
+        };
+        let Some(report) = self.bug_report() else {
+            log::warn!("too many inputs to fit in a bug report");
+            return;
+        };
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis());
+        let path = dir.join(format!("bugreport-{timestamp}.ron"));
+        match storage::write(&path, report.to_ron().as_bytes()) {
+            Ok(()) => log::info!("wrote a bug report to {}", path.display()),
+            Err(err) => log::warn!("failed to write a bug report to {}: {err}", path.display()),
+        }
+    }
+
+    /// Puts the game as it was at the start of a report, ready for its events to be played back
+    /// from the same tick
+    pub fn load_bug_report(&mut self, report: &BugReport) -> Result<(), SaveError> {
+        let start = report.start.clone();
+        self.restore(start.game)?;
+        self.phase = start.phase;
+        self.preset = start.preset;
+        self.controls = start.controls;
+        self.restart_capture();
+        Ok(())
+    }
+}
+
+/// The directory bug reports are written to, the one the game was started from
+pub fn default_dir() -> Option<PathBuf> {
+    std::env::current_dir().ok()
+}
+
+#[cfg(test)]
+use crate::{highscore::HighScoreTables, replay::Replay, ButtonState, Player};
+
+#[test]
+fn bug_report_replays_to_the_same_state() {
+    use rand::{Rng, SeedableRng};
+
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 11);
+    let mut input = rand::rngs::StdRng::seed_from_u64(11);
+    // long enough that the report starts from a checkpoint partway through
+    for _ in 0..2 * WINDOW + 1200 {
+        let event = match input.gen_range(0..40) {
+            0 => Action::Left(Player::One, ButtonState::Pressed),
+            1 => Action::Left(Player::One, ButtonState::Released),
+            2 => Action::Right(Player::One, ButtonState::Pressed),
+            3 => Action::Right(Player::One, ButtonState::Released),
+            4 => Action::Dash,
+            5 => Action::Serve,
+            _ => Action::Magnet(ButtonState::Pressed),
+        };
+        game.handle(event);
+        game.tick();
+    }
+
+    let report = game.bug_report().unwrap();
+    assert_eq!(report.start.tick, WINDOW);
+    assert!(!report.events.is_empty());
+    let report = BugReport::parse(&report.to_ron()).unwrap();
+    assert_eq!(report.config_hash, config_hash(&Config::default()));
+
+    let mut replayed = Game::new(Config::default(), HighScoreTables::default(), report.seed);
+    replayed.load_bug_report(&report).unwrap();
+    assert_eq!(replayed.ticks, WINDOW);
+    let mut replay = Replay::new(report.recording());
+    for expected in &report.ticks {
+        for event in replay.due(replayed.ticks) {
+            replayed.handle(event);
+        }
+        replayed.tick();
+        let state = replayed.capture.states.back().unwrap();
+        assert_eq!(state, expected);
+    }
+
+    assert_eq!(replayed.ticks, game.ticks);
+    let saved = |game: &Game| serde_json::to_string(&SavedGame::new(game)).unwrap();
+    assert_eq!(saved(&replayed), saved(&game));
+}
+
+#[test]
+fn capture_keeps_to_its_buffers() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+    let capacity = (
+        game.capture.events.capacity(),
+        game.capture.states.capacity(),
+    );
+    for _ in 0..3 * WINDOW {
+        game.handle(Action::Dash);
+        game.handle(Action::Dash);
+        game.handle(Action::Dash);
+        game.tick();
+    }
+    assert_eq!(game.capture.states.len(), MAX_STATES);
+    assert_eq!(game.capture.events.len(), MAX_EVENTS);
+    assert_eq!(
+        (
+            game.capture.events.capacity(),
+            game.capture.states.capacity()
+        ),
+        capacity
+    );
+    assert_eq!(game.capture.checkpoints.len(), 2);
+
+    // too many events to replay from the older checkpoint, but not the newer
+    let report = game.bug_report().unwrap();
+    assert_eq!(report.start.tick, 3 * WINDOW);
+    assert!(report.ticks.is_empty());
+}
@@ -0,0 +1,309 @@
+use super::Game;
+use crate::ai::Ai;
+use crate::bricks::Bricks;
+use crate::combo::Combo;
+use crate::contact::ContactTracker;
+use crate::difficulty::Preset;
+use crate::magnet::Magnet;
+use crate::menu::Mode;
+use crate::powerups::PowerUps;
+use crate::shield::Shield;
+use crate::speedrun::Speedrun;
+use crate::stats::SessionStats;
+use crate::storage;
+use crate::tween::Tween;
+use crate::{Ball, Paddle, Phase, Player, Serve};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever a change to the game makes older saves unreadable or play differently
+pub const VERSION: u32 = 1;
+
+/// Just enough of a save to tell which version wrote it, whatever the rest looks like
+#[derive(Deserialize)]
+pub(super) struct Header {
+    pub(super) version: u32,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    /// A bug report that isn't readable ron
+    Report(ron::error::SpannedError),
+    /// The version of the format the save was written in
+    Version(u32),
+    /// The name of the level saved on, which isn't among those being played
+    Level(String),
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Parse(err) => write!(f, "not a readable save: {err}"),
+            Self::Report(err) => write!(f, "not a readable bug report: {err}"),
+            Self::Version(version) => write!(
+                f,
+                "saved in format version {version}, but this build only reads version {VERSION}"
+            ),
+            Self::Level(name) => write!(f, "saved on level {name:?}, which isn't being played"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+/// A game in progress, with everything that decides how it plays on from here. Anything only
+/// drawn, like particles and the camera, starts afresh.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SavedGame {
+    /// First, so it's read before anything else can fail
+    version: u32,
+    mode: Mode,
+    preset: Preset,
+    /// Counting from 1, alongside the name it had to check it's still the same level
+    level: usize,
+    level_name: String,
+    level_ticks: u32,
+    ticks: u64,
+    scores: [u32; 2],
+    server: Player,
+    serve: Serve,
+    serve_bias: f32,
+    countdown: f32,
+    ball: Ball,
+    despawn: Tween,
+    paddles: [Paddle; 2],
+    /// Missing from saves made before contacts were tracked apart from the paddles
+    #[serde(default)]
+    contacts: ContactTracker,
+    ai: Ai,
+    magnet: Magnet,
+    combo: Combo,
+    bricks: Bricks,
+    power_ups: PowerUps,
+    shield: Option<Shield>,
+    obstacles: Vec<(usize, f32, bool)>,
+    portal_exit: Option<(usize, usize)>,
+    speedrun: Speedrun,
+    hitstop_ticks_remaining: u32,
+    /// Including the balls lost so far
+    stats: SessionStats,
+    rng: ChaCha12Rng,
+}
+
+impl SavedGame {
+    pub(super) fn new(game: &Game) -> Self {
+        Self {
+            version: VERSION,
+            mode: game.mode,
+            preset: game.active_preset,
+            level: game.level,
+            level_name: game.current_level().name.clone(),
+            level_ticks: game.level_ticks,
+            ticks: game.ticks,
+            scores: game.scores,
+            server: game.server,
+            serve: game.serve,
+            serve_bias: game.serve_bias,
+            countdown: game.countdown,
+            ball: game.ball.clone(),
+            despawn: game.despawn,
+            paddles: game.paddles.clone(),
+            contacts: game.contacts.clone(),
+            ai: game.ai.clone(),
+            magnet: game.magnet.clone(),
+            combo: game.combo,
+            bricks: game.bricks.clone(),
+            power_ups: game.power_ups.clone(),
+            shield: game.shield.clone(),
+            obstacles: game.obstacles.progress(),
+            portal_exit: game.portals.exit(),
+            speedrun: game.speedrun.clone(),
+            hitstop_ticks_remaining: game.hitstop_ticks_remaining,
+            stats: game.stats.clone(),
+            rng: game.rng.clone(),
+        }
+    }
+
+    /// The save file in the platform data directory
+    pub fn default_path() -> Option<PathBuf> {
+        storage::data_path("save.json")
+    }
+
+    /// Reads a save, checking its version before the rest so a save in another format is
+    /// turned away rather than misread
+    pub fn parse(source: &str) -> Result<Self, SaveError> {
+        let header: Header = serde_json::from_str(source).map_err(SaveError::Parse)?;
+        if header.version != VERSION {
+            return Err(SaveError::Version(header.version));
+        }
+        serde_json::from_str(source).map_err(SaveError::Parse)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SaveError> {
+        Self::parse(&std::fs::read_to_string(path).map_err(SaveError::Io)?)
+    }
+}
+
+impl Game {
+    /// Writes the game in progress to `path`
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        storage::save(path, &SavedGame::new(self))
+    }
+
+    /// Picks up a saved game where it left off, paused. A save of a level that isn't being
+    /// played is turned away, leaving the game as it was.
+    pub fn restore(&mut self, saved: SavedGame) -> Result<(), SaveError> {
+        let level = saved.level.max(1);
+        let index = (level - 1).min(self.levels.len() - 1);
+        if self.levels[index].name != saved.level_name {
+            return Err(SaveError::Level(saved.level_name));
+        }
+
+        self.mode = saved.mode;
+        self.active_preset = saved.preset;
+        self.tunables = saved.preset.tunables();
+        self.level = level;
+        self.ghost.clear();
+        self.start_level();
+
+        self.level_ticks = saved.level_ticks;
+        self.ticks = saved.ticks;
+        self.scores = saved.scores;
+        self.server = saved.server;
+        self.serve = saved.serve;
+        self.serve_bias = saved.serve_bias;
+        self.countdown = saved.countdown;
+        self.ball = saved.ball;
+        self.despawn = saved.despawn;
+        self.paddles = saved.paddles;
+        self.contacts = saved.contacts;
+        self.ai = saved.ai;
+        self.magnet = saved.magnet;
+        self.combo = saved.combo;
+        self.bricks = saved.bricks;
+        self.power_ups = saved.power_ups;
+        self.shield = saved.shield;
+        self.obstacles.set_progress(&saved.obstacles);
+        self.portals.set_exit(saved.portal_exit);
+        self.speedrun = saved.speedrun;
+        self.hitstop_ticks_remaining = saved.hitstop_ticks_remaining;
+        self.stats = saved.stats;
+        self.rng = saved.rng;
+
+        self.phase = Phase::Paused;
+        self.update_title(self.active_preset);
+        self.restart_capture();
+        Ok(())
+    }
+
+    pub fn resume(&mut self, path: &Path) -> Result<(), SaveError> {
+        self.restore(SavedGame::load(path)?)?;
+        self.resumed = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+use crate::{config::Config, highscore::HighScoreTables, Action, BallState, ButtonState};
+
+#[cfg(test)]
+fn saved(game: &Game) -> String {
+    serde_json::to_string(&SavedGame::new(game)).unwrap()
+}
+
+#[test]
+fn mid_game_save_plays_on_the_same() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 5);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.handle(Action::Serve);
+    game.handle(Action::Right(Player::One, ButtonState::Pressed));
+    for _ in 0..20 {
+        game.tick();
+    }
+    game.handle(Action::Right(Player::One, ButtonState::Released));
+    for _ in 0..40 {
+        game.tick();
+    }
+    game.power_ups
+        .activate(crate::powerups::PowerUpKind::GravityFlip);
+    game.handle(Action::Pause);
+    assert_eq!(game.phase, Phase::Paused);
+    assert_eq!(game.ball.state, BallState::Free);
+
+    let save = saved(&game);
+    let mut restored = Game::new(Config::default(), HighScoreTables::default(), 9);
+    restored.restore(SavedGame::parse(&save).unwrap()).unwrap();
+    assert_eq!(restored.phase, Phase::Paused);
+    assert_eq!(saved(&restored), save);
+
+    for game in [&mut game, &mut restored] {
+        game.handle(Action::Pause);
+        for _ in 0..300 {
+            game.tick();
+        }
+    }
+    assert_eq!(restored.ball.position, game.ball.position);
+    assert_eq!(saved(&restored), saved(&game));
+}
+
+#[test]
+fn save_from_another_version_is_turned_away() {
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 5);
+    game.handle(Action::Serve);
+    let mut save: serde_json::Value = serde_json::from_str(&saved(&game)).unwrap();
+
+    save["version"] = (VERSION + 1).into();
+    // so it would fail to read even if it got that far
+    save["ball"] = "round".into();
+    let err = SavedGame::parse(&save.to_string()).err().unwrap();
+    assert!(matches!(err, SaveError::Version(version) if version == VERSION + 1));
+    assert!(matches!(SavedGame::parse("[]"), Err(SaveError::Parse(_))));
+
+    // nor is a save of another level
+    save["version"] = VERSION.into();
+    save["ball"] = serde_json::to_value(&game.ball).unwrap();
+    save["level_name"] = "elsewhere".into();
+    let mut fresh = Game::new(Config::default(), HighScoreTables::default(), 5);
+    let saved = SavedGame::parse(&save.to_string()).unwrap();
+    assert!(matches!(fresh.restore(saved), Err(SaveError::Level(_))));
+    assert_eq!(fresh.phase, Phase::Menu);
+}
+
+#[test]
+fn shutdown_saves_a_game_in_progress_and_clears_a_finished_one() {
+    let path = std::env::temp_dir().join(format!("wgpu-fun-{}-save.json", std::process::id()));
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 5);
+    game.save_path = Some(path.clone());
+    game.handle(Action::Serve);
+    game.shutdown();
+
+    let mut resumed = Game::new(Config::default(), HighScoreTables::default(), 5);
+    resumed.save_path = Some(path.clone());
+    resumed.resume(&path).unwrap();
+    assert_eq!(resumed.phase, Phase::Paused);
+
+    resumed.phase = Phase::GameOver;
+    resumed.shutdown();
+    assert!(!path.exists());
+}
+
+#[test]
+fn quitting_from_the_menu_leaves_a_save_not_resumed_from_be() {
+    let path = std::env::temp_dir().join(format!("wgpu-fun-{}-kept.json", std::process::id()));
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 5);
+    game.save_path = Some(path.clone());
+    game.handle(Action::Serve);
+    game.shutdown();
+
+    // started without resuming it, as with --no-resume, and quit before playing
+    let mut fresh = Game::new(Config::default(), HighScoreTables::default(), 5);
+    fresh.save_path = Some(path.clone());
+    fresh.shutdown();
+    assert!(path.exists());
+    std::fs::remove_file(&path).unwrap();
+}
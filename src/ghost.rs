@@ -0,0 +1,107 @@
+use crate::renderer::buffer::MeshBuilder;
+use crate::renderer::shapes::SegmentCount;
+use cgmath::Vector2;
+use std::collections::VecDeque;
+
+/// Records the ball each tick of a practice rally, replaying the last lost rally alongside the
+/// live one as a translucent ghost
+#[derive(Debug, Clone, Default)]
+pub struct Ghost {
+    /// The current rally's positions, the oldest dropped past `CAPACITY`
+    recording: VecDeque<Vector2<f32>>,
+    /// Ticks into the current rally
+    tick: usize,
+    /// The last lost rally's positions, along with the tick the first of them was recorded on
+    last: Option<(usize, Vec<Vector2<f32>>)>,
+}
+
+impl Ghost {
+    /// A minute of ticks
+    const CAPACITY: usize = 6000;
+    const ALPHA: f32 = 0.3;
+
+    pub fn record(&mut self, position: Vector2<f32>) {
+        if self.recording.len() == Self::CAPACITY {
+            self.recording.pop_front();
+        }
+        self.recording.push_back(position);
+        self.tick += 1;
+    }
+
+    /// Ends a lost rally, making it the ghost of the next
+    pub fn finish(&mut self) {
+        let start = self.tick - self.recording.len();
+        self.last = Some((start, self.recording.drain(..).collect()));
+        self.tick = 0;
+    }
+
+    /// Ends a rally without keeping it, so the ghost stays as it was
+    pub fn restart(&mut self) {
+        self.recording.clear();
+        self.tick = 0;
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Where the ghost was on the tick last recorded
+    pub fn position(&self) -> Option<Vector2<f32>> {
+        let (start, positions) = self.last.as_ref()?;
+        let index = self.tick.checked_sub(start + 1)?;
+        positions.get(index).copied()
+    }
+
+    pub fn push(&self, mesh: &mut MeshBuilder, radius: f32, segments: SegmentCount) {
+        if let Some(position) = self.position() {
+            mesh.push_circle(position, radius, segments, [1., 1., 1., Self::ALPHA]);
+        }
+    }
+}
+
+#[test]
+fn ghost_replays_the_last_lost_rally_in_step() {
+    let mut ghost = Ghost::default();
+    for x in 0..3 {
+        ghost.record(Vector2::new(x as f32, 0.));
+    }
+    assert_eq!(ghost.position(), None);
+    ghost.finish();
+
+    ghost.record(Vector2::new(10., 0.));
+    assert_eq!(ghost.position(), Some(Vector2::new(0., 0.)));
+    ghost.record(Vector2::new(10., 0.));
+    assert_eq!(ghost.position(), Some(Vector2::new(1., 0.)));
+
+    // a rally that isn't lost starts the same ghost over
+    ghost.restart();
+    ghost.record(Vector2::new(10., 0.));
+    assert_eq!(ghost.position(), Some(Vector2::new(0., 0.)));
+
+    for _ in 0..3 {
+        ghost.record(Vector2::new(10., 0.));
+    }
+    assert_eq!(ghost.position(), None);
+
+    ghost.clear();
+    ghost.record(Vector2::new(10., 0.));
+    assert_eq!(ghost.position(), None);
+}
+
+#[test]
+fn recording_is_bounded_and_stays_aligned() {
+    let mut ghost = Ghost::default();
+    for x in 0..Ghost::CAPACITY + 10 {
+        ghost.record(Vector2::new(x as f32, 0.));
+    }
+    assert_eq!(ghost.recording.len(), Ghost::CAPACITY);
+    ghost.finish();
+
+    // the dropped start of the rally shows no ghost, then it picks up on the same tick
+    for _ in 0..10 {
+        ghost.record(Vector2::new(0., 0.));
+        assert_eq!(ghost.position(), None);
+    }
+    ghost.record(Vector2::new(0., 0.));
+    assert_eq!(ghost.position(), Some(Vector2::new(10., 0.)));
+}
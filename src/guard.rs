@@ -0,0 +1,45 @@
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+
+/// Runs `body`, handing its panic message to `on_panic` if it panics, so a thread's panic can be
+/// reported somewhere that will notice rather than just ending the thread
+pub fn catch_panic(body: impl FnOnce(), on_panic: impl FnOnce(String)) {
+    if let Err(payload) = std::panic::catch_unwind(AssertUnwindSafe(body)) {
+        on_panic(message(payload.as_ref()));
+    }
+}
+
+fn message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[test]
+fn game_panic_is_reported() {
+    use wgpu_fun::config::Config;
+    use wgpu_fun::game::Game;
+    use wgpu_fun::highscore::HighScoreTables;
+    use wgpu_fun::Action;
+
+    let (send, recv) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        catch_panic(
+            || {
+                // with no levels, starting a game can't load one
+                let mut game = Game::new(Config::default(), HighScoreTables::default(), 1);
+                game.levels.clear();
+                game.handle(Action::Serve);
+            },
+            |message| send.send(message).unwrap(),
+        )
+    })
+    .join()
+    .unwrap();
+
+    assert!(!recv.recv().unwrap().is_empty());
+}
@@ -0,0 +1,198 @@
+use crate::difficulty::Preset;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub score: u32,
+    /// Seconds since the unix epoch
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighScores {
+    entries: Vec<Entry>,
+}
+
+impl HighScores {
+    const CAPACITY: usize = 10;
+
+    /// Records a score, returning where in the table it placed if it made it in at all
+    pub fn insert(&mut self, score: u32) -> Option<usize> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        self.insert_entry(Entry { score, timestamp })
+    }
+
+    fn insert_entry(&mut self, entry: Entry) -> Option<usize> {
+        // after any it ties with, as earlier entries win ties
+        let index = self
+            .entries
+            .iter()
+            .position(|other| other.score < entry.score)
+            .unwrap_or(self.entries.len());
+        if index >= Self::CAPACITY {
+            return None;
+        }
+        self.entries.insert(index, entry);
+        self.entries.truncate(Self::CAPACITY);
+        Some(index)
+    }
+
+    fn normalize(&mut self) {
+        // stable, so earlier entries win ties
+        self.entries
+            .sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        self.entries.truncate(Self::CAPACITY);
+    }
+
+    pub fn best(&self) -> Option<u32> {
+        self.entries.first().map(|entry| entry.score)
+    }
+}
+
+/// A separate table for each difficulty preset
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighScoreTables {
+    tables: BTreeMap<Preset, HighScores>,
+    /// The fewest ticks each level has been cleared in for each preset, keyed by level name
+    #[serde(default)]
+    best_times: BTreeMap<Preset, BTreeMap<String, u64>>,
+}
+
+impl HighScoreTables {
+    /// The high score file in the platform data directory
+    pub fn default_path() -> Option<PathBuf> {
+        storage::data_path("highscores.json")
+    }
+
+    /// Loads the tables, starting fresh if the file is missing or corrupt
+    pub fn load(path: &Path) -> Self {
+        let mut tables: Self = storage::load(path);
+        for table in tables.tables.values_mut() {
+            table.normalize();
+        }
+        tables
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        storage::save(path, self)
+    }
+
+    pub fn get(&self, preset: Preset) -> Option<&HighScores> {
+        self.tables.get(&preset)
+    }
+
+    pub fn get_mut(&mut self, preset: Preset) -> &mut HighScores {
+        self.tables.entry(preset).or_default()
+    }
+
+    pub fn best_time(&self, preset: Preset, level: &str) -> Option<u64> {
+        self.best_times.get(&preset)?.get(level).copied()
+    }
+
+    /// Records a level's clear time in ticks, returning whether it's the new best
+    pub fn insert_time(&mut self, preset: Preset, level: &str, ticks: u64) -> bool {
+        let times = self.best_times.entry(preset).or_default();
+        if times.get(level).is_some_and(|&best| best <= ticks) {
+            return false;
+        }
+        times.insert(level.to_owned(), ticks);
+        true
+    }
+}
+
+#[cfg(test)]
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("wgpu-fun-{}-{name}", std::process::id()))
+}
+
+#[test]
+fn insert_keeps_top_ten() {
+    let mut scores = HighScores::default();
+    for score in 0..15 {
+        scores.insert_entry(Entry {
+            score,
+            timestamp: score as u64,
+        });
+    }
+
+    assert_eq!(scores.entries.len(), 10);
+    assert_eq!(scores.best(), Some(14));
+    assert_eq!(scores.entries.last().unwrap().score, 5);
+    assert_eq!(
+        scores.insert_entry(Entry {
+            score: 1,
+            timestamp: 0
+        }),
+        None
+    );
+}
+
+#[test]
+fn insert_says_where_the_entry_placed() {
+    let mut scores = HighScores::default();
+    for score in 5..15 {
+        scores.insert_entry(Entry {
+            score,
+            timestamp: score as u64,
+        });
+    }
+
+    // the same as the entry at the cutoff, which keeps its place as the earlier one
+    let tie = Entry {
+        score: 5,
+        timestamp: 5,
+    };
+    assert_eq!(scores.insert_entry(tie), None);
+    assert_eq!(scores.entries.len(), 10);
+
+    // ties are placed after the entries they tie with
+    let entry = Entry {
+        score: 12,
+        timestamp: 20,
+    };
+    assert_eq!(scores.insert_entry(entry), Some(3));
+    assert_eq!(scores.entries[3], entry);
+    assert_eq!(scores.entries.last().unwrap().score, 6);
+}
+
+#[test]
+fn save_then_load_round_trips() {
+    let path = temp_path("round-trip").join("highscores.json");
+    let mut tables = HighScoreTables::default();
+    tables.get_mut(Preset::Normal).insert(3);
+    tables.get_mut(Preset::Normal).insert(7);
+    tables.get_mut(Preset::Hard).insert(2);
+    assert!(tables.insert_time(Preset::Normal, "warm up", 500));
+    assert!(!tables.insert_time(Preset::Normal, "warm up", 600));
+    assert!(tables.insert_time(Preset::Normal, "warm up", 400));
+
+    tables.save(&path).unwrap();
+    let loaded = HighScoreTables::load(&path);
+    assert_eq!(loaded, tables);
+    assert_eq!(loaded.get(Preset::Normal).unwrap().best(), Some(7));
+    assert_eq!(loaded.get(Preset::Hard).unwrap().best(), Some(2));
+    assert!(loaded.get(Preset::Easy).is_none());
+    assert_eq!(loaded.best_time(Preset::Normal, "warm up"), Some(400));
+    assert_eq!(loaded.best_time(Preset::Hard, "warm up"), None);
+    assert!(!path.with_extension("json.tmp").exists());
+
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn missing_or_corrupt_file_starts_fresh() {
+    let path = temp_path("corrupt.json");
+    assert_eq!(HighScoreTables::load(&path), HighScoreTables::default());
+
+    std::fs::write(&path, "{ not json").unwrap();
+    assert_eq!(HighScoreTables::load(&path), HighScoreTables::default());
+
+    std::fs::remove_file(&path).unwrap();
+}
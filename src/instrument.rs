@@ -0,0 +1,152 @@
+//! Counts of the work a steady frame shouldn't need to do, kept per thread so the game and render
+//! threads, and tests running side by side, each see only their own
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Whether the binary counts heap allocations, which needs its global allocator swapped out
+pub const TRACKING_ALLOCATIONS: bool = cfg!(feature = "track-alloc");
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// Times a mesh builder's vertices or indices outgrew their capacity
+    pub builder_reallocations: u64,
+    /// Times a mesh's gpu buffers were too small to reuse and were made again
+    pub buffer_recreations: u64,
+    /// Heap allocations, only counted with the `track-alloc` feature
+    pub allocations: u64,
+    /// Pipelines and bind groups found already made
+    pub cache_hits: u64,
+    /// Pipelines and bind groups that had to be made
+    pub cache_misses: u64,
+}
+
+impl Counts {
+    const ZERO: Self = Self {
+        builder_reallocations: 0,
+        buffer_recreations: 0,
+        allocations: 0,
+        cache_hits: 0,
+        cache_misses: 0,
+    };
+
+    /// What was counted between `earlier` and these
+    pub fn since(&self, earlier: &Self) -> Self {
+        Self {
+            builder_reallocations: self.builder_reallocations - earlier.builder_reallocations,
+            buffer_recreations: self.buffer_recreations - earlier.buffer_recreations,
+            allocations: self.allocations - earlier.allocations,
+            cache_hits: self.cache_hits - earlier.cache_hits,
+            cache_misses: self.cache_misses - earlier.cache_misses,
+        }
+    }
+}
+
+impl std::ops::AddAssign for Counts {
+    fn add_assign(&mut self, other: Self) {
+        self.builder_reallocations += other.builder_reallocations;
+        self.buffer_recreations += other.buffer_recreations;
+        self.allocations += other.allocations;
+        self.cache_hits += other.cache_hits;
+        self.cache_misses += other.cache_misses;
+    }
+}
+
+thread_local! {
+    // const so reaching it never allocates, as the allocator itself does
+    static COUNTS: Cell<Counts> = const { Cell::new(Counts::ZERO) };
+}
+
+/// Everything counted on this thread so far
+pub fn counts() -> Counts {
+    COUNTS.with(Cell::get)
+}
+
+pub(crate) fn count(update: impl FnOnce(&mut Counts)) {
+    // gone while the thread is being torn down, when there's nothing left to count for
+    let _ = COUNTS.try_with(|counts| {
+        let mut updated = counts.get();
+        update(&mut updated);
+        counts.set(updated);
+    });
+}
+
+/// Counts handed from one thread to another, as the render thread's for a frame are to the game
+/// thread to draw
+#[derive(Debug, Default)]
+pub struct SharedCounts([AtomicU64; 5]);
+
+impl SharedCounts {
+    pub fn store(&self, counts: Counts) {
+        let values = [
+            counts.builder_reallocations,
+            counts.buffer_recreations,
+            counts.allocations,
+            counts.cache_hits,
+            counts.cache_misses,
+        ];
+        for (shared, value) in self.0.iter().zip(values) {
+            shared.store(value, Ordering::Relaxed);
+        }
+    }
+
+    pub fn load(&self) -> Counts {
+        let [builder_reallocations, buffer_recreations, allocations, cache_hits, cache_misses] =
+            self.0
+                .each_ref()
+                .map(|shared| shared.load(Ordering::Relaxed));
+        Counts {
+            builder_reallocations,
+            buffer_recreations,
+            allocations,
+            cache_hits,
+            cache_misses,
+        }
+    }
+}
+
+/// The system allocator, counting each allocation against the thread making it
+#[cfg(feature = "track-alloc")]
+pub struct CountingAllocator;
+
+#[cfg(feature = "track-alloc")]
+unsafe impl std::alloc::GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        count(|counts| counts.allocations += 1);
+        std::alloc::System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: std::alloc::Layout) -> *mut u8 {
+        count(|counts| counts.allocations += 1);
+        std::alloc::System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: std::alloc::Layout, size: usize) -> *mut u8 {
+        count(|counts| counts.allocations += 1);
+        std::alloc::System.realloc(ptr, layout, size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        std::alloc::System.dealloc(ptr, layout)
+    }
+}
+
+#[test]
+fn counts_stay_on_their_own_thread() {
+    let before = counts();
+    count(|counts| counts.buffer_recreations += 2);
+    std::thread::spawn(|| count(|counts| counts.builder_reallocations += 1))
+        .join()
+        .unwrap();
+    assert_eq!(
+        counts().since(&before),
+        Counts {
+            buffer_recreations: 2,
+            ..Counts::default()
+        }
+    );
+
+    let shared = SharedCounts::default();
+    shared.store(counts());
+    assert_eq!(shared.load(), counts());
+}
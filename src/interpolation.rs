@@ -0,0 +1,163 @@
+use crate::renderer::background::BackgroundKind;
+use crate::renderer::buffer::{MeshBuilder, ParticleInstance};
+use cgmath::{InnerSpace, Vector2, VectorSpace};
+use std::ops::Range;
+
+/// Where everything that moves smoothly between ticks was on a tick
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    pub ball: Vector2<f32>,
+    /// The paddles' x positions
+    pub paddles: [f32; 2],
+    pub camera_x: f32,
+    pub zoom: f32,
+}
+
+impl Pose {
+    /// Anything moving further than this in a tick jumped there, like a ball put back on the
+    /// paddle or sent through a portal, so is shown there straight away rather than slid across
+    const MAX_STEP: f32 = 0.5;
+
+    /// `t` of the way from this pose to `next`
+    pub fn lerp(&self, next: &Self, t: f32) -> Self {
+        let mix = |from: f32, to: f32| {
+            if (to - from).abs() > Self::MAX_STEP {
+                to
+            } else {
+                from + (to - from) * t
+            }
+        };
+
+        Self {
+            ball: if (next.ball - self.ball).magnitude() > Self::MAX_STEP {
+                next.ball
+            } else {
+                self.ball.lerp(next.ball, t)
+            },
+            paddles: [0, 1].map(|i| mix(self.paddles[i], next.paddles[i])),
+            camera_x: mix(self.camera_x, next.camera_x),
+            zoom: mix(self.zoom, next.zoom),
+        }
+    }
+}
+
+/// A tick's mesh along with the pose it was drawn at and the vertices drawing each moving part, so
+/// it can be redrawn at a pose partway to the next tick
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub mesh: MeshBuilder,
+    /// Where the hud starts in the mesh's indices, drawn over the particles rather than under
+    pub hud: usize,
+    /// Drawn over the mesh where they are on the tick, as they're too short-lived to slide
+    pub particles: Vec<ParticleInstance>,
+    /// Drawn behind the mesh by a shader of its own
+    pub background: BackgroundKind,
+    pub pose: Pose,
+    pub ball: Range<usize>,
+    pub paddles: [Range<usize>; 2],
+}
+
+impl Frame {
+    /// The mesh with the ball and paddles moved to where `pose` has them
+    pub fn mesh_at(&self, pose: &Pose) -> MeshBuilder {
+        let mut mesh = MeshBuilder::default();
+        self.mesh_at_into(pose, &mut mesh);
+        mesh
+    }
+
+    /// Like `mesh_at`, but built over `mesh` to reuse its capacity
+    pub fn mesh_at_into(&self, pose: &Pose, mesh: &mut MeshBuilder) {
+        mesh.copy_from(&self.mesh);
+        mesh.translate(self.ball.clone(), pose.ball - self.pose.ball);
+        for (i, vertices) in self.paddles.iter().enumerate() {
+            let offset = Vector2::new(pose.paddles[i] - self.pose.paddles[i], 0.);
+            mesh.translate(vertices.clone(), offset);
+        }
+    }
+}
+
+#[cfg(test)]
+fn square(mesh: &mut MeshBuilder, center: Vector2<f32>) -> Range<usize> {
+    let start = mesh.vertex_count();
+    mesh.push_rect(
+        center - Vector2::new(0.1, 0.1),
+        center + Vector2::new(0.1, 0.1),
+        [1.; 4],
+    );
+    start..mesh.vertex_count()
+}
+
+#[cfg(test)]
+fn vertices(mesh: &MeshBuilder, range: Range<usize>) -> Vec<[f32; 2]> {
+    mesh.vertices()[range]
+        .iter()
+        .map(|vertex| vertex.position)
+        .collect()
+}
+
+#[test]
+fn frame_is_redrawn_partway_to_the_next_tick() {
+    let previous = Pose {
+        ball: Vector2::new(0., 0.),
+        paddles: [0., 1.],
+        camera_x: 0.,
+        zoom: 1.,
+    };
+    let current = Pose {
+        ball: Vector2::new(0.2, 0.),
+        paddles: [0.1, 1.],
+        camera_x: 0.1,
+        zoom: 1.,
+    };
+
+    let mut mesh = MeshBuilder::default();
+    let ball = square(&mut mesh, current.ball);
+    let paddles = [
+        square(&mut mesh, Vector2::new(0.1, -0.7)),
+        square(&mut mesh, Vector2::new(1., 0.7)),
+    ];
+    let frame = Frame {
+        hud: mesh.index_count(),
+        mesh,
+        particles: Vec::new(),
+        background: BackgroundKind::default(),
+        pose: current,
+        ball: ball.clone(),
+        paddles: paddles.clone(),
+    };
+
+    let halfway = previous.lerp(&current, 0.5);
+    assert_eq!(halfway.camera_x, 0.05);
+    let moved = frame.mesh_at(&halfway);
+    let mut expected = MeshBuilder::default();
+    square(&mut expected, Vector2::new(0.1, 0.));
+    square(&mut expected, Vector2::new(0.05, -0.7));
+    square(&mut expected, Vector2::new(1., 0.7));
+    for range in [ball, paddles[0].clone(), paddles[1].clone()] {
+        for (a, b) in vertices(&moved, range.clone())
+            .iter()
+            .zip(vertices(&expected, range))
+        {
+            assert!((a[0] - b[0]).abs() < 1e-6 && (a[1] - b[1]).abs() < 1e-6);
+        }
+    }
+
+    // all the way there is just the tick as drawn
+    let there = frame.mesh_at(&previous.lerp(&current, 1.));
+    assert_eq!(there.checksum(), frame.mesh.checksum());
+
+    // a ball served again from the paddle snaps back rather than sliding
+    let served = Pose {
+        ball: Vector2::new(0., -0.6),
+        ..current
+    };
+    assert_eq!(
+        Pose {
+            ball: Vector2::new(0., 0.9),
+            ..previous
+        }
+        .lerp(&served, 0.1)
+        .ball,
+        served.ball
+    );
+}
@@ -0,0 +1,331 @@
+use crate::collison;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The levels built into the binary, played in order
+const BUNDLED: [&str; 3] = [
+    include_str!("../levels/1-warm-up.toml"),
+    include_str!("../levels/2-checkers.toml"),
+    include_str!("../levels/3-fortress.toml"),
+];
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BrickType {
+    /// Rgb, each in 0..=1
+    pub color: [f32; 3],
+    #[serde(default = "BrickType::default_hit_points")]
+    pub hit_points: u32,
+}
+
+impl BrickType {
+    fn default_hit_points() -> u32 {
+        1
+    }
+}
+
+/// How a moving obstacle carries on after its last waypoint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PathMode {
+    /// Back the way it came
+    #[default]
+    PingPong,
+    /// Straight on to the first
+    Loop,
+}
+
+/// An obstacle patrolling between waypoints
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MovingObstacle {
+    /// A convex polygon's corners, relative to its position on the path
+    pub shape: Vec<[f32; 2]>,
+    pub waypoints: Vec<[f32; 2]>,
+    /// Distance travelled per second
+    pub speed: f32,
+    #[serde(default)]
+    pub path: PathMode,
+}
+
+/// One end of a portal pair
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PortalEnd {
+    pub center: [f32; 2],
+    /// Degrees anticlockwise, the difference between the ends turning the ball as it passes
+    /// through
+    #[serde(default)]
+    pub angle: f32,
+}
+
+/// Two circles the ball passes between, entering either and leaving the other
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PortalPair {
+    pub a: PortalEnd,
+    pub b: PortalEnd,
+    #[serde(default = "PortalPair::default_radius")]
+    pub radius: f32,
+}
+
+impl PortalPair {
+    fn default_radius() -> f32 {
+        0.15
+    }
+}
+
+/// A rectangle accelerating any ball inside it
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ForceZone {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+    /// Units per second squared, added to those of any zones it overlaps
+    pub acceleration: [f32; 2],
+}
+
+/// What the zone at the top of the screen does in single player
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Ceiling {
+    /// Scores a point past the ai
+    #[default]
+    Goal,
+    /// Loses the ball like the bottom zone, for levels where it can float upwards
+    Lose,
+}
+
+/// A level as written in a toml file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Level {
+    pub name: String,
+    /// The ball bounces off walls at this x position on either side
+    #[serde(default = "Level::default_wall_x")]
+    pub wall_x: f32,
+    /// Which way gravity pulls, its length scaling the difficulty's gravity
+    #[serde(default = "Level::default_gravity")]
+    pub gravity: [f32; 2],
+    #[serde(default)]
+    pub ceiling: Ceiling,
+    /// Where along the paddle serves start from, before their random offset
+    #[serde(default)]
+    pub serve_offset: f32,
+    #[serde(default = "Level::default_ball_radius")]
+    pub ball_radius: f32,
+    #[serde(default)]
+    pub background: [f32; 3],
+    /// Rows of the brick grid from the top down, with a character per cell naming its type in
+    /// `types`, or `.` for no brick
+    #[serde(default)]
+    pub bricks: Vec<String>,
+    #[serde(default)]
+    pub types: BTreeMap<char, BrickType>,
+    /// Indestructible convex polygons, each a list of corners
+    #[serde(default)]
+    pub obstacles: Vec<Vec<[f32; 2]>>,
+    #[serde(default)]
+    pub moving_obstacles: Vec<MovingObstacle>,
+    #[serde(default)]
+    pub portals: Vec<PortalPair>,
+    #[serde(default)]
+    pub force_zones: Vec<ForceZone>,
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(std::io::Error),
+    /// Malformed toml or fields, with the line and field it was found at
+    Parse(toml::de::Error),
+    UnknownBrick {
+        row: usize,
+        column: usize,
+        cell: char,
+    },
+    NoHitPoints(char),
+    BallRadius(f32),
+    /// The index of an obstacle that isn't a convex polygon
+    NotConvex(usize),
+    /// The index of a moving obstacle and what's wrong with it
+    MovingObstacle(usize, &'static str),
+    /// The index of a portal pair with a radius that isn't positive
+    PortalRadius(usize),
+    /// The index of a force zone whose minimum corner isn't below and left of its maximum
+    ForceZone(usize),
+}
+
+impl std::fmt::Display for LevelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Parse(err) => write!(f, "{err}"),
+            Self::UnknownBrick { row, column, cell } => write!(
+                f,
+                "bricks row {row}, column {column}: no brick type `{cell}` in [types]"
+            ),
+            Self::NoHitPoints(cell) => write!(f, "types.{cell}: hit_points must be at least 1"),
+            Self::BallRadius(radius) => write!(f, "ball_radius must be positive, not {radius}"),
+            Self::NotConvex(i) => write!(f, "obstacles[{i}]: must be a convex polygon"),
+            Self::MovingObstacle(i, problem) => write!(f, "moving_obstacles[{i}]: {problem}"),
+            Self::PortalRadius(i) => write!(f, "portals[{i}]: radius must be positive"),
+            Self::ForceZone(i) => write!(f, "force_zones[{i}]: min must be below and left of max"),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl Level {
+    pub const EMPTY: char = '.';
+
+    fn default_wall_x() -> f32 {
+        crate::WALL_X
+    }
+
+    fn default_gravity() -> [f32; 2] {
+        [0., -1.]
+    }
+
+    fn default_ball_radius() -> f32 {
+        crate::Ball::default().radius
+    }
+
+    pub fn parse(source: &str) -> Result<Self, LevelError> {
+        let level: Self = toml::from_str(source).map_err(LevelError::Parse)?;
+
+        if level.ball_radius <= 0. || !level.ball_radius.is_finite() {
+            return Err(LevelError::BallRadius(level.ball_radius));
+        }
+
+        for (cell, brick) in &level.types {
+            if brick.hit_points == 0 {
+                return Err(LevelError::NoHitPoints(*cell));
+            }
+        }
+
+        for (i, points) in level.obstacles.iter().enumerate() {
+            let points = points.iter().map(|&point| point.into()).collect::<Vec<_>>();
+            if !collison::is_convex(&points) {
+                return Err(LevelError::NotConvex(i));
+            }
+        }
+
+        for (i, obstacle) in level.moving_obstacles.iter().enumerate() {
+            let shape = obstacle
+                .shape
+                .iter()
+                .map(|&point| point.into())
+                .collect::<Vec<_>>();
+            if !collison::is_convex(&shape) {
+                return Err(LevelError::MovingObstacle(
+                    i,
+                    "shape must be a convex polygon",
+                ));
+            }
+            if obstacle.waypoints.is_empty() {
+                return Err(LevelError::MovingObstacle(i, "needs at least one waypoint"));
+            }
+            if obstacle.speed < 0. || !obstacle.speed.is_finite() {
+                return Err(LevelError::MovingObstacle(i, "speed can't be negative"));
+            }
+        }
+
+        for (i, portal) in level.portals.iter().enumerate() {
+            if portal.radius <= 0. || !portal.radius.is_finite() {
+                return Err(LevelError::PortalRadius(i));
+            }
+        }
+
+        for (i, zone) in level.force_zones.iter().enumerate() {
+            if zone.min[0] >= zone.max[0] || zone.min[1] >= zone.max[1] {
+                return Err(LevelError::ForceZone(i));
+            }
+        }
+
+        for (row, line) in level.bricks.iter().enumerate() {
+            for (column, cell) in line.chars().enumerate() {
+                if cell != Self::EMPTY && !level.types.contains_key(&cell) {
+                    return Err(LevelError::UnknownBrick {
+                        row: row + 1,
+                        column: column + 1,
+                        cell,
+                    });
+                }
+            }
+        }
+
+        Ok(level)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, LevelError> {
+        Self::parse(&std::fs::read_to_string(path).map_err(LevelError::Io)?)
+    }
+
+    pub fn bundled() -> Vec<Self> {
+        BUNDLED
+            .iter()
+            .map(|source| Self::parse(source).expect("bundled levels are valid"))
+            .collect()
+    }
+}
+
+#[test]
+fn bundled_levels_parse() {
+    let levels = Level::bundled();
+    assert_eq!(levels.len(), BUNDLED.len());
+    assert_eq!(levels[0].wall_x, crate::WALL_X);
+    assert_eq!(levels[0].gravity, [0., -1.]);
+}
+
+#[test]
+fn level_errors_say_where() {
+    let err = Level::parse("name = \"x\"\nwall_x = \"wide\"\n").unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("line 2"), "{message}");
+    assert!(message.contains("wall_x"), "{message}");
+
+    let err = Level::parse(
+        r#"
+        name = "x"
+        bricks = ["RR", "R?"]
+        types.R = { color = [1.0, 0.0, 0.0] }
+        "#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "bricks row 2, column 2: no brick type `?` in [types]"
+    );
+
+    let err = Level::parse(
+        r#"
+        name = "x"
+        types.R = { color = [1.0, 0.0, 0.0], hit_points = 0 }
+        "#,
+    )
+    .unwrap_err();
+    assert!(matches!(err, LevelError::NoHitPoints('R')));
+
+    let err = Level::parse("name = \"x\"\nball_radius = 0.0\n").unwrap_err();
+    assert_eq!(err.to_string(), "ball_radius must be positive, not 0");
+
+    let err = Level::parse("name = \"x\"\nobstacles = [[[0.0, 0.0], [1.0, 0.0]]]\n").unwrap_err();
+    assert_eq!(err.to_string(), "obstacles[0]: must be a convex polygon");
+
+    let err = Level::parse(
+        r#"
+        name = "x"
+        [[moving_obstacles]]
+        shape = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]
+        waypoints = []
+        speed = 1.0
+        "#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "moving_obstacles[0]: needs at least one waypoint"
+    );
+}
@@ -0,0 +1,1539 @@
+use bricks::{BrickHit, Bricks};
+use cgmath::prelude::*;
+use cgmath::Vector2;
+use collison::{Obb, Shape};
+use contact::{Contact, ContactTracker};
+use dash::Dash;
+use difficulty::Preset;
+use highscore::HighScoreTables;
+use menu::Mode;
+use modifiers::WidthModifiers;
+use obstacles::Obstacles;
+use palette::{Palette, Role};
+use portals::Portals;
+use rand::Rng;
+use renderer::buffer::{MeshBuilder, Vertex};
+use renderer::shapes::SegmentCount;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{FRAC_PI_8, TAU};
+use tunables::Tunables;
+use zones::ForceZone;
+
+mod ai;
+pub mod audio;
+pub mod bindings;
+mod bricks;
+mod camera;
+pub mod collison;
+mod combo;
+pub mod config;
+mod contact;
+mod dash;
+mod difficulty;
+mod digits;
+pub mod game;
+mod ghost;
+pub mod highscore;
+pub mod instrument;
+pub mod interpolation;
+pub mod level;
+mod magnet;
+mod menu;
+mod modifiers;
+mod obstacles;
+pub mod palette;
+pub mod particles;
+mod picking;
+mod portals;
+mod powerups;
+pub mod renderer;
+pub mod replay;
+pub mod scheduler;
+mod share;
+mod shield;
+mod speedrun;
+pub mod stats;
+mod storage;
+mod trail;
+mod tunables;
+mod tween;
+mod zones;
+
+pub const TICK: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// The ball bounces off walls at this x position on either side
+const WALL_X: f32 = 5.5;
+
+/// The ball's number among the bodies whose contacts are tracked, with paddles numbered by their
+/// index
+const BALL: usize = 0;
+
+/// Paddle hits with the ball moving faster than this shake the camera
+const HARD_HIT_SPEED: f32 = 0.06;
+
+struct LoseZone {
+    /// -1 for the bottom of the screen, 1 for the top
+    side: f32,
+}
+
+impl LoseZone {
+    const HEIGHT: f32 = 0.1;
+    /// What the zone flashes when the ball enters it
+    const FLASH_COLOR: [f32; 4] = [1., 1., 1., 1.];
+
+    /// Pushes the zone, shifted towards `flash_color` by `flash` in 0..=1
+    fn push(&self, mesh: &mut MeshBuilder, flash: f32, flash_color: [f32; 4], palette: &Palette) {
+        let edge = self.side;
+        let inner = self.side * (1. - Self::HEIGHT);
+        let (bottom, top) = (edge.min(inner), edge.max(inner));
+        let base = palette.rgba(Role::LoseZone, 1.);
+        let color: [f32; 4] = std::array::from_fn(|i| base[i] + (flash_color[i] - base[i]) * flash);
+
+        mesh.push(
+            [[-10., bottom], [10., bottom], [10., top], [-10., top]]
+                .map(|position| Vertex { position, color }),
+            [0, 1, 2, 0, 2, 3],
+        )
+    }
+
+    /// Whether the ball has gone far enough into the zone to be lost to it, its centre past the
+    /// zone's inner edge
+    fn holds(&self, ball: &Ball) -> bool {
+        self.shape()
+            .intersect_circle(ball.position, ball.radius)
+            .is_some_and(|hit| hit.depth >= ball.radius)
+    }
+
+    /// The zone's collider, as wide as the one drawn and reaching well off the screen, so a ball
+    /// that's gone past the edge of the screen is still in it
+    fn shape(&self) -> Shape {
+        let (inner, outer) = (self.side * (1. - Self::HEIGHT), self.side * 10.);
+        Shape::Aabb {
+            min: Vector2::new(-10., outer.min(inner)),
+            max: Vector2::new(10., outer.max(inner)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum BallState {
+    /// Sitting on the paddle waiting to be served
+    Attached,
+    Free,
+    /// Shrinking away after reaching a zone, before the point goes to the scorer
+    Despawning(Player),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Ball {
+    position: Vector2<f32>,
+    velocity: Vector2<f32>,
+    /// Angular velocity in radians per tick, anticlockwise
+    spin: f32,
+    rotation: f32,
+    state: BallState,
+    radius: f32,
+}
+
+impl Default for Ball {
+    fn default() -> Self {
+        Self {
+            position: Vector2::zero(),
+            velocity: Vector2::zero(),
+            spin: 0.,
+            rotation: 0.,
+            state: BallState::Attached,
+            radius: 0.05,
+        }
+    }
+}
+
+impl Ball {
+    const SERVE_SPEED: f32 = 0.06;
+    const MAX_SUBSTEPS: usize = 8;
+    /// Edges of the collider's outline per unit of radius, so smaller balls get fewer
+    const SEGMENTS_PER_RADIUS: f32 = 400.;
+    /// Edges on the circle drawn for the ball, as many as it takes to look round however close
+    /// the camera's zoomed in
+    const SEGMENTS: SegmentCount = SegmentCount::Adaptive {
+        pixels_per_segment: 5.,
+    };
+
+    /// Edges on the outline of the ball's collider
+    fn segments(&self) -> usize {
+        ((self.radius * Self::SEGMENTS_PER_RADIUS).round() as usize).clamp(8, 64)
+    }
+
+    /// Points around the edge of the ball's collider
+    fn outline(&self) -> Vec<Vector2<f32>> {
+        let segments = self.segments();
+        (0..segments)
+            .map(|i| {
+                let (s, c) = (i as f32 / segments as f32 * TAU).sin_cos();
+                self.position + Vector2::new(c, s) * self.radius
+            })
+            .collect()
+    }
+
+    /// Changes the radius, pushing the ball back out of any wall or paddle it now overlaps
+    fn resize(&mut self, radius: f32, paddles: &[Paddle], wall_x: f32) {
+        self.radius = radius;
+
+        for (collider, shape) in colliders(paddles, wall_x) {
+            let Some(hit) = shape.intersect_circle(self.position, radius) else {
+                continue;
+            };
+            match collider {
+                Collider::Wall(_) => self.position += hit.normal * hit.depth,
+                Collider::Paddle(i) => {
+                    // out past whichever face the ball is nearer
+                    let paddle = &paddles[i];
+                    let normal = paddle.surface_normal();
+                    let offset = (self.position - Vector2::new(paddle.x, paddle.y())).dot(normal);
+                    let clearance = paddle.height / 2. + radius + f32::EPSILON.sqrt();
+                    self.position += normal * (offset.signum() * clearance - offset);
+                }
+            }
+        }
+    }
+
+    /// Pushes the ball scaled and faded by `size` in 0..=1
+    fn push(&self, mesh: &mut MeshBuilder, size: f32, palette: &Palette) {
+        let radius = self.radius * size;
+        let color = palette.rgba(Role::Ball, size);
+        mesh.push_circle(self.position, radius, Self::SEGMENTS, color);
+
+        // a marker showing the ball's spin
+        let (s, c) = self.rotation.sin_cos();
+        mesh.push_circle(
+            self.position + Vector2::new(c, s) * radius * 0.6,
+            radius * 0.25,
+            SegmentCount::Fixed(8),
+            palette.rgba(Role::BallSpin, size),
+        );
+    }
+
+    fn apply_gravity(&mut self, tunables: &Tunables) {
+        self.velocity += tunables.gravity;
+    }
+
+    /// Accelerates the ball by every force zone it's in
+    fn apply_zones(&mut self, tunables: &Tunables) {
+        self.velocity += ForceZone::total(&tunables.force_zones, self.position);
+    }
+
+    /// Pulls the ball towards the player's magnet if it's held
+    fn apply_magnet(&mut self, tunables: &Tunables) {
+        if let Some(attractor) = tunables.attractor {
+            self.velocity += attractor.acceleration(self.position);
+        }
+    }
+
+    /// Curves the ball's flight perpendicular to its velocity, and lets the spin wear off
+    fn apply_spin(&mut self, tunables: &Tunables) {
+        let perpendicular = Vector2::new(-self.velocity.y, self.velocity.x);
+        self.velocity += perpendicular * self.spin * tunables.magnus;
+        self.rotation = (self.rotation + self.spin) % TAU;
+        self.spin *= tunables.spin_decay;
+    }
+
+    fn apply_drag(&mut self, tunables: &Tunables) {
+        let max_speed = tunables.max_speed;
+        self.velocity = self.velocity.map(|x| x * 0.95);
+        self.velocity = self.velocity.map(|i| i.clamp(-max_speed, max_speed));
+    }
+
+    /// Reflects the velocity off a surface with the given outward normal
+    fn bounce(&mut self, normal: Vector2<f32>, tunables: &Tunables) {
+        let into = self.velocity.dot(normal);
+        if into < 0. {
+            self.velocity -= normal * 2. * into;
+        }
+
+        // the spinning surface grips what it hits, throwing the ball along it
+        let tangent = Vector2::new(-normal.y, normal.x);
+        self.velocity += tangent * self.spin * tunables.wall_spin_bias;
+        self.spin *= 0.5;
+    }
+
+    /// Bounces off a surface moving at `surface_velocity`, keeping `restitution` of the speed into
+    /// it and losing `friction` of the speed along it
+    fn rebound(
+        &mut self,
+        normal: Vector2<f32>,
+        surface_velocity: Vector2<f32>,
+        tunables: &Tunables,
+    ) {
+        let relative = self.velocity - surface_velocity;
+        let into = relative.dot(normal);
+        if into >= 0. {
+            return;
+        }
+
+        let normal_part = normal * into;
+        let tangent_part = relative - normal_part;
+        self.velocity = surface_velocity - normal_part * tunables.restitution
+            + tangent_part * (1. - tunables.friction);
+    }
+
+    /// How many pieces to split this tick's movement into so none travels further than the
+    /// ball's radius
+    fn substeps(&self) -> usize {
+        ((self.velocity.magnitude() / self.radius).ceil() as usize).clamp(1, Self::MAX_SUBSTEPS)
+    }
+
+    /// Moves the ball by `fraction` of its velocity, leaving what it's moved into to
+    /// [`meet_colliders`]
+    fn advance(&mut self, fraction: f32) {
+        self.position += self.velocity * fraction;
+    }
+
+    /// The positions of the ball's free flight over the next `ticks` ticks, ignoring the paddle
+    fn predict(&self, ticks: usize, tunables: &Tunables) -> Vec<Vector2<f32>> {
+        let mut ball = self.clone();
+        std::iter::once(ball.position)
+            .chain((0..ticks).map(|_| {
+                ball.apply_gravity(tunables);
+                ball.apply_zones(tunables);
+                ball.apply_magnet(tunables);
+                ball.apply_spin(tunables);
+                ball.apply_drag(tunables);
+                ball.advance(1.);
+                meet_colliders(&mut ball, &[], &ContactTracker::default(), tunables);
+                ball.position
+            }))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Paddle {
+    x: f32,
+    /// A value in -1..=1 for the paddle's x velocity
+    velocity: f32,
+    /// 1 for the player's paddle at the bottom facing up, -1 for one mirrored at the top
+    facing: f32,
+    /// Eased towards the modified width each tick rather than snapping to it
+    width: f32,
+    modifiers: WidthModifiers,
+    dash: Dash,
+    height: f32,
+    /// Tilt per unit of velocity
+    angle_multiplier: f32,
+}
+
+impl Default for Paddle {
+    fn default() -> Self {
+        Self {
+            x: 0.,
+            velocity: 0.,
+            facing: 1.,
+            width: 0.4,
+            modifiers: WidthModifiers::default(),
+            dash: Dash::default(),
+            height: 0.2,
+            angle_multiplier: FRAC_PI_8,
+        }
+    }
+}
+
+impl Paddle {
+    /// Distance from the centre of the screen
+    const DISTANCE: f32 = 0.7;
+    const NORMAL_ANGLE_MULTIPLIER: f32 = FRAC_PI_8 / 2.;
+    /// The furthest the struck normal leans back towards the middle, short of a right angle so
+    /// a paddle far out in a wide level still strikes the ball forwards
+    const MAX_LEAN: f32 = 3. * FRAC_PI_8;
+    /// Fraction of the way to its target width the paddle moves each tick
+    const RESIZE_RATE: f32 = 0.1;
+
+    fn new(facing: f32) -> Self {
+        Self {
+            facing,
+            ..Self::default()
+        }
+    }
+
+    fn y(&self) -> f32 {
+        -self.facing * Self::DISTANCE
+    }
+
+    /// Tilt anticlockwise, mirrored along with the paddle
+    fn angle(&self) -> f32 {
+        self.velocity * self.angle_multiplier * self.facing
+    }
+
+    fn steer(&mut self, controls: &Controls) {
+        // a dash holds the paddle at full tilt, whatever's pressed
+        if let Some(direction) = self.dash.direction() {
+            self.velocity = direction;
+            return;
+        }
+
+        match (controls.left.is_held(), controls.right.is_held()) {
+            (true, false) => {
+                self.velocity = (self.velocity - 0.05).max(-1.0);
+            }
+            (false, true) => {
+                self.velocity = (self.velocity + 0.05).min(1.0);
+            }
+            _ if controls.axis.abs() > Controls::DEADZONE => {
+                let axis = controls.axis;
+                self.velocity += (axis - self.velocity).clamp(-0.05, 0.05);
+            }
+            _ => {
+                self.velocity *= 0.95;
+            }
+        }
+    }
+
+    /// Eases the width towards `base_width` with the modifiers applied, holding off growing while
+    /// that would put the paddle over the ball
+    fn resize(&mut self, base_width: f32, ball: &Ball) {
+        self.modifiers.update(TICK.as_secs_f32());
+        let target = self.modifiers.apply(base_width);
+
+        let inside = self.contains(ball);
+        let previous = self.width;
+        self.width += (target - self.width) * Self::RESIZE_RATE;
+        if self.width > previous && !inside && self.contains(ball) {
+            self.width = previous;
+        }
+    }
+
+    /// Lurches the paddle the way it's moving, returning whether it could
+    fn dash(&mut self) -> bool {
+        self.dash.start(self.velocity)
+    }
+
+    fn advance(&mut self, wall_x: f32) {
+        let speed = if self.dash.direction().is_some() {
+            Dash::SPEED
+        } else {
+            1.
+        };
+        self.x = (self.x + self.velocity / 20. * speed).clamp(-wall_x, wall_x);
+        // stopping at the wall rather than pressing on into it
+        if self.x.abs() >= wall_x {
+            self.dash.stop();
+        }
+        self.dash.update();
+    }
+
+    /// The paddle's collider, turned with its tilt
+    fn obb(&self) -> Obb {
+        Obb {
+            center: Vector2::new(self.x, self.y()),
+            half_extents: Vector2::new(self.width, self.height) / 2.,
+            rotation: self.angle(),
+        }
+    }
+
+    fn shape(&self) -> Shape {
+        Shape::Obb(self.obb())
+    }
+
+    fn points(&self) -> [Vector2<f32>; 4] {
+        self.obb().corners()
+    }
+
+    fn push(&self, mesh: &mut MeshBuilder, palette: &Palette) {
+        let color = palette.rgba(Role::Paddle, 1.);
+        mesh.push(
+            self.points().map(|v| Vertex {
+                position: [v.x, v.y],
+                color,
+            }),
+            [0, 1, 2, 0, 2, 3],
+        );
+
+        let behind = Vector2::new(self.x, self.y() - self.facing * (self.height / 2. + 0.04));
+        self.dash.push(mesh, behind, self.width);
+    }
+
+    fn contains(&self, ball: &Ball) -> bool {
+        self.touches(ball.position, ball.radius)
+    }
+
+    /// Whether a circle overlaps the paddle
+    fn touches(&self, center: Vector2<f32>, radius: f32) -> bool {
+        self.shape().intersect_circle(center, radius).is_some()
+    }
+
+    /// The normal of the paddle's playing face, following its tilt
+    fn surface_normal(&self) -> Vector2<f32> {
+        let (s, c) = self.angle().sin_cos();
+        Vector2::new(-s, c) * self.facing
+    }
+
+    /// Where a ball of the given radius rests on the playing face, `offset` along it from the
+    /// middle, kept inside the walls
+    fn attach_point(&self, radius: f32, offset: f32, wall_x: f32) -> Vector2<f32> {
+        let (s, c) = self.angle().sin_cos();
+        let along = Vector2::new(c, s) * offset;
+        let point = Vector2::new(self.x, self.y())
+            + along
+            + self.surface_normal() * (self.height / 2. + radius);
+
+        let limit = (wall_x - radius).max(0.);
+        Vector2::new(point.x.clamp(-limit, limit), point.y)
+    }
+
+    /// Launches along the face's normal turned anticlockwise by `angle`
+    fn serve_velocity(&self, angle: f32) -> Vector2<f32> {
+        let rotation: cgmath::Basis2<f32> = cgmath::Rotation2::from_angle(cgmath::Rad(angle));
+        rotation.rotate_vector(self.surface_normal()) * Ball::SERVE_SPEED
+            + Vector2::new(self.velocity / 20., 0.)
+    }
+
+    /// Bounces a ball touching the paddle off its face
+    fn strike(&self, ball: &mut Ball, tunables: &Tunables) {
+        let normal = self.normal();
+        let surface_velocity =
+            Vector2::new(self.velocity / 20., 0.) + normal * tunables.paddle_kick;
+        ball.rebound(normal, surface_velocity, tunables);
+    }
+
+    /// The unit direction balls are struck in, halfway between one tilted with the paddle's
+    /// movement and one leaning back towards the middle of the arena the further out the paddle
+    /// is. The lean is deliberate rather than following the paddle's tilt: it sends balls struck
+    /// out by a wall back into play instead of along the wall.
+    fn normal(&self) -> Vector2<f32> {
+        let lean = (self.x * Self::NORMAL_ANGLE_MULTIPLIER).clamp(-Self::MAX_LEAN, Self::MAX_LEAN);
+        let normal =
+            (rotated_up(self.velocity * Self::NORMAL_ANGLE_MULTIPLIER) + rotated_up(lean)) * 0.5;
+        Vector2::new(normal.x, normal.y * self.facing).normalize()
+    }
+}
+
+/// Straight up turned anticlockwise by `angle`
+fn rotated_up(angle: f32) -> Vector2<f32> {
+    let rotation: cgmath::Basis2<f32> = cgmath::Rotation2::from_angle(cgmath::Rad(angle));
+    rotation.rotate_vector(Vector2::unit_y())
+}
+
+/// Where on the paddle the ball waits to be served, and which way it will go
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct Serve {
+    /// Distance along the paddle's face from its middle
+    offset: f32,
+    /// Anticlockwise from the face's normal
+    angle: f32,
+}
+
+impl Serve {
+    const MAX_OFFSET: f32 = 0.1;
+    /// Width of the cone serves are launched in
+    const SPREAD: f32 = FRAC_PI_8;
+
+    /// A random serve, leaning left for a `bias` of 1 and right for -1
+    fn random(rng: &mut impl Rng, bias: f32) -> Self {
+        const FRAC_SPREAD_2: f32 = Serve::SPREAD / 2.;
+
+        Self {
+            offset: rng.gen_range(-Self::MAX_OFFSET..=Self::MAX_OFFSET),
+            angle: bias * FRAC_SPREAD_2 + rng.gen_range(-FRAC_SPREAD_2..=FRAC_SPREAD_2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Player {
+    /// At the bottom, on the arrow keys
+    One,
+    /// At the top, on A and D
+    Two,
+}
+
+impl Player {
+    fn index(self) -> usize {
+        match self {
+            Self::One => 0,
+            Self::Two => 1,
+        }
+    }
+
+    fn other(self) -> Self {
+        match self {
+            Self::One => Self::Two,
+            Self::Two => Self::One,
+        }
+    }
+}
+
+/// An action along with the tick it's meant for, when whatever sent it knows
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct InputEvent {
+    /// Set by recordings so they play back on the same ticks, while live input is handled as it
+    /// arrives
+    pub tick_hint: Option<u64>,
+    pub action: Action,
+}
+
+impl InputEvent {
+    pub fn at(tick: u64, action: Action) -> Self {
+        Self {
+            tick_hint: Some(tick),
+            action,
+        }
+    }
+}
+
+impl From<Action> for InputEvent {
+    fn from(action: Action) -> Self {
+        Self {
+            tick_hint: None,
+            action,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    Left(Player, ButtonState),
+    Right(Player, ButtonState),
+    /// How far a stick steering the player's paddle is pushed, from -1 for fully left to 1 for
+    /// fully right
+    PaddleAxis(Player, f32),
+    /// Starts a game from the menu, launches an attached ball, skips the countdown, or returns to
+    /// the menu after a game over
+    Serve,
+    Pause,
+    /// Selects the next difficulty up on the menu
+    Harder,
+    Easier,
+    /// The window stopped receiving input, so any held keys will never be released
+    FocusLost,
+    /// Makes player one's paddle dash the way it's moving
+    Dash,
+    /// Holds or releases player one's magnet, pulling the ball towards their paddle
+    Magnet(ButtonState),
+    /// Shows or hides the physics debug overlay
+    ToggleDebug,
+    /// Swaps to the next built-in palette
+    NextPalette,
+    /// Runs the simulation at this fraction of full speed, without changing its outcome
+    SetTimeScale(f32),
+    /// Writes out a bug report of the last stretch of play
+    ReportBug,
+    /// Asks to quit, which a game in progress has confirmed by asking again
+    Quit,
+    /// Copies the game's seed, score and time to the clipboard for sharing
+    CopyResult,
+    /// The cursor's moved over this point in the world
+    PointAt(Vector2<f32>),
+    /// Clicked where the cursor's pointing, which logs what's there while debugging
+    Pick,
+}
+
+impl Action {
+    /// Whether the action can change how the game plays out, and so is worth recording or
+    /// capturing for a bug report
+    pub fn changes_play(self) -> bool {
+        !matches!(
+            self,
+            Action::ReportBug | Action::CopyResult | Action::PointAt(_) | Action::Pick
+        )
+    }
+
+    /// Whether the action is a key or button going down, which is what calls off quitting
+    fn is_press(self) -> bool {
+        match self {
+            Action::Left(_, state) | Action::Right(_, state) | Action::Magnet(state) => {
+                state.is_pressed()
+            }
+            Action::Serve
+            | Action::Pause
+            | Action::Harder
+            | Action::Easier
+            | Action::Dash
+            | Action::ToggleDebug
+            | Action::NextPalette
+            | Action::Quit => true,
+            Action::PaddleAxis(..)
+            | Action::FocusLost
+            | Action::SetTimeScale(_)
+            | Action::ReportBug
+            | Action::CopyResult
+            | Action::PointAt(_)
+            | Action::Pick => false,
+        }
+    }
+}
+
+/// Whether a key or button is held, kept apart from the windowing library's own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ButtonState {
+    Pressed,
+    Released,
+}
+
+impl ButtonState {
+    pub fn is_pressed(self) -> bool {
+        self == Self::Pressed
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Phase {
+    Menu,
+    Playing,
+    GameOver,
+    /// Every brick has been cleared
+    Victory,
+    Paused,
+    /// Waiting for quitting to be confirmed, with the game frozen as when paused
+    ConfirmQuit,
+}
+
+/// How many of the keys bound to a control are down, so letting go of one while another is still
+/// held leaves the control held
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Held(u32);
+
+impl Held {
+    fn update(&mut self, state: ButtonState) {
+        self.0 = match state {
+            ButtonState::Pressed => self.0 + 1,
+            ButtonState::Released => self.0.saturating_sub(1),
+        };
+    }
+
+    fn is_held(self) -> bool {
+        self.0 > 0
+    }
+}
+
+/// A player's held keys and stick, where holding one of the direction keys takes precedence over
+/// the stick, which steers when neither or both are held
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Controls {
+    left: Held,
+    right: Held,
+    magnet: ButtonState,
+    axis: f32,
+}
+
+impl Controls {
+    const RELEASED: Self = Self {
+        left: Held(0),
+        right: Held(0),
+        magnet: ButtonState::Released,
+        axis: 0.,
+    };
+    /// Sticks rarely rest at exactly zero, so anything within this is taken as centred
+    const DEADZONE: f32 = 0.15;
+
+    /// Both sets of controls steering together, with keys held on either counting and the stick
+    /// pushed furthest winning
+    fn combine(&self, other: &Self) -> Self {
+        Self {
+            left: Held(self.left.0 + other.left.0),
+            right: Held(self.right.0 + other.right.0),
+            magnet: self.magnet,
+            axis: if other.axis.abs() > self.axis.abs() {
+                other.axis
+            } else {
+                self.axis
+            },
+        }
+    }
+}
+
+/// In two player mode, the first to this many points wins
+const WINNING_SCORE: u32 = 5;
+
+const BRICK_SCORE: u32 = 5;
+
+/// How long the ball stays frozen after a reset or unpausing, counting down from 3
+const COUNTDOWN: f32 = 1.5;
+
+/// How long the ball takes to shrink away after reaching a zone
+const DESPAWN: f32 = 0.3;
+
+/// How long a zone flashes when the ball reaches it
+const FLASH: f32 = 0.12;
+
+/// How long the screen's edges flash after copying the result, long enough to be noticed
+const COPY_FLASH: f32 = 0.5;
+
+/// What happened to a free ball during a tick
+#[derive(Debug, Default)]
+struct BallTick {
+    /// The index of the paddle the ball started touching, if any
+    paddle_hit: Option<usize>,
+    /// The sign of the x position of the wall hit, if any
+    wall_hit: Option<f32>,
+    brick_hits: Vec<BrickHit>,
+    /// Where the ball last touched an obstacle, if it did
+    obstacle_hit: Option<Vector2<f32>>,
+    /// Where the ball came out of a portal, if it went through one
+    teleported: Option<Vector2<f32>>,
+}
+
+/// What a shape the ball collides with belongs to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Collider {
+    /// The wall on the side with this sign
+    Wall(f32),
+    /// The paddle at this index
+    Paddle(usize),
+}
+
+/// The walls and paddles, each as the shape the ball collides with. The walls are slabs reaching
+/// well off the screen rather than lines, so a ball that's gone past one's face is still pushed
+/// back out of it the way it came.
+fn colliders(paddles: &[Paddle], wall_x: f32) -> impl Iterator<Item = (Collider, Shape)> + '_ {
+    let walls = [-1., 1.].map(|side: f32| {
+        let (face, back) = (side * wall_x, side * (wall_x + 10.));
+        let shape = Shape::Aabb {
+            min: Vector2::new(face.min(back), -10.),
+            max: Vector2::new(face.max(back), 10.),
+        };
+        (Collider::Wall(side), shape)
+    });
+    let paddles = paddles
+        .iter()
+        .enumerate()
+        .map(|(i, paddle)| (Collider::Paddle(i), paddle.shape()));
+    walls.into_iter().chain(paddles)
+}
+
+/// What the ball met in [`meet_colliders`]
+#[derive(Debug, Default)]
+struct Meeting {
+    /// The sign of the x position of the wall the ball bounced off, if any
+    wall_hit: Option<f32>,
+    /// Whether the ball reached a paddle it wasn't already touching
+    reached_paddle: bool,
+}
+
+/// Pushes the ball back out of any wall it's gone into, bouncing it off, and notes whether it's
+/// reached a paddle. Paddles are struck at the start of the next tick instead, once.
+fn meet_colliders(
+    ball: &mut Ball,
+    paddles: &[Paddle],
+    contacts: &ContactTracker,
+    tunables: &Tunables,
+) -> Meeting {
+    let mut meeting = Meeting::default();
+    for (collider, shape) in colliders(paddles, tunables.wall_x) {
+        let Some(hit) = shape.intersect_circle(ball.position, ball.radius) else {
+            continue;
+        };
+        match collider {
+            // only resting against it is no bounce, or a ball left on the wall would keep
+            // bouncing
+            Collider::Wall(side) if hit.depth > 0. => {
+                ball.position += hit.normal * hit.depth;
+                ball.bounce(hit.normal, tunables);
+                meeting.wall_hit = Some(side);
+            }
+            Collider::Wall(_) => {}
+            Collider::Paddle(i) => meeting.reached_paddle |= !contacts.is_touching((BALL, i)),
+        }
+    }
+    meeting
+}
+
+/// Applies a tick's forces to the ball then moves it, in `substeps` pieces if given or as many as
+/// its speed needs otherwise
+#[allow(clippy::too_many_arguments)]
+fn tick_ball(
+    ball: &mut Ball,
+    paddles: &mut [Paddle],
+    contacts: &mut ContactTracker,
+    bricks: &mut Bricks,
+    obstacles: &Obstacles,
+    portals: &mut Portals,
+    tunables: &Tunables,
+    substeps: Option<usize>,
+) -> BallTick {
+    let mut tick = BallTick::default();
+
+    ball.apply_gravity(tunables);
+    ball.apply_zones(tunables);
+    ball.apply_magnet(tunables);
+
+    // struck once as it meets a paddle, rather than again every tick it stays touching
+    for (collider, shape) in colliders(paddles, tunables.wall_x) {
+        let Collider::Paddle(i) = collider else {
+            continue;
+        };
+        let touching = shape.intersect_circle(ball.position, ball.radius).is_some();
+        if contacts.update((BALL, i), touching) == Some(Contact::Enter) {
+            let paddle = &paddles[i];
+            paddle.strike(ball, tunables);
+            // a moving paddle drags the face of the ball it touches along, spinning it
+            ball.spin = paddle.velocity * paddle.facing * tunables.spin_transfer;
+            tick.paddle_hit = Some(i);
+        }
+    }
+
+    ball.apply_spin(tunables);
+    ball.apply_drag(tunables);
+
+    // a moving obstacle can cross the ball between substeps, so the ball goes only as far as
+    // where they first meet and bounces off it as it moves, spending the rest of the tick below
+    let mut remaining = 1.;
+    if let Some(sweep) = obstacles.sweep(ball.position, ball.velocity, ball.radius) {
+        let time = sweep.toi.time;
+        ball.advance(time);
+        let meeting = meet_colliders(ball, paddles, contacts, tunables);
+        tick.wall_hit = meeting.wall_hit.or(tick.wall_hit);
+        ball.velocity -= sweep.velocity;
+        ball.bounce(sweep.toi.normal, tunables);
+        ball.velocity += sweep.velocity;
+        tick.obstacle_hit = Some(ball.position - sweep.toi.normal * ball.radius);
+        remaining -= time;
+    }
+
+    // forces apply once per tick above, so only the movement and collisions are split up
+    let substeps = substeps.unwrap_or_else(|| ball.substeps());
+    for step in 0..substeps {
+        ball.advance(remaining / substeps as f32);
+        let meeting = meet_colliders(ball, paddles, contacts, tunables);
+        tick.wall_hit = meeting.wall_hit.or(tick.wall_hit);
+
+        if let Some(hit) = bricks.collide(ball.position, ball.velocity, ball.radius) {
+            ball.bounce(hit.normal, tunables);
+            tick.brick_hits.push(hit);
+        }
+
+        if let Some(hit) = obstacles.collide(ball.position, ball.radius) {
+            ball.position += hit.normal * hit.depth;
+            ball.bounce(hit.normal, tunables);
+
+            // a moving obstacle carries the ball along rather than passing through it
+            let behind = (hit.velocity - ball.velocity).dot(hit.normal);
+            if behind > 0. {
+                ball.velocity += hit.normal * behind;
+            }
+            tick.obstacle_hit = Some(ball.position - hit.normal * ball.radius);
+        }
+
+        if let Some(exit) = portals.teleport(&mut ball.position, &mut ball.velocity) {
+            tick.teleported = Some(exit);
+        }
+
+        // stop on reaching a paddle rather than passing through it, leaving the hit to the next
+        // tick
+        let last = step + 1 == substeps;
+        if !last && meeting.reached_paddle {
+            break;
+        }
+    }
+
+    tick
+}
+
+/// Moves to the victory screen as the last brick goes, returning whether it just did. Only fires
+/// once however many bricks go in the final tick, as the phase has moved on.
+fn complete_level(phase: &mut Phase, bricks: &Bricks) -> bool {
+    if *phase == Phase::Playing && bricks.is_empty() {
+        *phase = Phase::Victory;
+        true
+    } else {
+        false
+    }
+}
+
+fn window_title(
+    high_scores: &HighScoreTables,
+    preset: Preset,
+    mode: Mode,
+    scores: [u32; 2],
+) -> String {
+    let best = high_scores.get(preset).and_then(|table| table.best());
+    match (mode, best) {
+        (Mode::TwoPlayer, _) => format!("WGPU fun - P1 {} : {} P2", scores[0], scores[1]),
+        (Mode::Practice, _) => format!("WGPU fun - {preset:?} practice"),
+        (Mode::Speedrun, _) => format!("WGPU fun - {preset:?} speedrun"),
+        (Mode::OnePlayer, Some(best)) => format!("WGPU fun - {preset:?} best: {best}"),
+        (Mode::OnePlayer, None) => format!("WGPU fun - {preset:?}"),
+    }
+}
+
+#[test]
+fn moving_paddle_spins_and_curves_a_dropped_ball() {
+    let tunables = Tunables::default();
+    let mut paddles = [Paddle {
+        velocity: 1.,
+        ..Paddle::new(1.)
+    }];
+    let mut contacts = ContactTracker::default();
+    let mut ball = Ball {
+        position: paddles[0].attach_point(0.05, 0., WALL_X) + Vector2::new(0., 0.05),
+        velocity: Vector2::new(0., -0.02),
+        state: BallState::Free,
+        ..Ball::default()
+    };
+
+    let hit = (0..10)
+        .map(|_| {
+            tick_ball(
+                &mut ball,
+                &mut paddles,
+                &mut contacts,
+                &mut Bricks::default(),
+                &Obstacles::default(),
+                &mut Portals::default(),
+                &tunables,
+                None,
+            )
+        })
+        .any(|tick| tick.paddle_hit.is_some());
+
+    assert!(hit);
+    assert!(ball.spin > 0.);
+
+    let curved = ball.predict(30, &tunables);
+    let straight = Ball {
+        spin: 0.,
+        ..ball.clone()
+    }
+    .predict(30, &tunables);
+    assert!((curved[30] - straight[30]).magnitude() > 0.01);
+}
+
+#[test]
+fn paddle_tilts_with_its_velocity() {
+    let level = Paddle::new(1.);
+    let [a, b, c, d] = level.points();
+    assert!(a.y == b.y && c.y == d.y && a.x == d.x && b.x == c.x);
+    assert!((level.normal() - Vector2::unit_y()).magnitude() < 1e-6);
+
+    for velocity in [-1., 1.] {
+        let paddle = Paddle {
+            velocity,
+            ..Paddle::new(1.)
+        };
+        let [a, b, ..] = paddle.points();
+        let edge = b - a;
+        let angle = edge.y.atan2(edge.x);
+        assert!((angle - velocity * paddle.angle_multiplier).abs() < 1e-6);
+        // anticlockwise turns the face, and the normal with it, towards -x
+        assert_eq!(paddle.normal().x.signum(), -velocity);
+        assert_eq!(paddle.surface_normal().x.signum(), -velocity);
+    }
+}
+
+#[test]
+fn paddle_normal_is_unit_and_leans_back_towards_the_middle() {
+    for facing in [1., -1.] {
+        for x in [-20., -WALL_X, -1., 0., 1., WALL_X, 20.] {
+            for velocity in [-1., -0.5, 0., 0.5, 1.] {
+                let paddle = Paddle {
+                    x,
+                    velocity,
+                    ..Paddle::new(facing)
+                };
+                let normal = paddle.normal();
+                assert!((normal.magnitude() - 1.).abs() < 1e-6);
+                // always struck away from the paddle's own goal, however wide the level
+                assert!(normal.y * facing > 0., "{x} {velocity} {facing}");
+            }
+        }
+    }
+
+    let out = |x| {
+        Paddle {
+            x,
+            ..Paddle::new(1.)
+        }
+        .normal()
+        .x
+    };
+    assert!(out(WALL_X) < 0. && out(-WALL_X) > 0.);
+}
+
+#[test]
+fn paddle_corners_match_turning_each_one_by_hand() {
+    for facing in [1., -1.] {
+        for velocity in [-1., -0.3, 0., 0.7, 1.] {
+            let paddle = Paddle {
+                x: 0.4,
+                velocity,
+                ..Paddle::new(facing)
+            };
+            let (s, c) = paddle.angle().sin_cos();
+            let (w, h) = (paddle.width / 2., paddle.height / 2.);
+            let expected = [[-w, -h], [w, -h], [w, h], [-w, h]]
+                .map(|[x, y]| [x * c - y * s, x * s + y * c])
+                .map(|[x, y]| Vector2::new(paddle.x + x, paddle.y() + y));
+            assert_eq!(paddle.points(), expected);
+        }
+    }
+}
+
+#[test]
+fn mirrored_paddle_faces_down() {
+    let paddle = Paddle {
+        velocity: 0.5,
+        ..Paddle::new(-1.)
+    };
+    let bottom = Paddle {
+        velocity: 0.5,
+        ..Paddle::new(1.)
+    };
+
+    assert!(paddle.normal().y < 0.);
+    assert!(paddle.surface_normal().y < 0.);
+    assert!(paddle.attach_point(0.05, 0., WALL_X).y < paddle.y());
+
+    // the top paddle is the bottom one reflected in the x axis
+    let points = paddle.points();
+    for v in bottom.points() {
+        let mirrored = Vector2::new(v.x, -v.y);
+        assert!(points.iter().any(|p| (p - mirrored).magnitude() < 1e-6));
+    }
+    assert!((paddle.normal().x - bottom.normal().x).abs() < 1e-6);
+}
+
+#[test]
+fn serves_alternate_sides_within_the_cone() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..100 {
+        let left = Serve::random(&mut rng, 1.);
+        let right = Serve::random(&mut rng, -1.);
+
+        assert!((0. ..=Serve::SPREAD).contains(&left.angle));
+        assert!((-Serve::SPREAD..=0.).contains(&right.angle));
+        assert!(left.offset.abs() <= Serve::MAX_OFFSET);
+    }
+
+    let paddle = Paddle::new(1.);
+    let velocity = paddle.serve_velocity(Serve::SPREAD);
+    assert!(velocity.x < 0. && velocity.y > 0.);
+    assert!((velocity.magnitude() - Ball::SERVE_SPEED).abs() < 1e-6);
+}
+
+#[test]
+fn level_completes_exactly_once() {
+    let mut phase = Phase::Playing;
+    let bricks = Bricks::default();
+
+    assert!(complete_level(&mut phase, &bricks));
+    assert_eq!(phase, Phase::Victory);
+    assert!(!complete_level(&mut phase, &bricks));
+
+    let mut phase = Phase::Playing;
+    assert!(!complete_level(
+        &mut phase,
+        &Bricks::from_level(&level::Level::bundled()[0])
+    ));
+    assert_eq!(phase, Phase::Playing);
+}
+
+#[test]
+fn substepping_keeps_slow_balls_the_same() {
+    let tunables = Tunables::default();
+    let ball = Ball {
+        position: Vector2::new(0., 0.),
+        velocity: Vector2::new(0.02, 0.03),
+        spin: 0.1,
+        state: BallState::Free,
+        ..Ball::default()
+    };
+    assert_eq!(ball.substeps(), 1);
+    assert_eq!(
+        Ball {
+            velocity: Vector2::new(10., 0.),
+            ..ball.clone()
+        }
+        .substeps(),
+        Ball::MAX_SUBSTEPS
+    );
+
+    let run = |substeps| {
+        let mut ball = ball.clone();
+        for _ in 0..50 {
+            tick_ball(
+                &mut ball,
+                &mut [],
+                &mut ContactTracker::default(),
+                &mut Bricks::default(),
+                &Obstacles::default(),
+                &mut Portals::default(),
+                &tunables,
+                Some(substeps),
+            );
+        }
+        ball
+    };
+
+    let (whole, split) = (run(1), run(4));
+    assert!((whole.position - split.position).magnitude() < 1e-4);
+    assert!((whole.velocity - split.velocity).magnitude() < 1e-6);
+}
+
+#[test]
+fn fast_obstacle_knocks_a_ball_it_sweeps_over_ahead_of_it() {
+    let tunables = Tunables::default();
+    let mut obstacles = Obstacles::from_level(
+        &level::Level::parse(
+            r#"
+            name = "sweeper"
+            [[moving_obstacles]]
+            shape = [[-0.1, -0.1], [0.1, -0.1], [0.1, 0.1], [-0.1, 0.1]]
+            waypoints = [[0.0, 0.0], [2.0, 0.0]]
+            speed = 1.0
+            "#,
+        )
+        .unwrap(),
+    );
+    obstacles.update(0.2);
+
+    let mut ball = Ball {
+        position: Vector2::new(0.2, 0.),
+        radius: 0.05,
+        state: BallState::Free,
+        ..Ball::default()
+    };
+    let tick = tick_ball(
+        &mut ball,
+        &mut [],
+        &mut ContactTracker::default(),
+        &mut Bricks::default(),
+        &obstacles,
+        &mut Portals::default(),
+        &tunables,
+        None,
+    );
+    assert!(tick.obstacle_hit.is_some());
+    // bounced off at twice the obstacle's speed, rather than only carried along at its speed, and
+    // clear ahead of where it stopped
+    assert!((ball.velocity.x - 0.4).abs() < 1e-6);
+    assert!(ball.position.x - ball.radius > 0.3);
+}
+
+#[test]
+fn dropped_ball_rebounds_to_restitution_squared_of_its_height() {
+    let tunables = Tunables {
+        paddle_kick: 0.,
+        ..Tunables::default()
+    };
+    let paddle = Paddle::new(1.);
+
+    // with constant gravity and no drag, a ball dropped from `height` lands at this speed and
+    // rises back to `speed² / 2g`
+    let (gravity, height) = (0.001f32, 0.5);
+    let mut ball = Ball {
+        velocity: Vector2::new(0., -(2. * gravity * height).sqrt()),
+        state: BallState::Free,
+        ..Ball::default()
+    };
+    paddle.strike(&mut ball, &tunables);
+
+    let rebound_height = ball.velocity.y.powi(2) / (2. * gravity);
+    assert!((rebound_height / height - tunables.restitution.powi(2)).abs() < 1e-4);
+    assert_eq!(ball.velocity.x, 0.);
+
+    // sliding along the face loses the friction's share of that speed
+    let mut ball = Ball {
+        velocity: Vector2::new(0.05, -0.03),
+        state: BallState::Free,
+        ..Ball::default()
+    };
+    paddle.strike(&mut ball, &tunables);
+    assert!((ball.velocity.x - 0.05 * (1. - tunables.friction)).abs() < 1e-6);
+    assert!((ball.velocity.y - 0.03 * tunables.restitution).abs() < 1e-6);
+}
+
+#[test]
+fn widening_eases_without_growing_into_the_ball() {
+    use modifiers::WidthModifier;
+
+    let mut paddle = Paddle::new(1.);
+    paddle.modifiers.add(WidthModifier {
+        factor: 2.,
+        remaining: Some(10.),
+    });
+
+    // just clear of the paddle's end
+    let mut ball = Ball {
+        position: Vector2::new(0.26, paddle.y()),
+        ..Ball::default()
+    };
+    assert!(!paddle.contains(&ball));
+    for _ in 0..10 {
+        paddle.resize(0.4, &ball);
+        assert!(!paddle.contains(&ball));
+    }
+    assert_eq!(paddle.width, 0.4);
+
+    ball.position.y = 0.5;
+    paddle.resize(0.4, &ball);
+    assert!(paddle.width > 0.4 && paddle.width < 0.8);
+    for _ in 0..200 {
+        paddle.resize(0.4, &ball);
+    }
+    assert!((paddle.width - 0.8).abs() < 1e-3);
+}
+
+#[test]
+fn resizing_a_ball_leaves_it_clear_of_walls_and_paddles() {
+    let paddles = [Paddle::new(1.), Paddle::new(-1.)];
+    let mut ball = Ball {
+        position: Vector2::new(WALL_X - 0.05, 0.),
+        ..Ball::default()
+    };
+
+    ball.resize(0.2, &paddles, WALL_X);
+    assert!(ball.position.x + ball.radius <= WALL_X);
+    ball.resize(0.02, &paddles, WALL_X);
+    assert!(ball.position.x + ball.radius <= WALL_X);
+    assert!(ball.segments() < Ball::default().segments());
+
+    // growing into the paddle from just above its face
+    let paddle = &paddles[0];
+    ball.position = Vector2::new(0., paddle.y() + paddle.height / 2. + 0.03);
+    ball.resize(0.2, &paddles, WALL_X);
+    assert!(!paddles.iter().any(|paddle| paddle.contains(&ball)));
+    assert!(ball.position.y > paddle.y());
+    assert!(ball.segments() > Ball::default().segments());
+}
+
+#[test]
+fn prediction_follows_the_ball_through_a_force_zone() {
+    let tunables = Tunables {
+        force_zones: vec![ForceZone {
+            min: Vector2::new(-1., -1.),
+            max: Vector2::new(1., 0.),
+            acceleration: Vector2::new(0., 0.004),
+        }],
+        ..Tunables::default()
+    };
+    let mut ball = Ball {
+        position: Vector2::new(0., 0.2),
+        velocity: Vector2::new(0.01, -0.02),
+        state: BallState::Free,
+        ..Ball::default()
+    };
+
+    let predicted = ball.predict(60, &tunables);
+    let without = ball.predict(60, &Tunables::default());
+    let ticked = (0..60).map(|_| {
+        tick_ball(
+            &mut ball,
+            &mut [],
+            &mut ContactTracker::default(),
+            &mut Bricks::default(),
+            &Obstacles::default(),
+            &mut Portals::default(),
+            &tunables,
+            Some(1),
+        );
+        ball.position
+    });
+
+    for (predicted, ticked) in predicted[1..].iter().zip(ticked) {
+        assert!((predicted - ticked).magnitude() < 1e-6);
+    }
+    // the updraft held it up
+    assert!(predicted[60].y > without[60].y + 0.1);
+}
+
+#[test]
+fn mirrored_gravity_plays_out_mirrored() {
+    let mirror = |v: Vector2<f32>| Vector2::new(v.x, -v.y);
+    let run = |gravity: Vector2<f32>, position, velocity| {
+        let tunables = Tunables {
+            gravity,
+            ..Tunables::default()
+        };
+        let mut paddles = [Paddle::new(1.), Paddle::new(-1.)];
+        for paddle in &mut paddles {
+            paddle.velocity = 0.3;
+        }
+        let mut contacts = ContactTracker::default();
+        let mut ball = Ball {
+            position,
+            velocity,
+            state: BallState::Free,
+            ..Ball::default()
+        };
+
+        let predicted = ball.predict(40, &tunables);
+        let mut hits = 0;
+        let positions = (0..150)
+            .map(|_| {
+                let tick = tick_ball(
+                    &mut ball,
+                    &mut paddles,
+                    &mut contacts,
+                    &mut Bricks::default(),
+                    &Obstacles::default(),
+                    &mut Portals::default(),
+                    &tunables,
+                    None,
+                );
+                hits += tick.paddle_hit.is_some() as usize;
+                ball.position
+            })
+            .collect::<Vec<_>>();
+        (predicted, positions, hits)
+    };
+
+    let gravity = Tunables::default().gravity;
+    let (position, velocity) = (Vector2::new(0.05, 0.2), Vector2::new(-0.002, -0.03));
+    let (predicted, positions, hits) = run(gravity, position, velocity);
+    let (mirrored_predicted, mirrored_positions, mirrored_hits) =
+        run(mirror(gravity), mirror(position), mirror(velocity));
+
+    assert!(hits > 0);
+    assert_eq!(hits, mirrored_hits);
+    for (a, b) in predicted.iter().zip(&mirrored_predicted) {
+        assert!((mirror(*a) - b).magnitude() < 1e-5);
+    }
+    for (a, b) in positions.iter().zip(&mirrored_positions) {
+        assert!((mirror(*a) - b).magnitude() < 1e-4);
+    }
+
+    let (bottom, top) = (LoseZone { side: -1. }, LoseZone { side: 1. });
+    for y in [0.85, 0.95, 1.2] {
+        let ball = |position| Ball {
+            position,
+            ..Ball::default()
+        };
+        let point = Vector2::new(0.5, -y);
+        assert_eq!(bottom.holds(&ball(point)), top.holds(&ball(mirror(point))));
+        assert_eq!(bottom.holds(&ball(point)), y > 0.9);
+    }
+}
+
+#[test]
+fn balls_past_a_wall_are_pushed_back_out_of_it_and_bounced() {
+    let tunables = Tunables::default();
+    for side in [-1., 1.] {
+        let mut ball = Ball {
+            position: Vector2::new(side * (tunables.wall_x + 0.3), 0.2),
+            velocity: Vector2::new(side * 0.02, 0.01),
+            ..Ball::default()
+        };
+        let meeting = meet_colliders(&mut ball, &[], &ContactTracker::default(), &tunables);
+
+        assert_eq!(meeting.wall_hit, Some(side));
+        assert!((ball.position.x - side * (tunables.wall_x - ball.radius)).abs() < 1e-5);
+        assert!(ball.velocity.x * side < 0.);
+
+        // then resting against it, so not bounced back into it
+        let velocity = ball.velocity;
+        let meeting = meet_colliders(&mut ball, &[], &ContactTracker::default(), &tunables);
+        assert_eq!(meeting.wall_hit, None);
+        assert_eq!(ball.velocity, velocity);
+    }
+}
+
+#[test]
+fn dash_stops_at_the_wall() {
+    let mut paddle = Paddle {
+        x: WALL_X - 0.2,
+        velocity: 0.5,
+        ..Paddle::default()
+    };
+    assert!(paddle.dash());
+
+    paddle.steer(&Controls::RELEASED);
+    assert_eq!(paddle.velocity, 1.);
+    paddle.advance(WALL_X);
+    assert!((paddle.x - (WALL_X - 0.1)).abs() < 1e-6);
+
+    paddle.steer(&Controls::RELEASED);
+    paddle.advance(WALL_X);
+    assert_eq!(paddle.x, WALL_X);
+    assert_eq!(paddle.dash.direction(), None);
+}
+
+#[test]
+fn dash_waits_for_its_cooldown() {
+    let mut paddle = Paddle {
+        velocity: -0.5,
+        ..Paddle::default()
+    };
+    assert!(paddle.dash());
+
+    // the dash lasts 15 ticks at full speed, double the usual
+    for _ in 0..15 {
+        paddle.steer(&Controls::RELEASED);
+        paddle.advance(WALL_X);
+    }
+    assert!((paddle.x + 15. * 2. / 20.).abs() < 1e-5);
+    assert_eq!(paddle.dash.direction(), None);
+
+    assert!(!paddle.dash());
+    for _ in 0..185 {
+        paddle.advance(WALL_X);
+    }
+    assert!(paddle.dash());
+}
+
+#[test]
+fn stick_steers_unless_a_direction_key_is_held() {
+    let mut paddle = Paddle::new(1.);
+    let mut controls = Controls {
+        axis: 0.5,
+        ..Controls::RELEASED
+    };
+    for _ in 0..100 {
+        paddle.steer(&controls);
+    }
+    assert!((paddle.velocity - 0.5).abs() < 1e-6);
+
+    controls.left.update(ButtonState::Pressed);
+    for _ in 0..100 {
+        paddle.steer(&controls);
+    }
+    assert_eq!(paddle.velocity, -1.);
+
+    // a resting stick is left alone
+    paddle.steer(&Controls {
+        axis: 0.1,
+        ..Controls::RELEASED
+    });
+    assert_eq!(paddle.velocity, -0.95);
+}
+
+#[test]
+fn held_keys_shrug_off_stray_releases_and_stay_per_player() {
+    use ButtonState::{Pressed, Released};
+
+    // a release with nothing held, as after focus was lost, can't leave it owing a press
+    let mut held = Held::default();
+    held.update(Released);
+    held.update(Pressed);
+    assert!(held.is_held());
+
+    // one player's keys held while the other's are let go
+    let mut one = Controls::RELEASED;
+    let mut two = Controls::RELEASED;
+    one.left.update(Pressed);
+    two.left.update(Pressed);
+    one.left.update(Released);
+    assert!(one.combine(&two).left.is_held());
+    two.left.update(Released);
+    two.right.update(Pressed);
+    let combined = one.combine(&two);
+    assert!(!combined.left.is_held() && combined.right.is_held());
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn scenes_render_like_their_golden_images() {
+    let palette = &Palette::DEFAULT;
+    let mut mesh = MeshBuilder::default();
+    LoseZone { side: -1. }.push(&mut mesh, 0., LoseZone::FLASH_COLOR, palette);
+    let mut paddle = Paddle::new(1.);
+    paddle.velocity = 0.5;
+    paddle.push(&mut mesh, palette);
+    let ball = Ball {
+        position: Vector2::new(0.3, -0.2),
+        rotation: 1.,
+        ..Ball::default()
+    };
+    ball.push(&mut mesh, 1., palette);
+
+    renderer::headless::check("paddle_and_ball", &mesh, 0., 1.);
+    // off to one side and zoomed out, as the camera follows the paddle
+    renderer::headless::check("paddle_and_ball_panned", &mesh, 0.4, 0.8);
+}
@@ -0,0 +1,129 @@
+use crate::renderer::buffer::MeshBuilder;
+use cgmath::{InnerSpace, Vector2, Zero};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+/// Pulls the ball towards a point, harder the closer it is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attractor {
+    pub center: Vector2<f32>,
+}
+
+impl Attractor {
+    /// Velocity gained per tick at a distance of one unit
+    const STRENGTH: f32 = 0.0004;
+    /// The most velocity gained per tick, however close the ball gets
+    const MAX: f32 = 0.002;
+
+    pub fn acceleration(&self, position: Vector2<f32>) -> Vector2<f32> {
+        let offset = self.center - position;
+        let distance = offset.magnitude();
+        if distance <= f32::EPSILON {
+            return Vector2::zero();
+        }
+        offset / distance * (Self::STRENGTH / distance).min(Self::MAX)
+    }
+}
+
+/// A paddle's magnet, draining its energy while held and recharging while not
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Magnet {
+    /// In 0..=1
+    energy: f32,
+    active: bool,
+}
+
+impl Default for Magnet {
+    fn default() -> Self {
+        Self {
+            energy: 1.,
+            active: false,
+        }
+    }
+}
+
+impl Magnet {
+    /// Energy used per second held
+    const DRAIN: f32 = 0.5;
+    /// Energy regained per second released
+    const RECHARGE: f32 = 0.2;
+    const COLOR: [f32; 3] = [0.4, 0.7, 1.];
+
+    /// Runs the magnet for `dt` seconds if `held` and it has energy left
+    pub fn update(&mut self, held: bool, dt: f32) {
+        self.active = held && self.energy > 0.;
+        self.energy = if self.active {
+            (self.energy - Self::DRAIN * dt).max(0.)
+        } else {
+            (self.energy + Self::RECHARGE * dt).min(1.)
+        };
+    }
+
+    /// The pull towards `center` while the magnet is running
+    pub fn attractor(&self, center: Vector2<f32>) -> Option<Attractor> {
+        self.active.then_some(Attractor { center })
+    }
+
+    pub fn refill(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Pushes the energy left as a half ring around the back of the paddle at `center`, facing
+    /// up if `facing` is positive, hidden while full and unused
+    pub fn push(&self, mesh: &mut MeshBuilder, center: Vector2<f32>, facing: f32) {
+        if self.energy >= 1. && !self.active {
+            return;
+        }
+
+        let start = if facing > 0. { PI } else { 0. };
+        let [r, g, b] = Self::COLOR;
+        mesh.push_arc(center, 0.12, [start, PI], 0.015, 16, [r, g, b, 0.2]);
+        let alpha = if self.active { 1. } else { 0.6 };
+        mesh.push_arc(
+            center,
+            0.12,
+            [start, PI * self.energy],
+            0.015,
+            16,
+            [r, g, b, alpha],
+        );
+    }
+}
+
+#[test]
+fn magnet_drains_while_held_and_recharges() {
+    let mut magnet = Magnet::default();
+    let center = Vector2::new(0., 0.);
+
+    magnet.update(true, 1.);
+    assert!(magnet.attractor(center).is_some());
+    magnet.update(true, 1.);
+    assert_eq!(magnet.energy, 0.);
+
+    // out of energy, so holding does nothing until it recharges
+    magnet.update(true, 1.);
+    assert_eq!(magnet.attractor(center), None);
+    magnet.update(false, 1.);
+    magnet.update(true, 0.1);
+    assert!(magnet.attractor(center).is_some());
+
+    magnet.update(false, 100.);
+    assert_eq!(magnet.energy, 1.);
+    assert_eq!(magnet.attractor(center), None);
+}
+
+#[test]
+fn attraction_weakens_with_distance_up_to_a_cap() {
+    let attractor = Attractor {
+        center: Vector2::new(0., 0.),
+    };
+
+    let far = attractor.acceleration(Vector2::new(0., 2.));
+    let near = attractor.acceleration(Vector2::new(0., 1.));
+    assert!((near - Vector2::new(0., -Attractor::STRENGTH)).magnitude() < 1e-9);
+    assert!((far * 2. - near).magnitude() < 1e-9);
+
+    let touching = attractor.acceleration(Vector2::new(0.01, 0.));
+    assert!((touching.magnitude() - Attractor::MAX).abs() < 1e-9);
+    assert_eq!(attractor.acceleration(attractor.center), Vector2::zero());
+}
@@ -1,304 +1,613 @@
-use cgmath::prelude::*;
+use args::Args;
 use cgmath::Vector2;
 use futures_lite::future;
-use rand::Rng;
-use renderer::buffer::{Mesh, MeshBuilder, Vertex};
-use std::f32::consts::{FRAC_PI_8, TAU};
+use snapshot::Snapshot;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
-use winit::event::{ElementState, Event as WinitEvent, KeyEvent, WindowEvent};
-use winit::event_loop::EventLoop;
-use winit::keyboard::{Key, NamedKey};
-use winit::window::WindowBuilder;
-
-mod collison;
-mod renderer;
+use std::time::Duration;
+use wgpu_fun::audio::AudioHandle;
+use wgpu_fun::bindings::{self, Command, KeyPress};
+use wgpu_fun::config::Config;
+use wgpu_fun::game::bugreport::{self, BugReport};
+use wgpu_fun::game::save::{SaveError, SavedGame};
+use wgpu_fun::game::Game;
+use wgpu_fun::highscore::HighScoreTables;
+use wgpu_fun::instrument::{self, SharedCounts};
+use wgpu_fun::level::Level;
+use wgpu_fun::renderer::buffer::{MeshBuilder, ViewTransform};
+use wgpu_fun::renderer::post::{Crt, Tonemap};
+use wgpu_fun::renderer::{self, RenderReport, RendererOptions};
+use wgpu_fun::replay::{Recording, Replay};
+use wgpu_fun::scheduler::{self, FramePacer, SystemClock, TickScheduler};
+use wgpu_fun::stats::SessionStats;
+use wgpu_fun::{Action, ButtonState, InputEvent, TICK};
+use winit::dpi::LogicalSize;
+use winit::event::{ElementState, Event as WinitEvent, KeyEvent, MouseButton, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoopBuilder};
+use winit::keyboard::{Key, PhysicalKey};
+use winit::window::{Fullscreen, Window, WindowBuilder};
+
+#[cfg(feature = "track-alloc")]
+#[global_allocator]
+static ALLOCATOR: instrument::CountingAllocator = instrument::CountingAllocator;
+
+mod args;
+mod bench;
+mod diagnostics;
+mod guard;
+mod snapshot;
+
+/// Laid over the whole frame while a file is dragged over the window
+const DROP_TINT: [f32; 4] = [1., 1., 1., 0.06];
+
+/// The most frames captured at once, ten seconds at 60 Hz
+const CAPTURE_FRAMES: usize = 600;
 
 #[cfg(feature = "egl")]
 #[link(name = "EGL")]
 #[link(name = "GLESv2")]
 extern "C" {}
 
-struct LoseZone;
-
-impl LoseZone {
-    const HEIGHT: f32 = 0.1;
-    const COLOR: [f32; 3] = [1., 0.6, 0.];
-
-    fn push(&self, mesh: &mut MeshBuilder) {
-        mesh.push(
-            [
-                [-10., -1.],
-                [10., -1.],
-                [10., -1. + Self::HEIGHT],
-                [-10., -1. + Self::HEIGHT],
-            ]
-            .map(|position| Vertex {
-                position,
-                color: Self::COLOR,
-            }),
-            [0, 1, 2, 0, 2, 3],
-        )
-    }
-
-    fn contains(&self, point: Vector2<f32>) -> bool {
-        point.y < -1. + Self::HEIGHT
-    }
-}
-
-struct Ball {
-    position: Vector2<f32>,
-    velocity: Vector2<f32>,
-}
+/// How often a recording is written out while it's being made, besides once at the end
+const RECORDING_FLUSH: std::time::Duration = std::time::Duration::from_secs(10);
 
-impl Ball {
-    const SEGMENTS: usize = 20;
-    const RADIUS: f32 = 0.05;
-
-    fn push(&self, mesh: &mut MeshBuilder) {
-        let Self { position, .. } = self;
-        let Vector2 { x, y } = position;
-
-        let vertices = std::iter::once(Vertex {
-            position: [*x, *y],
-            color: [1., 1., 1.],
-        })
-        .chain(
-            (0..=Self::SEGMENTS)
-                .map(|i| {
-                    [
-                        (i as f32 / Self::SEGMENTS as f32 * TAU).sin() * Self::RADIUS,
-                        (i as f32 / Self::SEGMENTS as f32 * TAU).cos() * Self::RADIUS,
-                    ]
-                })
-                .map(|[vert_x, vert_y]| [vert_x + x, vert_y + y])
-                .map(|[x, y]| Vertex {
-                    position: [x, y],
-                    color: [1., 1., 1.],
-                }),
-        )
-        .collect::<Vec<_>>();
-
-        let indices = (1..Self::SEGMENTS + 1)
-            .flat_map(|i| [0u16, (i as u16 + 1) % vertices.len() as u16, i as u16])
-            .collect::<Vec<_>>();
-
-        mesh.push(vertices, indices)
-    }
+/// What the event loop tells the game thread
+#[derive(Debug)]
+enum GameEvent {
+    Input(InputEvent),
+    /// A file was dropped onto the window, to try loading as a level
+    LoadLevel(PathBuf),
 }
 
+/// What the game thread tells the event loop, which is the only way it reaches the window
 #[derive(Debug)]
-struct Paddle {
-    x: f32,
-    /// A value in -1..=1 for the paddle's x velocity
-    velocity: f32,
+enum UserEvent {
+    /// The window title changed, as it does with the score
+    Title(String),
+    /// A game just ended, with the final scores
+    GameOver([u32; 2]),
+    /// The game thread panicked, with the panic's message
+    Panic(String),
+    /// The player asked to quit and confirmed it if need be, and the game has been shut down
+    Quit,
 }
 
-impl Paddle {
-    const WIDTH: f32 = 0.4;
-    const HEIGHT: f32 = 0.2;
-    const Y: f32 = -0.7;
-    const ANGLE_MULTIPLIER: f32 = FRAC_PI_8;
-    const NORMAL_ANGLE_MULTIPLIER: f32 = FRAC_PI_8 / 2.;
-
-    fn points(&self) -> [Vector2<f32>; 4] {
-        let Self { x, velocity } = self;
-        let angle = velocity * Self::ANGLE_MULTIPLIER;
-        let (s, c) = angle.sin_cos();
-
-        const FRAC_WIDTH_2: f32 = Paddle::WIDTH / 2.;
-        const FRAC_HEIGHT_2: f32 = Paddle::HEIGHT / 2.;
-
-        [
-            [-FRAC_WIDTH_2, -FRAC_HEIGHT_2],
-            [FRAC_WIDTH_2, -FRAC_HEIGHT_2],
-            [FRAC_WIDTH_2, FRAC_HEIGHT_2],
-            [-FRAC_WIDTH_2, FRAC_HEIGHT_2],
-        ]
-        .map(|[x, y]| [x * c - y * s, x * s + y * c])
-        .map(|[vert_x, y]| [x + vert_x, y + Self::Y])
-        .map(|v| v.into())
-    }
-
-    fn push(&self, mesh: &mut MeshBuilder) {
-        mesh.push(
-            self.points().map(|v| Vertex {
-                position: [v.x, v.y],
-                color: [1., 1., 1.],
-            }),
-            [0, 1, 2, 0, 2, 3],
-        )
+fn button_state(state: ElementState) -> ButtonState {
+    match state {
+        ElementState::Pressed => ButtonState::Pressed,
+        ElementState::Released => ButtonState::Released,
     }
+}
 
-    fn contains(&self, ball: &Ball) -> bool {
-        let [a, b, c, d] = self.points();
-        collison::circle_intersects_triangle(ball.position, Ball::RADIUS, a, b, c)
-            | collison::circle_intersects_triangle(ball.position, Ball::RADIUS, a, c, d)
+/// A key event in the terms bindings are written in, which are winit's own names for keys
+fn key_press(physical: PhysicalKey, logical: &Key) -> KeyPress {
+    KeyPress {
+        physical: match physical {
+            PhysicalKey::Code(code) => Some(format!("{code:?}")),
+            PhysicalKey::Unidentified(_) => None,
+        },
+        logical: match logical {
+            Key::Named(name) => Some(bindings::Key::Named(format!("{name:?}"))),
+            Key::Character(c) => Some(bindings::Key::Character(c.to_string())),
+            Key::Unidentified(_) | Key::Dead(_) => None,
+        },
     }
+}
 
-    fn normal(&self) -> Vector2<f32> {
-        let angle = self.velocity * Self::NORMAL_ANGLE_MULTIPLIER;
-        let rotation: cgmath::Basis2<f32> = cgmath::Rotation2::from_angle(cgmath::Rad(angle));
-        let velocity = rotation.rotate_vector(Vector2::unit_y());
-
-        let angle = self.x * Self::NORMAL_ANGLE_MULTIPLIER;
-        let rotation: cgmath::Basis2<f32> = cgmath::Rotation2::from_angle(cgmath::Rad(angle));
-        let position = rotation.rotate_vector(Vector2::unit_y());
-
-        velocity * 0.5 + position * 0.5
+fn save_recording(path: &Path, recording: &Recording) {
+    if let Err(err) = recording.save(path) {
+        log::warn!("failed to save recording: {err}");
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Event {
-    Left(ElementState),
-    Right(ElementState),
-    Reset,
+/// A new directory for a capture, named after the time, beside where bug reports are written
+fn capture_dir() -> Option<PathBuf> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis());
+    Some(bugreport::default_dir()?.join(format!("capture-{timestamp}")))
 }
 
-struct Controls {
-    left: ElementState,
-    right: ElementState,
+/// The refresh rate of the display the window is mostly on
+fn display_rate(window: &Window) -> f32 {
+    scheduler::refresh_rate(
+        window
+            .current_monitor()
+            .and_then(|monitor| monitor.refresh_rate_millihertz()),
+    )
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
 
-    let event_loop = EventLoop::new().unwrap();
-    let window = WindowBuilder::new()
-        .with_title("WGPU fun")
-        .build(&event_loop)?;
-    let window = Arc::new(window);
-
-    let mut renderer = future::block_on(renderer::Renderer::new(window.as_ref()));
-    let (event_send, event_recv) = crossbeam::channel::unbounded();
-
-    let lose_zone = LoseZone;
+    let args = Args::parse(std::env::args().skip(1))?;
+    if args.help {
+        println!("{}", args::USAGE);
+        return Ok(());
+    }
 
-    let mut paddle = Paddle {
-        x: 0.,
-        velocity: 0.,
+    let bug_report = args
+        .load_bugreport
+        .as_deref()
+        .map(BugReport::load)
+        .transpose()?;
+    // a bug report plays back like a recording, once the game is put as it was at its start
+    let replay = match &bug_report {
+        Some(report) => Some(report.recording()),
+        None => args.replay.as_deref().map(Recording::load).transpose()?,
     };
-
-    let mut ball = Ball {
-        position: [0., 0.7].into(),
-        velocity: [0., 0.].into(),
+    // a replay must run with the seed it was recorded with
+    let seed = match (&replay, args.seed) {
+        (Some(recording), _) => recording.seed,
+        (None, Some(seed)) => seed,
+        (None, None) => rand::random(),
     };
+    log::info!("seed: {seed}");
+
+    let high_scores_path = HighScoreTables::default_path();
+    let high_scores = high_scores_path
+        .as_deref()
+        .map(HighScoreTables::load)
+        .unwrap_or_default();
+
+    let config_path = Config::default_path();
+    let config = config_path.as_deref().map(Config::load).unwrap_or_default();
+    // the game keeps the file's config to save, so command line options aren't remembered
+    let settings = args.apply(config.clone());
+
+    let in_file = config_path
+        .as_deref()
+        .map(diagnostics::file_fields)
+        .unwrap_or_default();
+    let config_lines = diagnostics::config_lines(&config, &in_file, &args);
+    if args.print_diagnostics {
+        println!("{}", diagnostics::adapter_line(args.require_hardware));
+        for line in diagnostics::display_lines(&settings) {
+            println!("{line}");
+        }
+        println!("config:");
+        for line in config_lines {
+            println!("    {line}");
+        }
+        return Ok(());
+    }
+    log::info!("config:\n    {}", config_lines.join("\n    "));
+
+    if let Some(frames) = args.bench_frames {
+        let options = bench::BenchOptions {
+            frames,
+            budget: args.budget_ms.map(|ms| Duration::from_secs_f64(ms / 1000.)),
+            require_hardware: args.require_hardware,
+        };
+        return Ok(bench::run(options, seed)?);
+    }
 
-    let mut controls = Controls {
-        left: ElementState::Released,
-        right: ElementState::Released,
-    };
+    if let Some(report) = &bug_report {
+        if report.config_hash != bugreport::config_hash(&config) {
+            log::warn!("the bug report was made with another config, so may not play the same");
+        }
+    }
+
+    let mut game = Game::new(config, high_scores, seed);
+    game.high_scores_path = high_scores_path;
+    game.config_path = config_path;
+    game.stats_dir = SessionStats::default_dir();
+    game.bug_report_dir = bugreport::default_dir();
+    game.camera.reduced_motion = std::env::var_os("WGPU_FUN_REDUCED_MOTION").is_some();
+    game.mesh_particles = args.mesh_particles;
+    game.audio = AudioHandle::new(settings.volume.clamp(0., 1.));
+    if let Some(path) = &args.level {
+        let level = Level::load(path)
+            .map_err(|err| format!("failed to load level {}: {err}", path.display()))?;
+        game.levels = vec![level];
+    }
+    if let Some(report) = &bug_report {
+        game.load_bug_report(report)
+            .map_err(|err| format!("failed to load the bug report: {err}"))?;
+    }
 
-    let mesh = Arc::new(Mutex::new(Mesh::builder()));
-    let camera_x = Arc::new(Mutex::new(0.0));
+    // replays and recordings start from the menu, and leave the save alone, as does a game told
+    // not to resume it
+    if replay.is_none() && args.record.is_none() && !args.no_resume {
+        game.save_path = SavedGame::default_path();
+    }
+    if let Some(path) = game.save_path.clone() {
+        match game.resume(&path) {
+            Ok(()) => log::info!("resumed the game saved in {}", path.display()),
+            Err(SaveError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => log::warn!(
+                "starting afresh rather than resuming {}: {err}",
+                path.display()
+            ),
+        }
+    }
 
-    std::thread::spawn({
-        let window = Arc::clone(&window);
-        let mesh = Arc::clone(&mesh);
-        let camera_x = Arc::clone(&camera_x);
+    let event_loop = EventLoopBuilder::with_user_event().build()?;
+    let mut window = WindowBuilder::new()
+        .with_title(&game.title)
+        .with_transparent(args.transparent);
+    if let (Some(width), Some(height)) = (settings.width, settings.height) {
+        window = window.with_inner_size(LogicalSize::new(width, height));
+    }
+    if settings.fullscreen {
+        window = window.with_fullscreen(Some(Fullscreen::Borderless(None)));
+    }
+    let window = window.build(&event_loop)?;
+    let size = window.inner_size();
+    log::info!(
+        "window: {}x{} at scale factor {}",
+        size.width,
+        size.height,
+        window.scale_factor()
+    );
+    let refresh_rate = display_rate(&window);
+    log::info!("display refresh rate: {refresh_rate} Hz");
+    // shared with the game thread as the bits of an f32, for the debug overlay
+    let shared_refresh_rate = Arc::new(AtomicU32::new(refresh_rate.to_bits()));
+    // shared with the game thread too, for drawing circles as finely as they show
+    let shared_surface_height = Arc::new(AtomicU32::new(size.height));
+    // and its shape, as the bits of an f32, for the camera to see as much as the window shows
+    let shared_surface_aspect = Arc::new(AtomicU32::new(
+        ViewTransform::aspect(size.width, size.height).to_bits(),
+    ));
+    // and whether the debug overlay's showing, as the cursor's only sent to the game while it is
+    let shared_debug = Arc::new(AtomicBool::new(false));
+    let mut pacer = FramePacer::new(
+        settings
+            .target_fps
+            .map_or(refresh_rate, |fps| fps.max(1) as f32),
+        std::time::Instant::now(),
+    );
+
+    let mut renderer = future::block_on(renderer::Renderer::new(
+        &window,
+        RendererOptions {
+            vsync: settings.vsync,
+            msaa: settings.msaa,
+            require_hardware: args.require_hardware,
+            crt: settings.crt.then(|| Crt::new(settings.crt_distortion)),
+            transparent: args.transparent,
+            hdr: settings.hdr.then(|| Tonemap::new(settings.hdr_brightness)),
+        },
+    ))
+    .map_err(|err| format!("failed to start rendering: {err}"))?;
+    let (event_send, event_recv) = crossbeam::channel::unbounded::<GameEvent>();
+    // keys only ever say what to do, leaving when to the game thread
+    let send = {
         let event_send = event_send.clone();
+        move |action: Action| event_send.send(GameEvent::Input(action.into())).unwrap()
+    };
+
+    let (mut snapshots, mut latest) = snapshot::triple_buffer(Snapshot::new(
+        &game,
+        game.pose(),
+        std::time::Instant::now(),
+        MeshBuilder::default(),
+        Vec::new(),
+    ));
+    // what the last frame drawn did, passed to the game thread for the debug overlay
+    let frame_counts = Arc::new(SharedCounts::default());
+    let frame_report = Arc::new(Mutex::new(RenderReport::default()));
+
+    let running = Arc::new(AtomicBool::new(true));
+    let game_thread = std::thread::spawn({
+        let running = Arc::clone(&running);
+        let mut replay = replay.map(Replay::new);
+        let mut recording = args.record.map(|path| (path, Recording::new(seed)));
+        let proxy = event_loop.create_proxy();
+        let panic_proxy = proxy.clone();
+        let quit_proxy = proxy.clone();
+        let refresh_rate = Arc::clone(&shared_refresh_rate);
+        let surface_height = Arc::clone(&shared_surface_height);
+        let surface_aspect = Arc::clone(&shared_surface_aspect);
+        let debug = Arc::clone(&shared_debug);
+        let frame_counts = Arc::clone(&frame_counts);
+        let frame_report = Arc::clone(&frame_report);
 
         move || {
-            let mut rng = rand::thread_rng();
-            loop {
-                for event in event_recv.try_iter() {
-                    match event {
-                        Event::Left(state) => controls.left = state,
-                        Event::Right(state) => controls.right = state,
-                        Event::Reset => {
-                            ball.position = [0., 0.7].into();
+            guard::catch_panic(
+                || {
+                    let mut title = game.title.clone();
+                    let mut over = game.is_over();
+                    let mut clock = SystemClock;
+                    let mut scheduler = TickScheduler::new(TICK, std::time::Instant::now());
+                    let mut last_flush = std::time::Instant::now();
+                    while running.load(Ordering::Relaxed) {
+                        let before = instrument::counts();
+                        let mut live = Vec::new();
+                        for event in event_recv.try_iter() {
+                            match event {
+                                GameEvent::Input(event) => live.push(event),
+                                // replays and recordings stick to the levels they started with
+                                GameEvent::LoadLevel(path)
+                                    if replay.is_some() || recording.is_some() =>
+                                {
+                                    log::warn!(
+                                        "not loading {} while replaying or recording",
+                                        path.display()
+                                    );
+                                }
+                                GameEvent::LoadLevel(path) => game.load_level_file(&path),
+                            }
                         }
-                    }
-                }
-
-                match controls {
-                    Controls {
-                        left: ElementState::Pressed,
-                        right: ElementState::Released,
-                    } => {
-                        paddle.velocity = (paddle.velocity - 0.05).max(-1.0);
-                    }
-                    Controls {
-                        left: ElementState::Released,
-                        right: ElementState::Pressed,
-                    } => {
-                        paddle.velocity = (paddle.velocity + 0.05).min(1.0);
-                    }
-                    _ => {
-                        paddle.velocity *= 0.95;
-                    }
-                }
-
-                paddle.x = (paddle.x + paddle.velocity / 20.).clamp(-5.5, 5.5);
-
-                // gravity
-                ball.velocity.y = ball.velocity.y + ball.velocity.y.clamp(-0.5, -0.1) * 0.01;
-
-                if paddle.contains(&ball) {
-                    ball.velocity += paddle.normal();
-                    ball.velocity.x += ((rng.gen::<f32>() * 2.) - 0.5) * 0.01;
-                }
-
-                ball.velocity = ball.velocity.map(|x| x * 0.95);
-                ball.velocity = ball.velocity.map(|i| i.clamp(-0.1, 0.1));
+                        // a replay stands in for the keyboard entirely
+                        let events = match &mut replay {
+                            // but quitting is still asked for live, and needs no confirming
+                            Some(replay) => {
+                                if live.iter().any(|event| event.action == Action::Quit) {
+                                    break;
+                                }
+                                replay.due(game.ticks)
+                            }
+                            None => live,
+                        };
+
+                        for event in events {
+                            // pointing and picking only inspect the game, so replay the same
+                            // without them
+                            let recording =
+                                recording.as_mut().filter(|_| event.action.changes_play());
+                            if let Some((_, recording)) = recording {
+                                recording
+                                    .events
+                                    .push(InputEvent::at(game.ticks, event.action));
+                            }
+                            game.handle(event);
+                        }
+                        debug.store(game.is_debugging(), Ordering::Relaxed);
 
-                ball.position += ball.velocity;
-                ball.position.x = ball.position.x.clamp(-5.5, 5.5);
+                        let previous = game.pose();
+                        game.step();
 
-                if lose_zone.contains(ball.position) {
-                    event_send.send(Event::Reset).unwrap();
-                }
+                        // written out now and then, so a crash loses only the last stretch
+                        if let Some((path, recording)) = &recording {
+                            if last_flush.elapsed() >= RECORDING_FLUSH {
+                                last_flush = std::time::Instant::now();
+                                save_recording(path, recording);
+                            }
+                        }
 
-                *mesh.lock().unwrap() = {
-                    let mut mesh = Mesh::builder();
-                    lose_zone.push(&mut mesh);
-                    paddle.push(&mut mesh);
-                    ball.push(&mut mesh);
-                    mesh
-                };
+                        let now = std::time::Instant::now();
+                        let (mesh, particles) = snapshots
+                            .recycle()
+                            .map(|snapshot| (snapshot.frame.mesh, snapshot.frame.particles))
+                            .unwrap_or_default();
+                        snapshots.publish(Snapshot::new(&game, previous, now, mesh, particles));
+                        game.tick_counts = instrument::counts().since(&before);
+                        game.frame_counts = frame_counts.load();
+                        game.frame_report = *frame_report.lock().unwrap();
+
+                        let mut messages = Vec::new();
+                        if game.title != title {
+                            title = game.title.clone();
+                            messages.push(UserEvent::Title(title.clone()));
+                        }
+                        if game.is_over() != over {
+                            over = !over;
+                            if over {
+                                messages.push(UserEvent::GameOver(game.scores()));
+                            }
+                        }
+                        let quitting = game.wants_to_quit();
+                        // sending fails once the event loop has gone, leaving nothing to draw to
+                        if messages
+                            .into_iter()
+                            .any(|message| proxy.send_event(message).is_err())
+                        {
+                            break;
+                        }
+                        if quitting {
+                            break;
+                        }
 
-                {
-                    let mut camera_x = camera_x.lock().unwrap();
-                    *camera_x = ((*camera_x * 10. + paddle.x) / 11.).clamp(-5.0, 5.0);
-                }
+                        scheduler.wait(&mut clock);
+                        game.tick_rate = scheduler.rate();
+                        game.display_rate = f32::from_bits(refresh_rate.load(Ordering::Relaxed));
+                        game.surface_height = surface_height.load(Ordering::Relaxed);
+                        game.set_surface_aspect(f32::from_bits(
+                            surface_aspect.load(Ordering::Relaxed),
+                        ));
+                    }
 
-                window.request_redraw();
-                std::thread::sleep(std::time::Duration::from_millis(10));
-            }
+                    if let Some((path, recording)) = &recording {
+                        save_recording(path, recording);
+                    }
+                    game.shutdown();
+                    // the game has stopped either way, so the window goes with it
+                    drop(quit_proxy.send_event(UserEvent::Quit));
+                },
+                // the event loop is gone if sending fails, so there's no one left to tell
+                |message| drop(panic_proxy.send_event(UserEvent::Panic(message))),
+            )
         }
     });
 
+    let mut shift = false;
+    let mut control = false;
+    let mut hovering = false;
+    // the last frame drawn, which is what the cursor's pointing at
+    let mut view = ViewTransform::new(0., 1., renderer.aspect());
+    let bindings = settings.bindings.clone();
+    let target_fps = settings.target_fps;
+    let crt_distortion = settings.crt_distortion;
+    // built over every frame rather than afresh
+    let mut frame_mesh = MeshBuilder::default();
+    let mut panicked = None;
+    let panic_message = &mut panicked;
     event_loop.run(move |event, elwt| match event {
+        // frames are drawn at the display's rate, between ticks as much as on them
+        WinitEvent::AboutToWait => {
+            if pacer.frame_due(std::time::Instant::now()) {
+                renderer.window.request_redraw();
+            }
+            elwt.set_control_flow(ControlFlow::WaitUntil(pacer.next_frame()));
+        }
+        WinitEvent::UserEvent(message) => match message {
+            UserEvent::Title(title) => renderer.window.set_title(&title),
+            UserEvent::GameOver([one, two]) => log::info!("game over at {one} to {two}"),
+            UserEvent::Quit => elwt.exit(),
+            UserEvent::Panic(message) => {
+                *panic_message = Some(message);
+                elwt.exit();
+            }
+        },
+        WinitEvent::Resumed => {
+            if let Err(err) = renderer.resume() {
+                log::error!("failed to draw to the window: {err}");
+                elwt.exit();
+            }
+        }
+        WinitEvent::Suspended => renderer.suspend(),
         WinitEvent::WindowEvent {
             ref event,
             window_id,
         } if window_id == renderer.window.id() => match event {
             WindowEvent::CloseRequested => elwt.exit(),
-            WindowEvent::Resized(size) => renderer.resize(*size),
+            // drawn straight away from the latest snapshot, since while the border's dragged some
+            // platforms (Windows) hold the event loop in a loop of their own that never reaches
+            // AboutToWait, which would leave the frame frozen and smeared over the new size. The
+            // view's as wide as the surface's shape makes it, so it fits as soon as it's drawn.
+            WindowEvent::Resized(size) => {
+                renderer.resize(*size);
+                shared_surface_height.store(size.height, Ordering::Relaxed);
+                shared_surface_aspect.store(renderer.aspect().to_bits(), Ordering::Relaxed);
+                renderer.window.request_redraw();
+            }
+            // regaining focus leaves the game paused until the pause key is pressed
+            WindowEvent::Focused(false) => send(Action::FocusLost),
+            WindowEvent::HoveredFile(_) => hovering = true,
+            WindowEvent::HoveredFileCancelled => hovering = false,
+            WindowEvent::DroppedFile(path) => {
+                hovering = false;
+                event_send.send(GameEvent::LoadLevel(path.clone())).unwrap();
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                shift = modifiers.state().shift_key();
+                control = modifiers.state().control_key();
+            }
             WindowEvent::ScaleFactorChanged { .. } => {
-                renderer.resize(renderer.window.inner_size());
+                let size = renderer.window.inner_size();
+                renderer.resize(size);
+                shared_surface_height.store(size.height, Ordering::Relaxed);
+                shared_surface_aspect.store(renderer.aspect().to_bits(), Ordering::Relaxed);
+                renderer.window.request_redraw();
             }
+            // the window may have moved onto a display refreshing at another rate
+            WindowEvent::Moved(_) => {
+                let rate = display_rate(renderer.window);
+                let previous = shared_refresh_rate.swap(rate.to_bits(), Ordering::Relaxed);
+                if f32::from_bits(previous) != rate {
+                    log::info!("display refresh rate: {rate} Hz");
+                    if target_fps.is_none() {
+                        pacer.set_rate(rate);
+                    }
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } if shared_debug.load(Ordering::Relaxed) => {
+                let pixel = Vector2::new(position.x as f32, position.y as f32);
+                let size = renderer.window.inner_size();
+                let size = Vector2::new(size.width as f32, size.height as f32);
+                send(Action::PointAt(view.pixels_to_world(pixel, size)));
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } if shared_debug.load(Ordering::Relaxed) => send(Action::Pick),
+            // held keys repeat, which would only send the same press again
             WindowEvent::KeyboardInput {
-                event: KeyEvent {
-                    state, logical_key, ..
-                },
+                event: KeyEvent { repeat: true, .. },
                 ..
-            } => match logical_key {
-                Key::Named(NamedKey::ArrowRight) => event_send.send(Event::Right(*state)).unwrap(),
-                Key::Named(NamedKey::ArrowLeft) => event_send.send(Event::Left(*state)).unwrap(),
-                Key::Named(NamedKey::Space) if state == &ElementState::Pressed => {
-                    event_send.send(Event::Reset).unwrap()
+            } => {}
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key,
+                        logical_key,
+                        state,
+                        ..
+                    },
+                ..
+            } => {
+                let pressed = state.is_pressed();
+                match bindings.command(&key_press(*physical_key, logical_key)) {
+                    Some(Command::Left(player)) => send(Action::Left(player, button_state(*state))),
+                    Some(Command::Right(player)) => {
+                        send(Action::Right(player, button_state(*state)))
+                    }
+                    Some(Command::Harder) if pressed => send(Action::Harder),
+                    Some(Command::Easier) => {
+                        if pressed {
+                            send(Action::Easier);
+                        }
+                        send(Action::Magnet(button_state(*state)))
+                    }
+                    Some(Command::Serve) if pressed => send(Action::Serve),
+                    Some(Command::Pause) if pressed => send(Action::Pause),
+                    Some(Command::Dash) if pressed => send(Action::Dash),
+                    // only while shift is held too
+                    Some(Command::SlowMotion) if shift || !pressed => {
+                        let scale = if pressed { 0.25 } else { 1. };
+                        send(Action::SetTimeScale(scale))
+                    }
+                    Some(Command::ToggleDebug) if pressed => send(Action::ToggleDebug),
+                    Some(Command::NextPalette) if pressed => send(Action::NextPalette),
+                    // only how it's drawn changes, which is no business of the game thread's
+                    Some(Command::ToggleCrt) if pressed => {
+                        let crt = renderer.crt().xor(Some(Crt::new(crt_distortion)));
+                        renderer.set_crt(crt);
+                    }
+                    Some(Command::ToggleCapture) if pressed => {
+                        if renderer.is_capturing() {
+                            renderer.stop_capture();
+                        } else if let Some(dir) = capture_dir() {
+                            if let Err(err) = renderer.start_capture(dir, CAPTURE_FRAMES) {
+                                log::warn!("failed to start capturing: {err}");
+                            }
+                        }
+                    }
+                    Some(Command::ReportBug) if pressed => send(Action::ReportBug),
+                    // only while control is held too, as the usual copy shortcut
+                    Some(Command::CopyResult) if pressed && control => send(Action::CopyResult),
+                    Some(Command::Quit) if pressed => send(Action::Quit),
+                    _ => {}
                 }
-                Key::Named(NamedKey::Escape) => elwt.exit(),
-                _ => {}
-            },
+            }
             WindowEvent::RedrawRequested => {
-                let mesh = mesh.lock().unwrap().clone().build(&renderer.device);
-                match renderer.render(mesh, *camera_x.lock().unwrap()) {
-                    Ok(_) => {}
-                    Err(wgpu::SurfaceError::Lost) => {
-                        renderer.resize(renderer.size);
+                // the game carries on in its own thread, and the next snapshot rebuilds the mesh
+                // on the new device
+                if renderer.is_lost() {
+                    log::warn!("recreating the renderer after losing the device");
+                    if let Err(err) = future::block_on(renderer.recreate()) {
+                        log::error!("failed to recreate the renderer: {err}");
+                        elwt.exit();
+                        return;
                     }
+                }
+                let before = instrument::counts();
+                let snapshot = latest.latest();
+                let pose = snapshot.interpolate_into(std::time::Instant::now(), &mut frame_mesh);
+                view = ViewTransform::new(pose.camera_x, pose.zoom, renderer.aspect());
+                if hovering {
+                    frame_mesh.push_rect(
+                        Vector2::new(pose.camera_x - 2., -1.),
+                        Vector2::new(pose.camera_x + 2., 1.),
+                        DROP_TINT,
+                    );
+                }
+                renderer.set_background(snapshot.frame.background);
+                let rendered = renderer.render(
+                    &frame_mesh,
+                    snapshot.frame.hud,
+                    &snapshot.frame.particles,
+                    pose.camera_x,
+                    pose.zoom,
+                );
+                frame_counts.store(instrument::counts().since(&before));
+                match rendered {
+                    Ok(report) => *frame_report.lock().unwrap() = report,
+                    Err(wgpu::SurfaceError::Lost) => renderer.reconfigure(),
                     Err(wgpu::SurfaceError::OutOfMemory) => {
                         elwt.exit();
                     }
@@ -312,5 +621,45 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         _ => {}
     })?;
 
+    running.store(false, Ordering::Relaxed);
+    if game_thread.join().is_err() {
+        log::error!("game thread panicked");
+    }
+    if let Some(message) = panicked {
+        return Err(format!("game thread panicked: {message}").into());
+    }
+
     Ok(())
 }
+
+#[test]
+fn key_presses_name_keys_as_the_default_bindings_do() {
+    use winit::keyboard::{KeyCode, NamedKey, NativeKey, NativeKeyCode};
+
+    let bindings = Config::default().bindings;
+    let press =
+        |code, logical: Key| bindings.command(&key_press(PhysicalKey::Code(code), &logical));
+    let unidentified = Key::Unidentified(NativeKey::Unidentified);
+    assert_eq!(
+        press(KeyCode::ArrowLeft, unidentified.clone()),
+        Some(Command::Left(wgpu_fun::Player::One))
+    );
+    assert_eq!(
+        press(KeyCode::F3, Key::Named(NamedKey::F3)),
+        Some(Command::ToggleDebug)
+    );
+    assert_eq!(
+        press(KeyCode::KeyC, Key::Character("C".into())),
+        Some(Command::CopyResult)
+    );
+    assert_eq!(
+        press(KeyCode::Tab, Key::Named(NamedKey::Tab)),
+        Some(Command::NextPalette)
+    );
+
+    let unplaced = key_press(
+        PhysicalKey::Unidentified(NativeKeyCode::Unidentified),
+        &unidentified,
+    );
+    assert_eq!(unplaced, KeyPress::default());
+}
@@ -0,0 +1,254 @@
+use crate::difficulty::Preset;
+use crate::palette::{PaletteName, Role};
+use crate::renderer::buffer::{MeshBuilder, Vertex};
+use crate::renderer::font::text_width;
+use crate::renderer::shapes::SegmentCount;
+use cgmath::Vector2;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mode {
+    /// Against the ai
+    OnePlayer,
+    /// Against a second player on the same keyboard
+    TwoPlayer,
+    /// Against the ai without keeping score, racing a ghost of the last lost rally
+    Practice,
+    /// Against the ai, racing the clock to clear each level
+    Speedrun,
+}
+
+impl Mode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::OnePlayer => Self::TwoPlayer,
+            Self::TwoPlayer => Self::Practice,
+            Self::Practice => Self::Speedrun,
+            Self::Speedrun => Self::OnePlayer,
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        self.next().next().next()
+    }
+
+    /// The name written above the mode's panel
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::OnePlayer => "ONE PLAYER",
+            Self::TwoPlayer => "TWO PLAYER",
+            Self::Practice => "PRACTICE",
+            Self::Speedrun => "SPEEDRUN",
+        }
+    }
+}
+
+const PANEL_SIZE: Vector2<f32> = Vector2::new(0.4, 0.4);
+const SWATCH_SIZE: Vector2<f32> = Vector2::new(0.12, 0.12);
+const SELECTED: [f32; 4] = [1., 1., 1., 1.];
+const UNSELECTED: [f32; 4] = [0.4, 0.4, 0.4, 1.];
+const LABEL_HEIGHT: f32 = 0.035;
+
+/// Pushes `text` centred on `center_x` with its baseline at `y`
+fn push_label(mesh: &mut MeshBuilder, text: &str, center_x: f32, y: f32, color: [f32; 4]) {
+    let left = center_x - text_width(text, LABEL_HEIGHT) / 2.;
+    mesh.push_text(text, Vector2::new(left, y), LABEL_HEIGHT, color);
+}
+
+/// Pushes the mode selection, drawn as a panel per mode with the selected one highlighted, the
+/// difficulty below as a bar per preset growing with how hard it is, and the palette beside it as
+/// a swatch of each palette's colours, each labelled
+pub fn push(
+    mesh: &mut MeshBuilder,
+    center_x: f32,
+    selected: Mode,
+    preset: Preset,
+    palette: PaletteName,
+) {
+    mesh.push_rect(
+        Vector2::new(center_x - 1., -0.45),
+        Vector2::new(center_x + 1., 0.5),
+        [0., 0., 0., 0.7],
+    );
+
+    let modes = [
+        (Mode::OnePlayer, -0.69),
+        (Mode::TwoPlayer, -0.23),
+        (Mode::Practice, 0.23),
+        (Mode::Speedrun, 0.69),
+    ];
+    for (mode, offset) in modes {
+        let color = if mode == selected {
+            SELECTED
+        } else {
+            UNSELECTED
+        };
+
+        let center = Vector2::new(center_x + offset, 0.1);
+        let min = center - PANEL_SIZE / 2.;
+        let max = center + PANEL_SIZE / 2.;
+        mesh.push_rect_outline(min, max, 0.02, color);
+        push_label(mesh, mode.label(), center.x, max.y + 0.04, color);
+
+        // a paddle for each player, with the ai's side left empty
+        let paddle = Vector2::new(0.1, 0.025);
+        let bottom = Vector2::new(center.x, min.y + 0.08);
+        mesh.push_rect(bottom - paddle, bottom + paddle, color);
+        if mode == Mode::TwoPlayer {
+            let top = Vector2::new(center.x, max.y - 0.08);
+            mesh.push_rect(top - paddle, top + paddle, color);
+        }
+        mesh.push_circle(center, 0.03, SegmentCount::Fixed(12), color);
+        if mode == Mode::Practice {
+            let [r, g, b, _] = color;
+            mesh.push_circle(
+                center + Vector2::new(0.08, 0.05),
+                0.03,
+                SegmentCount::Fixed(12),
+                [r, g, b, 0.3],
+            );
+        }
+        if mode == Mode::Speedrun {
+            // a stopwatch face around the ball
+            mesh.push_arc(center, 0.07, [0., TAU], 0.012, 16, color);
+            mesh.push_segment(center, center + Vector2::new(0.035, 0.035), 0.01, color);
+        }
+    }
+
+    push_label(mesh, "DIFFICULTY", center_x, -0.14, UNSELECTED);
+    for (i, option) in Preset::ALL.into_iter().enumerate() {
+        let color = if option == preset {
+            SELECTED
+        } else {
+            UNSELECTED
+        };
+
+        let x = center_x + (i as f32 - 1.) * 0.15;
+        let height = 0.06 * (i + 1) as f32;
+        mesh.push_rect(
+            Vector2::new(x - 0.05, -0.38),
+            Vector2::new(x + 0.05, -0.38 + height),
+            color,
+        );
+    }
+
+    let swatches = (PaletteName::ALL.len() - 1) as f32 * 0.16 + SWATCH_SIZE.x;
+    push_label(
+        mesh,
+        "PALETTE",
+        center_x + 0.5 + swatches / 2.,
+        -0.2,
+        UNSELECTED,
+    );
+    for (i, option) in PaletteName::ALL.into_iter().enumerate() {
+        let color = if option == palette {
+            SELECTED
+        } else {
+            UNSELECTED
+        };
+
+        let min = Vector2::new(center_x + 0.5 + i as f32 * 0.16, -0.38);
+        let max = min + SWATCH_SIZE;
+        // a stripe each of the colours that differ most between palettes
+        let roles = [Role::LoseZone, Role::PowerUpGood, Role::BrickRow(0)];
+        let width = SWATCH_SIZE.x / roles.len() as f32;
+        for (j, role) in roles.into_iter().enumerate() {
+            let x = min.x + j as f32 * width;
+            mesh.push_rect(
+                Vector2::new(x, min.y),
+                Vector2::new(x + width, max.y),
+                option.palette().rgba(role, 1.),
+            );
+        }
+        mesh.push_rect_outline(min, max, 0.015, color);
+    }
+}
+
+/// A red panel with a cross, shown after losing
+pub fn push_game_over(mesh: &mut MeshBuilder, center_x: f32) {
+    let center = Vector2::new(center_x, 0.2);
+    mesh.push_rect(
+        center - Vector2::new(0.4, 0.3),
+        center + Vector2::new(0.4, 0.3),
+        [0.4, 0., 0., 0.7],
+    );
+
+    let color = [1., 0.3, 0.3, 1.];
+    let arm = Vector2::new(0.15, 0.15);
+    mesh.push_segment(center - arm, center + arm, 0.05, color);
+    mesh.push_segment(
+        center + Vector2::new(-arm.x, arm.y),
+        center + Vector2::new(arm.x, -arm.y),
+        0.05,
+        color,
+    );
+}
+
+/// A green panel with a tick, shown after clearing a level
+pub fn push_victory(mesh: &mut MeshBuilder, center_x: f32) {
+    let center = Vector2::new(center_x, 0.2);
+    mesh.push_rect(
+        center - Vector2::new(0.4, 0.3),
+        center + Vector2::new(0.4, 0.3),
+        [0., 0.3, 0.1, 0.7],
+    );
+
+    let color = [0.4, 1., 0.5, 1.];
+    let corner = center + Vector2::new(-0.05, -0.12);
+    mesh.push_segment(center + Vector2::new(-0.17, 0.), corner, 0.05, color);
+    mesh.push_segment(corner, center + Vector2::new(0.18, 0.17), 0.05, color);
+}
+
+/// An hourglass in the corner while the game runs in slow motion
+pub fn push_slow_motion(mesh: &mut MeshBuilder, center_x: f32) {
+    let center = Vector2::new(center_x - 0.8, 0.75);
+    let color = [1., 1., 1., 0.6];
+
+    for side in [-1., 1.] {
+        mesh.push(
+            [
+                center,
+                center + Vector2::new(0.05 * side, 0.08 * side),
+                center + Vector2::new(-0.05 * side, 0.08 * side),
+            ]
+            .map(|v| Vertex {
+                position: v.into(),
+                color,
+            }),
+            [0, 1, 2],
+        );
+    }
+}
+
+/// Two bars, shown while paused
+pub fn push_paused(mesh: &mut MeshBuilder, center_x: f32) {
+    let center = Vector2::new(center_x, 0.2);
+    let bar = Vector2::new(0.04, 0.15);
+
+    for offset in [-0.08, 0.08] {
+        let bar_center = center + Vector2::new(offset, 0.);
+        mesh.push_rect(bar_center - bar, bar_center + bar, [1., 1., 1., 0.8]);
+    }
+}
+
+/// An amber panel with an arrow leaving a doorway, shown while waiting for quitting to be
+/// confirmed. It takes the pause bars' place, so the two never show together.
+pub fn push_confirm_quit(mesh: &mut MeshBuilder, center_x: f32) {
+    let center = Vector2::new(center_x, 0.2);
+    mesh.push_rect(
+        center - Vector2::new(0.4, 0.3),
+        center + Vector2::new(0.4, 0.3),
+        [0.35, 0.2, 0., 0.7],
+    );
+
+    let color = [1., 0.75, 0.3, 1.];
+    let door = center - Vector2::new(0.1, 0.);
+    mesh.push_rect_outline(
+        door - Vector2::new(0.1, 0.17),
+        door + Vector2::new(0.1, 0.17),
+        0.04,
+        color,
+    );
+    mesh.push_arrow(door, center + Vector2::new(0.25, 0.), 0.04, 0.12, color);
+}
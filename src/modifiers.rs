@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+/// A multiplier on a paddle's width, lasting for `remaining` seconds or for good
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WidthModifier {
+    pub factor: f32,
+    pub remaining: Option<f32>,
+}
+
+/// The width modifiers on a paddle, applied in the order they were added
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WidthModifiers {
+    modifiers: Vec<WidthModifier>,
+}
+
+impl WidthModifiers {
+    pub fn add(&mut self, modifier: WidthModifier) {
+        self.modifiers.push(modifier);
+    }
+
+    /// Adds `modifier`, or starts over a timed one by the same factor if it's already running
+    /// rather than stacking another on top
+    pub fn restart(&mut self, modifier: WidthModifier) {
+        let running = self
+            .modifiers
+            .iter_mut()
+            .find(|running| running.factor == modifier.factor && running.remaining.is_some());
+        match running {
+            Some(running) if modifier.remaining.is_some() => *running = modifier,
+            _ => self.add(modifier),
+        }
+    }
+
+    /// Counts down the timed modifiers, dropping any that run out
+    pub fn update(&mut self, dt: f32) {
+        for modifier in &mut self.modifiers {
+            if let Some(remaining) = &mut modifier.remaining {
+                *remaining -= dt;
+            }
+        }
+        self.modifiers
+            .retain(|modifier| modifier.remaining.is_none_or(|remaining| remaining > 0.));
+    }
+
+    pub fn apply(&self, width: f32) -> f32 {
+        self.modifiers
+            .iter()
+            .fold(width, |width, modifier| width * modifier.factor)
+    }
+
+    /// Drops the timed modifiers, leaving those that last for good
+    pub fn end_timed(&mut self) {
+        self.modifiers
+            .retain(|modifier| modifier.remaining.is_none());
+    }
+
+    pub fn clear(&mut self) {
+        self.modifiers.clear();
+    }
+}
+
+#[test]
+fn modifiers_stack_and_expire_in_order() {
+    let mut modifiers = WidthModifiers::default();
+    modifiers.add(WidthModifier {
+        factor: 1.5,
+        remaining: Some(10.),
+    });
+    modifiers.add(WidthModifier {
+        factor: 0.8,
+        remaining: None,
+    });
+    modifiers.add(WidthModifier {
+        factor: 2.,
+        remaining: Some(5.),
+    });
+    assert_eq!(modifiers.apply(1.), 1.5 * 0.8 * 2.);
+
+    modifiers.update(5.);
+    assert_eq!(modifiers.apply(1.), 1.5 * 0.8);
+
+    modifiers.update(4.);
+    assert_eq!(modifiers.apply(1.), 1.5 * 0.8);
+    modifiers.update(1.);
+    assert_eq!(modifiers.apply(1.), 0.8);
+
+    // permanent ones only go when cleared
+    modifiers.update(1000.);
+    assert_eq!(modifiers.apply(1.), 0.8);
+    modifiers.clear();
+    assert_eq!(modifiers.apply(1.), 1.);
+}
@@ -0,0 +1,493 @@
+use crate::collison::{self, Shape, Toi};
+use crate::level::{Level, PathMode};
+use crate::renderer::buffer::{MeshBuilder, Vertex};
+use cgmath::{InnerSpace, Vector2, Zero};
+
+/// A push out of the obstacles a ball overlaps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObstacleHit {
+    /// Unit length, the way to push the ball
+    pub normal: Vector2<f32>,
+    pub depth: f32,
+    /// How far the obstacle moved this tick, the deepest one's if several
+    pub velocity: Vector2<f32>,
+}
+
+/// The first moving obstacle a ball would meet over a tick
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObstacleSweep {
+    /// As a fraction of the tick
+    pub toi: Toi,
+    /// How far the obstacle moves over the tick
+    pub velocity: Vector2<f32>,
+}
+
+/// Moves along a list of waypoints at a steady speed
+#[derive(Debug, Clone)]
+struct Patrol {
+    waypoints: Vec<Vector2<f32>>,
+    speed: f32,
+    mode: PathMode,
+    /// The waypoint last passed
+    from: usize,
+    /// Distance travelled from it towards the next
+    travelled: f32,
+    /// Heading up the list of waypoints rather than back down it, which only ping-pong paths do
+    forward: bool,
+}
+
+impl Patrol {
+    fn new(waypoints: Vec<Vector2<f32>>, speed: f32, mode: PathMode) -> Self {
+        Self {
+            waypoints,
+            speed,
+            mode,
+            from: 0,
+            travelled: 0.,
+            forward: true,
+        }
+    }
+
+    fn to(&self) -> usize {
+        match (self.mode, self.forward) {
+            (PathMode::Loop, _) => (self.from + 1) % self.waypoints.len(),
+            (PathMode::PingPong, true) => self.from + 1,
+            (PathMode::PingPong, false) => self.from - 1,
+        }
+    }
+
+    fn segment_length(&self) -> f32 {
+        (self.waypoints[self.to()] - self.waypoints[self.from]).magnitude()
+    }
+
+    fn position(&self) -> Vector2<f32> {
+        let from = self.waypoints[self.from];
+        if self.waypoints.len() < 2 {
+            return from;
+        }
+
+        let length = self.segment_length();
+        if length == 0. {
+            return from;
+        }
+        from + (self.waypoints[self.to()] - from) * (self.travelled / length)
+    }
+
+    fn advance(&mut self, dt: f32) {
+        let total = self
+            .waypoints
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).magnitude())
+            .sum::<f32>();
+        if total == 0. {
+            return;
+        }
+
+        let mut remaining = self.speed * dt;
+        loop {
+            let left = self.segment_length() - self.travelled;
+            if remaining < left {
+                self.travelled += remaining;
+                return;
+            }
+
+            // arriving exactly on the waypoint, turning back there if it's the end of a ping-pong
+            remaining -= left;
+            self.from = self.to();
+            self.travelled = 0.;
+            if self.mode == PathMode::PingPong {
+                if self.from == self.waypoints.len() - 1 {
+                    self.forward = false;
+                } else if self.from == 0 {
+                    self.forward = true;
+                }
+            }
+            if remaining <= 0. {
+                return;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MovingObstacle {
+    /// Corners relative to the obstacle's position
+    shape: Vec<Vector2<f32>>,
+    patrol: Patrol,
+    /// How far it moved last tick
+    velocity: Vector2<f32>,
+    /// Its corners where it is now, kept up to date as it moves so checking the ball against it
+    /// needn't work them out afresh each time
+    points: Vec<Vector2<f32>>,
+    /// Its corners where it was last tick, which the ball is swept against
+    start: Vec<Vector2<f32>>,
+}
+
+impl MovingObstacle {
+    fn new(shape: Vec<Vector2<f32>>, patrol: Patrol) -> Self {
+        let mut obstacle = Self {
+            shape,
+            patrol,
+            velocity: Vector2::zero(),
+            points: Vec::new(),
+            start: Vec::new(),
+        };
+        obstacle.place();
+        obstacle
+    }
+
+    /// Moves the corners to where the patrol has it, in the buffers they were already in
+    fn place(&mut self) {
+        let position = self.patrol.position();
+        self.points.clear();
+        self.points
+            .extend(self.shape.iter().map(|corner| position + corner));
+        self.start.clear();
+        self.start
+            .extend(self.points.iter().map(|point| point - self.velocity));
+    }
+}
+
+/// A level's indestructible convex polygons, the still ones meshed once when the level starts
+/// and the moving ones each tick
+#[derive(Debug, Clone, Default)]
+pub struct Obstacles {
+    obstacles: Vec<Vec<Vector2<f32>>>,
+    mesh: MeshBuilder,
+    moving: Vec<MovingObstacle>,
+}
+
+impl Obstacles {
+    const COLOR: [f32; 4] = [0.35, 0.4, 0.55, 1.];
+    const MOVING_COLOR: [f32; 4] = [0.45, 0.5, 0.7, 1.];
+
+    pub fn from_level(level: &Level) -> Self {
+        let points =
+            |points: &[[f32; 2]]| points.iter().map(|&point| point.into()).collect::<Vec<_>>();
+        let mut obstacles = level
+            .obstacles
+            .iter()
+            .map(|obstacle| points(obstacle))
+            .collect::<Vec<_>>();
+        separate(&mut obstacles);
+        let moving = level
+            .moving_obstacles
+            .iter()
+            .map(|obstacle| {
+                let patrol =
+                    Patrol::new(points(&obstacle.waypoints), obstacle.speed, obstacle.path);
+                MovingObstacle::new(points(&obstacle.shape), patrol)
+            })
+            .collect();
+
+        let mut mesh = MeshBuilder::default();
+        for points in &obstacles {
+            push_polygon(&mut mesh, points, Self::COLOR);
+        }
+
+        Self {
+            obstacles,
+            mesh,
+            moving,
+        }
+    }
+
+    /// Moves the moving obstacles along their paths
+    pub fn update(&mut self, dt: f32) {
+        for obstacle in &mut self.moving {
+            let before = obstacle.patrol.position();
+            obstacle.patrol.advance(dt);
+            obstacle.velocity = obstacle.patrol.position() - before;
+            obstacle.place();
+        }
+    }
+
+    /// How far each moving obstacle is along its path, as the waypoint it last passed, the
+    /// distance on from it and whether it's heading up the list
+    pub fn progress(&self) -> Vec<(usize, f32, bool)> {
+        self.moving
+            .iter()
+            .map(|obstacle| {
+                let patrol = &obstacle.patrol;
+                (patrol.from, patrol.travelled, patrol.forward)
+            })
+            .collect()
+    }
+
+    /// Puts the moving obstacles back where `progress` had them, skipping any waypoint they
+    /// don't have
+    pub fn set_progress(&mut self, progress: &[(usize, f32, bool)]) {
+        for (obstacle, &(from, travelled, forward)) in self.moving.iter_mut().zip(progress) {
+            let patrol = &mut obstacle.patrol;
+            if from < patrol.waypoints.len() {
+                (patrol.from, patrol.travelled, patrol.forward) = (from, travelled, forward);
+                obstacle.place();
+            }
+        }
+    }
+
+    /// One push out of every obstacle the circle overlaps, so a ball wedged in a notch between
+    /// two is lifted out of both at once rather than knocked back and forth between them
+    pub fn collide(&self, position: Vector2<f32>, radius: f32) -> Option<ObstacleHit> {
+        // gone over a few times rather than collected, as it's done every substep
+        let contacts = || {
+            let still = self
+                .obstacles
+                .iter()
+                .map(|points| (points, Vector2::zero()));
+            let moving = self
+                .moving
+                .iter()
+                .map(|obstacle| (&obstacle.points, obstacle.velocity));
+            still.chain(moving).filter_map(|(points, velocity)| {
+                collison::circle_convex_polygon(position, radius, points)
+                    .map(|manifold| (manifold, velocity))
+            })
+        };
+
+        let combined = contacts().fold(Vector2::zero(), |sum: Vector2<f32>, (contact, _)| {
+            sum + contact.normal * contact.depth
+        });
+        if combined.magnitude2() <= f32::EPSILON * f32::EPSILON {
+            return None;
+        }
+
+        // far enough along the combined direction to clear every contact
+        let normal = combined.normalize();
+        let depth = contacts()
+            .filter_map(|(contact, _)| {
+                let along = contact.normal.dot(normal);
+                (along > 0.).then(|| contact.depth / along)
+            })
+            .fold(0., f32::max);
+        let (_, velocity) = contacts().max_by(|(a, _), (b, _)| a.depth.total_cmp(&b.depth))?;
+
+        Some(ObstacleHit {
+            normal,
+            depth,
+            velocity,
+        })
+    }
+
+    /// Where a ball moving `velocity` this tick first meets a moving obstacle on its way from where
+    /// it was last tick, which can sweep right over a ball between two substeps. Balls already
+    /// touching one are left to `collide`.
+    pub fn sweep(
+        &self,
+        position: Vector2<f32>,
+        velocity: Vector2<f32>,
+        radius: f32,
+    ) -> Option<ObstacleSweep> {
+        self.moving
+            .iter()
+            .filter_map(|obstacle| {
+                let toi = collison::swept_circle_vs_polygon(
+                    position,
+                    radius,
+                    velocity,
+                    &obstacle.start,
+                    obstacle.velocity,
+                    1.,
+                )?;
+                (toi.time > 0.).then_some(ObstacleSweep {
+                    toi,
+                    velocity: obstacle.velocity,
+                })
+            })
+            .min_by(|a, b| a.toi.time.total_cmp(&b.toi.time))
+    }
+
+    pub fn push(&self, mesh: &mut MeshBuilder) {
+        mesh.append(&self.mesh);
+        for obstacle in &self.moving {
+            push_polygon(mesh, &obstacle.points, Self::MOVING_COLOR);
+        }
+    }
+
+    /// Every obstacle's collider, moving ones where they are now
+    pub fn shapes(&self) -> impl Iterator<Item = Shape> + '_ {
+        let moving = self.moving.iter().map(|obstacle| obstacle.points.clone());
+        self.obstacles
+            .iter()
+            .cloned()
+            .chain(moving)
+            .map(Shape::ConvexPolygon)
+    }
+}
+
+/// Pushes a convex polygon as a fan from its first corner
+/// Pushes each still obstacle out of those before it, so ones placed overlapping don't leave the
+/// ball a seam to catch on
+fn separate(obstacles: &mut [Vec<Vector2<f32>>]) {
+    for i in 1..obstacles.len() {
+        let (placed, rest) = obstacles.split_at_mut(i);
+        let obstacle = &mut rest[0];
+        for other in placed.iter() {
+            if let Some(mtv) = collison::polygon_mtv(obstacle, other) {
+                log::warn!("pushing obstacles[{i}] out of another it overlaps");
+                for point in obstacle.iter_mut() {
+                    *point += mtv.axis * mtv.depth;
+                }
+            }
+        }
+    }
+}
+
+fn push_polygon(mesh: &mut MeshBuilder, points: &[Vector2<f32>], color: [f32; 4]) {
+    // triangles must wind anticlockwise so they aren't culled
+    let anticlockwise = collison::is_anticlockwise(points);
+    let fan = (1..points.len() as u16 - 1).flat_map(|i| {
+        if anticlockwise {
+            [0, i, i + 1]
+        } else {
+            [0, i + 1, i]
+        }
+    });
+    mesh.push(
+        points.iter().map(|point| Vertex {
+            position: (*point).into(),
+            color,
+        }),
+        fan,
+    );
+}
+
+#[cfg(test)]
+fn obstacles(source: &str) -> Obstacles {
+    Obstacles::from_level(&Level::parse(source).unwrap())
+}
+
+#[test]
+fn notch_lifts_the_ball_out_of_both_sides() {
+    // two ramps meeting in a v at the origin
+    let obstacles = obstacles(
+        r#"
+        name = "notch"
+        obstacles = [
+            [[-1.0, 1.0], [-1.0, 0.0], [0.0, 0.0]],
+            [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]],
+        ]
+        "#,
+    );
+
+    let radius = 0.1;
+    let position = Vector2::new(0., 0.1);
+    let contact = obstacles.collide(position, radius).unwrap();
+    assert!((contact.normal - Vector2::unit_y()).magnitude() < 1e-6);
+
+    let resolved = position + contact.normal * contact.depth;
+    assert_eq!(obstacles.collide(resolved, radius * 0.999), None);
+
+    // drawn as a triangle each
+    let mut mesh = MeshBuilder::default();
+    obstacles.push(&mut mesh);
+    assert_eq!(mesh.vertex_count(), 6);
+}
+
+#[test]
+fn overlapping_obstacles_are_pushed_apart() {
+    let obstacles = obstacles(
+        r#"
+        name = "overlap"
+        obstacles = [
+            [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]],
+            [[0.8, 0.2], [1.8, 0.2], [1.8, 0.9], [0.8, 0.9]],
+        ]
+        "#,
+    );
+
+    // out the shortest way, to the right
+    let [first, second] = [0, 1].map(|i| &obstacles.obstacles[i]);
+    assert_eq!(first[0], Vector2::new(0., 0.));
+    assert!((second[0] - Vector2::new(1., 0.2)).magnitude() < 1e-6);
+    assert_eq!(collison::polygon_mtv(second, first), None);
+}
+
+#[test]
+fn patrols_interpolate_and_turn_exactly_at_the_ends() {
+    let waypoints = vec![
+        Vector2::new(0., 0.),
+        Vector2::new(1., 0.),
+        Vector2::new(1., 1.),
+    ];
+    let mut patrol = Patrol::new(waypoints.clone(), 1., PathMode::PingPong);
+
+    patrol.advance(0.25);
+    assert_eq!(patrol.position(), Vector2::new(0.25, 0.));
+    patrol.advance(1.25);
+    assert_eq!(patrol.position(), Vector2::new(1., 0.5));
+
+    // reaching the end lands on it, then heads back
+    patrol.advance(0.5);
+    assert_eq!(patrol.position(), Vector2::new(1., 1.));
+    patrol.advance(0.25);
+    assert_eq!(patrol.position(), Vector2::new(1., 0.75));
+    patrol.advance(1.75);
+    assert_eq!(patrol.position(), Vector2::new(0., 0.));
+    patrol.advance(0.5);
+    assert_eq!(patrol.position(), Vector2::new(0.5, 0.));
+
+    // looping goes from the last straight on to the first
+    let mut patrol = Patrol::new(waypoints, 1., PathMode::Loop);
+    patrol.advance(2.5);
+    let along = 0.5 / 2f32.sqrt();
+    assert!((patrol.position() - Vector2::new(1. - along, 1. - along)).magnitude() < 1e-6);
+}
+
+#[test]
+fn fast_obstacle_is_met_where_it_first_reaches_the_ball() {
+    let mut obstacles = obstacles(
+        r#"
+        name = "sweeper"
+        [[moving_obstacles]]
+        shape = [[-0.1, -0.1], [0.1, -0.1], [0.1, 0.1], [-0.1, 0.1]]
+        waypoints = [[0.0, 0.0], [2.0, 0.0]]
+        speed = 1.0
+        "#,
+    );
+    obstacles.update(0.5);
+
+    // passed over entirely, so there's nothing left to overlap at the end of the tick
+    let (position, radius) = (Vector2::new(0.3, 0.), 0.05);
+    assert_eq!(obstacles.collide(position, radius), None);
+    let sweep = obstacles.sweep(position, Vector2::zero(), radius).unwrap();
+    assert!((sweep.toi.time - 0.3).abs() < 1e-6);
+    assert!((sweep.toi.normal - Vector2::unit_x()).magnitude() < 1e-6);
+    assert_eq!(sweep.velocity, Vector2::new(0.5, 0.));
+
+    // moved along in the buffers it already had, rather than new ones each tick
+    let buffers = |obstacles: &Obstacles| {
+        let obstacle = &obstacles.moving[0];
+        (obstacle.points.as_ptr(), obstacle.start.as_ptr())
+    };
+    let before = buffers(&obstacles);
+    obstacles.update(0.5);
+    assert_eq!(buffers(&obstacles), before);
+    let start = obstacles.moving[0].start[0];
+    assert!((start - Vector2::new(0.4, -0.1)).magnitude() < 1e-6);
+}
+
+#[test]
+fn moving_obstacle_pushes_a_slow_ball_along() {
+    let mut obstacles = obstacles(
+        r#"
+        name = "pusher"
+        [[moving_obstacles]]
+        shape = [[-0.1, -0.1], [0.1, -0.1], [0.1, 0.1], [-0.1, 0.1]]
+        waypoints = [[0.0, 0.0], [1.0, 0.0]]
+        speed = 1.0
+        "#,
+    );
+
+    // resting just ahead of the block as it moves into it
+    let radius = 0.05;
+    let position = Vector2::new(0.16, 0.);
+    assert_eq!(obstacles.collide(position, radius), None);
+    obstacles.update(0.1);
+
+    let hit = obstacles.collide(position, radius).unwrap();
+    assert!((hit.normal - Vector2::unit_x()).magnitude() < 1e-6);
+    assert!((hit.velocity - Vector2::new(0.1, 0.)).magnitude() < 1e-6);
+    let resolved = position + hit.normal * hit.depth;
+    assert!(resolved.x - radius >= 0.2 - 1e-6);
+}
@@ -0,0 +1,196 @@
+//! Colours looked up by what they mean rather than written where they're drawn, so the whole set
+//! can be swapped for one that players who can't tell orange from red or red from green can read
+
+use serde::{Deserialize, Serialize};
+
+/// The built-in palettes, as chosen in the config
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaletteName {
+    #[default]
+    Default,
+    /// Blues against oranges and yellows, kept apart without telling red from green
+    Deuteranopia,
+    /// Saturated colours on black, with nothing dim
+    HighContrast,
+}
+
+impl PaletteName {
+    pub const ALL: [Self; 3] = [Self::Default, Self::Deuteranopia, Self::HighContrast];
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Default => Self::Deuteranopia,
+            Self::Deuteranopia => Self::HighContrast,
+            Self::HighContrast => Self::Default,
+        }
+    }
+
+    pub fn palette(self) -> &'static Palette {
+        match self {
+            Self::Default => &Palette::DEFAULT,
+            Self::Deuteranopia => &Palette::DEUTERANOPIA,
+            Self::HighContrast => &Palette::HIGH_CONTRAST,
+        }
+    }
+}
+
+/// What a colour is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    LoseZone,
+    /// What the lose zone flashes when a file dropped onto the window isn't a level
+    RejectFlash,
+    Paddle,
+    Ball,
+    /// The marker showing which way the ball spins
+    BallSpin,
+    /// Counting up from the bottom row, the colours repeating every `BRICK_ROWS`
+    BrickRow(usize),
+    /// A power-up that helps, like the shield
+    PowerUpGood,
+    /// A power-up that makes things harder, like flipped gravity
+    PowerUpBad,
+    /// The end of a portal pair a level lists first
+    PortalA,
+    /// And the end it lists second, told apart from the first
+    PortalB,
+    /// The bar that saves the ball once
+    Shield,
+}
+
+impl Role {
+    /// Every role a palette has to colour
+    pub fn all() -> impl Iterator<Item = Self> {
+        [
+            Self::LoseZone,
+            Self::RejectFlash,
+            Self::Paddle,
+            Self::Ball,
+            Self::BallSpin,
+            Self::PowerUpGood,
+            Self::PowerUpBad,
+            Self::PortalA,
+            Self::PortalB,
+            Self::Shield,
+        ]
+        .into_iter()
+        .chain((0..BRICK_ROWS).map(Self::BrickRow))
+    }
+}
+
+/// How many rows of bricks are coloured before the colours come round again
+const BRICK_ROWS: usize = 4;
+
+/// Shown for a role a palette leaves out, to stand out as a mistake, so no palette uses it
+const MISSING: [f32; 3] = [1., 0., 0.5];
+
+/// A colour for each role
+#[derive(Debug)]
+pub struct Palette {
+    colors: &'static [(Role, [f32; 3])],
+    /// Whether bricks keep the colours their level gives them rather than being coloured by row
+    pub level_bricks: bool,
+}
+
+impl Palette {
+    pub const DEFAULT: Self = Self {
+        colors: &[
+            (Role::LoseZone, [1., 0.6, 0.]),
+            (Role::RejectFlash, [1., 0., 0.]),
+            (Role::Paddle, [1., 1., 1.]),
+            (Role::Ball, [1., 1., 1.]),
+            (Role::BallSpin, [0.2, 0.2, 0.2]),
+            (Role::PowerUpGood, [0.4, 0.9, 1.]),
+            (Role::PowerUpBad, [0.7, 0.4, 1.]),
+            (Role::PortalA, [1., 0.55, 0.1]),
+            (Role::PortalB, [0.2, 0.6, 1.]),
+            (Role::Shield, [0.4, 0.9, 1.]),
+            // the bundled levels' own, for levels that leave their bricks uncoloured
+            (Role::BrickRow(0), [0.9, 0.3, 0.3]),
+            (Role::BrickRow(1), [0.9, 0.7, 0.2]),
+            (Role::BrickRow(2), [0.3, 0.8, 0.4]),
+            (Role::BrickRow(3), [0.3, 0.5, 0.9]),
+        ],
+        level_bricks: true,
+    };
+
+    pub const DEUTERANOPIA: Self = Self {
+        colors: &[
+            (Role::LoseZone, [0., 0.45, 0.7]),
+            (Role::RejectFlash, [0.85, 0.35, 0.]),
+            (Role::Paddle, [1., 1., 1.]),
+            (Role::Ball, [1., 1., 1.]),
+            (Role::BallSpin, [0.2, 0.2, 0.2]),
+            (Role::PowerUpGood, [0.35, 0.7, 0.9]),
+            (Role::PowerUpBad, [0.9, 0.6, 0.]),
+            (Role::PortalA, [0.9, 0.6, 0.]),
+            (Role::PortalB, [0., 0.45, 0.7]),
+            (Role::Shield, [0.35, 0.7, 0.9]),
+            (Role::BrickRow(0), [0.8, 0.47, 0.65]),
+            (Role::BrickRow(1), [0.9, 0.6, 0.]),
+            (Role::BrickRow(2), [0.95, 0.9, 0.25]),
+            (Role::BrickRow(3), [0.35, 0.7, 0.9]),
+        ],
+        level_bricks: false,
+    };
+
+    pub const HIGH_CONTRAST: Self = Self {
+        colors: &[
+            (Role::LoseZone, [1., 1., 0.]),
+            (Role::RejectFlash, [1., 0., 0.]),
+            (Role::Paddle, [1., 1., 1.]),
+            (Role::Ball, [1., 1., 1.]),
+            (Role::BallSpin, [0., 0., 0.]),
+            (Role::PowerUpGood, [0., 1., 1.]),
+            (Role::PowerUpBad, [1., 0., 1.]),
+            (Role::PortalA, [1., 0.5, 0.]),
+            (Role::PortalB, [0., 0.5, 1.]),
+            (Role::Shield, [0., 1., 1.]),
+            (Role::BrickRow(0), [1., 1., 1.]),
+            (Role::BrickRow(1), [1., 1., 0.]),
+            (Role::BrickRow(2), [0., 1., 1.]),
+            (Role::BrickRow(3), [1., 0.5, 0.]),
+        ],
+        level_bricks: false,
+    };
+
+    /// The colour for `role`, if the palette has one
+    pub fn get(&self, role: Role) -> Option<[f32; 3]> {
+        let role = match role {
+            Role::BrickRow(row) => Role::BrickRow(row % BRICK_ROWS),
+            role => role,
+        };
+        self.colors
+            .iter()
+            .find(|(colored, _)| *colored == role)
+            .map(|&(_, color)| color)
+    }
+
+    pub fn color(&self, role: Role) -> [f32; 3] {
+        self.get(role).unwrap_or(MISSING)
+    }
+
+    /// The colour for `role` with `alpha`, as the mesh takes it
+    pub fn rgba(&self, role: Role, alpha: f32) -> [f32; 4] {
+        let [r, g, b] = self.color(role);
+        [r, g, b, alpha]
+    }
+}
+
+#[test]
+fn every_palette_colors_every_role() {
+    for name in PaletteName::ALL {
+        let palette = name.palette();
+        for role in Role::all() {
+            assert!(palette.get(role).is_some(), "{name:?} has no {role:?}");
+            // which would look like it had none
+            assert_ne!(palette.color(role), MISSING, "{name:?} {role:?}");
+        }
+        // and rows past those listed come round again
+        assert_eq!(
+            palette.get(Role::BrickRow(BRICK_ROWS + 1)),
+            palette.get(Role::BrickRow(1))
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use crate::renderer::buffer::{MeshBuilder, ParticleInstance, Vertex};
+use cgmath::Vector2;
+use rand::Rng;
+use std::f32::consts::FRAC_PI_3;
+
+#[derive(Debug, Clone, Copy)]
+struct Particle {
+    position: Vector2<f32>,
+    velocity: Vector2<f32>,
+    age: f32,
+    lifetime: f32,
+}
+
+/// Short-lived quads thrown out from impacts
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    /// Live particles allowed at once
+    limit: usize,
+}
+
+impl ParticleSystem {
+    /// Hard cap on live particles, so bursts can't grow the mesh without bound
+    const MAX_PARTICLES: usize = 512;
+    const SIZE: f32 = 0.015;
+    const GRAVITY: f32 = 3.;
+    /// Half angle of the cone particles are thrown out in around the normal
+    const SPREAD: f32 = FRAC_PI_3;
+    const COLOR: [f32; 3] = [1., 1., 1.];
+
+    pub fn new() -> Self {
+        Self::with_limit(Self::MAX_PARTICLES)
+    }
+
+    /// Allowing `limit` live particles rather than the usual cap, as for benchmarking
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            particles: Vec::with_capacity(limit),
+            limit,
+        }
+    }
+
+    pub fn spawn_burst(
+        &mut self,
+        position: Vector2<f32>,
+        normal: Vector2<f32>,
+        count: usize,
+        rng: &mut impl Rng,
+    ) {
+        let count = count.min(self.limit - self.particles.len());
+        let normal_angle = normal.y.atan2(normal.x);
+
+        self.particles.extend((0..count).map(|_| {
+            let angle = normal_angle + rng.gen_range(-Self::SPREAD..=Self::SPREAD);
+            let speed = rng.gen_range(0.3..1.2);
+            let (s, c) = angle.sin_cos();
+
+            Particle {
+                position,
+                velocity: Vector2::new(c, s) * speed,
+                age: 0.,
+                lifetime: rng.gen_range(0.35..0.65),
+            }
+        }));
+    }
+
+    /// Drops every live particle, as when the playfield is swapped out from under them
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let mut i = 0;
+        while i < self.particles.len() {
+            let particle = &mut self.particles[i];
+            particle.age += dt;
+
+            if particle.age >= particle.lifetime {
+                // order doesn't matter, and this never reallocates
+                self.particles.swap_remove(i);
+                continue;
+            }
+
+            particle.velocity.y -= Self::GRAVITY * dt;
+            particle.position += particle.velocity * dt;
+            i += 1;
+        }
+    }
+
+    /// Pushes an instance for each particle, for the shader to make the quads from
+    pub fn render_instances(&self, instances: &mut Vec<ParticleInstance>) {
+        instances.extend(self.particles.iter().map(|particle| ParticleInstance {
+            position: particle.position.into(),
+            size: Self::SIZE,
+            color: Self::COLOR,
+            alpha: Self::alpha(particle),
+        }));
+    }
+
+    /// Fading out over the particle's life
+    fn alpha(particle: &Particle) -> f32 {
+        1. - particle.age / particle.lifetime
+    }
+
+    /// Pushes a quad for each particle, as was done before they were drawn from instances
+    pub fn push(&self, mesh: &mut MeshBuilder) {
+        mesh.reserve(self.particles.len() * 4, self.particles.len() * 6);
+
+        for particle in &self.particles {
+            let Vector2 { x, y } = particle.position;
+            let [r, g, b] = Self::COLOR;
+            let color = [r, g, b, Self::alpha(particle)];
+            const FRAC_SIZE_2: f32 = ParticleSystem::SIZE / 2.;
+
+            mesh.push(
+                [
+                    [x - FRAC_SIZE_2, y - FRAC_SIZE_2],
+                    [x + FRAC_SIZE_2, y - FRAC_SIZE_2],
+                    [x + FRAC_SIZE_2, y + FRAC_SIZE_2],
+                    [x - FRAC_SIZE_2, y + FRAC_SIZE_2],
+                ]
+                .map(|position| Vertex { position, color }),
+                [0, 1, 2, 0, 2, 3],
+            );
+        }
+    }
+}
+
+impl Default for ParticleSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn particles_are_capped_and_retired() {
+    let mut rng = rand::thread_rng();
+    let mut particles = ParticleSystem::new();
+
+    particles.spawn_burst(Vector2::new(0., 0.), Vector2::unit_y(), 1000, &mut rng);
+    assert_eq!(particles.particles.len(), ParticleSystem::MAX_PARTICLES);
+
+    let capacity = particles.particles.capacity();
+    particles.update(0.4);
+    particles.spawn_burst(Vector2::new(0., 0.), Vector2::unit_y(), 20, &mut rng);
+    particles.update(0.3);
+    assert!(particles.particles.len() <= 20);
+    assert_eq!(particles.particles.capacity(), capacity);
+
+    particles.update(1.);
+    assert!(particles.particles.is_empty());
+}
+
+#[test]
+fn instances_describe_the_same_quads_as_the_mesh() {
+    let mut rng = rand::thread_rng();
+    let mut particles = ParticleSystem::new();
+    particles.spawn_burst(Vector2::new(0.2, 0.), Vector2::unit_y(), 10, &mut rng);
+    particles.update(0.1);
+
+    let mut mesh = MeshBuilder::default();
+    particles.push(&mut mesh);
+    let mut instances = Vec::new();
+    particles.render_instances(&mut instances);
+
+    assert_eq!(instances.len(), 10);
+    for (instance, quad) in instances.iter().zip(mesh.vertices().chunks(4)) {
+        // the middle of the quad and the far corner from its first
+        let [x, y] = instance.position;
+        let first = quad[0].position;
+        let opposite = quad[2].position;
+        assert!(((first[0] + opposite[0]) / 2. - x).abs() < 1e-6);
+        assert!(((first[1] + opposite[1]) / 2. - y).abs() < 1e-6);
+        assert!((opposite[0] - first[0] - instance.size).abs() < 1e-6);
+        let [r, g, b] = instance.color;
+        assert_eq!([r, g, b, instance.alpha], quad[0].color);
+    }
+}
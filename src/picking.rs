@@ -0,0 +1,89 @@
+//! Finding what's under the cursor, from each entity's collider registered afresh every tick
+
+use crate::collison::Shape;
+use cgmath::Vector2;
+
+/// Something in the world that can be picked out, numbered as the game holds it that tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityId {
+    Ball,
+    Paddle(usize),
+    Brick(usize),
+    Obstacle(usize),
+}
+
+impl EntityId {
+    /// Which entities are drawn over which, the topmost picked where they overlap
+    fn layer(self) -> u8 {
+        match self {
+            Self::Obstacle(_) => 0,
+            Self::Brick(_) => 1,
+            Self::Paddle(_) => 2,
+            Self::Ball => 3,
+        }
+    }
+}
+
+/// Every pickable entity's shape as of the last tick it was filled
+#[derive(Debug, Clone, Default)]
+pub struct PickSet {
+    entries: Vec<(EntityId, Shape)>,
+}
+
+impl PickSet {
+    /// Forgets every entity, keeping the room they took
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn add(&mut self, id: EntityId, shape: Shape) {
+        self.entries.push((id, shape));
+    }
+
+    /// The topmost entity covering `point`, and of those in the same layer the last added
+    pub fn query(&self, point: Vector2<f32>) -> Option<EntityId> {
+        self.entries
+            .iter()
+            .filter(|(_, shape)| shape.contains_point(point))
+            .max_by_key(|(id, _)| id.layer())
+            .map(|&(id, _)| id)
+    }
+
+    pub fn shape(&self, id: EntityId) -> Option<&Shape> {
+        self.entries
+            .iter()
+            .find(|(entry, _)| *entry == id)
+            .map(|(_, shape)| shape)
+    }
+}
+
+#[test]
+fn the_topmost_entity_is_picked() {
+    use cgmath::vec2;
+
+    let square = |min: Vector2<f32>, size: f32| Shape::Aabb {
+        min,
+        max: min + vec2(size, size),
+    };
+    let mut picks = PickSet::default();
+    picks.add(
+        EntityId::Ball,
+        Shape::Circle {
+            center: vec2(0.5, 0.5),
+            radius: 0.1,
+        },
+    );
+    picks.add(EntityId::Obstacle(0), square(vec2(0., 0.), 2.));
+    picks.add(EntityId::Brick(3), square(vec2(0., 0.), 1.));
+    picks.add(EntityId::Brick(4), square(vec2(0.8, 0.8), 0.5));
+
+    assert_eq!(picks.query(vec2(0.5, 0.5)), Some(EntityId::Ball));
+    assert_eq!(picks.query(vec2(0.2, 0.2)), Some(EntityId::Brick(3)));
+    // two bricks overlapping, the one added later drawn over the other
+    assert_eq!(picks.query(vec2(0.9, 0.9)), Some(EntityId::Brick(4)));
+    assert_eq!(picks.query(vec2(1.5, 0.2)), Some(EntityId::Obstacle(0)));
+    assert_eq!(picks.query(vec2(3., 3.)), None);
+
+    picks.clear();
+    assert_eq!(picks.query(vec2(0.5, 0.5)), None);
+}
@@ -0,0 +1,159 @@
+use crate::level::Level;
+use crate::palette::{Palette, Role};
+use crate::renderer::buffer::MeshBuilder;
+use cgmath::{Basis2, InnerSpace, Rad, Rotation, Rotation2, Vector2};
+use std::f32::consts::TAU;
+
+#[derive(Debug, Clone, Copy)]
+struct Portal {
+    center: Vector2<f32>,
+    /// Radians anticlockwise
+    angle: f32,
+}
+
+#[derive(Debug, Clone)]
+struct Pair {
+    ends: [Portal; 2],
+    radius: f32,
+}
+
+/// A level's portal pairs, each carrying the ball from one end to the other
+#[derive(Debug, Clone, Default)]
+pub struct Portals {
+    pairs: Vec<Pair>,
+    /// The pair and end the ball last came out of, which it must leave before it can go through
+    /// a portal again rather than bouncing straight back
+    exit: Option<(usize, usize)>,
+    /// Seconds since the level started, turning the rings
+    time: f32,
+}
+
+impl Portals {
+    /// Ring turns per second
+    const SPIN: f32 = 0.5;
+    const ARCS: usize = 3;
+
+    pub fn from_level(level: &Level) -> Self {
+        let pairs = level
+            .portals
+            .iter()
+            .map(|pair| Pair {
+                ends: [pair.a, pair.b].map(|end| Portal {
+                    center: end.center.into(),
+                    angle: end.angle.to_radians(),
+                }),
+                radius: pair.radius,
+            })
+            .collect();
+
+        Self {
+            pairs,
+            ..Self::default()
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+    }
+
+    /// Lets the ball go through a portal again straight away, for when it's put back on a paddle
+    pub fn reset(&mut self) {
+        self.exit = None;
+    }
+
+    /// The pair and end the ball must leave before going through a portal again
+    pub fn exit(&self) -> Option<(usize, usize)> {
+        self.exit
+    }
+
+    /// Restores what `exit` returned, unless the level has no such portal
+    pub fn set_exit(&mut self, exit: Option<(usize, usize)>) {
+        self.exit = exit.filter(|&(pair, end)| pair < self.pairs.len() && end < 2);
+    }
+
+    /// Moves a ball whose centre has crossed into a portal out of the other end of its pair,
+    /// turned by the difference between the ends, returning where it came out
+    pub fn teleport(
+        &mut self,
+        position: &mut Vector2<f32>,
+        velocity: &mut Vector2<f32>,
+    ) -> Option<Vector2<f32>> {
+        let inside = |pair: &Pair, end: usize| {
+            (*position - pair.ends[end].center).magnitude2() < pair.radius * pair.radius
+        };
+
+        if let Some((pair, end)) = self.exit {
+            if inside(&self.pairs[pair], end) {
+                return None;
+            }
+            self.exit = None;
+        }
+
+        let (i, end) = self
+            .pairs
+            .iter()
+            .enumerate()
+            .find_map(|(i, pair)| (0..2).find(|&end| inside(pair, end)).map(|end| (i, end)))?;
+        let pair = &self.pairs[i];
+        let (from, to) = (pair.ends[end], pair.ends[1 - end]);
+
+        let turn: Basis2<f32> = Rotation2::from_angle(Rad(to.angle - from.angle));
+        *position = to.center + turn.rotate_vector(*position - from.center);
+        *velocity = turn.rotate_vector(*velocity);
+        self.exit = Some((i, 1 - end));
+
+        Some(*position)
+    }
+
+    /// Pushes each portal as a ring of arcs, turning over time, in the palette's colour for its
+    /// end of the pair
+    pub fn push(&self, mesh: &mut MeshBuilder, palette: &Palette) {
+        let colors = [Role::PortalA, Role::PortalB].map(|role| palette.rgba(role, 1.));
+        for pair in &self.pairs {
+            for (portal, color) in pair.ends.iter().zip(colors) {
+                let turned = portal.angle + self.time * Self::SPIN * TAU;
+                for arc in 0..Self::ARCS {
+                    let start = turned + arc as f32 * TAU / Self::ARCS as f32;
+                    mesh.push_arc(
+                        portal.center,
+                        pair.radius,
+                        [start, TAU / Self::ARCS as f32 * 0.7],
+                        0.02,
+                        8,
+                        color,
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn turned_portal_turns_the_ball_and_waits_for_it_to_leave() {
+    let level = Level::parse(
+        r#"
+        name = "portals"
+        [[portals]]
+        a = { center = [-1.0, 0.0] }
+        b = { center = [1.0, 0.0], angle = 90.0 }
+        "#,
+    )
+    .unwrap();
+    let mut portals = Portals::from_level(&level);
+
+    let mut position = Vector2::new(-1.1, 0.);
+    let mut velocity = Vector2::new(0.1, 0.);
+    let exit = portals.teleport(&mut position, &mut velocity).unwrap();
+    assert!((exit - Vector2::new(1., -0.1)).magnitude() < 1e-6);
+    assert!((velocity - Vector2::new(0., 0.1)).magnitude() < 1e-6);
+
+    // still within the exit, so it doesn't go straight back
+    assert_eq!(portals.teleport(&mut position, &mut velocity), None);
+
+    position = Vector2::new(1., 0.5);
+    assert_eq!(portals.teleport(&mut position, &mut velocity), None);
+    position = Vector2::new(1., 0.1);
+    let exit = portals.teleport(&mut position, &mut velocity).unwrap();
+    assert!((exit - Vector2::new(-0.9, 0.)).magnitude() < 1e-6);
+    assert!((velocity - Vector2::new(0.1, 0.)).magnitude() < 1e-6);
+}
@@ -0,0 +1,232 @@
+use crate::palette::{Palette, Role};
+use crate::renderer::buffer::MeshBuilder;
+use crate::renderer::shapes::SegmentCount;
+use crate::tunables::Tunables;
+use crate::Paddle;
+use cgmath::Vector2;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerUpKind {
+    /// Turns gravity around, floating the ball away from the paddle
+    GravityFlip,
+    /// Puts a bar above the lose zone that saves the ball once
+    Shield,
+    /// Widens the player's paddle
+    Wide,
+}
+
+impl PowerUpKind {
+    const ALL: [Self; 3] = [Self::GravityFlip, Self::Shield, Self::Wide];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::GravityFlip => "gravity_flip",
+            Self::Shield => "shield",
+            Self::Wide => "wide",
+        }
+    }
+
+    /// Seconds it lasts once collected
+    pub fn duration(self) -> f32 {
+        match self {
+            Self::GravityFlip => 5.,
+            Self::Shield => 15.,
+            Self::Wide => 10.,
+        }
+    }
+
+    /// Whether it helps or hinders, which is what it's coloured by
+    fn role(self) -> Role {
+        match self {
+            Self::GravityFlip => Role::PowerUpBad,
+            Self::Shield | Self::Wide => Role::PowerUpGood,
+        }
+    }
+}
+
+/// A power-up falling from a broken brick towards the player's paddle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Pickup {
+    kind: PowerUpKind,
+    position: Vector2<f32>,
+}
+
+/// The power-ups falling to be caught and those caught and still running
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PowerUps {
+    pickups: Vec<Pickup>,
+    /// Each with the seconds it has left
+    active: Vec<(PowerUpKind, f32)>,
+}
+
+impl PowerUps {
+    /// Units per second
+    const FALL_SPEED: f32 = 0.4;
+    const RADIUS: f32 = 0.04;
+
+    /// Drops a random power-up at `position` with the tunables' chance
+    pub fn maybe_drop(&mut self, position: Vector2<f32>, tunables: &Tunables, rng: &mut impl Rng) {
+        if rng.gen_bool(tunables.power_up_chance) {
+            let kind = PowerUpKind::ALL[rng.gen_range(0..PowerUpKind::ALL.len())];
+            self.pickups.push(Pickup { kind, position });
+        }
+    }
+
+    /// Moves the pickups towards `paddle`, returning those it caught, and counts down the active
+    /// power-ups
+    pub fn update(&mut self, dt: f32, paddle: &Paddle) -> Vec<PowerUpKind> {
+        for (_, remaining) in &mut self.active {
+            *remaining -= dt;
+        }
+        self.active.retain(|(_, remaining)| *remaining > 0.);
+
+        let mut caught = Vec::new();
+        self.pickups.retain_mut(|pickup| {
+            pickup.position.y -= paddle.facing * Self::FALL_SPEED * dt;
+            if paddle.touches(pickup.position, Self::RADIUS) {
+                caught.push(pickup.kind);
+                false
+            } else {
+                // kept until past the paddle
+                (pickup.position.y - paddle.y()) * paddle.facing > -paddle.height
+            }
+        });
+
+        for &kind in &caught {
+            // catching one already running starts it over
+            self.active.retain(|(active, _)| *active != kind);
+            self.active.push((kind, kind.duration()));
+        }
+        caught
+    }
+
+    #[cfg(test)]
+    pub fn activate(&mut self, kind: PowerUpKind) {
+        self.active.push((kind, kind.duration()));
+    }
+
+    #[cfg(test)]
+    pub fn drop_at(&mut self, kind: PowerUpKind, position: Vector2<f32>) {
+        self.pickups.push(Pickup { kind, position });
+    }
+
+    /// Ends a power-up before it runs out
+    pub fn end(&mut self, kind: PowerUpKind) {
+        self.active.retain(|(active, _)| *active != kind);
+    }
+
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.active.iter().any(|(active, _)| *active == kind)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn push(&self, mesh: &mut MeshBuilder, palette: &Palette) {
+        for pickup in &self.pickups {
+            let color = palette.rgba(pickup.kind.role(), 1.);
+            mesh.push_circle(
+                pickup.position,
+                Self::RADIUS,
+                SegmentCount::Fixed(16),
+                color,
+            );
+            match pickup.kind {
+                PowerUpKind::GravityFlip => {
+                    let arm = Vector2::new(0., Self::RADIUS * 0.6);
+                    mesh.push_arrow(
+                        pickup.position - arm,
+                        pickup.position + arm,
+                        0.008,
+                        0.024,
+                        [1.; 4],
+                    );
+                }
+                PowerUpKind::Shield => {
+                    let half = Vector2::new(Self::RADIUS * 0.6, 0.);
+                    mesh.push_segment(
+                        pickup.position - half,
+                        pickup.position + half,
+                        0.01,
+                        [1.; 4],
+                    );
+                }
+                PowerUpKind::Wide => {
+                    // arrows out to either side
+                    let arm = Vector2::new(Self::RADIUS * 0.6, 0.);
+                    for arm in [arm, -arm] {
+                        mesh.push_arrow(
+                            pickup.position,
+                            pickup.position + arm,
+                            0.008,
+                            0.016,
+                            [1.; 4],
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn caught_power_up_runs_for_its_duration() {
+    let paddle = Paddle::new(1.);
+    let mut power_ups = PowerUps {
+        pickups: vec![Pickup {
+            kind: PowerUpKind::GravityFlip,
+            position: Vector2::new(paddle.x, paddle.y() + 0.2),
+        }],
+        ..PowerUps::default()
+    };
+
+    let dt = 0.01;
+    let mut caught = Vec::new();
+    for _ in 0..100 {
+        caught.extend(power_ups.update(dt, &paddle));
+    }
+    assert_eq!(caught, [PowerUpKind::GravityFlip]);
+    assert!(power_ups.is_active(PowerUpKind::GravityFlip));
+
+    for _ in 0..500 {
+        power_ups.update(dt, &paddle);
+    }
+    assert!(!power_ups.is_active(PowerUpKind::GravityFlip));
+}
+
+#[test]
+fn missed_power_up_falls_away() {
+    let paddle = Paddle::new(1.);
+    let mut power_ups = PowerUps {
+        pickups: vec![Pickup {
+            kind: PowerUpKind::GravityFlip,
+            position: Vector2::new(paddle.x + 1., paddle.y() + 0.2),
+        }],
+        ..PowerUps::default()
+    };
+
+    for _ in 0..200 {
+        assert!(power_ups.update(0.01, &paddle).is_empty());
+    }
+    assert!(power_ups.pickups.is_empty());
+}
+
+#[test]
+fn drops_follow_the_presets_chance() {
+    use crate::difficulty::Preset;
+    use rand::SeedableRng;
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    let [easy, hard] = [Preset::Easy, Preset::Hard].map(|preset| {
+        let tunables = preset.tunables();
+        let mut power_ups = PowerUps::default();
+        for _ in 0..2000 {
+            power_ups.maybe_drop(Vector2::new(0., 0.), &tunables, &mut rng);
+        }
+        power_ups.pickups.len()
+    });
+    assert!(easy > hard, "{easy} drops on easy, {hard} on hard");
+}
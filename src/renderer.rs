@@ -1,41 +1,204 @@
 use self::buffer::Vertex;
-use buffer::Mesh;
+use crate::instrument;
+use arena::Arena;
+use background::{BackgroundKind, BackgroundUniform, Registry};
+use buffer::{DrawItem, Mesh, MeshBuilder, ParticleInstance, Transform2D, ViewTransform};
+use cache::{BindGroupKey, Cache, PipelineKey, SamplerDesc, ShaderId, Stencil, TextureId};
+use capture::{Capture, CaptureError};
+use mask::{DrawList, Masking};
+use post::{Crt, Effect, PostProcess, Tonemap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use texture::Textures;
 use wgpu::include_wgsl;
-use wgpu::util::DeviceExt;
 use winit::window::Window;
 
+pub mod arena;
+pub mod background;
 pub mod buffer;
+pub mod cache;
+pub mod capture;
+pub mod debug;
+pub mod font;
+pub mod headless;
+pub mod mask;
+pub mod post;
+pub mod shapes;
+pub mod texture;
 
-pub struct Renderer<'a> {
+/// Choices about how to render, made at startup
+#[derive(Debug, Clone, Copy)]
+pub struct RendererOptions {
+    pub vsync: bool,
+    /// Samples per pixel for antialiasing, falling back to 1 if the surface can't do it
+    pub msaa: u32,
+    /// Refuse software adapters rather than falling back to one
+    pub require_hardware: bool,
+    /// Drawn through a crt filter, or straight to the screen without
+    pub crt: Option<Crt>,
+    /// Leave what's behind the window showing where nothing's drawn, if the surface can
+    pub transparent: bool,
+    /// Drawn in linear half floats then tonemapped onto an extended range surface, if there's
+    /// one to be had
+    pub hdr: Option<Tonemap>,
+}
+
+/// What drawing a frame did, counted as it's drawn rather than asked of the gpu afterwards
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RenderReport {
+    /// Draw calls recorded, with one indirect call drawing many items counting once
+    pub draw_calls: u32,
+    /// Vertices drawn without indices: the particles' corners and a background's triangle
+    pub vertices: u32,
+    /// Indices drawn from the mesh
+    pub indices: u32,
+    /// Bytes written to buffers on the gpu, the mesh included if it was written for the frame
+    pub bytes_uploaded: u64,
+    /// Whether the surface was configured again first, as it is after a resize
+    pub reconfigured: bool,
+    /// How long recording and submitting the commands took, not counting the wait for a texture
+    /// to draw on
+    pub encode_time: Duration,
+}
+
+/// What the device can do beyond what every device can, for picking between ways of drawing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Many draws issued by one call from arguments in a buffer, rather than a call each
+    pub multi_draw_indirect: bool,
+}
+
+impl Capabilities {
+    pub fn of(features: wgpu::Features) -> Self {
+        Self {
+            multi_draw_indirect: features.contains(wgpu::Features::MULTI_DRAW_INDIRECT),
+        }
+    }
+
+    /// The features a device has to be asked for to do all this
+    pub fn features(self) -> wgpu::Features {
+        let mut features = wgpu::Features::empty();
+        features.set(
+            wgpu::Features::MULTI_DRAW_INDIRECT,
+            self.multi_draw_indirect,
+        );
+        features
+    }
+}
+
+#[derive(Debug)]
+pub enum RendererError {
+    /// Every way of finding an adapter came up empty, with the ways tried in order
+    NoAdapter {
+        tried: Vec<&'static str>,
+        require_hardware: bool,
+    },
+    Device(wgpu::RequestDeviceError),
+    Surface(wgpu::CreateSurfaceError),
+    /// The adapter, picked before there was a surface, can't draw to the window's
+    UnsupportedSurface {
+        adapter: String,
+    },
+    /// The surface offers no texture formats at all to draw in
+    NoSurfaceFormat,
+}
+
+impl std::fmt::Display for RendererError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoAdapter {
+                tried,
+                require_hardware,
+            } => {
+                write!(
+                    f,
+                    "no graphics adapter found after trying {}. This usually means no Vulkan \
+                     driver (ICD) is installed, there's no display to draw to, or the graphics \
+                     drivers are out of date",
+                    tried.join(", then ")
+                )?;
+                if *require_hardware {
+                    write!(
+                        f,
+                        ". Software rendering was skipped as hardware was required"
+                    )?;
+                }
+                Ok(())
+            }
+            Self::Device(err) => write!(f, "failed to open the graphics device: {err}"),
+            Self::Surface(err) => write!(f, "failed to create a surface for the window: {err}"),
+            Self::UnsupportedSurface { adapter } => {
+                write!(f, "{adapter} can't draw to the window's surface")
+            }
+            Self::NoSurfaceFormat => write!(f, "the window's surface has no formats to draw in"),
+        }
+    }
+}
+
+impl std::error::Error for RendererError {}
+
+/// What can only be made once the window has a surface to draw on, and goes with it
+struct SurfaceState {
     surface: wgpu::Surface,
+    config: wgpu::SurfaceConfiguration,
+    pipeline: PipelineKey,
+    sample_count: u32,
+    /// Rendered into then resolved onto the surface when multisampling
+    multisampled: Option<wgpu::TextureView>,
+    depth_stencil: wgpu::TextureView,
+    /// Only made while the crt is on, so without it the scene's drawn in one pass
+    post: Option<PostProcess>,
+    /// Composited over the desktop, with the background left undrawn
+    transparent: bool,
+    /// Only made for an hdr surface, the last pass of all
+    tonemap: Option<PostProcess>,
+}
+
+/// Made in two phases, the device up front and the surface each time the app is resumed, since
+/// on some platforms the window can't be drawn on until then
+pub struct Renderer<'a> {
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     queue: wgpu::Queue,
-    config: wgpu::SurfaceConfiguration,
-    pub size: winit::dpi::PhysicalSize<u32>,
-    render_pipeline: wgpu::RenderPipeline,
-    camera_x: wgpu::BindGroupLayout,
+    options: RendererOptions,
+    capabilities: Capabilities,
+    pipeline: Pipeline,
+    surface: Option<SurfaceState>,
+    /// The size the surface was last configured at
+    size: winit::dpi::PhysicalSize<u32>,
+    /// The latest size asked for since, applied just before the next frame so a burst of
+    /// resizes only reconfigures the surface once
+    pending_size: Option<winit::dpi::PhysicalSize<u32>>,
+    /// Set from the device's error handler once it's been lost
+    lost: Arc<AtomicBool>,
+    /// Shared buffers each frame's mesh is allocated from for just that frame, kept from frame
+    /// to frame so they're written over rather than made again
+    arena: Arena,
+    /// The frame's items moved to where its mesh is in the arena, kept for the room
+    items: Vec<DrawItem>,
+    background: BackgroundKind,
+    /// When backgrounds' animations count from
+    started: Instant,
+    capture: Option<Capture>,
+    /// Captures stopped but still being written, waited for before the renderer goes
+    writers: Vec<JoinHandle<()>>,
     pub window: &'a Window,
 }
 
 impl<'a> Renderer<'a> {
-    pub async fn new(window: &'a Window) -> Self {
-        let size = window.inner_size();
+    /// Room in the arena to start with, enough for a busy scene without growing
+    const ARENA_VERTICES: u32 = 1 << 14;
+    const ARENA_INDICES: u32 = 1 << 15;
 
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            dx12_shader_compiler: Default::default(),
-        });
+    pub async fn new(window: &'a Window, options: RendererOptions) -> Result<Self, RendererError> {
+        let size = window.inner_size();
 
-        let surface = unsafe { instance.create_surface(&window) }.unwrap();
-
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
+        let (instance, adapter) = request_adapter(options.require_hardware).await?;
+        log::info!("adapter: {}", describe_adapter(&adapter.get_info()));
 
         let (device, queue) = adapter
             .request_device(
@@ -47,66 +210,1005 @@ impl<'a> Renderer<'a> {
                 None,
             )
             .await
-            .unwrap();
+            .map_err(RendererError::Device)?;
+
+        let lost = Arc::new(AtomicBool::new(false));
+        device.on_uncaptured_error(Box::new({
+            let lost = Arc::clone(&lost);
+            move |err| {
+                if !means_device_lost(&err) {
+                    panic!("wgpu error: {err}");
+                }
+                log::error!("graphics device lost: {err}");
+                lost.store(true, Ordering::Relaxed);
+            }
+        }));
+
+        let capabilities = Capabilities::of(device.features());
+        log::info!("capabilities: {capabilities:?}");
+        let pipeline = Pipeline::new(&device);
+        let arena = Arena::new(&device, Self::ARENA_VERTICES, Self::ARENA_INDICES);
+
+        Ok(Self {
+            instance,
+            adapter,
+            device,
+            queue,
+            options,
+            capabilities,
+            pipeline,
+            surface: None,
+            size,
+            pending_size: None,
+            lost,
+            arena,
+            items: Vec::new(),
+            background: BackgroundKind::default(),
+            started: Instant::now(),
+            capture: None,
+            writers: Vec::new(),
+            window,
+        })
+    }
+
+    /// Whether the device has been lost, as it can be when the machine is suspended, after which
+    /// nothing drawn with it shows until the renderer is recreated
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Relaxed)
+    }
+
+    /// Starts again with a new device and pipelines, bringing the surface back if there was one
+    pub async fn recreate(&mut self) -> Result<(), RendererError> {
+        let resumed = self.surface.is_some();
+        // the old surface goes before the new device takes over the window
+        self.surface = None;
+        *self = Self::new(self.window, self.options).await?;
+        if resumed {
+            self.resume()?;
+        }
+        Ok(())
+    }
+
+    /// Creates the surface, if there isn't one already, along with the pipeline drawing to it
+    pub fn resume(&mut self) -> Result<(), RendererError> {
+        if self.surface.is_some() {
+            return Ok(());
+        }
 
-        let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
+        let surface =
+            unsafe { self.instance.create_surface(self.window) }.map_err(RendererError::Surface)?;
+        if !self.adapter.is_surface_supported(&surface) {
+            return Err(RendererError::UnsupportedSurface {
+                adapter: describe_adapter(&self.adapter.get_info()),
+            });
+        }
+
+        let surface_caps = surface.get_capabilities(&self.adapter);
+        let (surface_format, hdr) =
+            choose_format(&surface_caps.formats, self.options.hdr.is_some())
+                .ok_or(RendererError::NoSurfaceFormat)?;
+        if self.options.hdr.is_some() && !hdr {
+            log::info!("the surface has no hdr format, so it is sdr");
+        }
+
+        let (alpha_mode, transparent) =
+            choose_alpha_mode(&surface_caps.alpha_modes, self.options.transparent);
+        if self.options.transparent && !transparent {
+            log::warn!(
+                "the surface can't be premultiplied, only {:?}, so it is opaque",
+                surface_caps.alpha_modes
+            );
+        }
 
+        self.size = self.window.inner_size();
+        self.pending_size = None;
+        // copied from for captures, where it can be
+        let usage = wgpu::TextureUsages::RENDER_ATTACHMENT
+            | (surface_caps.usages & wgpu::TextureUsages::COPY_SRC);
         let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage,
             format: surface_format,
-            width: size.width,
-            height: size.height,
-            present_mode: surface_caps.present_modes[0],
-            alpha_mode: surface_caps.alpha_modes[0],
+            width: self.size.width,
+            height: self.size.height,
+            present_mode: if self.options.vsync {
+                wgpu::PresentMode::AutoVsync
+            } else {
+                wgpu::PresentMode::AutoNoVsync
+            },
+            alpha_mode,
             view_formats: vec![],
         };
-        surface.configure(&device, &config);
+        surface.configure(&self.device, &config);
 
-        let shader = device.create_shader_module(include_wgsl!("shaders/shader.wgsl"));
+        let sample_count = if self
+            .adapter
+            .get_texture_format_features(config.format)
+            .flags
+            .sample_count_supported(self.options.msaa)
+        {
+            self.options.msaa
+        } else {
+            log::warn!("{}x msaa is unsupported, so it is off", self.options.msaa);
+            1
+        };
 
-        let camera_x_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }],
-                label: Some("Camera x Bind Group Layout"),
+        // made now rather than on the first frame, unless it was already before a suspend
+        let pipeline = Pipeline::key(config.format, sample_count);
+        self.pipeline.prepare(&self.device, pipeline);
+
+        log::info!(
+            "surface: {:?}, {:?}, {:?} alpha, {sample_count}x msaa",
+            config.format,
+            config.present_mode,
+            config.alpha_mode
+        );
+
+        let multisampled = Self::create_multisampled(&self.device, &config, sample_count);
+        let depth_stencil =
+            create_depth_stencil(&self.device, config.width, config.height, sample_count);
+
+        let post = self
+            .options
+            .crt
+            .map(|crt| self.create_post(config.format, Effect::Crt(crt)));
+        let tonemap = self
+            .options
+            .hdr
+            .filter(|_| hdr)
+            .map(|tonemap| self.create_post(config.format, Effect::Tonemap(tonemap)));
+
+        self.surface = Some(SurfaceState {
+            surface,
+            config,
+            pipeline,
+            sample_count,
+            multisampled,
+            depth_stencil,
+            post,
+            transparent,
+            tonemap,
+        });
+        Ok(())
+    }
+
+    fn create_post(&self, format: wgpu::TextureFormat, effect: Effect) -> PostProcess {
+        let size = (self.size.width, self.size.height);
+        PostProcess::new(&self.device, &self.queue, format, size, effect)
+    }
+
+    /// Turns the crt filter on or off, taking effect from the next frame
+    pub fn set_crt(&mut self, crt: Option<Crt>) {
+        self.options.crt = crt;
+        let post = match (&self.surface, crt) {
+            (Some(state), Some(crt)) => {
+                Some(self.create_post(state.config.format, Effect::Crt(crt)))
+            }
+            _ => None,
+        };
+        if let Some(state) = &mut self.surface {
+            state.post = post;
+        }
+    }
+
+    pub fn crt(&self) -> Option<Crt> {
+        self.options.crt
+    }
+
+    /// What's drawn behind the scene from the next frame on
+    pub fn set_background(&mut self, background: BackgroundKind) {
+        self.background = background;
+    }
+
+    /// Compiles `source`, a fragment shader written after `background.wgsl`, and registers its
+    /// `entry_point` as a procedural background, returning the id to draw it by
+    pub fn register_background(&mut self, source: &str, entry_point: &str) -> ShaderId {
+        self.pipeline
+            .backgrounds
+            .register(&self.device, source, entry_point)
+    }
+
+    /// Uploads `width` by `height` srgb pixels with straight alpha, top row first, to be drawn
+    /// with `sampler` by draw items that don't ask for another
+    pub fn create_texture(
+        &mut self,
+        width: u32,
+        height: u32,
+        rgba: &[u8],
+        sampler: SamplerDesc,
+    ) -> TextureId {
+        self.pipeline
+            .textures
+            .create(&self.device, &self.queue, (width, height), rgba, sampler)
+    }
+
+    /// Drops a texture along with the bind groups made for it, after which draws of it are
+    /// skipped
+    pub fn evict_texture(&mut self, texture: TextureId) {
+        self.pipeline.textures.remove(texture);
+        self.pipeline.bind_groups.evict(|key| key.uses(texture));
+    }
+
+    /// Drops the surface, which mustn't outlive the app being suspended
+    pub fn suspend(&mut self) {
+        self.stop_capture();
+        self.surface = None;
+    }
+
+    /// Copies each frame presented from now on into `dir` as a numbered png, along with the time
+    /// it was shown, until `max_frames` have been or the capture's stopped
+    pub fn start_capture(&mut self, dir: PathBuf, max_frames: usize) -> Result<(), CaptureError> {
+        self.stop_capture();
+        self.writers.retain(|writer| !writer.is_finished());
+
+        let state = self.surface.as_ref().ok_or(CaptureError::NoSurface)?;
+        if !state.config.usage.contains(wgpu::TextureUsages::COPY_SRC) {
+            return Err(CaptureError::NotCopyable);
+        }
+        let size = (state.config.width, state.config.height);
+        let capture = Capture::start(&self.device, state.config.format, size, dir, max_frames)?;
+        self.capture = Some(capture);
+        Ok(())
+    }
+
+    /// Stops capturing, leaving the frames already read back to be written in the background
+    pub fn stop_capture(&mut self) {
+        if let Some(capture) = self.capture.take() {
+            self.writers.push(capture.stop(&self.device));
+        }
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    fn create_multisampled(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        sample_count: u32,
+    ) -> Option<wgpu::TextureView> {
+        if sample_count == 1 {
+            return None;
+        }
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Multisampled Texture"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    /// How many times wider than tall the next frame's drawn, as its view is
+    pub fn aspect(&self) -> f32 {
+        let size = self.pending_size.unwrap_or(self.size);
+        ViewTransform::aspect(size.width, size.height)
+    }
+
+    /// Asks for the surface to be resized before the next frame, if the size has changed
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        if new_size.width == 0 || new_size.height == 0 {
+            return;
+        }
+        if new_size != self.pending_size.unwrap_or(self.size) {
+            self.pending_size = Some(new_size);
+        }
+    }
+
+    /// Asks for the surface to be configured again before the next frame, as after it's lost
+    pub fn reconfigure(&mut self) {
+        self.pending_size.get_or_insert(self.size);
+    }
+
+    /// Configures the surface at the size asked for since the last frame, returning whether it
+    /// was
+    fn apply_resize(&mut self) -> bool {
+        let Some(size) = self.pending_size else {
+            return false;
+        };
+        if self.surface.is_none() {
+            return false;
+        }
+
+        self.pending_size = None;
+        self.size = size;
+        if let Some(capture) = &self.capture {
+            if !capture.fits(size.width, size.height) {
+                log::info!("capture stopped by the window resizing");
+                self.stop_capture();
+            }
+        }
+        let Some(state) = &mut self.surface else {
+            return false;
+        };
+        state.config.width = size.width;
+        state.config.height = size.height;
+        state.surface.configure(&self.device, &state.config);
+        state.multisampled =
+            Self::create_multisampled(&self.device, &state.config, state.sample_count);
+        state.depth_stencil =
+            create_depth_stencil(&self.device, size.width, size.height, state.sample_count);
+        for post in state.post.iter_mut().chain(&mut state.tonemap) {
+            post.resize(&self.device, size.width, size.height);
+        }
+        true
+    }
+
+    /// Draws the mesh with the particles over all but the hud, which starts `hud` indices in,
+    /// skipping the frame while there's no surface to draw them on
+    pub fn render(
+        &mut self,
+        builder: &MeshBuilder,
+        hud: usize,
+        particles: &[ParticleInstance],
+        camera_x: f32,
+        zoom: f32,
+    ) -> Result<RenderReport, wgpu::SurfaceError> {
+        let count = builder.index_count() as u32;
+        let hud = (hud as u32).min(count);
+        let items = [0..hud, hud..count].map(|indices| DrawItem {
+            indices,
+            ..DrawItem::default()
+        });
+        // leaving out whichever's empty, so a frame without a hud is still a single draw
+        let scene = usize::from(hud > 0);
+        let items = &items[1 - scene..1 + usize::from(hud < count)];
+        self.render_items(builder, items, scene, particles, camera_x, zoom)
+    }
+
+    /// Draws the list's items from the mesh, clipped to the masks they were pushed inside, then
+    /// the particles
+    pub fn render_list(
+        &mut self,
+        builder: &MeshBuilder,
+        list: &DrawList,
+        particles: &[ParticleInstance],
+        camera_x: f32,
+        zoom: f32,
+    ) -> Result<RenderReport, wgpu::SurfaceError> {
+        let items = list.items();
+        self.render_items(builder, items, items.len(), particles, camera_x, zoom)
+    }
+
+    /// Draws `items` from the mesh, with the particles over those before `overlay`
+    fn render_items(
+        &mut self,
+        builder: &MeshBuilder,
+        items: &[DrawItem],
+        overlay: usize,
+        particles: &[ParticleInstance],
+        camera_x: f32,
+        zoom: f32,
+    ) -> Result<RenderReport, wgpu::SurfaceError> {
+        let reconfigured = self.apply_resize();
+        let Some(state) = &self.surface else {
+            return Ok(RenderReport::default());
+        };
+        let texture = state.surface.get_current_texture()?;
+        let start = Instant::now();
+        // last frame's mesh was drawn by the time this one's written over it
+        self.arena.reset_frame();
+        let handle = self.arena.alloc_frame(&self.device, &self.queue, builder);
+        self.items.clear();
+        self.items
+            .extend(items.iter().map(|item| handle.offset(item)));
+        let view = texture
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
             });
+        // the scene's finished in its own texture when there's a pass to run over it, and the
+        // crt's drawn into the tonemap's in turn
+        let output = state.tonemap.as_ref().map_or(&view, PostProcess::scene);
+        let scene = state.post.as_ref().map_or(output, PostProcess::scene);
+        let target = match &state.multisampled {
+            Some(multisampled) => Target {
+                view: multisampled,
+                resolve: Some(scene),
+                depth_stencil: &state.depth_stencil,
+            },
+            None => Target {
+                view: scene,
+                resolve: None,
+                depth_stencil: &state.depth_stencil,
+            },
+        };
+        let drawn = self.pipeline.draw(
+            &self.device,
+            &self.queue,
+            &mut encoder,
+            state.pipeline,
+            target,
+            self.arena.mesh(),
+            &self.items,
+            overlay,
+            self.capabilities.multi_draw_indirect,
+            particles,
+            [
+                camera_x,
+                zoom,
+                ViewTransform::aspect(state.config.width, state.config.height),
+            ],
+            if state.transparent {
+                BackgroundKind::Transparent
+            } else {
+                self.background
+            },
+            self.started.elapsed().as_secs_f32(),
+        );
+        if let Some(post) = &state.post {
+            post.apply(&mut encoder, output);
+        }
+        if let Some(tonemap) = &state.tonemap {
+            tonemap.apply(&mut encoder, &view);
+        }
+        let copied = self
+            .capture
+            .as_mut()
+            .and_then(|capture| capture.copy(&mut encoder, &texture.texture));
 
-        let render_pipeline_layout =
+        self.queue.submit(std::iter::once(encoder.finish()));
+        let report = RenderReport {
+            bytes_uploaded: drawn.bytes_uploaded + builder.upload_size(),
+            reconfigured,
+            encode_time: start.elapsed(),
+            ..drawn
+        };
+        texture.present();
+
+        if let Some(capture) = &mut self.capture {
+            if let Some(slot) = copied {
+                capture.map(slot);
+            }
+            capture.collect(&self.device, false);
+            if capture.is_done() {
+                self.stop_capture();
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+impl Drop for Renderer<'_> {
+    /// Finishes writing any captures, which would otherwise be cut off by the app exiting
+    fn drop(&mut self) {
+        self.stop_capture();
+        for writer in self.writers.drain(..) {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// How wgpu-core 0.17 words `DeviceError::Lost` in the errors it reports. This text isn't a stable
+/// api, so it's checked against wgpu-core's own error by a test, which has to be kept on the same
+/// version as wgpu.
+const DEVICE_LOST: &str = "Parent device is lost";
+
+/// wgpu 0.17 has no callback for a lost device, so it's recognised from the errors it causes
+fn means_device_lost(err: &wgpu::Error) -> bool {
+    match err {
+        wgpu::Error::OutOfMemory { .. } => true,
+        wgpu::Error::Validation { description, .. } => description.contains(DEVICE_LOST),
+    }
+}
+
+/// The ways of finding an adapter, tried in turn until one turns one up: the backends each
+/// platform is built around, then any backend at all, then a software one
+const ATTEMPTS: [(&str, wgpu::Backends, bool); 3] = [
+    ("the primary backends", wgpu::Backends::PRIMARY, false),
+    ("every backend", wgpu::Backends::all(), false),
+    ("a software fallback", wgpu::Backends::all(), true),
+];
+
+/// The adapter to render with and the instance it came from, picked without a surface since
+/// there might not be one yet
+async fn request_adapter(
+    require_hardware: bool,
+) -> Result<(wgpu::Instance, wgpu::Adapter), RendererError> {
+    let mut tried = Vec::new();
+    for (name, backends, fallback) in ATTEMPTS {
+        if fallback && require_hardware {
+            continue;
+        }
+        tried.push(name);
+
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends,
+            dx12_shader_compiler: Default::default(),
+        });
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter: fallback,
+            })
+            .await;
+        match adapter {
+            Some(adapter)
+                if require_hardware && adapter.get_info().device_type == wgpu::DeviceType::Cpu =>
+            {
+                log::info!(
+                    "skipping {}, a software adapter",
+                    describe_adapter(&adapter.get_info())
+                );
+            }
+            Some(adapter) => return Ok((instance, adapter)),
+            None => log::info!("no adapter from {name}"),
+        }
+    }
+
+    Err(RendererError::NoAdapter {
+        tried,
+        require_hardware,
+    })
+}
+
+/// The adapter a renderer would use, found without opening a window
+pub async fn probe_adapter(require_hardware: bool) -> Result<wgpu::AdapterInfo, RendererError> {
+    Ok(request_adapter(require_hardware).await?.1.get_info())
+}
+
+pub fn describe_adapter(info: &wgpu::AdapterInfo) -> String {
+    format!("{} ({:?}, {:?})", info.name, info.backend, info.device_type)
+}
+
+/// Where a frame is drawn, resolved onto a second texture when multisampling
+struct Target<'a> {
+    view: &'a wgpu::TextureView,
+    resolve: Option<&'a wgpu::TextureView>,
+    /// The same size and sample count as `view`
+    depth_stencil: &'a wgpu::TextureView,
+}
+
+/// The depth and stencil buffer every pipeline draws with, for masks to be drawn into the stencil
+const DEPTH_STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
+
+fn create_depth_stencil(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    sample_count: u32,
+) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth Stencil Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_STENCIL_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// Every draw's transform followed by where it samples its texture, each at an offset the device
+/// can bind a uniform from, written over each frame and grown when a frame has more draws than
+/// there's room for
+struct TransformRing {
+    layout: wgpu::BindGroupLayout,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    /// Bytes from one transform to the next
+    stride: u64,
+    capacity: usize,
+    /// Reused for each frame's transforms, so writing them doesn't allocate
+    contents: Vec<u8>,
+}
+
+impl TransformRing {
+    const INITIAL_CAPACITY: usize = 64;
+    const SIZE: u64 = std::mem::size_of::<[Transform2D; 2]>() as u64;
+
+    fn new(device: &wgpu::Device) -> Self {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(Self::SIZE),
+                },
+                count: None,
+            }],
+            label: Some("Transform Bind Group Layout"),
+        });
+        let stride = wgpu::util::align_to(
+            Self::SIZE,
+            device.limits().min_uniform_buffer_offset_alignment as u64,
+        );
+        let (buffer, bind_group) = Self::create(device, &layout, stride, Self::INITIAL_CAPACITY);
+
+        Self {
+            layout,
+            buffer,
+            bind_group,
+            stride,
+            capacity: Self::INITIAL_CAPACITY,
+            contents: Vec::new(),
+        }
+    }
+
+    fn create(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        stride: u64,
+        capacity: usize,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Transform Buffer"),
+            size: stride * capacity as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(Self::SIZE),
+                }),
+            }],
+            label: Some("Transform Bind Group"),
+        });
+        (buffer, bind_group)
+    }
+
+    /// Writes each item's transforms, making the buffer again first if it's too small, returning
+    /// how many bytes that was
+    fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, items: &[DrawItem]) -> u64 {
+        if items.len() > self.capacity {
+            instrument::count(|counts| counts.buffer_recreations += 1);
+            self.capacity = items.len().next_power_of_two();
+            (self.buffer, self.bind_group) =
+                Self::create(device, &self.layout, self.stride, self.capacity);
+        }
+
+        // what's between each transform is left as it was, as nothing reads it
+        self.contents.resize(self.stride as usize * items.len(), 0);
+        for (chunk, item) in self.contents.chunks_mut(self.stride as usize).zip(items) {
+            let uv = item
+                .texturing
+                .map_or(Transform2D::IDENTITY, |texturing| texturing.uv);
+            chunk[..Self::SIZE as usize].copy_from_slice(bytemuck::bytes_of(&[item.transform, uv]));
+        }
+        if !self.contents.is_empty() {
+            queue.write_buffer(&self.buffer, 0, &self.contents);
+        }
+        self.contents.len() as u64
+    }
+
+    /// Where the `i`th item's transform was written
+    fn offset(&self, i: usize) -> wgpu::DynamicOffset {
+        (self.stride * i as u64) as wgpu::DynamicOffset
+    }
+}
+
+/// The draws of a multi-draw, written over each frame and grown when a frame has more draws than
+/// there's room for
+struct IndirectBuffer {
+    buffer: wgpu::Buffer,
+    capacity: usize,
+    /// Reused for each frame's commands, so writing them doesn't allocate
+    contents: Vec<u8>,
+}
+
+impl IndirectBuffer {
+    const INITIAL_CAPACITY: usize = 64;
+    const SIZE: u64 = std::mem::size_of::<wgpu::util::DrawIndexedIndirect>() as u64;
+
+    fn new(device: &wgpu::Device) -> Self {
+        Self {
+            buffer: Self::create(device, Self::INITIAL_CAPACITY),
+            capacity: Self::INITIAL_CAPACITY,
+            contents: Vec::new(),
+        }
+    }
+
+    fn create(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Indirect Buffer"),
+            size: Self::SIZE * capacity as u64,
+            usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Writes a draw for each item, making the buffer again first if it's too small, returning
+    /// how many bytes that was
+    fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, items: &[DrawItem]) -> u64 {
+        if items.len() > self.capacity {
+            instrument::count(|counts| counts.buffer_recreations += 1);
+            self.capacity = items.len().next_power_of_two();
+            self.buffer = Self::create(device, self.capacity);
+        }
+
+        self.contents.clear();
+        for item in items {
+            let draw = wgpu::util::DrawIndexedIndirect {
+                vertex_count: item.indices.len() as u32,
+                instance_count: 1,
+                base_index: item.indices.start,
+                vertex_offset: item.base_vertex,
+                base_instance: 0,
+            };
+            self.contents.extend_from_slice(draw.as_bytes());
+        }
+        if !self.contents.is_empty() {
+            queue.write_buffer(&self.buffer, 0, &self.contents);
+        }
+        self.contents.len() as u64
+    }
+}
+
+/// A shader along with the layout of what it binds
+struct Program {
+    shader: wgpu::ShaderModule,
+    layout: wgpu::PipelineLayout,
+}
+
+/// The shaders and layouts render pipelines are made from, whatever they draw to, and the
+/// pipelines and bind groups made from them so far
+struct Pipeline {
+    mesh: Program,
+    particles: Program,
+    /// With an entry point for the gradient and each built-in procedural background
+    background: Program,
+    backgrounds: Registry,
+    textured: Program,
+    camera_x: wgpu::BindGroupLayout,
+    /// Written over with each draw's camera
+    camera: wgpu::Buffer,
+    transforms: TransformRing,
+    indirect: IndirectBuffer,
+    pipelines: Cache<PipelineKey, wgpu::RenderPipeline>,
+    bind_groups: Cache<BindGroupKey, wgpu::BindGroup>,
+    /// Written over with each draw's particles, and made again when there are too many to fit
+    instances: wgpu::Buffer,
+    background_layout: wgpu::BindGroupLayout,
+    /// Written over with each frame's background
+    background_uniform: wgpu::Buffer,
+    textures: Textures,
+    /// Cleared each frame and filled with the pipelines it draws with, each only once
+    frame_pipelines: Vec<PipelineKey>,
+    /// Cleared each frame and filled with the texture bind groups it draws with, each only once
+    frame_bind_groups: Vec<BindGroupKey>,
+}
+
+impl Pipeline {
+    /// Room for this many particles before the instance buffer has to grow
+    const INITIAL_PARTICLES: u64 = 64;
+
+    fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(include_wgsl!("shaders/shader.wgsl"));
+        let particle_shader = device.create_shader_module(include_wgsl!("shaders/particles.wgsl"));
+        let background_shader =
+            device.create_shader_module(include_wgsl!("shaders/background.wgsl"));
+        let textured_shader = device.create_shader_module(include_wgsl!("shaders/textured.wgsl"));
+
+        let camera_x = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Camera x Bind Group Layout"),
+        });
+
+        let camera = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Camera x Buffer"),
+            size: std::mem::size_of::<ViewTransform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let transforms = TransformRing::new(device);
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Render Pipeline Layout"),
+            bind_group_layouts: &[&camera_x, &transforms.layout],
+            push_constant_ranges: &[],
+        });
+        let textures = Textures::new(device);
+        let textured_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Textured Pipeline Layout"),
+            bind_group_layouts: &[&camera_x, &transforms.layout, &textures.layout],
+            push_constant_ranges: &[],
+        });
+        // particles are placed by their instances alone
+        let particle_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Pipeline Layout"),
+            bind_group_layouts: &[&camera_x],
+            push_constant_ranges: &[],
+        });
+
+        let background_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Background Bind Group Layout"),
+        });
+        let background_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[&camera_x_bind_group_layout],
+                label: Some("Background Pipeline Layout"),
+                bind_group_layouts: &[&background_layout],
                 push_constant_ranges: &[],
             });
+        let background_uniform = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Background Buffer"),
+            size: std::mem::size_of::<BackgroundUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let instances = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Buffer"),
+            size: Self::INITIAL_PARTICLES * std::mem::size_of::<ParticleInstance>() as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            mesh: Program { shader, layout },
+            particles: Program {
+                shader: particle_shader,
+                layout: particle_layout,
+            },
+            background: Program {
+                shader: background_shader,
+                layout: background_pipeline_layout,
+            },
+            backgrounds: Registry::new(),
+            textured: Program {
+                shader: textured_shader,
+                layout: textured_layout,
+            },
+            camera_x,
+            camera,
+            transforms,
+            indirect: IndirectBuffer::new(device),
+            pipelines: Cache::default(),
+            bind_groups: Cache::default(),
+            instances,
+            background_layout,
+            background_uniform,
+            textures,
+            frame_pipelines: Vec::new(),
+            frame_bind_groups: Vec::new(),
+        }
+    }
+
+    /// The pipeline drawing meshes to textures of `format`
+    fn key(format: wgpu::TextureFormat, sample_count: u32) -> PipelineKey {
+        PipelineKey {
+            shader: ShaderId::Mesh,
+            format,
+            sample_count,
+            // straight colours in, but premultiplied ones out, since alpha's blended over the
+            // target's own rather than multiplied: as a transparent surface wants them
+            blend: wgpu::BlendState::ALPHA_BLENDING,
+            stencil: Stencil::Ignore,
+        }
+    }
+
+    /// Makes the pipeline ahead of drawing with it, if it hasn't been already
+    fn prepare(&mut self, device: &wgpu::Device, key: PipelineKey) {
+        let programs = [
+            &self.mesh,
+            &self.particles,
+            &self.background,
+            &self.textured,
+        ];
+        let backgrounds = &self.backgrounds;
+        self.pipelines
+            .get_or_create(key, |key| Self::create(device, programs, backgrounds, key));
+    }
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+    /// The pipeline for `key`, from the mesh program, the particle one, the background one, then
+    /// the textured one, or the background registered for it
+    fn create(
+        device: &wgpu::Device,
+        [mesh, particles, background, textured]: [&Program; 4],
+        backgrounds: &Registry,
+        key: &PipelineKey,
+    ) -> wgpu::RenderPipeline {
+        let registered = backgrounds.get(key.shader);
+        let (program, buffers, fragment) = match key.shader {
+            ShaderId::Mesh => (mesh, Some(Vertex::buffer_layout()), "fs_main"),
+            ShaderId::Particles => (
+                particles,
+                Some(ParticleInstance::buffer_layout()),
+                "fs_main",
+            ),
+            ShaderId::Gradient => (background, None, "fs_gradient"),
+            ShaderId::Procedural(_) => {
+                let (_, entry_point) = registered.expect("only drawn once registered");
+                (background, None, entry_point)
+            }
+            ShaderId::Textured => (textured, Some(Vertex::buffer_layout()), "fs_main"),
+        };
+        // from the program's own module, unless it was registered with one of its own
+        let module = registered
+            .and_then(|(module, _)| module)
+            .unwrap_or(&program.shader);
+        // backgrounds have a pass of their own, before the stencil's needed
+        let scene = buffers.is_some();
+        // masks only go into the stencil
+        let (compare, pass_op, write_mask) = match key.stencil {
+            Stencil::Ignore => (
+                wgpu::CompareFunction::Always,
+                wgpu::StencilOperation::Keep,
+                wgpu::ColorWrites::ALL,
+            ),
+            Stencil::WriteMask => (
+                wgpu::CompareFunction::Equal,
+                wgpu::StencilOperation::IncrementClamp,
+                wgpu::ColorWrites::empty(),
+            ),
+            Stencil::ClearMask => (
+                wgpu::CompareFunction::Equal,
+                wgpu::StencilOperation::DecrementClamp,
+                wgpu::ColorWrites::empty(),
+            ),
+            Stencil::TestMask => (
+                wgpu::CompareFunction::Equal,
+                wgpu::StencilOperation::Keep,
+                wgpu::ColorWrites::ALL,
+            ),
+        };
+        let face = wgpu::StencilFaceState {
+            compare,
+            fail_op: wgpu::StencilOperation::Keep,
+            depth_fail_op: wgpu::StencilOperation::Keep,
+            pass_op,
+        };
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
+            layout: Some(&program.layout),
             vertex: wgpu::VertexState {
-                module: &shader,
+                module,
                 entry_point: "vs_main",
-                buffers: &[Vertex::buffer_layout()],
+                buffers: buffers.as_slice(),
             },
             fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
+                module,
+                entry_point: fragment,
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
+                    format: key.format,
+                    blend: Some(key.blend),
+                    write_mask,
                 })],
             }),
             primitive: wgpu::PrimitiveState {
@@ -121,103 +1223,470 @@ impl<'a> Renderer<'a> {
                 // Requires Features::CONSERVATIVE_RASTERIZATION
                 conservative: false,
             },
-            depth_stencil: None,
+            depth_stencil: scene.then(|| wgpu::DepthStencilState {
+                format: DEPTH_STENCIL_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState {
+                    front: face,
+                    back: face,
+                    read_mask: !0,
+                    write_mask: !0,
+                },
+                bias: wgpu::DepthBiasState::default(),
+            }),
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: key.sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
-        });
+        })
+    }
 
-        Self {
-            surface,
+    /// Fills the target with the background, at `time` seconds into its animation, and draws the
+    /// mesh's `items` on it, each moved by its own transform, masked and textured as it says, with
+    /// the particles drawn over those before `overlay` and under the rest, seen from
+    /// `[camera x, zoom, aspect]`. With `indirect` the items either side are each drawn by one
+    /// call, which the device has to be capable of, and otherwise by a call each, which look the
+    /// same. Items whose texture has been dropped are skipped. Reports what it drew and uploaded,
+    /// other than the mesh.
+    #[allow(clippy::too_many_arguments)]
+    fn draw(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: PipelineKey,
+        target: Target,
+        mesh: &Mesh,
+        items: &[DrawItem],
+        overlay: usize,
+        indirect: bool,
+        particles: &[ParticleInstance],
+        camera: [f32; 3],
+        background: BackgroundKind,
+        time: f32,
+    ) -> RenderReport {
+        let overlay = overlay.min(items.len());
+        // a background nothing was registered for is left black rather than drawn
+        let background = match background.shader() {
+            Some(shader @ ShaderId::Procedural(_)) if self.backgrounds.get(shader).is_none() => {
+                BackgroundKind::default()
+            }
+            _ => background,
+        };
+        let view = ViewTransform::new(camera[0], camera[1], camera[2]);
+        queue.write_buffer(&self.camera, 0, bytemuck::bytes_of(&view));
+        let transforms = self.transforms.write(device, queue, items);
+        let mut report = RenderReport {
+            bytes_uploaded: (std::mem::size_of_val(&view) + std::mem::size_of_val(particles))
+                as u64
+                + transforms,
+            ..RenderReport::default()
+        };
+        Mesh::write_buffer(
             device,
             queue,
-            config,
-            size,
-            camera_x: camera_x_bind_group_layout,
-            render_pipeline,
-            window,
-        }
-    }
+            &mut self.instances,
+            "Particle Buffer",
+            wgpu::BufferUsages::VERTEX,
+            bytemuck::cast_slice(particles),
+            &[],
+        );
 
-    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.size = new_size;
-            self.config.width = new_size.width;
-            self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
+        let particle_pipeline = PipelineKey {
+            shader: ShaderId::Particles,
+            ..pipeline
+        };
+        let item_pipeline = |item: &DrawItem| PipelineKey {
+            shader: match item.texturing {
+                Some(_) => ShaderId::Textured,
+                None => ShaderId::Mesh,
+            },
+            stencil: item.masking.stencil,
+            ..pipeline
+        };
+        // each only once, so a steady frame counts a hit for each rather than for every item
+        let mut frame_pipelines = std::mem::take(&mut self.frame_pipelines);
+        frame_pipelines.clear();
+        frame_pipelines.push(pipeline);
+        self.frame_bind_groups.clear();
+        for item in items {
+            let key = item_pipeline(item);
+            if !frame_pipelines.contains(&key) {
+                frame_pipelines.push(key);
+            }
+            let texture = item
+                .texturing
+                .and_then(|texturing| self.textures.key(&texturing));
+            if let Some(key) = texture.filter(|key| !self.frame_bind_groups.contains(key)) {
+                self.frame_bind_groups.push(key);
+            }
+        }
+        for &key in &frame_pipelines {
+            self.prepare(device, key);
+        }
+        self.frame_pipelines = frame_pipelines;
+        for &key in &self.frame_bind_groups {
+            self.bind_groups
+                .get_or_create(key, |key| self.textures.bind_group(device, key));
+        }
+        if !particles.is_empty() {
+            self.prepare(device, particle_pipeline);
+        }
+        let background_pipeline = background
+            .shader()
+            .map(|shader| PipelineKey { shader, ..pipeline });
+        if let Some(key) = background_pipeline {
+            let uniform = background.uniform(time, camera[0]);
+            queue.write_buffer(&self.background_uniform, 0, bytemuck::bytes_of(&uniform));
+            report.bytes_uploaded += std::mem::size_of_val(&uniform) as u64;
+            self.prepare(device, key);
         }
-    }
 
-    pub fn render(&mut self, mesh: Mesh, camera_x: f32) -> Result<(), wgpu::SurfaceError> {
-        let texture = self.surface.get_current_texture()?;
-        let view = texture
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let Self {
+            camera_x,
+            camera,
+            transforms,
+            indirect: indirect_buffer,
+            pipelines,
+            bind_groups,
+            instances,
+            background_layout,
+            background_uniform,
+            textures,
+            ..
+        } = self;
 
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
+        // a pass of its own so the scene's pass can load what it drew rather than clearing
+        if let Some(key) = background_pipeline {
+            let bind_group = bind_groups.get_or_create(BindGroupKey::Background, |_| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    layout: background_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: background_uniform.as_entire_binding(),
+                    }],
+                    label: Some("Background Bind Group"),
+                })
             });
-
-        let buffer = self
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Camera x Buffer"),
-                contents: bytemuck::cast_slice(&[camera_x]),
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Background Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
             });
+            render_pass.set_pipeline(pipelines.get(&key).expect("made above"));
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+            report.draw_calls += 1;
+            report.vertices += 3;
+        }
+        bind_groups.get_or_create(BindGroupKey::Camera, |_| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: camera_x,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera.as_entire_binding(),
+                }],
+                label: Some("Camera x Bind Group"),
+            })
+        });
+        let bind_group = bind_groups.get(&BindGroupKey::Camera).expect("made above");
+        // `None` for an untextured item, and `Some(None)` for one whose texture's gone
+        let texture_bind_group = |item: &DrawItem| {
+            item.texturing.map(|texturing| {
+                textures
+                    .key(&texturing)
+                    .and_then(|key| bind_groups.get(&key))
+            })
+        };
 
-        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &self.camera_x,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: buffer.as_entire_binding(),
-            }],
-            label: Some("Camera x Bind Group"),
+        // a single draw gains nothing from the buffer, and the transform, masking and texture can
+        // only change between calls
+        let same_state = items.windows(2).all(|pair| {
+            pair[0].transform == pair[1].transform
+                && pair[0].masking == pair[1].masking
+                && pair[0].texturing == pair[1].texturing
+        });
+        let drawable = items
+            .first()
+            .is_none_or(|item| !matches!(texture_bind_group(item), Some(None)));
+        let indirect = (indirect && items.len() > 1 && same_state && drawable).then(|| {
+            report.bytes_uploaded += indirect_buffer.write(device, queue, items);
+            &indirect_buffer.buffer
         });
 
         let Mesh {
             vertex_buffer,
             index_buffer,
-            index_count,
+            ..
         } = mesh;
 
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Render Pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
-                resolve_target: None,
+                view: target.view,
+                resolve_target: target.resolve,
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.,
-                        g: 0.,
-                        b: 0.,
-                        a: 1.,
-                    }),
+                    load: background
+                        .clear_color()
+                        .map_or(wgpu::LoadOp::Load, wgpu::LoadOp::Clear),
                     store: true,
                 },
             })],
-            depth_stencil_attachment: None,
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: target.depth_stencil,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.),
+                    store: false,
+                }),
+                stencil_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(0),
+                    store: false,
+                }),
+            }),
         });
 
-        render_pass.set_pipeline(&self.render_pipeline);
+        let mut masking = items.first().map_or(Masking::NONE, |item| item.masking);
+        let mut key = items.first().map_or(pipeline, item_pipeline);
+        render_pass.set_pipeline(pipelines.get(&key).expect("made above"));
+        render_pass.set_stencil_reference(masking.reference);
 
         render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
         render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
 
-        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.set_bind_group(0, bind_group, &[]);
+
+        let particle_pipeline =
+            (!particles.is_empty()).then(|| pipelines.get(&particle_pipeline).expect("made above"));
+        match indirect {
+            Some(buffer) => {
+                render_pass.set_bind_group(1, &transforms.bind_group, &[transforms.offset(0)]);
+                if let Some(Some(texture)) = texture_bind_group(&items[0]) {
+                    render_pass.set_bind_group(2, texture, &[]);
+                }
+                let (scene, hud) = items.split_at(overlay);
+                for (start, run) in [(0, scene), (overlay, hud)] {
+                    if run.is_empty() {
+                        continue;
+                    }
+                    if let Some(particle_pipeline) = particle_pipeline.filter(|_| start == overlay)
+                    {
+                        Self::draw_particles(
+                            &mut render_pass,
+                            particle_pipeline,
+                            bind_group,
+                            instances,
+                            particles,
+                            &mut report,
+                        );
+                        render_pass.set_pipeline(pipelines.get(&key).expect("made above"));
+                        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                    }
+                    let offset = IndirectBuffer::SIZE * start as u64;
+                    render_pass.multi_draw_indexed_indirect(buffer, offset, run.len() as u32);
+                    report.draw_calls += 1;
+                    report.indices += run
+                        .iter()
+                        .map(|item| item.indices.len() as u32)
+                        .sum::<u32>();
+                }
+            }
+            None => {
+                for (i, item) in items.iter().enumerate() {
+                    if let Some(particle_pipeline) = particle_pipeline.filter(|_| i == overlay) {
+                        Self::draw_particles(
+                            &mut render_pass,
+                            particle_pipeline,
+                            bind_group,
+                            instances,
+                            particles,
+                            &mut report,
+                        );
+                        render_pass.set_pipeline(pipelines.get(&key).expect("made above"));
+                        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                    }
+                    let texture = match texture_bind_group(item) {
+                        Some(None) => continue,
+                        texture => texture.flatten(),
+                    };
+                    if item_pipeline(item) != key {
+                        key = item_pipeline(item);
+                        render_pass.set_pipeline(pipelines.get(&key).expect("made above"));
+                    }
+                    if item.masking != masking {
+                        masking = item.masking;
+                        render_pass.set_stencil_reference(masking.reference);
+                    }
+                    render_pass.set_bind_group(1, &transforms.bind_group, &[transforms.offset(i)]);
+                    if let Some(texture) = texture {
+                        render_pass.set_bind_group(2, texture, &[]);
+                    }
+                    render_pass.draw_indexed(item.indices.clone(), item.base_vertex, 0..1);
+                    report.draw_calls += 1;
+                    report.indices += item.indices.len() as u32;
+                }
+            }
+        }
+        // with nothing over them
+        if let Some(particle_pipeline) = particle_pipeline.filter(|_| overlay == items.len()) {
+            Self::draw_particles(
+                &mut render_pass,
+                particle_pipeline,
+                bind_group,
+                instances,
+                particles,
+                &mut report,
+            );
+        }
+        report
+    }
 
-        render_pass.draw_indexed(0..index_count, 0, 0..1);
+    /// Draws a square for each particle over what the pass has drawn so far, leaving the pass
+    /// drawing particles
+    fn draw_particles<'a>(
+        render_pass: &mut wgpu::RenderPass<'a>,
+        pipeline: &'a wgpu::RenderPipeline,
+        camera: &'a wgpu::BindGroup,
+        instances: &'a wgpu::Buffer,
+        particles: &[ParticleInstance],
+        report: &mut RenderReport,
+    ) {
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, camera, &[]);
+        let size = std::mem::size_of_val(particles) as u64;
+        render_pass.set_vertex_buffer(0, instances.slice(..size));
+        // two triangles' worth of corners for each particle
+        render_pass.draw(0..6, 0..particles.len() as u32);
+        report.draw_calls += 1;
+        report.vertices += 6 * particles.len() as u32;
+    }
+}
 
-        drop(render_pass);
+/// The format for the surface, and whether it's hdr: linear half floats with room past white,
+/// or the srgb format there'll almost always be otherwise
+fn choose_format(
+    formats: &[wgpu::TextureFormat],
+    hdr: bool,
+) -> Option<(wgpu::TextureFormat, bool)> {
+    let extended = wgpu::TextureFormat::Rgba16Float;
+    if hdr && formats.contains(&extended) {
+        return Some((extended, true));
+    }
+    let sdr = formats.iter().copied().find(|format| format.is_srgb());
+    Some((sdr.or(formats.first().copied())?, false))
+}
 
-        self.queue.submit(std::iter::once(encoder.finish()));
-        texture.present();
+#[test]
+fn hdr_falls_back_to_srgb() {
+    use wgpu::TextureFormat::*;
 
-        Ok(())
+    let formats = [Bgra8Unorm, Bgra8UnormSrgb, Rgba16Float];
+    assert_eq!(choose_format(&formats, true), Some((Rgba16Float, true)));
+    assert_eq!(
+        choose_format(&formats, false),
+        Some((Bgra8UnormSrgb, false))
+    );
+    assert_eq!(
+        choose_format(&[Bgra8Unorm, Bgra8UnormSrgb], true),
+        Some((Bgra8UnormSrgb, false))
+    );
+    assert_eq!(
+        choose_format(&[Bgra8Unorm], true),
+        Some((Bgra8Unorm, false))
+    );
+    assert_eq!(choose_format(&[], false), None);
+}
+
+/// The alpha mode for the surface, and whether it lets what's behind the window through.
+///
+/// Only premultiplied will do for that: blending straight colours over a cleared-transparent
+/// target leaves them multiplied by their alpha already, which a compositor multiplying again
+/// would darken into fringes around the edges of shapes.
+fn choose_alpha_mode(
+    modes: &[wgpu::CompositeAlphaMode],
+    transparent: bool,
+) -> (wgpu::CompositeAlphaMode, bool) {
+    let premultiplied = wgpu::CompositeAlphaMode::PreMultiplied;
+    if transparent && modes.contains(&premultiplied) {
+        return (premultiplied, true);
+    }
+    let opaque = wgpu::CompositeAlphaMode::Opaque;
+    (
+        if modes.contains(&opaque) {
+            opaque
+        } else {
+            // left to the backend when the surface lists none
+            modes
+                .first()
+                .copied()
+                .unwrap_or(wgpu::CompositeAlphaMode::Auto)
+        },
+        false,
+    )
+}
+
+#[test]
+fn transparency_needs_a_premultiplied_surface() {
+    use wgpu::CompositeAlphaMode::*;
+
+    assert_eq!(
+        choose_alpha_mode(&[Opaque, PreMultiplied], true),
+        (PreMultiplied, true)
+    );
+    assert_eq!(
+        choose_alpha_mode(&[Opaque, PreMultiplied], false),
+        (Opaque, false)
+    );
+    // fringed if chosen, so left opaque instead
+    assert_eq!(
+        choose_alpha_mode(&[PostMultiplied, Opaque], true),
+        (Opaque, false)
+    );
+    assert_eq!(choose_alpha_mode(&[Inherit], true), (Inherit, false));
+    assert_eq!(choose_alpha_mode(&[], true), (Auto, false));
+}
+
+#[test]
+fn missing_adapter_says_what_was_tried() {
+    let message = RendererError::NoAdapter {
+        tried: ATTEMPTS[..2].iter().map(|(name, ..)| *name).collect(),
+        require_hardware: true,
     }
+    .to_string();
+    assert!(message.contains("the primary backends, then every backend"));
+    assert!(message.contains("Vulkan"));
+    assert!(message.contains("hardware was required"));
+}
+
+#[test]
+fn lost_devices_are_told_apart_from_bugs() {
+    use wgpu_core::device::queue::QueueSubmitError;
+    use wgpu_core::device::DeviceError;
+
+    // as wgpu reports an error from wgpu-core, its description following each source in turn
+    let validation = |error: QueueSubmitError| wgpu::Error::Validation {
+        description: format!("Validation Error\n\nCaused by:\n    {error}\n"),
+        source: Box::new(error),
+    };
+    assert!(means_device_lost(&validation(QueueSubmitError::Queue(
+        DeviceError::Lost
+    ))));
+    assert!(!means_device_lost(&validation(QueueSubmitError::Queue(
+        DeviceError::Invalid
+    ))));
+    assert!(!means_device_lost(&validation(
+        QueueSubmitError::SurfaceOutputDropped
+    )));
+    assert!(means_device_lost(&wgpu::Error::OutOfMemory {
+        source: Box::new(std::io::Error::from(std::io::ErrorKind::OutOfMemory)),
+    }));
 }
@@ -0,0 +1,356 @@
+//! One large vertex buffer and index buffer shared by many small meshes, each drawn from its own
+//! ranges of them, so drawing a lot of meshes doesn't mean binding a lot of buffers
+
+use super::buffer::{DrawItem, Mesh, MeshBuilder, Vertex};
+use crate::instrument;
+use std::ops::Range;
+
+/// Where a mesh's vertices and indices are in the arena. Its indices count from the start of its
+/// own vertices, so it's drawn with that as the base vertex.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeshHandle {
+    pub vertex_range: Range<u32>,
+    pub index_range: Range<u32>,
+}
+
+impl MeshHandle {
+    pub fn item(&self) -> DrawItem {
+        DrawItem {
+            indices: self.index_range.clone(),
+            base_vertex: self.vertex_range.start as i32,
+            ..DrawItem::default()
+        }
+    }
+
+    /// `item` of the mesh as it was built, moved to where its indices and vertices are in the
+    /// arena
+    pub fn offset(&self, item: &DrawItem) -> DrawItem {
+        let start = self.index_range.start;
+        DrawItem {
+            indices: item.indices.start + start..item.indices.end + start,
+            base_vertex: item.base_vertex + self.vertex_range.start as i32,
+            ..item.clone()
+        }
+    }
+}
+
+/// Shared buffers meshes are allocated from, either to keep until they're freed or only until
+/// the frame is over. Nothing moves once allocated, so handles stay good when the buffers grow.
+#[derive(Debug)]
+pub struct Arena {
+    buffers: Mesh,
+    vertices: Space,
+    indices: Space,
+}
+
+impl Arena {
+    /// Room for `vertices` and `indices` to start with, grown as it's needed
+    pub fn new(device: &wgpu::Device, vertices: u32, indices: u32) -> Self {
+        let indices = indices.next_multiple_of(2);
+        Self {
+            buffers: Mesh {
+                vertex_buffer: create_buffer(device, VERTICES, vertices),
+                index_buffer: create_buffer(device, INDICES, indices),
+                index_count: 0,
+            },
+            vertices: Space::new(vertices),
+            indices: Space::new(indices),
+        }
+    }
+
+    /// The shared buffers, to draw the items of handles from
+    pub fn mesh(&self) -> &Mesh {
+        &self.buffers
+    }
+
+    /// Uploads the builder to keep until it's freed
+    pub fn alloc(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        builder: &MeshBuilder,
+    ) -> MeshHandle {
+        self.place(device, queue, builder, Space::alloc_static)
+    }
+
+    /// Uploads the builder until the next `reset_frame`, after which its handle mustn't be drawn
+    pub fn alloc_frame(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        builder: &MeshBuilder,
+    ) -> MeshHandle {
+        self.place(device, queue, builder, Space::alloc_frame)
+    }
+
+    /// Gives back what `alloc` handed out, to be allocated again
+    pub fn free(&mut self, handle: MeshHandle) {
+        let MeshHandle {
+            vertex_range,
+            index_range,
+        } = handle;
+        self.vertices.free(vertex_range);
+        self.indices
+            .free(index_range.start..index_range.end.next_multiple_of(2));
+    }
+
+    /// Frees everything from `alloc_frame`
+    pub fn reset_frame(&mut self) {
+        self.vertices.reset();
+        self.indices.reset();
+    }
+
+    fn place(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        builder: &MeshBuilder,
+        alloc: fn(&mut Space, u32) -> Option<Range<u32>>,
+    ) -> MeshHandle {
+        let vertex_range = allocate(
+            device,
+            queue,
+            &mut self.buffers.vertex_buffer,
+            &mut self.vertices,
+            VERTICES,
+            builder.vertices.len() as u32,
+            alloc,
+        );
+        // copies have to be a whole number of 4 bytes, so indices go in pairs
+        let index_count = builder.indices.len() as u32;
+        let reserved = allocate(
+            device,
+            queue,
+            &mut self.buffers.index_buffer,
+            &mut self.indices,
+            INDICES,
+            index_count.next_multiple_of(2),
+            alloc,
+        );
+
+        if !builder.vertices.is_empty() {
+            queue.write_buffer(
+                &self.buffers.vertex_buffer,
+                VERTICES.offset(vertex_range.start),
+                bytemuck::cast_slice(&builder.vertices),
+            );
+        }
+        // an odd index out is padded out to a pair, without copying the rest to do it
+        let (pairs, odd) = builder.indices.split_at(builder.indices.len() & !1);
+        if !pairs.is_empty() {
+            queue.write_buffer(
+                &self.buffers.index_buffer,
+                INDICES.offset(reserved.start),
+                bytemuck::cast_slice(pairs),
+            );
+        }
+        if let Some(&index) = odd.first() {
+            queue.write_buffer(
+                &self.buffers.index_buffer,
+                INDICES.offset(reserved.start + pairs.len() as u32),
+                bytemuck::cast_slice(&[index, 0]),
+            );
+        }
+
+        MeshHandle {
+            vertex_range,
+            index_range: reserved.start..reserved.start + index_count,
+        }
+    }
+}
+
+/// What one of the shared buffers holds
+#[derive(Debug, Clone, Copy)]
+struct Kind {
+    label: &'static str,
+    usage: wgpu::BufferUsages,
+    size: u64,
+}
+
+impl Kind {
+    fn offset(self, element: u32) -> wgpu::BufferAddress {
+        element as u64 * self.size
+    }
+}
+
+const VERTICES: Kind = Kind {
+    label: "Arena Vertex Buffer",
+    usage: wgpu::BufferUsages::VERTEX,
+    size: std::mem::size_of::<Vertex>() as u64,
+};
+const INDICES: Kind = Kind {
+    label: "Arena Index Buffer",
+    usage: wgpu::BufferUsages::INDEX,
+    size: std::mem::size_of::<u16>() as u64,
+};
+
+fn create_buffer(device: &wgpu::Device, kind: Kind, capacity: u32) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(kind.label),
+        size: kind.offset(capacity),
+        usage: kind.usage | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    })
+}
+
+/// Allocates `len` from the space, first growing its buffer if it's out of room, copying
+/// everything already in it to the same place in the new one
+fn allocate(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    buffer: &mut wgpu::Buffer,
+    space: &mut Space,
+    kind: Kind,
+    len: u32,
+    alloc: fn(&mut Space, u32) -> Option<Range<u32>>,
+) -> Range<u32> {
+    if let Some(range) = alloc(space, len) {
+        return range;
+    }
+
+    instrument::count(|counts| counts.buffer_recreations += 1);
+    let capacity = space.grown_for(len);
+    let grown = create_buffer(device, kind, capacity);
+    if space.bump > 0 {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Arena Grow Encoder"),
+        });
+        encoder.copy_buffer_to_buffer(buffer, 0, &grown, 0, kind.offset(space.bump));
+        // anything written to the old buffer beforehand is written before this copies it
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+    *buffer = grown;
+    space.capacity = capacity;
+
+    alloc(space, len).expect("grown to fit")
+}
+
+/// Hands out ranges of one buffer, counted in elements. Ranges kept for a while come from gaps
+/// left by those freed, or else from the top, and ranges for the frame are bumped along from
+/// the top. Nothing moves once handed out, so growing only has to copy the buffer over as it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Space {
+    capacity: u32,
+    /// Past the last range kept, where the frame's ranges start from after a reset
+    static_end: u32,
+    /// Where the next range from the top goes
+    bump: u32,
+    /// Gaps below `static_end`, in order and never touching each other
+    free: Vec<Range<u32>>,
+    /// This frame's ranges, to be freed at the reset if any are under a range kept since
+    frame: Vec<Range<u32>>,
+}
+
+impl Space {
+    fn new(capacity: u32) -> Self {
+        Self {
+            capacity,
+            static_end: 0,
+            bump: 0,
+            free: Vec::new(),
+            frame: Vec::new(),
+        }
+    }
+
+    /// How big the buffer has to become for `len` more to fit at the top
+    fn grown_for(&self, len: u32) -> u32 {
+        (self.bump + len).next_power_of_two().max(self.capacity * 2)
+    }
+
+    /// Bumps the top along by `len`, unless that would run out of room
+    fn bump(&mut self, len: u32) -> Option<Range<u32>> {
+        let start = self.bump;
+        let end = start.checked_add(len).filter(|&end| end <= self.capacity)?;
+        self.bump = end;
+        Some(start..end)
+    }
+
+    /// A range to keep until it's freed, from the first gap it fits in, otherwise from above
+    /// this frame's ranges, or `None` if the buffer has to grow first
+    fn alloc_static(&mut self, len: u32) -> Option<Range<u32>> {
+        if let Some(i) = self.free.iter().position(|gap| gap.len() as u32 >= len) {
+            let start = self.free[i].start;
+            self.free[i].start += len;
+            if self.free[i].is_empty() {
+                self.free.remove(i);
+            }
+            return Some(start..start + len);
+        }
+
+        let range = self.bump(len)?;
+        self.static_end = range.end;
+        Some(range)
+    }
+
+    /// A range that's only good until the next reset, or `None` if the buffer has to grow first
+    fn alloc_frame(&mut self, len: u32) -> Option<Range<u32>> {
+        let range = self.bump(len)?;
+        self.frame.push(range.clone());
+        Some(range)
+    }
+
+    fn free(&mut self, range: Range<u32>) {
+        if range.is_empty() {
+            return;
+        }
+        let i = self.free.partition_point(|gap| gap.start < range.start);
+        self.free.insert(i, range);
+        // joined up with the gaps either side where they meet
+        if i + 1 < self.free.len() && self.free[i].end == self.free[i + 1].start {
+            self.free[i].end = self.free.remove(i + 1).end;
+        }
+        if i > 0 && self.free[i - 1].end == self.free[i].start {
+            self.free[i - 1].end = self.free.remove(i).end;
+        }
+    }
+
+    /// Frees the frame's ranges, leaving any under a range kept since as a gap, and gives a gap
+    /// at the top back to it
+    fn reset(&mut self) {
+        for range in std::mem::take(&mut self.frame) {
+            if range.end <= self.static_end {
+                self.free(range);
+            }
+        }
+        if self
+            .free
+            .last()
+            .is_some_and(|gap| gap.end == self.static_end)
+        {
+            self.static_end = self.free.pop().unwrap().start;
+        }
+        self.bump = self.static_end;
+    }
+}
+
+#[test]
+// each gap compared is a range, not a list of numbers
+#[allow(clippy::single_range_in_vec_init)]
+fn kept_ranges_reuse_gaps_and_frame_ranges_last_a_frame() {
+    let mut space = Space::new(16);
+    let a = space.alloc_static(4).unwrap();
+    let b = space.alloc_static(4).unwrap();
+    assert_eq!((a.clone(), b.clone()), (0..4, 4..8));
+
+    // the frame's ranges go on top, and a range kept after them goes above those
+    assert_eq!(space.alloc_frame(3), Some(8..11));
+    assert_eq!(space.alloc_static(2), Some(11..13));
+    assert_eq!(space.alloc_frame(4), None);
+    assert_eq!(space.grown_for(4), 32);
+
+    // the frame's range under the kept one is left as a gap to be filled
+    space.reset();
+    assert_eq!(space.free, [8..11]);
+    assert_eq!(space.bump, 13);
+    assert_eq!(space.alloc_static(3), Some(8..11));
+
+    // freed ranges join up, and reach back down to the top once nothing's kept above them
+    space.free(a);
+    space.free(b);
+    assert_eq!(space.free, [0..8]);
+    space.free(8..11);
+    space.free(11..13);
+    assert_eq!(space.free, [0..13]);
+    space.reset();
+    assert_eq!(space, Space::new(16));
+}
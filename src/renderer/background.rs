@@ -0,0 +1,143 @@
+//! What's behind the scene, filling the screen from a shader of its own rather than from geometry
+//! pushed into the mesh every frame
+
+use super::cache::ShaderId;
+
+/// Dim clouds drifting past
+pub const NEBULA: ShaderId = ShaderId::Procedural(0);
+/// Layers of stars, scrolling slower than the playfield the further off they are
+pub const STARFIELD: ShaderId = ShaderId::Procedural(1);
+
+/// A background shader registered with the renderer
+struct Registered {
+    /// Compiled along with `background.wgsl`, or `None` for those built into it, which are drawn
+    /// from the background program's own module
+    module: Option<wgpu::ShaderModule>,
+    entry_point: String,
+}
+
+/// The shaders procedural backgrounds are drawn by, each under the `ShaderId` it was registered
+/// as: those built into `background.wgsl` first, then any the renderer's been given
+pub(super) struct Registry {
+    shaders: Vec<Registered>,
+}
+
+impl Registry {
+    const BUILT_IN: [(ShaderId, &'static str); 2] =
+        [(NEBULA, "fs_nebula"), (STARFIELD, "fs_starfield")];
+
+    pub(super) fn new() -> Self {
+        let shaders = Self::BUILT_IN
+            .iter()
+            .enumerate()
+            .map(|(i, &(id, entry_point))| {
+                debug_assert_eq!(id, ShaderId::Procedural(i as u32));
+                Registered {
+                    module: None,
+                    entry_point: entry_point.into(),
+                }
+            })
+            .collect();
+        Self { shaders }
+    }
+
+    /// Compiles `source` after `background.wgsl`, so it can use its uniform, vertex output and
+    /// noise, and registers its `entry_point` as a background to draw by the id returned
+    pub(super) fn register(
+        &mut self,
+        device: &wgpu::Device,
+        source: &str,
+        entry_point: &str,
+    ) -> ShaderId {
+        let source = format!("{}\n{source}", include_str!("../shaders/background.wgsl"));
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(entry_point),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        self.shaders.push(Registered {
+            module: Some(module),
+            entry_point: entry_point.into(),
+        });
+        ShaderId::Procedural(self.shaders.len() as u32 - 1)
+    }
+
+    /// The module, unless it's the background program's, and entry point registered as `id`
+    pub(super) fn get(&self, id: ShaderId) -> Option<(Option<&wgpu::ShaderModule>, &str)> {
+        let ShaderId::Procedural(i) = id else {
+            return None;
+        };
+        let registered = self.shaders.get(i as usize)?;
+        Some((registered.module.as_ref(), &registered.entry_point))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BackgroundKind {
+    /// Cleared to, with nothing drawn
+    Solid([f32; 3]),
+    /// From the bottom of the screen to the top
+    VerticalGradient([f32; 3], [f32; 3]),
+    /// Drawn by the shader registered as the id, over a colour it's given if it wants one
+    Procedural(ShaderId, [f32; 3]),
+    /// Cleared to nothing, so whatever's behind a transparent window shows through
+    Transparent,
+}
+
+impl Default for BackgroundKind {
+    fn default() -> Self {
+        Self::Solid([0.; 3])
+    }
+}
+
+impl BackgroundKind {
+    /// The colour the scene's pass clears to, or `None` if it's drawn over a background pass
+    pub(super) fn clear_color(self) -> Option<wgpu::Color> {
+        match self {
+            Self::Solid([r, g, b]) => Some(wgpu::Color {
+                r: r as f64,
+                g: g as f64,
+                b: b as f64,
+                a: 1.,
+            }),
+            Self::Transparent => Some(wgpu::Color::TRANSPARENT),
+            _ => None,
+        }
+    }
+
+    /// The shader drawing the background pass, if it needs one
+    pub(super) fn shader(self) -> Option<ShaderId> {
+        match self {
+            Self::Solid(_) | Self::Transparent => None,
+            Self::VerticalGradient(..) => Some(ShaderId::Gradient),
+            Self::Procedural(shader, _) => Some(shader),
+        }
+    }
+
+    pub(super) fn uniform(self, time: f32, camera_x: f32) -> BackgroundUniform {
+        let ([r, g, b], [r2, g2, b2]) = match self {
+            Self::Solid(color) | Self::Procedural(_, color) => (color, color),
+            Self::VerticalGradient(bottom, top) => (bottom, top),
+            Self::Transparent => ([0.; 3], [0.; 3]),
+        };
+        BackgroundUniform {
+            bottom: [r, g, b, 1.],
+            top: [r2, g2, b2, 1.],
+            time,
+            camera_x,
+            padding: [0.; 2],
+        }
+    }
+}
+
+/// Laid out as the shader's uniform is
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub(super) struct BackgroundUniform {
+    bottom: [f32; 4],
+    top: [f32; 4],
+    /// Seconds since the renderer started
+    time: f32,
+    camera_x: f32,
+    /// Rounding the size up to 16 bytes, as uniforms have to be
+    padding: [f32; 2],
+}
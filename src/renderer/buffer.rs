@@ -1,3 +1,8 @@
+use super::mask::Masking;
+use super::texture::Texturing;
+use crate::instrument;
+use cgmath::Vector2;
+use std::ops::Range;
 use wgpu::util::DeviceExt;
 
 #[derive(Debug)]
@@ -7,41 +12,242 @@ pub struct Mesh {
     pub(super) index_count: u32,
 }
 
+/// A run of a mesh's indices drawn by one draw call
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawItem {
+    pub indices: Range<u32>,
+    /// Added to each index before it's looked up in the vertex buffer
+    pub base_vertex: i32,
+    /// Where the vertices are moved to before the camera sees them
+    pub transform: Transform2D,
+    pub masking: Masking,
+    /// Drawn in the vertices' colours alone without one
+    pub texturing: Option<Texturing>,
+}
+
+impl Default for DrawItem {
+    /// Drawing nothing, unmoved and unmasked
+    fn default() -> Self {
+        Self {
+            indices: 0..0,
+            base_vertex: 0,
+            transform: Transform2D::IDENTITY,
+            masking: Masking::NONE,
+            texturing: None,
+        }
+    }
+}
+
+/// `count` runs of whole triangles covering `index_count` indices, as even as they can be, or
+/// fewer if there aren't enough triangles to go round
+fn split_indices(index_count: u32, count: usize) -> Vec<DrawItem> {
+    let triangles = index_count / 3;
+    let count = (count as u32).min(triangles);
+    (0..count)
+        .map(|i| DrawItem {
+            indices: triangles * i / count * 3..triangles * (i + 1) / count * 3,
+            ..DrawItem::default()
+        })
+        .collect()
+}
+
 impl Mesh {
     pub fn builder() -> MeshBuilder {
         MeshBuilder::default()
     }
+
+    /// The whole mesh as one draw
+    pub fn whole(&self) -> DrawItem {
+        DrawItem {
+            indices: 0..self.index_count,
+            ..DrawItem::default()
+        }
+    }
+
+    /// The mesh cut into `count` draws, as though it were that many meshes
+    pub fn split(&self, count: usize) -> Vec<DrawItem> {
+        split_indices(self.index_count, count)
+    }
+
+    /// Uploads the builder's contents, reusing the buffers when they're big enough and otherwise
+    /// making them again with room to grow
+    pub fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, builder: &MeshBuilder) {
+        let vertices: &[u8] = bytemuck::cast_slice(&builder.vertices);
+        Self::write_buffer(
+            device,
+            queue,
+            &mut self.vertex_buffer,
+            "Vertex Buffer",
+            wgpu::BufferUsages::VERTEX,
+            vertices,
+            &[],
+        );
+
+        // copies have to be a whole number of 4 bytes, so an odd index out is padded
+        let indices = &builder.indices;
+        let (pairs, odd) = indices.split_at(indices.len() & !1);
+        let padded = odd.first().map(|&index| [index, 0]);
+        Self::write_buffer(
+            device,
+            queue,
+            &mut self.index_buffer,
+            "Index Buffer",
+            wgpu::BufferUsages::INDEX,
+            bytemuck::cast_slice(pairs),
+            padded
+                .as_ref()
+                .map_or(&[], |padded| bytemuck::cast_slice(padded)),
+        );
+        self.index_count = indices.len() as u32;
+    }
+
+    /// Writes `contents` then `tail` to the start of the buffer, making it again first if it's
+    /// too small
+    pub(super) fn write_buffer(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        buffer: &mut wgpu::Buffer,
+        label: &str,
+        usage: wgpu::BufferUsages,
+        contents: &[u8],
+        tail: &[u8],
+    ) {
+        let size = (contents.len() + tail.len()) as u64;
+        if size > buffer.size() {
+            instrument::count(|counts| counts.buffer_recreations += 1);
+            *buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: size.next_power_of_two(),
+                usage: usage | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+        }
+        if !contents.is_empty() {
+            queue.write_buffer(buffer, 0, contents);
+        }
+        if !tail.is_empty() {
+            queue.write_buffer(buffer, contents.len() as u64, tail);
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct MeshBuilder {
-    vertices: Vec<Vertex>,
-    indices: Vec<u16>,
+    pub(super) vertices: Vec<Vertex>,
+    pub(super) indices: Vec<u16>,
+    /// How many pixels tall a unit of the world shows on screen, for working out how finely to
+    /// draw curves, or `None` if it's not been said
+    pub(super) pixels_per_unit: Option<f32>,
 }
 
 impl MeshBuilder {
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn index_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// How many bytes uploading it writes, with an odd index out padded as `Mesh::write` does
+    pub fn upload_size(&self) -> u64 {
+        (std::mem::size_of_val(self.vertices.as_slice())
+            + self.indices.len().next_multiple_of(2) * std::mem::size_of::<u16>()) as u64
+    }
+
+    #[cfg(test)]
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    /// A hash of the mesh's exact contents, for checking a scene renders the same as before
+    #[cfg(test)]
+    pub fn checksum(&self) -> u64 {
+        // fnv-1a
+        let vertices: &[u8] = bytemuck::cast_slice(&self.vertices);
+        let indices: &[u8] = bytemuck::cast_slice(&self.indices);
+        let bytes = vertices.iter().chain(indices);
+        bytes.fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    fn capacity(&self) -> (usize, usize) {
+        (self.vertices.capacity(), self.indices.capacity())
+    }
+
+    /// Counts a reallocation if the capacity changed from `before`
+    fn track(&self, before: (usize, usize)) {
+        if self.capacity() != before {
+            instrument::count(|counts| counts.builder_reallocations += 1);
+        }
+    }
+
+    pub fn reserve(&mut self, vertices: usize, indices: usize) {
+        let before = self.capacity();
+        self.vertices.reserve(vertices);
+        self.indices.reserve(indices);
+        self.track(before);
+    }
+
+    /// Empties the mesh, keeping its capacity to build the next one in
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+    }
+
+    /// Says how the mesh will be shown, `zoom` times over on a surface `surface_height` pixels tall
+    pub fn set_screen_scale(&mut self, zoom: f32, surface_height: u32) {
+        // the world's -1..=1 fills the surface's height at no zoom
+        self.pixels_per_unit = Some(zoom * surface_height as f32 / 2.);
+    }
+
+    /// Makes this a copy of `other`, in the capacity it already has where it can
+    pub fn copy_from(&mut self, other: &MeshBuilder) {
+        self.clear();
+        self.append(other);
+    }
+
     pub fn push(
         &mut self,
         vertices: impl IntoIterator<Item = Vertex>,
         indices: impl IntoIterator<Item = u16>,
     ) {
+        let before = self.capacity();
         let current_vertex = self.vertices.len() as u16;
         self.indices
             .extend(indices.into_iter().map(|i| current_vertex + i));
-        self.vertices.extend(vertices.into_iter());
+        self.vertices.extend(vertices);
+        self.track(before);
+    }
+
+    /// Moves a run of vertices already pushed by `offset`
+    pub fn translate(&mut self, vertices: Range<usize>, offset: Vector2<f32>) {
+        for vertex in &mut self.vertices[vertices] {
+            vertex.position[0] += offset.x;
+            vertex.position[1] += offset.y;
+        }
+    }
+
+    /// Pushes everything in `other` on top of this
+    pub fn append(&mut self, other: &MeshBuilder) {
+        self.push(
+            other.vertices.iter().copied(),
+            other.indices.iter().copied(),
+        );
     }
 
-    pub fn build(self, device: &wgpu::Device) -> Mesh {
+    pub fn build(&self, device: &wgpu::Device) -> Mesh {
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer"),
             contents: bytemuck::cast_slice(&self.vertices),
-            usage: wgpu::BufferUsages::VERTEX,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
         });
 
         let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Index Buffer"),
             contents: bytemuck::cast_slice(&self.indices),
-            usage: wgpu::BufferUsages::INDEX,
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
         });
 
         Mesh {
@@ -52,16 +258,112 @@ impl MeshBuilder {
     }
 }
 
+/// Scales, turns then moves a draw's vertices, laid out as the shader's uniform is
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Transform2D {
+    /// Where the x and y axes end up, scaled and turned
+    x_axis: [f32; 2],
+    y_axis: [f32; 2],
+    translation: [f32; 2],
+    /// Rounding the size up to 16 bytes, as uniforms have to be
+    padding: [f32; 2],
+}
+
+impl Transform2D {
+    pub const IDENTITY: Self = Self {
+        x_axis: [1., 0.],
+        y_axis: [0., 1.],
+        translation: [0., 0.],
+        padding: [0., 0.],
+    };
+
+    pub fn new(translation: Vector2<f32>, rotation: f32, scale: Vector2<f32>) -> Self {
+        let (s, c) = rotation.sin_cos();
+        Self {
+            x_axis: [c * scale.x, s * scale.x],
+            y_axis: [-s * scale.y, c * scale.y],
+            translation: translation.into(),
+            padding: [0., 0.],
+        }
+    }
+
+    /// Where `point` is moved to, as the shader does it
+    #[cfg(test)]
+    pub(super) fn apply(&self, point: Vector2<f32>) -> Vector2<f32> {
+        Vector2::from(self.x_axis) * point.x
+            + Vector2::from(self.y_axis) * point.y
+            + Vector2::from(self.translation)
+    }
+}
+
+/// Takes the world to the screen's -1 to 1 as the camera sees it, laid out as the shaders'
+/// uniform is. Anything finding where something in the world shows on screen goes through this
+/// too, so it can't drift from what's drawn.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ViewTransform {
+    scale: [f32; 2],
+    offset: [f32; 2],
+}
+
+impl ViewTransform {
+    /// Looking at `camera_x`, scaled `zoom` times around it, on a surface `aspect` times as wide
+    /// as it's tall. The view's two units tall at a zoom of 1, and as wide as that makes it, so
+    /// nothing's stretched whatever the surface's shape.
+    pub fn new(camera_x: f32, zoom: f32, aspect: f32) -> Self {
+        let scale = zoom / aspect;
+        Self {
+            scale: [scale, zoom],
+            offset: [-camera_x * scale, 0.],
+        }
+    }
+
+    /// How many times wider than tall a surface `width` by `height` is, as if it were at least a
+    /// pixel each way, as a minimised window's isn't
+    pub fn aspect(width: u32, height: u32) -> f32 {
+        width.max(1) as f32 / height.max(1) as f32
+    }
+
+    pub fn world_to_ndc(&self, world: Vector2<f32>) -> Vector2<f32> {
+        Vector2::new(
+            world.x * self.scale[0] + self.offset[0],
+            world.y * self.scale[1] + self.offset[1],
+        )
+    }
+
+    pub fn ndc_to_world(&self, ndc: Vector2<f32>) -> Vector2<f32> {
+        Vector2::new(
+            (ndc.x - self.offset[0]) / self.scale[0],
+            (ndc.y - self.offset[1]) / self.scale[1],
+        )
+    }
+
+    /// Which pixel `world` shows on in a window `size` pixels across and down, counted from its
+    /// top left corner
+    pub fn world_to_pixels(&self, world: Vector2<f32>, size: Vector2<f32>) -> Vector2<f32> {
+        let ndc = self.world_to_ndc(world);
+        Vector2::new((ndc.x + 1.) / 2. * size.x, (1. - ndc.y) / 2. * size.y)
+    }
+
+    /// What in the world shows at `pixel` in a window `size` pixels across and down, as from the
+    /// cursor
+    pub fn pixels_to_world(&self, pixel: Vector2<f32>, size: Vector2<f32>) -> Vector2<f32> {
+        let ndc = Vector2::new(pixel.x / size.x * 2. - 1., 1. - pixel.y / size.y * 2.);
+        self.ndc_to_world(ndc)
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
     pub position: [f32; 2],
-    pub color: [f32; 3],
+    pub color: [f32; 4],
 }
 
 impl Vertex {
     const ATTRIBS: [wgpu::VertexAttribute; 2] =
-        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x3];
+        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4];
 
     pub fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
         use std::mem;
@@ -73,3 +375,64 @@ impl Vertex {
         }
     }
 }
+
+#[test]
+fn split_draws_cover_every_triangle_once() {
+    let items = split_indices(3 * 10, 4);
+    let lengths: Vec<_> = items.iter().map(|item| item.indices.len()).collect();
+    assert_eq!(lengths, [6, 9, 6, 9]);
+    // each picks up where the last left off, ending at the last index
+    assert_eq!(items[0].indices.start, 0);
+    assert!(items
+        .windows(2)
+        .all(|pair| pair[0].indices.end == pair[1].indices.start));
+    assert_eq!(items[3].indices.end, 30);
+
+    assert_eq!(split_indices(6, 1000).len(), 2);
+    assert!(split_indices(0, 1000).is_empty());
+}
+
+#[test]
+fn views_are_as_wide_as_the_surface_and_unstretched() {
+    for (width, height) in [(800, 600), (1920, 1080), (600, 800), (256, 256)] {
+        let size = Vector2::new(width as f32, height as f32);
+        let view = ViewTransform::new(0.5, 1.25, ViewTransform::aspect(width, height));
+        // a unit square covers as many pixels across as down
+        let corner = view.world_to_pixels(Vector2::new(0., 0.), size);
+        let across = view.world_to_pixels(Vector2::new(1., 0.), size) - corner;
+        let down = corner - view.world_to_pixels(Vector2::new(0., 1.), size);
+        assert!((across.x - down.y).abs() < 1e-3, "{width}x{height}");
+        // with the top edge of the view where the zoom puts it, whatever the width
+        assert_eq!(
+            view.world_to_ndc(Vector2::new(0.5, 0.8)),
+            Vector2::new(0., 1.)
+        );
+    }
+
+    // a minimised window's as if it were a pixel across, rather than dividing by nothing
+    assert_eq!(ViewTransform::aspect(0, 0), 1.);
+}
+
+/// One particle, drawn as a square the shader makes from it rather than as vertices of its own
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ParticleInstance {
+    pub position: [f32; 2],
+    /// The width of the square
+    pub size: f32,
+    pub color: [f32; 3],
+    pub alpha: f32,
+}
+
+impl ParticleInstance {
+    const ATTRIBS: [wgpu::VertexAttribute; 4] =
+        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32, 2 => Float32x3, 3 => Float32];
+
+    pub fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Gpu objects made the first time they're asked for and reused after, so a steady frame makes
+//! none, with hits and misses counted into the frame stats to check it doesn't
+
+use crate::instrument;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// The shaders pipelines can be made from, each with the vertex layout it reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderId {
+    /// Coloured triangles from a mesh
+    Mesh,
+    /// A square for each particle instance
+    Particles,
+    /// A background shading the screen from one colour to another
+    Gradient,
+    /// A background drawn entirely by a shader registered for it, numbered in the order they were
+    /// registered
+    Procedural(u32),
+    /// Triangles from a mesh, tinted by a texture
+    Textured,
+}
+
+/// What a pipeline does with the stencil buffer, for drawing masks and clipping to them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stencil {
+    /// Passes everywhere and leaves it be
+    Ignore,
+    /// Counts up where the stencil matches, without drawing any colour
+    WriteMask,
+    /// Counts back down where the stencil matches, without drawing any colour
+    ClearMask,
+    /// Passes only where the stencil matches
+    TestMask,
+}
+
+/// Everything a render pipeline is made from that can differ from one to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineKey {
+    pub shader: ShaderId,
+    pub format: wgpu::TextureFormat,
+    pub sample_count: u32,
+    pub blend: wgpu::BlendState,
+    pub stencil: Stencil,
+}
+
+/// A texture, numbered by whoever made it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureId(pub u64);
+
+/// How a texture is sampled, the same in both directions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SamplerDesc {
+    /// Used where the texture's drawn smaller than it is
+    pub min_filter: wgpu::FilterMode,
+    /// Used where the texture's drawn bigger than it is
+    pub mag_filter: wgpu::FilterMode,
+    /// What's sampled outside the texture, from 0 to 1
+    pub address_mode: wgpu::AddressMode,
+}
+
+impl SamplerDesc {
+    /// Blocky when zoomed in, as pixel art wants
+    pub const NEAREST: Self = Self {
+        min_filter: wgpu::FilterMode::Nearest,
+        mag_filter: wgpu::FilterMode::Nearest,
+        address_mode: wgpu::AddressMode::ClampToEdge,
+    };
+    pub const LINEAR: Self = Self {
+        min_filter: wgpu::FilterMode::Linear,
+        mag_filter: wgpu::FilterMode::Linear,
+        address_mode: wgpu::AddressMode::ClampToEdge,
+    };
+
+    pub fn with_address_mode(self, address_mode: wgpu::AddressMode) -> Self {
+        Self {
+            address_mode,
+            ..self
+        }
+    }
+}
+
+/// What a bind group binds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindGroupKey {
+    /// The camera's uniform, one buffer written over each draw
+    Camera,
+    /// The background's uniform, written over each frame
+    Background,
+    Texture {
+        texture: TextureId,
+        sampler: SamplerDesc,
+    },
+}
+
+impl BindGroupKey {
+    pub fn uses(&self, texture: TextureId) -> bool {
+        matches!(self, Self::Texture { texture: used, .. } if *used == texture)
+    }
+}
+
+#[derive(Debug)]
+pub struct Cache<K, V> {
+    entries: HashMap<K, V>,
+}
+
+impl<K, V> Default for Cache<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> Cache<K, V> {
+    /// The entry for `key`, made with `create` if there isn't one yet
+    pub fn get_or_create(&mut self, key: K, create: impl FnOnce(&K) -> V) -> &V {
+        let hit = self.entries.contains_key(&key);
+        instrument::count(|counts| {
+            if hit {
+                counts.cache_hits += 1;
+            } else {
+                counts.cache_misses += 1;
+            }
+        });
+        self.entries.entry(key).or_insert_with_key(create)
+    }
+
+    /// The entry for `key` if it's been made, without counting as a hit
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key)
+    }
+
+    /// Drops the entries `stale` picks out, as when what they were made from is gone
+    pub fn evict(&mut self, mut stale: impl FnMut(&K) -> bool) {
+        self.entries.retain(|key, _| !stale(key));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[test]
+fn entries_are_made_once_and_dropped_with_their_texture() {
+    let sampler = SamplerDesc::NEAREST;
+    let texture = |id| BindGroupKey::Texture {
+        texture: TextureId(id),
+        sampler,
+    };
+    let mut cache = Cache::default();
+    let mut made = 0;
+    let mut create = |_: &BindGroupKey| {
+        made += 1;
+        made
+    };
+
+    let before = instrument::counts();
+    assert_eq!(*cache.get_or_create(texture(1), &mut create), 1);
+    assert_eq!(*cache.get_or_create(texture(2), &mut create), 2);
+    assert_eq!(*cache.get_or_create(texture(1), &mut create), 1);
+    assert_eq!(*cache.get_or_create(BindGroupKey::Camera, &mut create), 3);
+    let counts = instrument::counts().since(&before);
+    assert_eq!((counts.cache_hits, counts.cache_misses), (1, 3));
+
+    // the first texture's gone, so binding it again would make it anew
+    cache.evict(|key| key.uses(TextureId(1)));
+    assert_eq!(cache.len(), 2);
+    assert_eq!(*cache.get_or_create(texture(1), &mut create), 4);
+}
@@ -0,0 +1,375 @@
+//! Recording what's presented frame by frame, as numbered pngs alongside a file of when each was
+//! shown for an encoder to time them by
+
+use crossbeam::channel::{self, Receiver, Sender, TrySendError};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How many frames can be on their way back from the gpu at once, any more being dropped
+const RING: usize = 3;
+/// How many frames read back can wait to be written before any more are dropped
+const QUEUE: usize = 8;
+/// The file each frame's number and time is listed in
+const TIMESTAMPS: &str = "timestamps.csv";
+
+#[derive(Debug)]
+pub enum CaptureError {
+    /// There's nothing to capture while the app is suspended
+    NoSurface,
+    /// The surface can't be copied from on this platform
+    NotCopyable,
+    /// Frames of this format don't fit in a png without converting them first
+    Format(wgpu::TextureFormat),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSurface => write!(f, "there is no surface to capture"),
+            Self::NotCopyable => write!(f, "the surface can't be copied from"),
+            Self::Format(format) => write!(f, "frames in {format:?} can't be captured"),
+            Self::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+impl From<std::io::Error> for CaptureError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// A frame read back, as rows of rgb without padding
+struct Frame {
+    number: usize,
+    /// Since the capture started
+    time: Duration,
+    pixels: Vec<u8>,
+}
+
+/// Copies each frame into whichever of a ring of buffers is free, reading them back a frame or
+/// so later for a thread of its own to encode and write, so the game doesn't wait on either
+pub(super) struct Capture {
+    dir: PathBuf,
+    max_frames: usize,
+    size: wgpu::Extent3d,
+    /// Bytes in each row of the buffers, padded as copies to them need
+    padded_row: u32,
+    /// Whether the surface's channels are blue first, to be swapped
+    bgra: bool,
+    buffers: Vec<wgpu::Buffer>,
+    /// The number and time of the frame copied into each buffer, until it's been read back
+    pending: [Option<(usize, Duration)>; RING],
+    /// Which buffers have been mapped since, and whether they could be
+    mapped: Receiver<(usize, bool)>,
+    mapped_send: Sender<(usize, bool)>,
+    frames: Sender<Frame>,
+    writer: JoinHandle<()>,
+    /// Set by the writer when it's given up, having failed to write
+    failed: Arc<AtomicBool>,
+    started: Instant,
+    copied: usize,
+    dropped: usize,
+}
+
+impl Capture {
+    pub(super) fn start(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        (width, height): (u32, u32),
+        dir: PathBuf,
+        max_frames: usize,
+    ) -> Result<Self, CaptureError> {
+        let bgra = match format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            other => return Err(CaptureError::Format(other)),
+        };
+
+        std::fs::create_dir_all(&dir)?;
+        let mut timestamps = BufWriter::new(File::create(dir.join(TIMESTAMPS))?);
+        writeln!(timestamps, "frame,seconds")?;
+
+        let padded_row = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffers = (0..RING)
+            .map(|_| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Capture Buffer"),
+                    size: (padded_row * height) as u64,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                })
+            })
+            .collect();
+
+        let (mapped_send, mapped) = channel::unbounded();
+        let (frames, received) = channel::bounded(QUEUE);
+        let failed = Arc::new(AtomicBool::new(false));
+        let writer = std::thread::spawn({
+            let dir = dir.clone();
+            let failed = Arc::clone(&failed);
+            move || write_frames(&dir, (width, height), received, timestamps, &failed)
+        });
+
+        log::info!("capturing up to {max_frames} frames to {}", dir.display());
+        Ok(Self {
+            dir,
+            max_frames,
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            padded_row,
+            bgra,
+            buffers,
+            pending: [None; RING],
+            mapped,
+            mapped_send,
+            frames,
+            writer,
+            failed,
+            started: Instant::now(),
+            copied: 0,
+            dropped: 0,
+        })
+    }
+
+    /// Whether it's copied all the frames it was asked for, or the writer's given up
+    pub(super) fn is_done(&self) -> bool {
+        self.copied >= self.max_frames || self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Whether frames of `size` are what it's capturing
+    pub(super) fn fits(&self, width: u32, height: u32) -> bool {
+        (self.size.width, self.size.height) == (width, height)
+    }
+
+    /// Records copying `texture` into a free buffer, returning which to map once it's submitted,
+    /// or `None` if they're all still being read back
+    pub(super) fn copy(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) -> Option<usize> {
+        if self.is_done() {
+            return None;
+        }
+        let Some(slot) = self.pending.iter().position(Option::is_none) else {
+            self.dropped += 1;
+            return None;
+        };
+
+        self.pending[slot] = Some((self.copied, self.started.elapsed()));
+        self.copied += 1;
+        if self.copied == self.max_frames {
+            log::info!("capture reached its limit of {} frames", self.max_frames);
+        }
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &self.buffers[slot],
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.padded_row),
+                    rows_per_image: None,
+                },
+            },
+            self.size,
+        );
+        Some(slot)
+    }
+
+    /// Starts reading a copy back, once the commands making it have been submitted
+    pub(super) fn map(&self, slot: usize) {
+        let mapped = self.mapped_send.clone();
+        self.buffers[slot]
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                // only gone if the capture has been stopped since
+                let _ = mapped.send((slot, result.is_ok()));
+            });
+    }
+
+    /// Hands whatever's been read back since to the writer, first waiting for every copy still
+    /// on the gpu if `wait`
+    pub(super) fn collect(&mut self, device: &wgpu::Device, wait: bool) {
+        if self.pending.iter().all(Option::is_none) {
+            return;
+        }
+        device.poll(if wait {
+            wgpu::Maintain::Wait
+        } else {
+            wgpu::Maintain::Poll
+        });
+
+        while let Ok((slot, mapped)) = self.mapped.try_recv() {
+            let Some((number, time)) = self.pending[slot].take() else {
+                continue;
+            };
+            if !mapped {
+                self.dropped += 1;
+                continue;
+            }
+            let buffer = &self.buffers[slot];
+            let pixels = self.unpad(&buffer.slice(..).get_mapped_range());
+            buffer.unmap();
+
+            match self.frames.try_send(Frame {
+                number,
+                time,
+                pixels,
+            }) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => self.dropped += 1,
+                // the writer's failed, and said so
+                Err(TrySendError::Disconnected(_)) => {}
+            }
+        }
+    }
+
+    /// The rows of rgb in a buffer of padded rows of four channels
+    fn unpad(&self, padded: &[u8]) -> Vec<u8> {
+        let width = self.size.width as usize;
+        let mut pixels = Vec::with_capacity(width * self.size.height as usize * 3);
+        for row in padded.chunks_exact(self.padded_row as usize) {
+            for pixel in row[..width * 4].chunks_exact(4) {
+                if self.bgra {
+                    pixels.extend([pixel[2], pixel[1], pixel[0]]);
+                } else {
+                    pixels.extend(&pixel[..3]);
+                }
+            }
+        }
+        pixels
+    }
+
+    /// Waits for the frames still on the gpu, leaving the writer to finish writing them, which
+    /// it's done once the handle returned can be joined
+    pub(super) fn stop(mut self, device: &wgpu::Device) -> JoinHandle<()> {
+        self.collect(device, true);
+        if self.dropped > 0 {
+            log::warn!(
+                "capture to {} dropped {} of {} frames, which its timestamps leave out",
+                self.dir.display(),
+                self.dropped,
+                self.copied + self.dropped
+            );
+        }
+        self.writer
+    }
+}
+
+/// Writes frames until they stop coming, or until one fails to write
+fn write_frames(
+    dir: &Path,
+    (width, height): (u32, u32),
+    frames: Receiver<Frame>,
+    mut timestamps: BufWriter<File>,
+    failed: &AtomicBool,
+) {
+    let mut written = 0;
+    for frame in frames {
+        let path = dir.join(format!("frame-{:05}.png", frame.number));
+        let result = encode_png(width, height, &frame.pixels)
+            .and_then(|png| std::fs::write(&path, png))
+            .and_then(|()| {
+                let seconds = frame.time.as_secs_f64();
+                writeln!(timestamps, "{},{seconds:.6}", frame.number)
+            })
+            .and_then(|()| timestamps.flush());
+        if let Err(err) = result {
+            log::warn!(
+                "capture stopped after {written} frames, failed to write {}: {err}",
+                path.display()
+            );
+            failed.store(true, Ordering::Relaxed);
+            return;
+        }
+        written += 1;
+    }
+    log::info!("captured {written} frames to {}", dir.display());
+}
+
+/// Rows of rgb as a png, compressed quickly as the game's flat colours shrink well anyway
+fn encode_png(width: u32, height: u32, pixels: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(png::Compression::Fast);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(pixels)?;
+    writer.finish()?;
+    Ok(png)
+}
+
+#[test]
+fn pngs_hold_the_pixels_they_were_given() {
+    // flat runs, noise, and rows repeating the one above, as frames have
+    let (width, height) = (37, 6);
+    let mut pixels = Vec::new();
+    let mut seed = 7u32;
+    for y in 0..height {
+        for x in 0..width {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let noise = (seed >> 16) as u8;
+            pixels.extend(match (x, y) {
+                (0..=11, _) => [20, 30, 200],
+                (12..=23, 0..=2) => [noise, noise / 2, 255 - noise],
+                (12..=23, _) => [x as u8 * 9, 50, 50],
+                _ => [y as u8, 255, noise % 3],
+            });
+        }
+    }
+    let png = encode_png(width, height, &pixels).unwrap();
+
+    let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+    let mut decoded = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut decoded).unwrap();
+    assert_eq!((info.width, info.height), (width, height));
+    assert_eq!(info.color_type, png::ColorType::Rgb);
+    assert_eq!(&decoded[..info.buffer_size()], pixels);
+    // and smaller than the pixels alone, for all the noise
+    assert!(png.len() < pixels.len(), "{} bytes", png.len());
+}
+
+#[test]
+fn captures_stop_at_the_first_frame_that_fails_to_write() {
+    let dir = std::env::temp_dir().join(format!("wgpu-fun-{}-capture", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    // where the second frame would go, so it can't
+    std::fs::create_dir_all(dir.join("frame-00001.png")).unwrap();
+    let timestamps = BufWriter::new(File::create(dir.join(TIMESTAMPS)).unwrap());
+
+    let (send, frames) = channel::unbounded();
+    for number in 0..3 {
+        let time = Duration::from_millis(number as u64 * 16);
+        let pixels = vec![255; 2 * 2 * 3];
+        send.send(Frame {
+            number,
+            time,
+            pixels,
+        })
+        .unwrap();
+    }
+    drop(send);
+    let failed = AtomicBool::new(false);
+    write_frames(&dir, (2, 2), frames, timestamps, &failed);
+
+    assert!(failed.load(Ordering::Relaxed));
+    assert!(dir.join("frame-00000.png").is_file());
+    assert!(!dir.join("frame-00002.png").exists());
+    let timestamps = std::fs::read_to_string(dir.join(TIMESTAMPS)).unwrap();
+    assert_eq!(timestamps, "0,0.000000\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,140 @@
+//! Gizmos for the debug overlay, pushed into the same mesh as everything else so dozens of them
+//! cost no more draws than one
+
+use super::buffer::{MeshBuilder, Vertex};
+use cgmath::{InnerSpace, Vector2};
+use std::f32::consts::TAU;
+
+/// How thick the lines of gizmos without a thickness of their own are
+pub const GIZMO_THICKNESS: f32 = 0.01;
+
+impl MeshBuilder {
+    /// Pushes a line from `from` to `to` with a triangular head `head_size` long at `to`, or
+    /// just a dot at `to` when there's no line to point along
+    pub fn push_arrow(
+        &mut self,
+        from: Vector2<f32>,
+        to: Vector2<f32>,
+        thickness: f32,
+        head_size: f32,
+        color: [f32; 4],
+    ) {
+        let direction = to - from;
+        let length = direction.magnitude();
+        if length <= f32::EPSILON {
+            let half = Vector2::new(thickness, thickness);
+            self.push_rect(to - half, to + half, color);
+            return;
+        }
+
+        // never longer than the arrow, so short ones are all head rather than pointing backwards
+        let along = direction / length * head_size.min(length);
+        let across = Vector2::new(-along.y, along.x) / 1.5;
+        let base = to - along;
+        self.push_segment(from, base, thickness, color);
+        self.push(
+            [to, base + across, base - across].map(|v| Vertex {
+                position: v.into(),
+                color,
+            }),
+            [0, 1, 2],
+        )
+    }
+
+    /// Pushes a diagonal cross `size` across, marking a point without hiding it
+    pub fn push_cross(&mut self, center: Vector2<f32>, size: f32, color: [f32; 4]) {
+        let arm = Vector2::new(size, size) / 2.;
+        let other = Vector2::new(arm.x, -arm.y);
+        self.push_segment(center - arm, center + arm, GIZMO_THICKNESS, color);
+        self.push_segment(center - other, center + other, GIZMO_THICKNESS, color);
+    }
+
+    /// Pushes the outline of a circle with a tick from its centre out along its radius, so it
+    /// reads as a radius rather than only a ring
+    pub fn push_circle_gizmo(&mut self, center: Vector2<f32>, radius: f32, color: [f32; 4]) {
+        const SEGMENTS: usize = 24;
+
+        self.push_arc(center, radius, [0., TAU], GIZMO_THICKNESS, SEGMENTS, color);
+        self.push_segment(
+            center,
+            center + Vector2::new(radius, 0.),
+            GIZMO_THICKNESS,
+            color,
+        );
+    }
+}
+
+#[test]
+fn gizmos_push_a_fixed_number_of_vertices() {
+    let pushed = |push: &dyn Fn(&mut MeshBuilder)| {
+        let mut mesh = MeshBuilder::default();
+        push(&mut mesh);
+        (mesh.vertices.len(), mesh.indices.len())
+    };
+    let origin = Vector2::new(0., 0.);
+
+    // a segment and a head
+    let arrow = |to| move |mesh: &mut MeshBuilder| mesh.push_arrow(origin, to, 0.1, 0.3, [1.; 4]);
+    assert_eq!(pushed(&arrow(Vector2::new(1., 0.))), (7, 9));
+    // just a dot
+    assert_eq!(pushed(&arrow(origin)), (4, 6));
+    assert_eq!(
+        pushed(&|mesh| mesh.push_cross(origin, 0.2, [1.; 4])),
+        (8, 12)
+    );
+    // a ring of 24 quads and the tick
+    assert_eq!(
+        pushed(&|mesh| mesh.push_circle_gizmo(origin, 0.5, [1.; 4])),
+        (50 + 4, 24 * 6 + 6)
+    );
+}
+
+#[test]
+fn arrow_heads_point_at_the_end_and_fit_short_arrows() {
+    let mut mesh = MeshBuilder::default();
+    mesh.push_arrow(
+        Vector2::new(0., 0.),
+        Vector2::new(1., 0.),
+        0.1,
+        0.3,
+        [1.; 4],
+    );
+    assert_eq!(mesh.vertices[4].position, [1., 0.]);
+    assert!((mesh.vertices[5].position[0] - 0.7).abs() < 1e-6);
+
+    let mut short = MeshBuilder::default();
+    short.push_arrow(
+        Vector2::new(0., 0.),
+        Vector2::new(0.1, 0.),
+        0.02,
+        0.3,
+        [1.; 4],
+    );
+    // all head, its base back at the start rather than behind it
+    assert_eq!(short.vertices.len(), 3);
+    assert!(short.vertices[1].position[0].abs() < 1e-6);
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn gizmos_render_like_their_golden_image() {
+    let mut mesh = MeshBuilder::default();
+    mesh.push_arrow(
+        Vector2::new(-0.6, -0.4),
+        Vector2::new(0.2, 0.3),
+        GIZMO_THICKNESS * 2.,
+        0.1,
+        [1., 0., 1., 1.],
+    );
+    mesh.push_arrow(
+        Vector2::new(0.6, -0.6),
+        Vector2::new(0.6, -0.6),
+        GIZMO_THICKNESS,
+        0.1,
+        [1., 1., 1., 1.],
+    );
+    mesh.push_cross(Vector2::new(-0.5, 0.5), 0.2, [1., 0., 0., 1.]);
+    mesh.push_circle_gizmo(Vector2::new(0.4, 0.2), 0.3, [0., 1., 0., 1.]);
+
+    super::headless::check("gizmos", &mesh, 0., 1.);
+}
@@ -0,0 +1,176 @@
+//! A tiny stroke font pushed straight into the mesh, so text zooms and turns with the world and
+//! stays sharp however close the camera gets
+
+use super::buffer::MeshBuilder;
+use cgmath::Vector2;
+
+/// Lines drawn through points on a grid `WIDTH` wide and `HEIGHT` tall, from the bottom left
+type Glyph = &'static [&'static [[i8; 2]]];
+
+const WIDTH: f32 = 4.;
+const HEIGHT: f32 = 6.;
+/// From one glyph's left to the next one's, in grid units
+const ADVANCE: f32 = 6.;
+/// Stroke width in grid units
+const STROKE: f32 = 0.6;
+
+/// The outline of 0, O and Q, with its corners cut off so it isn't taken for a box
+const RING: &[[i8; 2]] = &[
+    [1, 0],
+    [3, 0],
+    [4, 1],
+    [4, 5],
+    [3, 6],
+    [1, 6],
+    [0, 5],
+    [0, 1],
+    [1, 0],
+];
+
+/// Drawn for any character the font hasn't got
+const BOX: Glyph = &[&[[0, 0], [4, 0], [4, 6], [0, 6], [0, 0]]];
+
+/// The strokes making up `c`, with lowercase letters drawn as capitals, or `None` if there aren't
+/// any
+fn glyph(c: char) -> Option<Glyph> {
+    Some(match c.to_ascii_uppercase() {
+        ' ' => &[],
+        '0' => &[RING, &[[0, 1], [4, 5]]],
+        '1' => &[&[[1, 5], [2, 6], [2, 0]], &[[1, 0], [3, 0]]],
+        '2' => &[&[[0, 6], [4, 6], [4, 3], [0, 3], [0, 0], [4, 0]]],
+        '3' => &[&[[0, 6], [4, 6], [4, 0], [0, 0]], &[[1, 3], [4, 3]]],
+        '4' => &[&[[0, 6], [0, 3], [4, 3]], &[[4, 6], [4, 0]]],
+        '5' => &[&[[4, 6], [0, 6], [0, 3], [4, 3], [4, 0], [0, 0]]],
+        '6' => &[&[[4, 6], [0, 6], [0, 0], [4, 0], [4, 3], [0, 3]]],
+        '7' => &[&[[0, 6], [4, 6], [1, 0]]],
+        '8' => &[&[[0, 0], [4, 0], [4, 6], [0, 6], [0, 0]], &[[0, 3], [4, 3]]],
+        '9' => &[&[[4, 3], [0, 3], [0, 6], [4, 6], [4, 0], [0, 0]]],
+        'A' => &[&[[0, 0], [0, 4], [2, 6], [4, 4], [4, 0]], &[[0, 3], [4, 3]]],
+        'B' => &[
+            &[[0, 0], [0, 6], [3, 6], [4, 5], [4, 4], [3, 3], [0, 3]],
+            &[[3, 3], [4, 2], [4, 1], [3, 0], [0, 0]],
+        ],
+        'C' => &[&[[4, 6], [0, 6], [0, 0], [4, 0]]],
+        'D' => &[&[[0, 0], [0, 6], [2, 6], [4, 4], [4, 2], [2, 0], [0, 0]]],
+        'E' => &[&[[4, 6], [0, 6], [0, 0], [4, 0]], &[[0, 3], [3, 3]]],
+        'F' => &[&[[4, 6], [0, 6], [0, 0]], &[[0, 3], [3, 3]]],
+        'G' => &[&[[4, 6], [0, 6], [0, 0], [4, 0], [4, 3], [2, 3]]],
+        'H' => &[&[[0, 6], [0, 0]], &[[4, 6], [4, 0]], &[[0, 3], [4, 3]]],
+        'I' => &[&[[1, 6], [3, 6]], &[[2, 6], [2, 0]], &[[1, 0], [3, 0]]],
+        'J' => &[&[[4, 6], [4, 0], [0, 0], [0, 2]]],
+        'K' => &[&[[0, 6], [0, 0]], &[[4, 6], [0, 3], [4, 0]]],
+        'L' => &[&[[0, 6], [0, 0], [4, 0]]],
+        'M' => &[&[[0, 0], [0, 6], [2, 3], [4, 6], [4, 0]]],
+        'N' => &[&[[0, 0], [0, 6], [4, 0], [4, 6]]],
+        'O' => &[RING],
+        'P' => &[&[[0, 0], [0, 6], [4, 6], [4, 3], [0, 3]]],
+        'Q' => &[RING, &[[2, 2], [4, -1]]],
+        'R' => &[&[[0, 0], [0, 6], [4, 6], [4, 3], [0, 3], [4, 0]]],
+        'S' => &[&[
+            [4, 5],
+            [3, 6],
+            [1, 6],
+            [0, 5],
+            [0, 4],
+            [1, 3],
+            [3, 3],
+            [4, 2],
+            [4, 1],
+            [3, 0],
+            [1, 0],
+            [0, 1],
+        ]],
+        'T' => &[&[[0, 6], [4, 6]], &[[2, 6], [2, 0]]],
+        'U' => &[&[[0, 6], [0, 0], [4, 0], [4, 6]]],
+        'V' => &[&[[0, 6], [2, 0], [4, 6]]],
+        'W' => &[&[[0, 6], [1, 0], [2, 3], [3, 0], [4, 6]]],
+        'X' => &[&[[0, 6], [4, 0]], &[[0, 0], [4, 6]]],
+        'Y' => &[&[[0, 6], [2, 3], [4, 6]], &[[2, 3], [2, 0]]],
+        'Z' => &[&[[0, 6], [4, 6], [0, 0], [4, 0]]],
+        '.' => &[&[[2, 0]]],
+        ',' => &[&[[2, 1], [1, -1]]],
+        ':' => &[&[[2, 1]], &[[2, 4]]],
+        '!' => &[&[[2, 6], [2, 2]], &[[2, 0]]],
+        '?' => &[
+            &[[0, 5], [1, 6], [3, 6], [4, 5], [4, 4], [2, 3], [2, 2]],
+            &[[2, 0]],
+        ],
+        '\'' => &[&[[2, 6], [2, 4]]],
+        '-' => &[&[[1, 3], [3, 3]]],
+        '+' => &[&[[0, 3], [4, 3]], &[[2, 1], [2, 5]]],
+        '=' => &[&[[0, 2], [4, 2]], &[[0, 4], [4, 4]]],
+        '/' => &[&[[0, 0], [4, 6]]],
+        '(' => &[&[[3, 6], [1, 4], [1, 2], [3, 0]]],
+        ')' => &[&[[1, 6], [3, 4], [3, 2], [1, 0]]],
+        _ => return None,
+    })
+}
+
+impl MeshBuilder {
+    /// Pushes `text` on one line from `origin`, the bottom left of its first character, with
+    /// capitals `height` tall. Characters the font hasn't got are drawn as boxes.
+    pub fn push_text(&mut self, text: &str, origin: Vector2<f32>, height: f32, color: [f32; 4]) {
+        let unit = height / HEIGHT;
+        let thickness = STROKE * unit;
+        for (i, c) in text.chars().enumerate() {
+            let left = origin + Vector2::new(i as f32 * ADVANCE * unit, 0.);
+            let point = |&[x, y]: &[i8; 2]| left + Vector2::new(x as f32, y as f32) * unit;
+            for stroke in glyph(c).unwrap_or(BOX).iter() {
+                for pair in stroke.windows(2) {
+                    self.push_segment(point(&pair[0]), point(&pair[1]), thickness, color);
+                }
+                // a square over each point, filling the notches where strokes meet and making
+                // the single points of dots
+                let half = Vector2::new(thickness, thickness) / 2.;
+                for corner in stroke.iter().map(point) {
+                    self.push_rect(corner - half, corner + half, color);
+                }
+            }
+        }
+    }
+}
+
+/// How wide `text` is pushed with capitals `height` tall, for centring or right aligning it
+pub fn text_width(text: &str, height: f32) -> f32 {
+    match text.chars().count() {
+        0 => 0.,
+        count => ((count - 1) as f32 * ADVANCE + WIDTH) * height / HEIGHT,
+    }
+}
+
+#[test]
+fn every_character_promised_has_a_glyph_inside_its_cell() {
+    let characters = ('0'..='9').chain('A'..='Z').chain(".,:!?'-+=/()".chars());
+    for c in characters {
+        let glyph = glyph(c).unwrap_or_else(|| panic!("no glyph for {c:?}"));
+        assert!(!glyph.is_empty(), "{c:?} draws nothing");
+        for &[x, y] in glyph.iter().flat_map(|stroke| stroke.iter()) {
+            // only descenders go below the line
+            assert!(
+                (0..=4).contains(&x) && (-1..=6).contains(&y),
+                "{c:?} at {x}, {y}"
+            );
+        }
+    }
+    assert_eq!(glyph('a'), glyph('A'));
+}
+
+#[test]
+fn unknown_characters_are_boxes_and_spaces_only_advance() {
+    let pushed = |text: &str| {
+        let mut mesh = MeshBuilder::default();
+        mesh.push_text(text, Vector2::new(0., 0.), 1., [1.; 4]);
+        mesh
+    };
+    assert_eq!(pushed("~").vertex_count(), pushed("é").vertex_count());
+    assert_eq!(pushed("  ").vertex_count(), 0);
+    // a box is four strokes between five corners, one of them twice
+    assert_eq!(pushed("~").vertex_count(), (4 + 5) * 4);
+
+    // the second character starts an advance along from the first
+    let spaced = pushed(" I");
+    let first = pushed("I");
+    let offset = spaced.vertices[0].position[0] - first.vertices[0].position[0];
+    assert!((offset - ADVANCE / HEIGHT).abs() < 1e-6);
+    assert!((text_width("HI", 1.) - 10. / 6.).abs() < 1e-6);
+}
@@ -0,0 +1,1044 @@
+//! Renders to an offscreen texture, for benchmarking without a window and for tests that check
+//! the pixels against golden images
+
+use super::background::BackgroundKind;
+use super::buffer::{DrawItem, Mesh, MeshBuilder, ParticleInstance};
+use super::cache::{PipelineKey, SamplerDesc, ShaderId, TextureId};
+use super::post::{Crt, Effect, PostProcess};
+use super::{Capabilities, Pipeline, RenderReport, RendererError, Target};
+#[cfg(test)]
+use futures_lite::future;
+#[cfg(test)]
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// Width and height of rendered images, a whole number of the 256 byte rows a texture copies in
+pub const SIZE: u32 = 256;
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+/// How far any channel of a pixel may be from the golden's, for differences between gpus
+#[cfg(test)]
+const TOLERANCE: u8 = 2;
+
+/// A device drawing to a `SIZE` square texture in place of a window
+pub struct Headless {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub adapter: wgpu::AdapterInfo,
+    pub capabilities: Capabilities,
+    pipeline: Pipeline,
+    render_pipeline: PipelineKey,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    depth_stencil: wgpu::TextureView,
+    post: Option<PostProcess>,
+    background: BackgroundKind,
+    /// Where the texture is copied to be read back
+    output: wgpu::Buffer,
+}
+
+impl Headless {
+    const EXTENT: wgpu::Extent3d = wgpu::Extent3d {
+        width: SIZE,
+        height: SIZE,
+        depth_or_array_layers: 1,
+    };
+
+    pub async fn new(require_hardware: bool) -> Result<Self, RendererError> {
+        let (_, adapter) = super::request_adapter(require_hardware).await?;
+        let capabilities = Capabilities::of(adapter.features());
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    features: capabilities.features(),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .map_err(RendererError::Device)?;
+
+        let mut pipeline = Pipeline::new(&device);
+        let render_pipeline = Pipeline::key(FORMAT, 1);
+        pipeline.prepare(&device, render_pipeline);
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Headless Texture"),
+            size: Self::EXTENT,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let depth_stencil = super::create_depth_stencil(&device, SIZE, SIZE, 1);
+        let output = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Headless Output"),
+            size: (SIZE * SIZE * 4) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Ok(Self {
+            device,
+            queue,
+            adapter: adapter.get_info(),
+            capabilities,
+            pipeline,
+            render_pipeline,
+            texture,
+            view,
+            depth_stencil,
+            post: None,
+            background: BackgroundKind::default(),
+            output,
+        })
+    }
+
+    /// Records drawing the mesh's items with the particles over those before `overlay`,
+    /// indirectly if asked and the device can, copying the texture out afterwards if
+    /// `read_back`, along with what was drawn
+    #[allow(clippy::too_many_arguments)]
+    fn encode(
+        &mut self,
+        mesh: &Mesh,
+        items: &[DrawItem],
+        overlay: usize,
+        indirect: bool,
+        particles: &[ParticleInstance],
+        camera_x: f32,
+        zoom: f32,
+        read_back: bool,
+    ) -> (wgpu::CommandBuffer, RenderReport) {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Headless Encoder"),
+            });
+        let report = self.pipeline.draw(
+            &self.device,
+            &self.queue,
+            &mut encoder,
+            self.render_pipeline,
+            Target {
+                view: self.post.as_ref().map_or(&self.view, PostProcess::scene),
+                resolve: None,
+                depth_stencil: &self.depth_stencil,
+            },
+            mesh,
+            items,
+            overlay,
+            indirect && self.capabilities.multi_draw_indirect,
+            particles,
+            // square, so as wide as it's tall
+            [camera_x, zoom, 1.],
+            self.background,
+            // still, so pictures of it come out the same
+            0.,
+        );
+        if let Some(post) = &self.post {
+            post.apply(&mut encoder, &self.view);
+        }
+        if read_back {
+            encoder.copy_texture_to_buffer(
+                self.texture.as_image_copy(),
+                wgpu::ImageCopyBuffer {
+                    buffer: &self.output,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(SIZE * 4),
+                        rows_per_image: None,
+                    },
+                },
+                Self::EXTENT,
+            );
+        }
+        (encoder.finish(), report)
+    }
+
+    /// Draws through a crt filter from now on, or straight to the texture without
+    pub fn set_crt(&mut self, crt: Option<Crt>) {
+        self.post = crt.map(|crt| {
+            PostProcess::new(
+                &self.device,
+                &self.queue,
+                FORMAT,
+                (SIZE, SIZE),
+                Effect::Crt(crt),
+            )
+        });
+    }
+
+    /// Uploads a texture for draw items to be textured with, as the renderer's `create_texture`
+    pub fn create_texture(
+        &mut self,
+        width: u32,
+        height: u32,
+        rgba: &[u8],
+        sampler: SamplerDesc,
+    ) -> TextureId {
+        self.pipeline
+            .textures
+            .create(&self.device, &self.queue, (width, height), rgba, sampler)
+    }
+
+    /// What's drawn behind the scene from now on
+    pub fn set_background(&mut self, background: BackgroundKind) {
+        self.background = background;
+    }
+
+    /// Registers a procedural background, as the renderer's `register_background`
+    pub fn register_background(&mut self, source: &str, entry_point: &str) -> ShaderId {
+        self.pipeline
+            .backgrounds
+            .register(&self.device, source, entry_point)
+    }
+
+    /// Submits the commands and waits for the gpu to finish them
+    fn submit(&self, commands: wgpu::CommandBuffer) {
+        self.queue.submit(std::iter::once(commands));
+        self.device.poll(wgpu::Maintain::Wait);
+    }
+
+    /// Uploads the builder into `mesh`, reusing its buffers, or into a new mesh if there isn't one
+    pub fn write<'a>(&self, mesh: &'a mut Option<Mesh>, builder: &MeshBuilder) -> &'a Mesh {
+        match mesh {
+            Some(mesh) => {
+                mesh.write(&self.device, &self.queue, builder);
+                mesh
+            }
+            None => mesh.insert(builder.build(&self.device)),
+        }
+    }
+
+    /// Draws the mesh then the particles and waits for the gpu to finish, so timing this times
+    /// the gpu's work too. Reports what was drawn, timing only how long the commands took to
+    /// record, and leaving out the mesh, which was uploaded by `write`.
+    pub fn draw(
+        &mut self,
+        mesh: &Mesh,
+        particles: &[ParticleInstance],
+        camera_x: f32,
+        zoom: f32,
+    ) -> RenderReport {
+        let start = Instant::now();
+        let (commands, report) = self.encode(
+            mesh,
+            &[mesh.whole()],
+            1,
+            false,
+            particles,
+            camera_x,
+            zoom,
+            false,
+        );
+        let encode_time = start.elapsed();
+        self.submit(commands);
+        RenderReport {
+            encode_time,
+            ..report
+        }
+    }
+
+    /// Draws the mesh as separate `items`, all by one indirect call if `indirect` and the device
+    /// can, and waits for the gpu to finish. Reports what was drawn as `draw` does.
+    pub fn draw_items(
+        &mut self,
+        mesh: &Mesh,
+        items: &[DrawItem],
+        indirect: bool,
+        camera_x: f32,
+        zoom: f32,
+    ) -> RenderReport {
+        let start = Instant::now();
+        let (commands, report) = self.encode(
+            mesh,
+            items,
+            items.len(),
+            indirect,
+            &[],
+            camera_x,
+            zoom,
+            false,
+        );
+        let encode_time = start.elapsed();
+        self.submit(commands);
+        RenderReport {
+            encode_time,
+            ..report
+        }
+    }
+
+    /// Draws the mesh's items then the particles and reads back the rgba pixels
+    #[cfg(test)]
+    fn pixels(
+        &mut self,
+        mesh: &Mesh,
+        items: &[DrawItem],
+        indirect: bool,
+        particles: &[ParticleInstance],
+        camera_x: f32,
+        zoom: f32,
+    ) -> Vec<u8> {
+        self.pixels_under(
+            mesh,
+            items,
+            items.len(),
+            indirect,
+            particles,
+            camera_x,
+            zoom,
+        )
+    }
+
+    /// Like `pixels`, but with the items from `overlay` on drawn over the particles, as the hud
+    /// is
+    #[cfg(test)]
+    #[allow(clippy::too_many_arguments)]
+    fn pixels_under(
+        &mut self,
+        mesh: &Mesh,
+        items: &[DrawItem],
+        overlay: usize,
+        indirect: bool,
+        particles: &[ParticleInstance],
+        camera_x: f32,
+        zoom: f32,
+    ) -> Vec<u8> {
+        let (commands, _) = self.encode(
+            mesh, items, overlay, indirect, particles, camera_x, zoom, true,
+        );
+        self.submit(commands);
+        let slice = self.output.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        self.device.poll(wgpu::Maintain::Wait);
+        let pixels = slice.get_mapped_range().to_vec();
+        self.output.unmap();
+        pixels
+    }
+}
+
+/// The renderer for a test of `what`, which fails without an adapter since gpu tests only run
+/// when asked for with `WGPU_FUN_GPU_TESTS`
+#[cfg(test)]
+pub fn for_test(what: &str) -> Headless {
+    future::block_on(Headless::new(false))
+        .unwrap_or_else(|err| panic!("{what} needs an adapter: {err}"))
+}
+
+/// Renders the mesh and compares it with `goldens/<name>.png`, writing the golden instead when
+/// `WGPU_FUN_BLESS` is set
+#[cfg(test)]
+pub fn check(name: &str, mesh: &MeshBuilder, camera_x: f32, zoom: f32) {
+    let mut headless = for_test(&format!("golden image {name}"));
+    let mesh = mesh.build(&headless.device);
+    let pixels = headless.pixels(&mesh, &[mesh.whole()], false, &[], camera_x, zoom);
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("goldens")
+        .join(format!("{name}.png"));
+    if std::env::var_os("WGPU_FUN_BLESS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, encode(&pixels).unwrap()).unwrap();
+        return;
+    }
+
+    let golden = std::fs::read(&path)
+        .ok()
+        .and_then(|bytes| decode(&bytes))
+        .unwrap_or_else(|| {
+            panic!(
+                "no golden image at {}, run with WGPU_FUN_BLESS=1 to make it",
+                path.display()
+            )
+        });
+    let worst = pixels
+        .iter()
+        .zip(&golden)
+        .map(|(a, b)| a.abs_diff(*b))
+        .max()
+        .unwrap_or(0);
+    assert!(
+        golden.len() == pixels.len() && worst <= TOLERANCE,
+        "{name} differs from its golden image by up to {worst} in a channel"
+    );
+}
+
+/// The pixels as a png, which anything can show when a golden needs looking at
+#[cfg(test)]
+fn encode(pixels: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, SIZE, SIZE);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(pixels)?;
+    writer.finish()?;
+    Ok(png)
+}
+
+/// The pixels of a png written by `encode`, if it is one of the right size
+#[cfg(test)]
+fn decode(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut reader = png::Decoder::new(bytes).read_info().ok()?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).ok()?;
+    let expected = (SIZE, SIZE, png::ColorType::Rgba, png::BitDepth::Eight);
+    pixels.truncate(info.buffer_size());
+    ((info.width, info.height, info.color_type, info.bit_depth) == expected).then_some(pixels)
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn split_draws_render_the_same_either_way() {
+    let mut headless = for_test("split draws");
+    // overlapping, so drawing anything out of order would show
+    let mut builder = MeshBuilder::default();
+    for i in 0..40 {
+        let x = i as f32 * 0.04 - 0.8;
+        let shade = i as f32 / 40.;
+        builder.push_rect(
+            cgmath::vec2(x, -0.5),
+            cgmath::vec2(x + 0.2, 0.5),
+            [shade, 1. - shade, 0.5, 0.6],
+        );
+    }
+    let mesh = builder.build(&headless.device);
+    let items = mesh.split(25);
+
+    let whole = headless.pixels(&mesh, &[mesh.whole()], false, &[], 0., 1.);
+    assert!(whole.iter().any(|&channel| channel != 0 && channel != 255));
+    assert_eq!(headless.pixels(&mesh, &items, false, &[], 0., 1.), whole);
+    // falling back to a loop where it can't, which has to match all the same
+    assert_eq!(headless.pixels(&mesh, &items, true, &[], 0., 1.), whole);
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn arena_meshes_render_as_one_mesh_would() {
+    let mut headless = for_test("arena meshes");
+    let rect = |i: usize| {
+        let mut builder = MeshBuilder::default();
+        let x = i as f32 * 0.15 - 0.8;
+        builder.push_rect(
+            cgmath::vec2(x, -0.4),
+            cgmath::vec2(x + 0.3, 0.4),
+            [i as f32 / 10., 0.5, 1. - i as f32 / 10., 0.7],
+        );
+        builder
+    };
+    let mut whole = MeshBuilder::default();
+    (0..10).for_each(|i| whole.append(&rect(i)));
+    let whole = whole.build(&headless.device);
+    let expected = headless.pixels(&whole, &[whole.whole()], false, &[], 0., 1.);
+
+    // too small for even one, so it grows with meshes already in it more than once
+    let mut arena = super::arena::Arena::new(&headless.device, 3, 3);
+    let (device, queue) = (&headless.device, &headless.queue);
+    let gone = arena.alloc(device, queue, &rect(0));
+    let mut handles: Vec<_> = (1..5)
+        .map(|i| arena.alloc(device, queue, &rect(i)))
+        .collect();
+    // the first mesh's space is reused, for the frame's meshes that go after it
+    arena.free(gone);
+    handles.insert(0, arena.alloc(device, queue, &rect(0)));
+    handles.extend((5..10).map(|i| arena.alloc_frame(device, queue, &rect(i))));
+    let items: Vec<_> = handles.iter().map(|handle| handle.item()).collect();
+
+    assert_eq!(
+        headless.pixels(arena.mesh(), &items, false, &[], 0., 1.),
+        expected
+    );
+    assert_eq!(
+        headless.pixels(arena.mesh(), &items, true, &[], 0., 1.),
+        expected
+    );
+
+    // a frame's whole mesh split into items of its own, as the renderer draws a draw list, with
+    // an odd number of indices ahead of it
+    arena.reset_frame();
+    let mut odd = MeshBuilder::default();
+    odd.push(
+        [0., 0.5].map(|x| super::buffer::Vertex {
+            position: [x, 2.],
+            color: [0.; 4],
+        }),
+        [0, 1, 0],
+    );
+    arena.alloc_frame(&headless.device, &headless.queue, &odd);
+    let mut frame = MeshBuilder::default();
+    (0..10).for_each(|i| frame.append(&rect(i)));
+    let handle = arena.alloc_frame(&headless.device, &headless.queue, &frame);
+    let items: Vec<_> = whole
+        .split(4)
+        .iter()
+        .map(|item| handle.offset(item))
+        .collect();
+    assert_eq!(
+        headless.pixels(arena.mesh(), &items, false, &[], 0., 1.),
+        expected
+    );
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn each_draw_is_moved_by_its_own_transform() {
+    use super::buffer::Transform2D;
+    use cgmath::vec2;
+
+    let mut headless = for_test("transforms");
+    let mut builder = MeshBuilder::default();
+    builder.push_rect(vec2(0., 0.), vec2(1., 1.), [1.; 4]);
+    let mesh = builder.build(&headless.device);
+    let at = |transform| DrawItem {
+        transform,
+        ..mesh.whole()
+    };
+    let quads = [
+        at(Transform2D::new(vec2(-0.9, -0.9), 0., vec2(0.2, 0.2))),
+        at(Transform2D::new(vec2(0.5, 0.5), 0., vec2(0.3, 0.1))),
+        // a quarter turn swings it round to the left of where it's moved to
+        at(Transform2D::new(
+            vec2(0.1, -0.5),
+            std::f32::consts::FRAC_PI_2,
+            vec2(0.2, 0.2),
+        )),
+    ];
+
+    let check = |pixels: &[u8]| {
+        let lit = |x: f32, y: f32| {
+            let column = ((x + 1.) / 2. * SIZE as f32) as usize;
+            let row = ((1. - y) / 2. * SIZE as f32) as usize;
+            pixels[(row * SIZE as usize + column) * 4] == 255
+        };
+        assert!(lit(-0.8, -0.8) && lit(0.65, 0.55) && lit(0., -0.4));
+        assert!(!lit(0., 0.) && !lit(0.2, -0.4) && !lit(0.65, 0.65) && !lit(-0.6, -0.8));
+        // nothing lit beyond the three of them
+        let count = pixels.chunks(4).filter(|pixel| pixel[0] == 255).count() as f32;
+        let expected = (0.04 + 0.03 + 0.04) / 4. * (SIZE * SIZE) as f32;
+        assert!((count - expected).abs() < expected * 0.05, "{count} lit");
+    };
+    check(&headless.pixels(&mesh, &quads, false, &[], 0., 1.));
+
+    // with more draws than the transforms have room for to start with, most of them off screen
+    let offscreen = at(Transform2D::new(vec2(5., 5.), 0., vec2(1., 1.)));
+    let mut items = vec![offscreen; 100];
+    items.extend(quads);
+    check(&headless.pixels(&mesh, &items, false, &[], 0., 1.));
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn steady_frames_make_no_pipelines_or_bind_groups() {
+    let mut headless = for_test("gpu cache");
+    let mut builder = MeshBuilder::default();
+    builder.push_rect(cgmath::vec2(0., 0.), cgmath::vec2(0.5, 0.5), [1.; 4]);
+    let mesh = builder.build(&headless.device);
+
+    // the pipeline was made with the renderer, so only the camera's bind group is left to make
+    let start = crate::instrument::counts();
+    headless.draw(&mesh, &[], 0., 1.);
+    let warmed_up = crate::instrument::counts();
+    assert_eq!(warmed_up.since(&start).cache_misses, 1);
+
+    for camera_x in [0.1, 0.2, 0.3] {
+        headless.draw(&mesh, &[], camera_x, 1.);
+    }
+    let steady = crate::instrument::counts().since(&warmed_up);
+    assert_eq!(steady.cache_misses, 0);
+    assert_eq!(steady.cache_hits, 3 * 2);
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn instanced_particles_render_as_meshed_ones_do() {
+    use rand::SeedableRng;
+
+    let mut headless = for_test("instanced particles");
+    let mut system = crate::particles::ParticleSystem::with_limit(200);
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    system.spawn_burst(cgmath::vec2(0., -0.3), cgmath::vec2(0., 1.), 200, &mut rng);
+    system.update(0.2);
+
+    let mut builder = MeshBuilder::default();
+    builder.push_rect(
+        cgmath::vec2(-1., -1.),
+        cgmath::vec2(1., 1.),
+        [0.2, 0.1, 0.3, 1.],
+    );
+    let background = builder.build(&headless.device);
+    system.push(&mut builder);
+    let meshed = builder.build(&headless.device);
+    let mut instances = Vec::new();
+    system.render_instances(&mut instances);
+
+    assert_eq!(
+        headless.pixels(
+            &background,
+            &[background.whole()],
+            false,
+            &instances,
+            0.1,
+            2.
+        ),
+        headless.pixels(&meshed, &[meshed.whole()], false, &[], 0.1, 2.)
+    );
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn particles_go_under_the_hud() {
+    let mut headless = for_test("particles under the hud");
+    let mut builder = MeshBuilder::default();
+    builder.push_rect(
+        cgmath::vec2(-1., -1.),
+        cgmath::vec2(1., 1.),
+        [0., 0., 0.2, 1.],
+    );
+    let hud = builder.indices.len() as u32;
+    builder.push_rect(cgmath::vec2(-0.2, -0.2), cgmath::vec2(0.2, 0.2), [1.; 4]);
+    let mesh = builder.build(&headless.device);
+    let items = [
+        DrawItem {
+            indices: 0..hud,
+            ..DrawItem::default()
+        },
+        DrawItem {
+            indices: hud..mesh.whole().indices.end,
+            ..DrawItem::default()
+        },
+    ];
+    let particles = [ParticleInstance {
+        position: [0., 0.],
+        size: 0.3,
+        color: [1., 0., 0.],
+        alpha: 1.,
+    }];
+    let center = |pixels: &[u8]| {
+        let i = ((SIZE / 2 * SIZE + SIZE / 2) * 4) as usize;
+        pixels[i..i + 4].to_vec()
+    };
+
+    let over = headless.pixels(&mesh, &items, false, &particles, 0., 1.);
+    assert_ne!(center(&over), [255; 4]);
+    for indirect in [false, true] {
+        let under = headless.pixels_under(&mesh, &items, 1, indirect, &particles, 0., 1.);
+        assert_eq!(center(&under), [255; 4]);
+    }
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn nothing_is_drawn_outside_a_circular_mask() {
+    let mut headless = for_test("stencil masks");
+    let mut builder = MeshBuilder::default();
+    builder.push_circle(
+        cgmath::vec2(0., 0.),
+        0.5,
+        super::shapes::SegmentCount::Fixed(64),
+        [1.; 4],
+    );
+    let circle = 0..builder.indices.len() as u32;
+    builder.push_rect(cgmath::vec2(-1., -1.), cgmath::vec2(1., 1.), [1.; 4]);
+    let mesh = builder.build(&headless.device);
+    let item = |indices| DrawItem {
+        indices,
+        ..DrawItem::default()
+    };
+
+    let mut list = super::mask::DrawList::default();
+    let mask = list.begin_mask(&[item(circle.clone())]);
+    list.push(item(circle.end..mesh.index_count));
+    list.end_mask(mask);
+    let pixels = headless.pixels(&mesh, list.items(), false, &[], 0., 1.);
+
+    let at = |x: f32, y: f32| {
+        let column = ((x + 1.) / 2. * SIZE as f32) as usize;
+        let row = ((1. - y) / 2. * SIZE as f32) as usize;
+        &pixels[(row * SIZE as usize + column) * 4..][..4]
+    };
+    // the mask itself draws nothing, and the square only shows through it
+    assert_eq!(at(0., 0.), [255; 4]);
+    assert_eq!(at(0.3, -0.3), [255; 4]);
+    for (x, y) in [(-0.9, -0.9), (0.6, 0.), (0., -0.6), (0.4, 0.4)] {
+        assert_eq!(at(x, y), [0, 0, 0, 255], "lit outside the mask at {x}, {y}");
+    }
+    let lit = pixels.chunks(4).filter(|pixel| pixel[0] == 255).count() as f32;
+    let expected = std::f32::consts::PI * 0.25 / 4. * (SIZE * SIZE) as f32;
+    assert!((lit - expected).abs() < expected * 0.02, "{lit} lit");
+
+    // with the mask ended, what's drawn after it isn't clipped
+    list.push(item(circle.end..mesh.index_count));
+    let pixels = headless.pixels(&mesh, list.items(), false, &[], 0., 1.);
+    assert!(pixels.chunks(4).all(|pixel| pixel[0] == 255));
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn checkerboards_zoomed_in_show_each_filter() {
+    use super::texture::Texturing;
+    use cgmath::vec2;
+
+    let mut headless = for_test("texture filtering");
+    let checkerboard: Vec<u8> = (0..8 * 8)
+        .flat_map(|i| {
+            let shade = if (i % 8 + i / 8) % 2 == 0 { 255 } else { 0 };
+            [shade, shade, shade, 255]
+        })
+        .collect();
+    let texture = headless.create_texture(8, 8, &checkerboard, SamplerDesc::NEAREST);
+    let mut builder = MeshBuilder::default();
+    builder.push_rect(vec2(-1., -1.), vec2(1., 1.), [1.; 4]);
+    let mesh = builder.build(&headless.device);
+    // each texel 32 pixels across
+    let textured = Texturing::stretched(texture, vec2(-1., -1.), vec2(1., 1.));
+    let item = |texturing| DrawItem {
+        texturing: Some(texturing),
+        ..mesh.whole()
+    };
+    let greys = |pixels: &[u8]| {
+        pixels
+            .chunks(4)
+            .filter(|pixel| pixel[0] > 8 && pixel[0] < 247)
+            .count()
+    };
+
+    // nearest keeps every texel's edges hard, and the top left one is white
+    let nearest = headless.pixels(&mesh, &[item(textured)], false, &[], 0., 1.);
+    assert_eq!(&nearest[..4], [255; 4]);
+    assert_eq!(&nearest[32 * 4..][..4], [0, 0, 0, 255]);
+    assert_eq!(greys(&nearest), 0);
+
+    // the same texture drawn by one item sampling it smoothly blends across every edge
+    let smooth = textured.with_sampler(SamplerDesc::LINEAR);
+    let linear = headless.pixels(&mesh, &[item(smooth)], false, &[], 0., 1.);
+    assert!(
+        greys(&linear) > (SIZE * SIZE / 4) as usize,
+        "{} grey",
+        greys(&linear)
+    );
+
+    // a sampler for each, made once
+    let before = crate::instrument::counts();
+    headless.pixels(&mesh, &[item(textured), item(smooth)], false, &[], 0., 1.);
+    assert_eq!(crate::instrument::counts().since(&before).cache_misses, 0);
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn crt_dims_alternate_rows_and_keeps_the_corners_on_screen() {
+    let mut headless = for_test("crt");
+    let mut builder = MeshBuilder::default();
+    builder.push_rect(cgmath::vec2(-0.5, -0.5), cgmath::vec2(0.5, 0.5), [1.; 4]);
+    // a square in each corner, right up against the edges
+    for [x, y] in [[-1., -1.], [0.95, -1.], [-1., 0.95], [0.95, 0.95]] {
+        builder.push_rect(
+            cgmath::vec2(x, y),
+            cgmath::vec2(x + 0.05, y + 0.05),
+            [1.; 4],
+        );
+    }
+    let mesh = builder.build(&headless.device);
+    let plain = headless.pixels(&mesh, &[mesh.whole()], false, &[], 0., 1.);
+
+    headless.set_crt(Some(Crt::new(f32::INFINITY)));
+    let filtered = headless.pixels(&mesh, &[mesh.whole()], false, &[], 0., 1.);
+    let red = |pixels: &[u8], column: usize, row: usize| pixels[(row * SIZE as usize + column) * 4];
+    let middle = SIZE as usize / 2;
+    // odd rows dimmed, with the middle left as bright as it was
+    assert!(red(&filtered, middle, middle - 1) < red(&filtered, middle, middle));
+    assert_eq!(red(&filtered, middle, middle), red(&plain, middle, middle));
+
+    // each corner's square is squeezed inwards but still shows
+    let (half, size) = (SIZE as usize / 2, SIZE as usize);
+    let quarters = [
+        (0..half, 0..half),
+        (half..size, 0..half),
+        (0..half, half..size),
+        (half..size, half..size),
+    ];
+    for (columns, rows) in quarters {
+        let lit = rows
+            .flat_map(|row| columns.clone().map(move |column| (column, row)))
+            .filter(|&(column, row)| {
+                // away from the middle square
+                column.abs_diff(half) > half / 2 + 8 || row.abs_diff(half) > half / 2 + 8
+            })
+            .any(|(column, row)| red(&filtered, column, row) > 0);
+        assert!(lit, "a corner's square was pushed off screen");
+    }
+
+    // turned off, the scene's drawn straight to the texture again
+    headless.set_crt(None);
+    assert_eq!(
+        headless.pixels(&mesh, &[mesh.whole()], false, &[], 0., 1.),
+        plain
+    );
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn backgrounds_fill_behind_the_scene() {
+    use super::background::{NEBULA, STARFIELD};
+
+    let mut headless = for_test("backgrounds");
+    let mut builder = MeshBuilder::default();
+    builder.push_rect(cgmath::vec2(-0.2, -0.2), cgmath::vec2(0.2, 0.2), [1.; 4]);
+    let mesh = builder.build(&headless.device);
+    let mut draw = |background| {
+        headless.set_background(background);
+        headless.pixels(&mesh, &[mesh.whole()], false, &[], 0., 1.)
+    };
+    let pixel = |pixels: &[u8], column: usize, row: usize| {
+        let i = (row * SIZE as usize + column) * 4;
+        [pixels[i], pixels[i + 1], pixels[i + 2]]
+    };
+    let middle = SIZE as usize / 2;
+
+    // cleared to rather than drawn, looking the same as a gradient of one colour
+    let color = [0.2, 0.4, 0.6];
+    let solid = draw(BackgroundKind::Solid(color));
+    let gradient = draw(BackgroundKind::VerticalGradient(color, color));
+    assert!(solid
+        .iter()
+        .zip(&gradient)
+        .all(|(a, b)| a.abs_diff(*b) <= 1));
+    assert_eq!(pixel(&solid, 0, 0), pixel(&solid, 0, SIZE as usize - 1));
+
+    // the scene loads the background pass's pixels and draws over them
+    let gradient = draw(BackgroundKind::VerticalGradient([0.; 3], [1.; 3]));
+    let (top, bottom) = (
+        pixel(&gradient, 0, 0),
+        pixel(&gradient, 0, SIZE as usize - 1),
+    );
+    assert!(top[0] > 240 && bottom[0] < 15, "{top:?} over {bottom:?}");
+    assert_eq!(pixel(&gradient, middle, middle), [255; 3]);
+
+    let nebula = draw(BackgroundKind::Procedural(NEBULA, [0.; 3]));
+    assert_eq!(pixel(&nebula, middle, middle), [255; 3]);
+    let corners = [(0, 0), (SIZE as usize - 1, 0), (0, 200), (200, 40)];
+    let shades: Vec<_> = corners
+        .iter()
+        .map(|&(column, row)| pixel(&nebula, column, row))
+        .collect();
+    assert!(shades.iter().any(|shade| *shade != shades[0]), "{shades:?}");
+
+    // the colour given, with a scattering of brighter stars
+    let color = [0.1, 0., 0.];
+    let solid = draw(BackgroundKind::Solid(color));
+    let stars = draw(BackgroundKind::Procedural(STARFIELD, color));
+    let lit = solid
+        .chunks(4)
+        .zip(stars.chunks(4))
+        .filter(|(solid, star)| star[0] > solid[0].saturating_add(10))
+        .count();
+    assert!(lit > 0 && lit < (SIZE * SIZE / 50) as usize, "{lit} lit");
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn registered_backgrounds_are_drawn_by_their_id() {
+    let mut headless = for_test("registered backgrounds");
+    let mesh = MeshBuilder::default().build(&headless.device);
+    let mut draw = |background| {
+        headless.set_background(background);
+        headless.pixels(&mesh, &[mesh.whole()], false, &[], 0., 1.)
+    };
+    let solid = draw(BackgroundKind::Solid([0.2, 0.4, 0.6]));
+    // unknown until it's registered, and left black till then
+    let unregistered = draw(BackgroundKind::Procedural(ShaderId::Procedural(9), [1.; 3]));
+    assert!(unregistered.chunks(4).all(|pixel| pixel[..3] == [0; 3]));
+
+    let given = headless.register_background(
+        "@fragment fn fs_given(in: VertexOutput) -> @location(0) vec4<f32> {
+            return background.bottom;
+        }",
+        "fs_given",
+    );
+    let flat = headless.register_background(
+        "@fragment fn fs_flat(in: VertexOutput) -> @location(0) vec4<f32> {
+            return vec4(0.0, 1.0, 0.0, 1.0);
+        }",
+        "fs_flat",
+    );
+    assert_ne!(given, flat);
+    let mut draw = |background| {
+        headless.set_background(background);
+        headless.pixels(&mesh, &[mesh.whole()], false, &[], 0., 1.)
+    };
+    let drawn = draw(BackgroundKind::Procedural(given, [0.2, 0.4, 0.6]));
+    assert!(solid.iter().zip(&drawn).all(|(a, b)| a.abs_diff(*b) <= 1));
+    let drawn = draw(BackgroundKind::Procedural(flat, [0.2, 0.4, 0.6]));
+    assert_eq!(drawn[..4], [0, 255, 0, 255]);
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn transparent_backgrounds_come_out_premultiplied() {
+    let mut headless = for_test("transparent background");
+    let mut builder = MeshBuilder::default();
+    builder.push_rect(
+        cgmath::vec2(-0.2, -0.2),
+        cgmath::vec2(0.2, 0.2),
+        [1., 1., 1., 0.5],
+    );
+    let mesh = builder.build(&headless.device);
+    headless.set_background(BackgroundKind::Transparent);
+
+    for crt in [None, Some(Crt::new(0.))] {
+        headless.set_crt(crt);
+        let pixels = headless.pixels(&mesh, &[mesh.whole()], false, &[], 0., 1.);
+        let pixel = |column: usize, row: usize| {
+            let i = (row * SIZE as usize + column) * 4;
+            [pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3]]
+        };
+        let middle = SIZE as usize / 2;
+
+        assert_eq!(pixel(0, 0), [0; 4], "through {crt:?}");
+        // half of white, written in srgb, with half the alpha: straight would be white
+        let [r, g, b, a] = pixel(middle, middle);
+        assert!(a.abs_diff(128) <= 1, "{a} through {crt:?}");
+        for channel in [r, g, b] {
+            assert!(channel.abs_diff(188) <= 2, "{channel} through {crt:?}");
+        }
+    }
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn captures_write_each_frame_until_their_limit() {
+    use super::capture::Capture;
+
+    let mut headless = for_test("capture");
+    let dir = std::env::temp_dir().join(format!("wgpu-fun-{}-frames", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut capture = Capture::start(&headless.device, FORMAT, (SIZE, SIZE), dir.clone(), 2)
+        .expect("capture to start");
+
+    let mesh = MeshBuilder::default().build(&headless.device);
+    for frame in 0..3 {
+        let (commands, _) = headless.encode(&mesh, &[mesh.whole()], 1, false, &[], 0., 1., false);
+        let mut encoder = headless
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        let copied = capture.copy(&mut encoder, &headless.texture);
+        assert_eq!(copied.is_some(), frame < 2, "frame {frame}");
+        headless.queue.submit([commands, encoder.finish()]);
+        if let Some(slot) = copied {
+            capture.map(slot);
+        }
+        capture.collect(&headless.device, false);
+    }
+    assert!(capture.is_done());
+    capture.stop(&headless.device).join().unwrap();
+
+    for frame in 0..2 {
+        let png = std::fs::read(dir.join(format!("frame-0000{frame}.png"))).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+    assert!(!dir.join("frame-00002.png").exists());
+    let timestamps = std::fs::read_to_string(dir.join("timestamps.csv")).unwrap();
+    assert_eq!(timestamps.lines().count(), 3, "{timestamps}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn reports_count_what_was_submitted() {
+    use super::background::NEBULA;
+
+    let mut headless = for_test("render reports");
+    let mut builder = MeshBuilder::default();
+    for i in 0..3 {
+        let x = i as f32 * 0.3 - 0.5;
+        builder.push_rect(cgmath::vec2(x, -0.1), cgmath::vec2(x + 0.2, 0.1), [1.; 4]);
+    }
+    let mesh = builder.build(&headless.device);
+    let stride = headless.pipeline.transforms.stride;
+    let camera = std::mem::size_of::<super::buffer::ViewTransform>() as u64;
+
+    let report = headless.draw(&mesh, &[], 0., 1.);
+    assert_eq!(
+        (report.draw_calls, report.vertices, report.indices),
+        (1, 0, 18)
+    );
+    assert_eq!(report.bytes_uploaded, camera + stride);
+    assert!(!report.reconfigured);
+
+    let items = mesh.split(3);
+    let report = headless.draw_items(&mesh, &items, false, 0., 1.);
+    assert_eq!((report.draw_calls, report.indices), (3, 18));
+    assert_eq!(report.bytes_uploaded, camera + 3 * stride);
+    // the transforms are staged, and the pipelines gathered, in the same memory frame after frame
+    let staged = headless.pipeline.transforms.contents.as_ptr();
+    let gathered = headless.pipeline.frame_pipelines.as_ptr();
+    headless.draw_items(&mesh, &items, false, 0., 1.);
+    assert_eq!(headless.pipeline.transforms.contents.as_ptr(), staged);
+    assert_eq!(headless.pipeline.frame_pipelines.as_ptr(), gathered);
+    if headless.capabilities.multi_draw_indirect {
+        let report = headless.draw_items(&mesh, &items, true, 0., 1.);
+        assert_eq!((report.draw_calls, report.indices), (1, 18));
+        let arguments = std::mem::size_of::<wgpu::util::DrawIndexedIndirect>() as u64;
+        assert_eq!(report.bytes_uploaded, camera + 3 * stride + 3 * arguments);
+
+        // written over rather than made again each frame
+        let before = crate::instrument::counts();
+        headless.draw_items(&mesh, &items, true, 0., 1.);
+        assert_eq!(
+            crate::instrument::counts()
+                .since(&before)
+                .buffer_recreations,
+            0
+        );
+        assert_eq!(
+            headless.pipeline.indirect.contents.len() as u64,
+            3 * arguments
+        );
+    }
+
+    // particles' corners and the background's triangle are drawn without indices
+    let particles = [ParticleInstance {
+        position: [0.; 2],
+        size: 0.1,
+        color: [1.; 3],
+        alpha: 1.,
+    }; 5];
+    headless.set_background(BackgroundKind::Procedural(NEBULA, [0.; 3]));
+    let report = headless.draw(&mesh, &particles, 0., 1.);
+    assert_eq!(
+        (report.draw_calls, report.vertices, report.indices),
+        (3, 3 + 5 * 6, 18)
+    );
+    assert!(report.bytes_uploaded > camera + stride + std::mem::size_of_val(&particles) as u64);
+}
+
+#[test]
+#[cfg_attr(not(gpu_tests), ignore = "needs a gpu, run with WGPU_FUN_GPU_TESTS=1")]
+fn tonemapping_leaves_sdr_colours_be() {
+    use super::post::Tonemap;
+
+    let mut headless = for_test("tonemap");
+    let mut builder = MeshBuilder::default();
+    builder.push_rect(
+        cgmath::vec2(-0.5, -0.5),
+        cgmath::vec2(0.5, 0.5),
+        [0.9, 0.4, 0.1, 1.],
+    );
+    let mesh = builder.build(&headless.device);
+    let plain = headless.pixels(&mesh, &[mesh.whole()], false, &[], 0., 1.);
+
+    // run over the srgb texture here, so only colours short of white go through it
+    let tonemap = Effect::Tonemap(Tonemap::default());
+    headless.post = Some(PostProcess::new(
+        &headless.device,
+        &headless.queue,
+        FORMAT,
+        (SIZE, SIZE),
+        tonemap,
+    ));
+    let tonemapped = headless.pixels(&mesh, &[mesh.whole()], false, &[], 0., 1.);
+    assert!(plain
+        .iter()
+        .zip(&tonemapped)
+        .all(|(a, b)| a.abs_diff(*b) <= 1));
+}
@@ -0,0 +1,137 @@
+//! Clipping draws to any shape by first drawing the shape into the stencil buffer, for what a
+//! scissor rect can't cut out, like a round minimap
+
+use super::buffer::DrawItem;
+use super::cache::Stencil;
+
+/// What a draw does with the stencil buffer. The stencil counts how many masks cover each pixel,
+/// so masks inside masks only let through where they overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Masking {
+    pub stencil: Stencil,
+    /// What the stencil has to be where the draw goes through
+    pub reference: u32,
+}
+
+impl Masking {
+    /// Drawn everywhere, leaving the stencil be
+    pub const NONE: Self = Self {
+        stencil: Stencil::Ignore,
+        reference: 0,
+    };
+}
+
+/// A mask opened on a draw list, which has to be ended on it, innermost first
+#[derive(Debug)]
+#[must_use = "a mask has to be ended"]
+pub struct MaskHandle {
+    /// The mask's own items, drawn again at the end to take it back out of the stencil
+    items: Vec<DrawItem>,
+    depth: u32,
+}
+
+/// Draw items in the order they're drawn, with masks opened and closed around runs of them
+#[derive(Debug, Clone, Default)]
+pub struct DrawList {
+    items: Vec<DrawItem>,
+    /// How many masks are open
+    depth: u32,
+}
+
+impl DrawList {
+    /// Adds an item, clipped to the masks open
+    pub fn push(&mut self, item: DrawItem) {
+        let masking = match self.depth {
+            0 => Masking::NONE,
+            depth => Masking {
+                stencil: Stencil::TestMask,
+                reference: depth,
+            },
+        };
+        self.items.push(DrawItem { masking, ..item });
+    }
+
+    /// Draws `mask` into the stencil rather than the colour, clipping what's pushed until it's
+    /// ended to where it covers, within any masks already open
+    pub fn begin_mask(&mut self, mask: &[DrawItem]) -> MaskHandle {
+        let write = Masking {
+            stencil: Stencil::WriteMask,
+            reference: self.depth,
+        };
+        self.items.extend(mask.iter().map(|item| DrawItem {
+            masking: write,
+            ..item.clone()
+        }));
+        self.depth += 1;
+        MaskHandle {
+            items: mask.to_vec(),
+            depth: self.depth,
+        }
+    }
+
+    /// Takes the mask back out of the stencil, so what's pushed after isn't clipped by it
+    pub fn end_mask(&mut self, handle: MaskHandle) {
+        assert_eq!(handle.depth, self.depth, "masks end innermost first");
+        let clear = Masking {
+            stencil: Stencil::ClearMask,
+            reference: self.depth,
+        };
+        self.items
+            .extend(handle.items.into_iter().map(|item| DrawItem {
+                masking: clear,
+                ..item
+            }));
+        self.depth -= 1;
+    }
+
+    pub fn items(&self) -> &[DrawItem] {
+        &self.items
+    }
+
+    pub fn clear(&mut self) {
+        assert_eq!(self.depth, 0, "masks left open");
+        self.items.clear();
+    }
+}
+
+#[test]
+fn masks_nest_and_come_back_out_of_the_stencil() {
+    let item = |start| DrawItem {
+        indices: start..start + 3,
+        ..DrawItem::default()
+    };
+    let mut list = DrawList::default();
+    list.push(item(0));
+    let outer = list.begin_mask(&[item(3)]);
+    list.push(item(6));
+    let inner = list.begin_mask(&[item(9)]);
+    list.push(item(12));
+    list.end_mask(inner);
+    list.end_mask(outer);
+    list.push(item(15));
+
+    let steps: Vec<_> = list
+        .items()
+        .iter()
+        .map(|item| {
+            (
+                item.indices.start,
+                item.masking.stencil,
+                item.masking.reference,
+            )
+        })
+        .collect();
+    assert_eq!(
+        steps,
+        [
+            (0, Stencil::Ignore, 0),
+            (3, Stencil::WriteMask, 0),
+            (6, Stencil::TestMask, 1),
+            (9, Stencil::WriteMask, 1),
+            (12, Stencil::TestMask, 2),
+            (9, Stencil::ClearMask, 2),
+            (3, Stencil::ClearMask, 1),
+            (15, Stencil::Ignore, 0),
+        ]
+    );
+}
@@ -0,0 +1,364 @@
+//! Passes run over the finished scene, which is drawn into an offscreen texture first when there
+//! are any, and straight onto the screen when there aren't
+
+use wgpu::include_wgsl;
+
+/// An old television's look: scanlines, a screen bulging out from the middle, and darker corners
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crt {
+    distortion: f32,
+}
+
+impl Crt {
+    /// How far the screen bulges, any more squeezing the scene too far into the middle
+    pub const MAX_DISTORTION: f32 = 0.25;
+    pub const DEFAULT_DISTORTION: f32 = 0.1;
+    /// How much every other row is dimmed
+    const SCANLINES: f32 = 0.15;
+    /// How much the corners are dimmed
+    const VIGNETTE: f32 = 0.3;
+
+    /// With the distortion clamped to `0..=MAX_DISTORTION`, since bulging in rather than out
+    /// would push the corners of the scene off the screen
+    pub fn new(distortion: f32) -> Self {
+        Self {
+            distortion: distortion.clamp(0., Self::MAX_DISTORTION),
+        }
+    }
+
+    pub fn distortion(self) -> f32 {
+        self.distortion
+    }
+
+    /// Where in the scene a point on the screen shows, both from -1 to 1, as the shader does it
+    #[cfg(test)]
+    fn sample_point(self, screen: cgmath::Vector2<f32>) -> cgmath::Vector2<f32> {
+        use cgmath::InnerSpace;
+        screen * (1. + self.distortion * screen.magnitude2()) / (1. + self.distortion)
+    }
+
+    fn uniform(self) -> [f32; 4] {
+        [self.distortion, Self::SCANLINES, Self::VIGNETTE, 0.]
+    }
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_DISTORTION)
+    }
+}
+
+/// Brings a linear scene onto an hdr display, letting highlights past white through as far as
+/// `PEAK` rather than clipping them, and brightening the lot for displays where white looks dim
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tonemap {
+    brightness: f32,
+}
+
+impl Tonemap {
+    /// How many times brighter than white the brightest highlight comes out, before brightening
+    const PEAK: f32 = 4.;
+    pub const MAX_BRIGHTNESS: f32 = 4.;
+    pub const DEFAULT_BRIGHTNESS: f32 = 1.;
+
+    /// With the brightness clamped to `0.25..=MAX_BRIGHTNESS`, since any dimmer and the scene
+    /// would be darker than on an sdr display
+    pub fn new(brightness: f32) -> Self {
+        Self {
+            brightness: brightness.clamp(0.25, Self::MAX_BRIGHTNESS),
+        }
+    }
+
+    pub fn brightness(self) -> f32 {
+        self.brightness
+    }
+
+    /// What a channel of the scene comes out as, as the shader does it
+    #[cfg(test)]
+    fn map(self, channel: f32) -> f32 {
+        let knee = Self::PEAK - 1.;
+        let over = (channel - 1.).max(0.);
+        (channel.min(1.) + knee * (1. - (-over / knee).exp())) * self.brightness
+    }
+
+    fn uniform(self) -> [f32; 4] {
+        [self.brightness, Self::PEAK, 0., 0.]
+    }
+}
+
+impl Default for Tonemap {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_BRIGHTNESS)
+    }
+}
+
+/// What a pass over the finished scene does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Effect {
+    Crt(Crt),
+    Tonemap(Tonemap),
+}
+
+impl Effect {
+    fn shader(self) -> wgpu::ShaderModuleDescriptor<'static> {
+        match self {
+            Self::Crt(_) => include_wgsl!("../shaders/crt.wgsl"),
+            Self::Tonemap(_) => include_wgsl!("../shaders/tonemap.wgsl"),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Crt(_) => "Crt",
+            Self::Tonemap(_) => "Tonemap",
+        }
+    }
+
+    fn uniform(self) -> [f32; 4] {
+        match self {
+            Self::Crt(crt) => crt.uniform(),
+            Self::Tonemap(tonemap) => tonemap.uniform(),
+        }
+    }
+}
+
+/// A pass over the scene, with the texture the scene's drawn into for it to read from
+pub(super) struct PostProcess {
+    pipeline: wgpu::RenderPipeline,
+    layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    uniform: wgpu::Buffer,
+    format: wgpu::TextureFormat,
+    scene: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+}
+
+impl PostProcess {
+    pub(super) fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        (width, height): (u32, u32),
+        effect: Effect,
+    ) -> Self {
+        let name = effect.name();
+        let shader = device.create_shader_module(effect.shader());
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some(&format!("{name} Bind Group Layout")),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(&format!("{name} Pipeline Layout")),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(&format!("{name} Pipeline")),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(&format!("{name} Sampler")),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let uniform = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{name} Buffer")),
+            size: std::mem::size_of::<[f32; 4]>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        queue.write_buffer(&uniform, 0, bytemuck::cast_slice(&effect.uniform()));
+
+        let (scene, bind_group) =
+            Self::create_scene(device, &layout, &sampler, &uniform, format, width, height);
+        Self {
+            pipeline,
+            layout,
+            sampler,
+            uniform,
+            format,
+            scene,
+            bind_group,
+        }
+    }
+
+    fn create_scene(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        uniform: &wgpu::Buffer,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> (wgpu::TextureView, wgpu::BindGroup) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Scene Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let scene = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&scene),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: uniform.as_entire_binding(),
+                },
+            ],
+            label: Some("Post Bind Group"),
+        });
+        (scene, bind_group)
+    }
+
+    /// Makes the scene texture again at the new size
+    pub(super) fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        (self.scene, self.bind_group) = Self::create_scene(
+            device,
+            &self.layout,
+            &self.sampler,
+            &self.uniform,
+            self.format,
+            width,
+            height,
+        );
+    }
+
+    /// Where the scene's drawn, or resolved to when multisampling
+    pub(super) fn scene(&self) -> &wgpu::TextureView {
+        &self.scene
+    }
+
+    /// Draws the scene onto `output` through the effect
+    pub(super) fn apply(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Post Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    // every pixel's drawn over
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+#[test]
+fn distortion_keeps_the_whole_scene_on_screen() {
+    use cgmath::vec2;
+
+    assert_eq!(Crt::new(10.).distortion(), Crt::MAX_DISTORTION);
+    assert_eq!(Crt::new(-1.).distortion(), 0.);
+
+    // the screen's edges show the scene's edges or beyond them, never short of them, so
+    // nothing inside the scene is left off
+    for distortion in [0., Crt::DEFAULT_DISTORTION, Crt::MAX_DISTORTION] {
+        let crt = Crt::new(distortion);
+        for i in 0..=20 {
+            let along = i as f32 / 10. - 1.;
+            for edge in [
+                vec2(1., along),
+                vec2(along, 1.),
+                vec2(-1., along),
+                vec2(along, -1.),
+            ] {
+                let sampled = crt.sample_point(edge);
+                assert!(
+                    sampled.x.abs().max(sampled.y.abs()) >= 1. - 1e-6,
+                    "{edge:?} shows {sampled:?} at {distortion}"
+                );
+            }
+        }
+        // and the middle of the screen stays in the middle
+        assert_eq!(crt.sample_point(vec2(0., 0.)), vec2(0., 0.));
+    }
+}
+
+#[test]
+fn tonemapping_keeps_white_and_holds_highlights_back() {
+    let tonemap = Tonemap::default();
+    // anything up to white is left as it was
+    for channel in [0., 0.25, 0.5, 1.] {
+        assert_eq!(tonemap.map(channel), channel);
+    }
+    // and past it, brighter still but never past the peak
+    let highlights = [1.5, 2., 4., 8., 100.].map(|channel| tonemap.map(channel));
+    assert!(
+        highlights.windows(2).all(|pair| pair[0] < pair[1]),
+        "{highlights:?}"
+    );
+    assert!(highlights[0] > 1. && highlights[4] <= Tonemap::PEAK);
+
+    let brighter = Tonemap::new(2.);
+    assert_eq!(brighter.map(0.5), 1.);
+    assert_eq!(Tonemap::new(100.).brightness(), Tonemap::MAX_BRIGHTNESS);
+}
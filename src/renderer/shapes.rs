@@ -0,0 +1,487 @@
+use super::buffer::{MeshBuilder, Vertex};
+use crate::collison::Shape;
+use cgmath::{InnerSpace, Vector2};
+use std::f32::consts::TAU;
+
+impl MeshBuilder {
+    /// Pushes a quad of the given thickness along the segment from `a` to `b`
+    pub fn push_segment(
+        &mut self,
+        a: Vector2<f32>,
+        b: Vector2<f32>,
+        thickness: f32,
+        color: [f32; 4],
+    ) {
+        let direction = b - a;
+        if direction.magnitude2() <= f32::EPSILON {
+            return;
+        }
+
+        let normal = Vector2::new(-direction.y, direction.x).normalize() * thickness / 2.;
+
+        self.push(
+            [a - normal, b - normal, b + normal, a + normal].map(|v| Vertex {
+                position: v.into(),
+                color,
+            }),
+            [0, 1, 2, 0, 2, 3],
+        )
+    }
+
+    /// Pushes an axis-aligned rectangle spanning `min` to `max`
+    pub fn push_rect(&mut self, min: Vector2<f32>, max: Vector2<f32>, color: [f32; 4]) {
+        self.push(
+            [
+                [min.x, min.y],
+                [max.x, min.y],
+                [max.x, max.y],
+                [min.x, max.y],
+            ]
+            .map(|position| Vertex { position, color }),
+            [0, 1, 2, 0, 2, 3],
+        )
+    }
+
+    /// Pushes the border of an axis-aligned rectangle, drawn inside its bounds
+    pub fn push_rect_outline(
+        &mut self,
+        min: Vector2<f32>,
+        max: Vector2<f32>,
+        thickness: f32,
+        color: [f32; 4],
+    ) {
+        let t = thickness;
+        self.push_rect(min, Vector2::new(max.x, min.y + t), color);
+        self.push_rect(Vector2::new(min.x, max.y - t), max, color);
+        self.push_rect(
+            Vector2::new(min.x, min.y + t),
+            Vector2::new(min.x + t, max.y - t),
+            color,
+        );
+        self.push_rect(
+            Vector2::new(max.x - t, min.y + t),
+            Vector2::new(max.x, max.y - t),
+            color,
+        );
+    }
+
+    /// Pushes a filled circle as a fan of `segments` triangles
+    pub fn push_circle(
+        &mut self,
+        center: Vector2<f32>,
+        radius: f32,
+        segments: SegmentCount,
+        color: [f32; 4],
+    ) {
+        let segments = segments.count(radius, self.pixels_per_unit);
+        let Vector2 { x, y } = center;
+
+        let vertices = std::iter::once(Vertex {
+            position: [x, y],
+            color,
+        })
+        .chain(
+            (0..=segments)
+                .map(|i| {
+                    [
+                        (i as f32 / segments as f32 * TAU).sin() * radius,
+                        (i as f32 / segments as f32 * TAU).cos() * radius,
+                    ]
+                })
+                .map(|[vert_x, vert_y]| Vertex {
+                    position: [vert_x + x, vert_y + y],
+                    color,
+                }),
+        )
+        .collect::<Vec<_>>();
+
+        let indices = (1..segments + 1)
+            .flat_map(|i| [0u16, (i as u16 + 1) % vertices.len() as u16, i as u16])
+            .collect::<Vec<_>>();
+
+        self.push(vertices, indices)
+    }
+
+    /// Pushes a ring segment of `thickness` centred on `radius`, from angle `start` anticlockwise
+    /// through `sweep` radians in `segments` pieces
+    pub fn push_arc(
+        &mut self,
+        center: Vector2<f32>,
+        radius: f32,
+        [start, sweep]: [f32; 2],
+        thickness: f32,
+        segments: usize,
+        color: [f32; 4],
+    ) {
+        let (inner, outer) = (radius - thickness / 2., radius + thickness / 2.);
+        let vertices = (0..=segments).flat_map(|i| {
+            let (s, c) = (start + sweep * i as f32 / segments as f32).sin_cos();
+            let direction = Vector2::new(c, s);
+            [center + direction * inner, center + direction * outer].map(|v| Vertex {
+                position: v.into(),
+                color,
+            })
+        });
+        let indices = (0..segments as u16).flat_map(|i| {
+            let [a, b, c, d] = [i * 2, i * 2 + 1, i * 2 + 2, i * 2 + 3];
+            [a, d, c, a, b, d]
+        });
+
+        self.push(vertices, indices)
+    }
+
+    /// Pushes a strip `width` either side of `points`, `style` giving the fraction of that width
+    /// and the colour at each point from its position along the strip in 0..=1
+    pub fn push_ribbon(
+        &mut self,
+        points: &[Vector2<f32>],
+        width: f32,
+        style: impl Fn(f32) -> (f32, [f32; 4]),
+    ) {
+        let count = points.len();
+        if count < 2 {
+            return;
+        }
+
+        self.reserve(count * 2, (count - 1) * 6);
+
+        let vertices = (0..count).flat_map(|i| {
+            let previous = points[i.saturating_sub(1)];
+            let next = points[(i + 1).min(count - 1)];
+            let direction = next - previous;
+            let normal = if direction.magnitude2() > f32::EPSILON {
+                Vector2::new(-direction.y, direction.x).normalize()
+            } else {
+                Vector2::unit_x()
+            };
+
+            let (scale, color) = style(i as f32 / (count - 1) as f32);
+            let offset = normal * width * scale;
+            [points[i] + offset, points[i] - offset].map(|v| Vertex {
+                position: v.into(),
+                color,
+            })
+        });
+
+        let indices = (0..count as u16 - 1).flat_map(|i| {
+            let (left, right) = (i * 2, i * 2 + 1);
+            let (next_left, next_right) = (left + 2, right + 2);
+            [right, next_right, next_left, right, next_left, left]
+        });
+
+        self.push(vertices, indices);
+    }
+
+    /// Pushes the line through `points`
+    pub fn push_polyline(&mut self, points: &[Vector2<f32>], thickness: f32, color: [f32; 4]) {
+        for pair in points.windows(2) {
+            self.push_segment(pair[0], pair[1], thickness, color);
+        }
+    }
+
+    /// Pushes the border of the polygon with corners `points`, closing it back to the first
+    pub fn push_outline(&mut self, points: &[Vector2<f32>], thickness: f32, color: [f32; 4]) {
+        self.push_polyline(points, thickness, color);
+        if let (Some(&first), Some(&last)) = (points.first(), points.last()) {
+            self.push_segment(last, first, thickness, color);
+        }
+    }
+
+    /// Pushes the border of a collider, with circles as polygons of `CIRCLE_SEGMENTS` sides
+    pub fn push_shape_outline(&mut self, shape: &Shape, thickness: f32, color: [f32; 4]) {
+        const CIRCLE_SEGMENTS: usize = 32;
+
+        match shape {
+            &Shape::Circle { center, radius } => {
+                let points: Vec<_> = (0..CIRCLE_SEGMENTS)
+                    .map(|i| {
+                        let (s, c) = (i as f32 / CIRCLE_SEGMENTS as f32 * TAU).sin_cos();
+                        center + Vector2::new(c, s) * radius
+                    })
+                    .collect();
+                self.push_outline(&points, thickness, color);
+            }
+            &Shape::Aabb { min, max } => self.push_rect_outline(min, max, thickness, color),
+            Shape::Obb(obb) => self.push_outline(&obb.corners(), thickness, color),
+            Shape::ConvexPolygon(points) => self.push_outline(points, thickness, color),
+            &Shape::Segment { a, b } => self.push_segment(a, b, thickness, color),
+        }
+    }
+
+    /// Pushes the dashes of the line through `points` as `style` lays them out along it,
+    /// colouring each by how far along the line it starts, in 0..=1
+    pub fn push_dashed_polyline(
+        &mut self,
+        points: &[Vector2<f32>],
+        thickness: f32,
+        style: LineStyle,
+        color: impl Fn(f32) -> [f32; 4],
+    ) {
+        let lengths: Vec<f32> = points
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).magnitude())
+            .collect();
+        let total = lengths.iter().sum::<f32>().max(f32::EPSILON);
+        // a negative length would run the pattern backwards, never reaching the end of the line
+        let (dash_length, gap_length) = (style.dash_length.max(0.), style.gap_length.max(0.));
+        let period = dash_length + gap_length;
+
+        // how far into the pattern the line's got, carried over from one segment to the next
+        let mut into = style.phase.rem_euclid(period);
+        let mut along = 0.;
+        for (pair, &length) in points.windows(2).zip(&lengths) {
+            let (a, b) = (pair[0], pair[1]);
+            if gap_length <= 0. {
+                self.push_segment(a, b, thickness, color(along / total));
+                along += length;
+                continue;
+            }
+            let direction = (b - a) / length.max(f32::EPSILON);
+            let mut done = 0.;
+            while done < length {
+                let on = into < dash_length;
+                let left = if on {
+                    dash_length - into
+                } else {
+                    period - into
+                };
+                let end = (done + left).min(length);
+                if end <= done {
+                    // too little left to move on by, so onto the next run outright
+                    into = if on { dash_length } else { 0. };
+                    continue;
+                }
+                if on {
+                    self.push_segment(
+                        a + direction * done,
+                        a + direction * end,
+                        thickness,
+                        color((along + done) / total),
+                    );
+                }
+                into += end - done;
+                if into >= period {
+                    into -= period;
+                }
+                done = end;
+            }
+            along += length;
+        }
+    }
+}
+
+/// How many edges a circle's drawn with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SegmentCount {
+    Fixed(usize),
+    /// As many as keep each edge around this many pixels long on screen, so circles stay round
+    /// zoomed in close without wasting triangles on ones shown tiny
+    Adaptive {
+        pixels_per_segment: f32,
+    },
+}
+
+impl SegmentCount {
+    /// The fewest and most edges an adaptive circle's drawn with
+    const ADAPTIVE_RANGE: std::ops::RangeInclusive<usize> = 8..=128;
+    /// Taken for a mesh that hasn't said how it'll be shown: the world's height over 600 pixels
+    const FALLBACK_PIXELS_PER_UNIT: f32 = 300.;
+
+    /// The edges a circle of `radius` gets when a unit of the world is `pixels_per_unit` tall
+    fn count(self, radius: f32, pixels_per_unit: Option<f32>) -> usize {
+        match self {
+            Self::Fixed(segments) => segments,
+            Self::Adaptive { pixels_per_segment } => {
+                let scale = pixels_per_unit.unwrap_or(Self::FALLBACK_PIXELS_PER_UNIT);
+                let circumference = TAU * radius * scale;
+                let segments = (circumference / pixels_per_segment).ceil() as usize;
+                segments.clamp(*Self::ADAPTIVE_RANGE.start(), *Self::ADAPTIVE_RANGE.end())
+            }
+        }
+    }
+}
+
+/// How a line's broken into dashes, measured along it, corners and all
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineStyle {
+    pub dash_length: f32,
+    /// Between one dash and the next, with none at all making the line solid
+    pub gap_length: f32,
+    /// How far into the pattern the line starts, which marches the dashes along when it's
+    /// advanced each tick
+    pub phase: f32,
+}
+
+impl LineStyle {
+    pub const SOLID: Self = Self {
+        dash_length: 1.,
+        gap_length: 0.,
+        phase: 0.,
+    };
+
+    /// Dashes and gaps of the given lengths, neither of which can be less than nothing
+    pub fn dashed(dash_length: f32, gap_length: f32) -> Self {
+        Self {
+            dash_length: dash_length.max(0.),
+            gap_length: gap_length.max(0.),
+            phase: 0.,
+        }
+    }
+
+    /// Dashes as long as their gaps, each a thickness long, for lines of dots
+    pub fn dotted(thickness: f32) -> Self {
+        Self::dashed(thickness, thickness)
+    }
+
+    pub fn with_phase(self, phase: f32) -> Self {
+        Self { phase, ..self }
+    }
+}
+
+#[test]
+fn segment_is_one_quad() {
+    let mut mesh = MeshBuilder::default();
+    mesh.push_segment(Vector2::new(0., 0.), Vector2::new(1., 0.), 0.2, [1.; 4]);
+
+    assert_eq!(mesh.vertices.len(), 4);
+    assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3]);
+    assert_eq!(mesh.vertices[0].position, [0., -0.1]);
+    assert_eq!(mesh.vertices[2].position, [1., 0.1]);
+
+    // degenerate segments are skipped
+    mesh.push_segment(Vector2::new(1., 1.), Vector2::new(1., 1.), 0.2, [1.; 4]);
+    assert_eq!(mesh.vertices.len(), 4);
+}
+
+/// How long each quad pushed is, end to end
+#[cfg(test)]
+fn quad_lengths(mesh: &MeshBuilder) -> Vec<f32> {
+    mesh.vertices
+        .chunks(4)
+        .map(|quad| (Vector2::from(quad[1].position) - Vector2::from(quad[0].position)).magnitude())
+        .collect()
+}
+
+#[test]
+fn dashes_cover_their_share_of_the_line_across_corners() {
+    let zigzag = [(0., 0.), (1.3, 0.), (1.3, 0.7), (2.9, 1.5), (2.9, 3.)].map(Vector2::from);
+    let length: f32 = zigzag
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).magnitude())
+        .sum();
+
+    for (dash, gap, phase) in [(0.1, 0.1, 0.), (0.3, 0.1, 0.05), (0.05, 0.2, -1.33)] {
+        let mut mesh = MeshBuilder::default();
+        let style = LineStyle::dashed(dash, gap).with_phase(phase);
+        mesh.push_dashed_polyline(&zigzag, 0.02, style, |_| [1.; 4]);
+        let drawn: f32 = quad_lengths(&mesh).iter().sum();
+        // off by at most the dash cut off at the end
+        let expected = length * dash / (dash + gap);
+        assert!(
+            (drawn - expected).abs() <= dash,
+            "{drawn} drawn of {expected}"
+        );
+    }
+
+    // the pattern carries on round a corner rather than starting afresh: a dash of 0.3 from 0.9
+    // along a segment 1 long carries 0.2 into the next
+    let corner = [(0., 0.), (1., 0.), (1., 1.)].map(Vector2::from);
+    let mut mesh = MeshBuilder::default();
+    let style = LineStyle::dashed(0.3, 0.15).with_phase(0.45);
+    mesh.push_dashed_polyline(&corner, 0.02, style, |t| [1., 1., 1., t]);
+    let lengths = quad_lengths(&mesh);
+    assert!((lengths[2] - 0.1).abs() < 1e-5 && (lengths[3] - 0.2).abs() < 1e-5);
+    // coloured by how far along they start
+    assert!((mesh.vertices[12].color[3] - 0.5).abs() < 1e-5);
+}
+
+#[test]
+fn dashes_without_gaps_make_a_solid_line() {
+    let points = (0..6)
+        .map(|x| Vector2::new(x as f32, 0.))
+        .collect::<Vec<_>>();
+    let mut dashed = MeshBuilder::default();
+    dashed.push_dashed_polyline(&points, 0.1, LineStyle::dashed(0.3, 0.), |_| [1.; 4]);
+    let mut solid = MeshBuilder::default();
+    solid.push_polyline(&points, 0.1, [1.; 4]);
+
+    let positions =
+        |mesh: &MeshBuilder| mesh.vertices.iter().map(|v| v.position).collect::<Vec<_>>();
+    assert_eq!(positions(&dashed), positions(&solid));
+    assert_eq!(LineStyle::SOLID.gap_length, 0.);
+}
+
+#[test]
+fn negative_dash_lengths_draw_nothing_rather_than_running_forever() {
+    let points = [Vector2::new(0., 0.), Vector2::new(1., 0.)];
+    let style = LineStyle {
+        dash_length: -0.1,
+        gap_length: 0.1,
+        phase: 0.,
+    };
+    let mut mesh = MeshBuilder::default();
+    mesh.push_dashed_polyline(&points, 0.1, style, |_| [1.; 4]);
+    assert!(mesh.vertices.is_empty());
+
+    // and a negative gap draws it solid
+    let mut mesh = MeshBuilder::default();
+    mesh.push_dashed_polyline(
+        &points,
+        0.1,
+        LineStyle {
+            gap_length: -0.1,
+            ..style
+        },
+        |_| [1.; 4],
+    );
+    assert_eq!(mesh.vertices.len(), 4);
+
+    assert_eq!(LineStyle::dashed(-0.1, -0.2), LineStyle::dashed(0., 0.));
+}
+
+#[test]
+fn outline_closes_the_polygon() {
+    let mut mesh = MeshBuilder::default();
+    let square = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)].map(Vector2::from);
+    mesh.push_outline(&square, 0.1, [1.; 4]);
+
+    assert_eq!(mesh.vertices.len(), 16);
+    // the last side runs from the last corner back to the first
+    assert_eq!(mesh.vertices[12].position, [-0.05, 1.]);
+}
+
+#[test]
+fn adaptive_circles_get_finer_as_they_are_shown_bigger() {
+    let adaptive = SegmentCount::Adaptive {
+        pixels_per_segment: 5.,
+    };
+    let at_zoom = |zoom: f32| {
+        let mut mesh = MeshBuilder::default();
+        mesh.set_screen_scale(zoom, 600);
+        mesh.push_circle(Vector2::new(0., 0.), 0.05, adaptive, [1.; 4]);
+        // the centre and a point closing the fan as well as one per edge
+        mesh.vertices.len() - 2
+    };
+
+    // about 94 pixels around at no zoom, in edges 5 pixels long
+    assert_eq!(at_zoom(1.), 19);
+    assert_eq!(at_zoom(2.), 38);
+    assert_eq!(at_zoom(0.5), 10);
+    // never so few it stops looking round, nor more than anyone could see
+    assert_eq!(at_zoom(0.1), 8);
+    assert_eq!(at_zoom(20.), 128);
+    // and unscaled meshes taken as shown at no zoom
+    assert_eq!(adaptive.count(0.05, None), 19);
+    assert_eq!(SegmentCount::Fixed(5).count(0.05, Some(1e4)), 5);
+}
+
+#[test]
+fn arc_is_a_strip_of_quads() {
+    let mut mesh = MeshBuilder::default();
+    mesh.push_arc(Vector2::new(0., 0.), 1., [0., TAU / 4.], 0.2, 4, [1.; 4]);
+
+    assert_eq!(mesh.vertices.len(), 10);
+    assert_eq!(mesh.indices.len(), 24);
+    assert_eq!(mesh.vertices[1].position, [1.1, 0.]);
+    assert!((mesh.vertices[8].position[1] - 0.9).abs() < 1e-6);
+}
@@ -0,0 +1,208 @@
+//! Images uploaded once and drawn over the mesh's triangles, each sampled as it was made to be
+//! unless a draw asks for otherwise
+
+use super::buffer::Transform2D;
+use super::cache::{BindGroupKey, Cache, SamplerDesc, TextureId};
+use cgmath::Vector2;
+use std::collections::HashMap;
+
+/// What a draw's triangles are textured with, and where in the texture each vertex samples
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Texturing {
+    pub texture: TextureId,
+    /// Sampled this way rather than as the texture was made to be, like an atlas drawn smoothly
+    /// into a zoomed out minimap
+    pub sampler: Option<SamplerDesc>,
+    /// From the vertices' positions, before the draw's transform moves them, to the texture's
+    /// coordinates, 0 to 1 from its top left corner
+    pub uv: Transform2D,
+}
+
+impl Texturing {
+    /// The whole texture stretched over the rect from `min` to `max`
+    pub fn stretched(texture: TextureId, min: Vector2<f32>, max: Vector2<f32>) -> Self {
+        let size = max - min;
+        Self {
+            texture,
+            sampler: None,
+            // the texture's rows go down from its top, where the mesh's go up
+            uv: Transform2D::new(
+                cgmath::vec2(-min.x / size.x, max.y / size.y),
+                0.,
+                cgmath::vec2(1. / size.x, -1. / size.y),
+            ),
+        }
+    }
+
+    pub fn with_sampler(self, sampler: SamplerDesc) -> Self {
+        Self {
+            sampler: Some(sampler),
+            ..self
+        }
+    }
+}
+
+struct Texture {
+    /// Kept alive for as long as the view is
+    _texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    /// How it's sampled unless a draw says otherwise
+    sampler: SamplerDesc,
+}
+
+/// Every texture made so far, and a sampler for each way they've been sampled
+pub(super) struct Textures {
+    pub(super) layout: wgpu::BindGroupLayout,
+    textures: HashMap<TextureId, Texture>,
+    samplers: Cache<SamplerDesc, wgpu::Sampler>,
+    next: u64,
+}
+
+impl Textures {
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+    pub(super) fn new(device: &wgpu::Device) -> Self {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("Texture Bind Group Layout"),
+        });
+        Self {
+            layout,
+            textures: HashMap::new(),
+            samplers: Cache::default(),
+            next: 0,
+        }
+    }
+
+    /// Uploads `width` by `height` srgb pixels with straight alpha, a row at a time from the top
+    pub(super) fn create(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        (width, height): (u32, u32),
+        rgba: &[u8],
+        sampler: SamplerDesc,
+    ) -> TextureId {
+        assert_eq!(
+            rgba.len(),
+            (width * height * 4) as usize,
+            "a texture's pixels are 4 bytes each"
+        );
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            texture.as_image_copy(),
+            rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(width * 4),
+                rows_per_image: None,
+            },
+            size,
+        );
+
+        let id = TextureId(self.next);
+        self.next += 1;
+        self.textures.insert(
+            id,
+            Texture {
+                view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                _texture: texture,
+                sampler,
+            },
+        );
+        id
+    }
+
+    pub(super) fn remove(&mut self, texture: TextureId) {
+        self.textures.remove(&texture);
+    }
+
+    /// The bind group drawing with `texturing` is keyed by, or `None` if its texture's gone
+    pub(super) fn key(&self, texturing: &Texturing) -> Option<BindGroupKey> {
+        let texture = self.textures.get(&texturing.texture)?;
+        Some(BindGroupKey::Texture {
+            texture: texturing.texture,
+            sampler: texturing.sampler.unwrap_or(texture.sampler),
+        })
+    }
+
+    /// The bind group for a `Texture` key, with its sampler made too if it's the first time it's
+    /// been asked for
+    pub(super) fn bind_group(
+        &mut self,
+        device: &wgpu::Device,
+        key: &BindGroupKey,
+    ) -> wgpu::BindGroup {
+        let BindGroupKey::Texture { texture, sampler } = *key else {
+            unreachable!("only texture keys are bound from textures")
+        };
+        let sampler = self.samplers.get_or_create(sampler, |desc| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Texture Sampler"),
+                address_mode_u: desc.address_mode,
+                address_mode_v: desc.address_mode,
+                address_mode_w: desc.address_mode,
+                mag_filter: desc.mag_filter,
+                min_filter: desc.min_filter,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            })
+        });
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&self.textures[&texture].view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: Some("Texture Bind Group"),
+        })
+    }
+}
+
+#[test]
+fn stretched_textures_cover_the_rect_top_left_first() {
+    use cgmath::vec2;
+
+    let texturing = Texturing::stretched(TextureId(0), vec2(-0.5, 0.), vec2(1.5, 0.5));
+    let uv = |p| texturing.uv.apply(p);
+    assert_eq!(uv(vec2(-0.5, 0.5)), vec2(0., 0.));
+    assert_eq!(uv(vec2(1.5, 0.)), vec2(1., 1.));
+    assert_eq!(uv(vec2(0.5, 0.25)), vec2(0.5, 0.5));
+}
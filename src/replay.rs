@@ -0,0 +1,106 @@
+use crate::storage;
+use crate::InputEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+
+/// A session's seed and every event, hinted with the tick it was handled on, enough to play it
+/// back exactly
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    pub seed: u64,
+    pub events: Vec<InputEvent>,
+}
+
+impl Recording {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    /// Writes the recording out whole, replacing any older copy only once it's written
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        storage::save(path, self)
+    }
+}
+
+/// Hands back a recording's events on the ticks they were recorded on
+pub struct Replay {
+    events: VecDeque<InputEvent>,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            events: recording.events.into(),
+        }
+    }
+
+    /// The events due by `tick`, in the order they were recorded, with any missing a hint due
+    /// straight away
+    pub fn due(&mut self, tick: u64) -> Vec<InputEvent> {
+        let count = self
+            .events
+            .partition_point(|event| event.tick_hint.unwrap_or(0) <= tick);
+        self.events.drain(..count).collect()
+    }
+}
+
+#[test]
+fn replay_reproduces_the_session() {
+    use crate::config::Config;
+    use crate::game::Game;
+    use crate::highscore::HighScoreTables;
+    use crate::{Action, ButtonState, Player};
+
+    const TICKS: u64 = 600;
+    let script = [
+        (0, Action::Serve),
+        (1, Action::Serve),
+        (2, Action::Serve),
+        (5, Action::Right(Player::One, ButtonState::Pressed)),
+        (40, Action::Right(Player::One, ButtonState::Released)),
+        (60, Action::Left(Player::One, ButtonState::Pressed)),
+        (120, Action::Left(Player::One, ButtonState::Released)),
+    ];
+
+    let mut game = Game::new(Config::default(), HighScoreTables::default(), 7);
+    let mut recording = Recording::new(7);
+    for tick in 0..TICKS {
+        for (_, event) in script.iter().filter(|(at, _)| *at == tick) {
+            recording.events.push(InputEvent::at(game.ticks, *event));
+            game.handle(*event);
+        }
+        game.tick();
+    }
+
+    let path = std::env::temp_dir().join(format!("wgpu-fun-replay-{}.json", std::process::id()));
+    recording.save(&path).unwrap();
+    let recording = Recording::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut replayed = Game::new(
+        Config::default(),
+        HighScoreTables::default(),
+        recording.seed,
+    );
+    let mut replay = Replay::new(recording);
+    for _ in 0..TICKS {
+        for event in replay.due(replayed.ticks) {
+            replayed.handle(event);
+        }
+        replayed.tick();
+    }
+
+    assert_ne!(game.ball.state, crate::BallState::Attached);
+    assert_eq!(
+        replayed.ball.position.map(f32::to_bits),
+        game.ball.position.map(f32::to_bits)
+    );
+}
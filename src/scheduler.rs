@@ -0,0 +1,235 @@
+use std::time::{Duration, Instant};
+
+/// Where the scheduler gets the time from and how it waits
+pub trait Clock {
+    fn now(&self) -> Instant;
+    fn sleep(&mut self, duration: Duration);
+    /// Gives up the rest of the time slice while spinning up to a deadline
+    fn yield_now(&mut self);
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+
+    fn yield_now(&mut self) {
+        std::thread::yield_now();
+    }
+}
+
+/// Waits out each tick against deadlines counted from a fixed start, so oversleeping one tick
+/// is taken off the next rather than adding up
+#[derive(Debug, Clone)]
+pub struct TickScheduler {
+    period: Duration,
+    start: Instant,
+    ticks: u32,
+    /// When the current rate measurement started, and the ticks since
+    window: (Instant, u32),
+    rate: f32,
+}
+
+impl TickScheduler {
+    /// Sleeps stop this far short of the deadline, which is spun out instead since sleeps can
+    /// overrun by a whole scheduler quantum
+    const SPIN: Duration = Duration::from_millis(2);
+    /// Falling this many ticks behind, say after the machine was suspended, starts the deadlines
+    /// afresh rather than rushing through the backlog
+    const MAX_LAG: u32 = 10;
+    const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+    pub fn new(period: Duration, now: Instant) -> Self {
+        Self {
+            period,
+            start: now,
+            ticks: 0,
+            window: (now, 0),
+            rate: 1. / period.as_secs_f32(),
+        }
+    }
+
+    /// Waits until the next tick is due
+    pub fn wait(&mut self, clock: &mut impl Clock) {
+        self.ticks += 1;
+        let deadline = self.start + self.period * self.ticks;
+
+        let now = clock.now();
+        if now > deadline + self.period * Self::MAX_LAG {
+            self.start = now;
+            self.ticks = 0;
+        } else {
+            if let Some(remaining) = deadline.checked_duration_since(now) {
+                if remaining > Self::SPIN {
+                    clock.sleep(remaining - Self::SPIN);
+                }
+            }
+            while clock.now() < deadline {
+                clock.yield_now();
+            }
+        }
+
+        let (since, ticks) = &mut self.window;
+        *ticks += 1;
+        let elapsed = clock.now() - *since;
+        if elapsed >= Self::RATE_WINDOW {
+            self.rate = *ticks as f32 / elapsed.as_secs_f32();
+            self.window = (clock.now(), 0);
+        }
+    }
+
+    /// Ticks per second measured over the last second or so
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+}
+
+/// Assumed when the platform can't say how often the display refreshes
+pub const FALLBACK_REFRESH_RATE: f32 = 60.;
+
+/// The display's refresh rate in hertz from what the platform reports in millihertz, if anything
+pub fn refresh_rate(millihertz: Option<u32>) -> f32 {
+    millihertz
+        .filter(|&millihertz| millihertz > 0)
+        .map_or(FALLBACK_REFRESH_RATE, |millihertz| {
+            millihertz as f32 / 1000.
+        })
+}
+
+/// Spaces redraws out to a target frame rate, counting each deadline on from the last so the rate
+/// holds on average, and skipping frames missed rather than rushing to catch up
+#[derive(Debug, Clone)]
+pub struct FramePacer {
+    period: Duration,
+    next: Instant,
+}
+
+impl FramePacer {
+    pub fn new(rate: f32, now: Instant) -> Self {
+        Self {
+            period: Duration::from_secs_f32(1. / rate),
+            next: now,
+        }
+    }
+
+    /// Takes effect from the next frame, as when the window moves to a faster display
+    pub fn set_rate(&mut self, rate: f32) {
+        self.period = Duration::from_secs_f32(1. / rate);
+    }
+
+    pub fn rate(&self) -> f32 {
+        1. / self.period.as_secs_f32()
+    }
+
+    /// When the next frame is due
+    pub fn next_frame(&self) -> Instant {
+        self.next
+    }
+
+    /// Whether a frame is due, moving on to the one after if so
+    pub fn frame_due(&mut self, now: Instant) -> bool {
+        if now < self.next {
+            return false;
+        }
+        self.next += self.period;
+        if self.next <= now {
+            self.next = now + self.period;
+        }
+        true
+    }
+}
+
+/// A clock that only moves when slept on, oversleeping by a fixed amount each time
+#[cfg(test)]
+struct MockClock {
+    now: Instant,
+    overshoot: Duration,
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.now
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        self.now += duration + self.overshoot;
+    }
+
+    fn yield_now(&mut self) {
+        self.now += Duration::from_micros(10);
+    }
+}
+
+#[test]
+fn oversleeping_does_not_drift() {
+    let start = Instant::now();
+    let period = Duration::from_millis(10);
+    let mut clock = MockClock {
+        now: start,
+        overshoot: Duration::from_millis(5),
+    };
+    let mut scheduler = TickScheduler::new(period, start);
+
+    // ten minutes of ticks
+    for _ in 0..60_000 {
+        scheduler.wait(&mut clock);
+    }
+    let late = clock.now - (start + period * 60_000);
+    assert!(late < period, "{late:?} behind");
+    assert!((scheduler.rate() - 100.).abs() < 1.);
+}
+
+#[test]
+fn falling_far_behind_starts_afresh() {
+    let start = Instant::now();
+    let period = Duration::from_millis(10);
+    let mut clock = MockClock {
+        now: start,
+        overshoot: Duration::ZERO,
+    };
+    let mut scheduler = TickScheduler::new(period, start);
+
+    scheduler.wait(&mut clock);
+    clock.now += Duration::from_secs(5);
+    scheduler.wait(&mut clock);
+
+    // the next tick is a period away rather than due straight away
+    let before = clock.now;
+    scheduler.wait(&mut clock);
+    assert!(clock.now - before >= period);
+}
+
+#[test]
+fn frames_follow_the_display_rate() {
+    assert_eq!(refresh_rate(Some(144_000)), 144.);
+    assert_eq!(refresh_rate(None), FALLBACK_REFRESH_RATE);
+    assert_eq!(refresh_rate(Some(0)), FALLBACK_REFRESH_RATE);
+
+    let start = Instant::now();
+    let count = |pacer: &mut FramePacer, from: Instant| {
+        (0..1000)
+            .filter(|ms| pacer.frame_due(from + Duration::from_millis(*ms)))
+            .count()
+    };
+    let mut pacer = FramePacer::new(refresh_rate(Some(60_000)), start);
+    assert_eq!(count(&mut pacer, start), 60);
+
+    // moved to a faster display partway through
+    pacer.set_rate(refresh_rate(Some(144_000)));
+    let second = start + Duration::from_secs(1);
+    assert!((143..=145).contains(&count(&mut pacer, second)));
+
+    // a stall skips the frames it missed
+    let later = second + Duration::from_secs(5);
+    assert!(pacer.frame_due(later));
+    assert!(!pacer.frame_due(later + Duration::from_millis(1)));
+    assert!(pacer.next_frame() > later);
+}
@@ -0,0 +1,85 @@
+struct Background {
+    bottom: vec4<f32>,
+    top: vec4<f32>,
+    time: f32,
+    camera_x: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // from -1 to 1 across the screen, as the scene's positions are
+    @location(0) position: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> background: Background;
+
+// one triangle big enough to cover the screen, with no vertex buffer
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let position = vec2(f32(index == 1u) * 4.0 - 1.0, f32(index == 2u) * 4.0 - 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = vec4(position, 0.0, 1.0);
+    out.position = position;
+    return out;
+}
+
+@fragment
+fn fs_gradient(in: VertexOutput) -> @location(0) vec4<f32> {
+    return mix(background.bottom, background.top, (in.position.y + 1.0) / 2.0);
+}
+
+fn hash(cell: vec2<f32>) -> f32 {
+    return fract(sin(dot(cell, vec2(127.1, 311.7))) * 43758.5453);
+}
+
+// smoothly blended random values at whole numbers
+fn noise(point: vec2<f32>) -> f32 {
+    let cell = floor(point);
+    let along = smoothstep(vec2(0.0), vec2(1.0), fract(point));
+    let bottom = mix(hash(cell), hash(cell + vec2(1.0, 0.0)), along.x);
+    let top = mix(hash(cell + vec2(0.0, 1.0)), hash(cell + vec2(1.0, 1.0)), along.x);
+    return mix(bottom, top, along.y);
+}
+
+// clouds of dim colour drifting slowly, a little behind the camera so they seem far off
+@fragment
+fn fs_nebula(in: VertexOutput) -> @location(0) vec4<f32> {
+    let point = vec2(in.position.x + background.camera_x * 0.2, in.position.y) * 1.5;
+    let drift = vec2(background.time * 0.03, background.time * 0.01);
+    var density = 0.0;
+    var scale = 1.0;
+    for (var octave = 0; octave < 4; octave++) {
+        density += noise(point * scale + drift * scale) / scale;
+        scale *= 2.0;
+    }
+    density = smoothstep(0.6, 1.6, density);
+    let color = mix(vec3(0.01, 0.0, 0.03), vec3(0.12, 0.04, 0.2), density);
+    return vec4(color, 1.0);
+}
+
+// how far apart stars are, each in a cell of its own
+const STAR_CELL: f32 = 0.2;
+
+// how lit `position` is by a layer of stars `size` across, scrolling with `parallax` of the
+// camera's movement, with one somewhere in most cells and `seed` keeping layers apart
+fn stars(position: vec2<f32>, parallax: f32, size: f32, seed: f32) -> f32 {
+    let point = vec2(position.x + background.camera_x * parallax, position.y) / STAR_CELL;
+    let cell = floor(point) + seed;
+    // nudged away from the cell's middle, but not so far as to be cut off by its edge
+    let margin = size / STAR_CELL;
+    let star = floor(point) + 0.5 + (vec2(hash(cell), hash(cell + 17.0)) - 0.5) * (1.0 - margin);
+    let lit = hash(cell + 31.0) < 0.8;
+    let inside = all(abs(point - star) < vec2(margin / 2.0));
+    return f32(lit && inside);
+}
+
+// stars in layers over the colour given, the dimmer and smaller ones further off and slower
+@fragment
+fn fs_starfield(in: VertexOutput) -> @location(0) vec4<f32> {
+    var light = stars(in.position, 0.1, 0.006, 0.0) * 0.3;
+    light = max(light, stars(in.position, 0.25, 0.009, 53.0) * 0.5);
+    light = max(light, stars(in.position, 0.5, 0.012, 97.0) * 0.8);
+    return vec4(mix(background.bottom.rgb, vec3(1.0), light), 1.0);
+}
@@ -0,0 +1,50 @@
+struct Crt {
+    distortion: f32,
+    scanlines: f32,
+    vignette: f32,
+    padding: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var scene: texture_2d<f32>;
+@group(0) @binding(1)
+var scene_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> crt: Crt;
+
+// one triangle big enough to cover the screen, with no vertex buffer
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let position = vec2(f32(index == 1u) * 4.0 - 1.0, f32(index == 2u) * 4.0 - 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = vec4(position, 0.0, 1.0);
+    out.uv = vec2(position.x + 1.0, 1.0 - position.y) / 2.0;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // bulging out from the middle, scaled back so the middle of each edge stays put and the
+    // whole scene still fits on the screen
+    let centered = in.uv * 2.0 - 1.0;
+    let bulged = centered * (1.0 + crt.distortion * dot(centered, centered)) / (1.0 + crt.distortion);
+    let uv = (bulged + 1.0) / 2.0;
+    let inside = all(uv >= vec2(0.0)) && all(uv <= vec2(1.0));
+    let sampled = textureSample(scene, scene_sampler, uv);
+    var color = sampled.rgb;
+
+    // every other row of pixels dimmed
+    let odd_row = (u32(in.clip_position.y) & 1u) == 1u;
+    color *= select(1.0, 1.0 - crt.scanlines, odd_row);
+    // darker towards the corners
+    color *= 1.0 - crt.vignette * dot(centered, centered) / 2.0;
+
+    // premultiplied, so dimming the colour alone keeps a transparent window's alpha as it was
+    return select(vec4(0.0), vec4(color, sampled.a), inside);
+}
@@ -0,0 +1,44 @@
+struct ParticleInput {
+    @location(0) position: vec2<f32>,
+    @location(1) size: f32,
+    @location(2) color: vec3<f32>,
+    @location(3) alpha: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+// taking the world to the screen's -1 to 1, scaled then offset
+struct Camera {
+    scale: vec2<f32>,
+    offset: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+// each particle is a square of two anticlockwise triangles, with no index buffer, so the corner
+// comes from which of the six vertices this is: bottom left, bottom right, top right, then
+// bottom left, top right, top left
+@vertex
+fn vs_main(
+    @builtin(vertex_index) index: u32,
+    particle: ParticleInput,
+) -> VertexOutput {
+    let right = index == 1u || index == 2u || index == 4u;
+    let top = index == 2u || index == 4u || index == 5u;
+    let corner = vec2(select(-0.5, 0.5, right), select(-0.5, 0.5, top));
+    let position = particle.position + corner * particle.size;
+
+    var out: VertexOutput;
+    out.color = vec4(particle.color, particle.alpha);
+    out.clip_position = vec4<f32>(position * camera.scale + camera.offset, 0.0, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
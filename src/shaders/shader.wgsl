@@ -1,15 +1,31 @@
 struct VertexInput {
     @location(0) position: vec2<f32>,
-    @location(1) color: vec3<f32>,
+    @location(1) color: vec4<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) color: vec3<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+// taking the world to the screen's -1 to 1, scaled then offset
+struct Camera {
+    scale: vec2<f32>,
+    offset: vec2<f32>,
 };
 
 @group(0) @binding(0)
-var<uniform> camera_x: f32;
+var<uniform> camera: Camera;
+
+// the draw's own, picked out of a buffer of every draw's by the bind group's offset
+struct Transform {
+    x_axis: vec2<f32>,
+    y_axis: vec2<f32>,
+    translation: vec2<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> transform: Transform;
 
 @vertex
 fn vs_main(
@@ -17,12 +33,14 @@ fn vs_main(
 ) -> VertexOutput {
     var out: VertexOutput;
     out.color = model.color;
-    out.clip_position = vec4<f32>(model.position - vec2(camera_x, 0.0), 0.0, 1.0);
+    let position = transform.x_axis * model.position.x + transform.y_axis * model.position.y
+        + transform.translation;
+    out.clip_position = vec4<f32>(position * camera.scale + camera.offset, 0.0, 1.0);
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(in.color, 1.0);
+    return in.color;
 }
 
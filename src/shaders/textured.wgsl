@@ -0,0 +1,62 @@
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+// taking the world to the screen's -1 to 1, scaled then offset
+struct Camera {
+    scale: vec2<f32>,
+    offset: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+struct Affine {
+    x_axis: vec2<f32>,
+    y_axis: vec2<f32>,
+    translation: vec2<f32>,
+};
+
+// the draw's own as the mesh shader has it, followed by where its vertices sample the texture
+struct Transform {
+    position: Affine,
+    // uniforms' structs start on 16 bytes, as the transform's padded out to
+    @align(16) uv: Affine,
+};
+
+@group(1) @binding(0)
+var<uniform> transform: Transform;
+
+@group(2) @binding(0)
+var texture: texture_2d<f32>;
+@group(2) @binding(1)
+var texture_sampler: sampler;
+
+fn apply(affine: Affine, point: vec2<f32>) -> vec2<f32> {
+    return affine.x_axis * point.x + affine.y_axis * point.y + affine.translation;
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = model.color;
+    out.uv = apply(transform.uv, model.position);
+    let position = apply(transform.position, model.position);
+    out.clip_position = vec4<f32>(position * camera.scale + camera.offset, 0.0, 1.0);
+    return out;
+}
+
+// the texture tinted by the vertices' colour
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(texture, texture_sampler, in.uv) * in.color;
+}
@@ -0,0 +1,41 @@
+struct Tonemap {
+    brightness: f32,
+    // how many times brighter than white the brightest highlight comes out
+    peak: f32,
+    padding: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var scene: texture_2d<f32>;
+@group(0) @binding(1)
+var scene_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> tonemap: Tonemap;
+
+// one triangle big enough to cover the screen, with no vertex buffer
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let position = vec2(f32(index == 1u) * 4.0 - 1.0, f32(index == 2u) * 4.0 - 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = vec4(position, 0.0, 1.0);
+    out.uv = vec2(position.x + 1.0, 1.0 - position.y) / 2.0;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let sampled = textureSample(scene, scene_sampler, in.uv);
+
+    // untouched up to white, and past it easing off towards the peak rather than clipping
+    let knee = tonemap.peak - 1.0;
+    let over = max(sampled.rgb - 1.0, vec3(0.0));
+    let mapped = min(sampled.rgb, vec3(1.0)) + knee * (1.0 - exp(-over / knee));
+
+    return vec4(mapped * tonemap.brightness, sampled.a);
+}
@@ -0,0 +1,51 @@
+use crate::TICK;
+use crossbeam::channel::Receiver;
+use std::sync::Mutex;
+
+/// Kept for as long as the game runs, as on some platforms what's copied is only on the clipboard
+/// while the clipboard that copied it is
+static CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+/// A game's result in one line for pasting elsewhere, kept the same from version to version so
+/// results shared over time can be compared
+pub fn result_line(mode: &str, seed: u64, score: u32, ticks: u64) -> String {
+    let centiseconds = ticks * TICK.as_micros() as u64 / 10_000;
+    let (minutes, centiseconds) = (centiseconds / 6000, centiseconds % 6000);
+    format!(
+        "wgpu-fun {mode} | seed 0x{seed:X} | score {score} | time {minutes}:{:02}.{:02}",
+        centiseconds / 100,
+        centiseconds % 100,
+    )
+}
+
+/// Puts `text` on the system clipboard, on a thread of its own as reaching the clipboard can take
+/// its time. Whether it worked arrives on the returned channel.
+pub fn copy_to_clipboard(text: String) -> Receiver<Result<(), arboard::Error>> {
+    let (sender, receiver) = crossbeam::channel::bounded(1);
+    std::thread::spawn(move || drop(sender.send(copy(text))));
+    receiver
+}
+
+fn copy(text: String) -> Result<(), arboard::Error> {
+    let mut clipboard = CLIPBOARD
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let clipboard = match &mut *clipboard {
+        Some(clipboard) => clipboard,
+        None => clipboard.insert(arboard::Clipboard::new()?),
+    };
+    clipboard.set_text(text)
+}
+
+#[test]
+fn result_line_keeps_its_format() {
+    let ticks = (92.1 / TICK.as_secs_f64()).round() as u64;
+    assert_eq!(
+        result_line("oneplayer", 0x1a2b, 4300, ticks),
+        "wgpu-fun oneplayer | seed 0x1A2B | score 4300 | time 1:32.10"
+    );
+    assert_eq!(
+        result_line("speedrun", 0, 0, 0),
+        "wgpu-fun speedrun | seed 0x0 | score 0 | time 0:00.00"
+    );
+}
@@ -0,0 +1,129 @@
+use crate::collison;
+use crate::palette::{Palette, Role};
+use crate::renderer::buffer::MeshBuilder;
+use crate::tunables::Tunables;
+use crate::{Ball, LoseZone};
+use cgmath::{InnerSpace, Vector2};
+use serde::{Deserialize, Serialize};
+
+/// A bar just above the player's lose zone that turns the ball back once, then shatters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Shield {
+    /// The bar's ends
+    ends: [Vector2<f32>; 2],
+}
+
+impl Shield {
+    const GAP: f32 = 0.03;
+    const THICKNESS: f32 = 0.015;
+    /// The way it turns the ball back
+    const NORMAL: Vector2<f32> = Vector2::new(0., 1.);
+
+    /// Spans the playfield between the walls
+    pub fn new(wall_x: f32) -> Self {
+        let y = -(1. - LoseZone::HEIGHT) + Self::GAP;
+        Self {
+            ends: [Vector2::new(-wall_x, y), Vector2::new(wall_x, y)],
+        }
+    }
+
+    /// Bounces a ball moving into the bar back out, whether it's touching it or crossed it on
+    /// its way from `from`, returning whether it did and so used the shield up. A ball touching
+    /// it while not moving into it, like one rolling along it, leaves it be rather than using it
+    /// up and carrying on through.
+    pub fn collide(&self, ball: &mut Ball, from: Vector2<f32>, tunables: &Tunables) -> bool {
+        let [a, b] = self.ends;
+        if ball.velocity.dot(Self::NORMAL) >= 0. {
+            return false;
+        }
+        // where the ball's centre is as it rests on the bar
+        let rest = a.y + ball.radius;
+        // a ball fast enough can go from one side to the other between checks without touching
+        let crossed = from.y >= rest && ball.position.y < rest;
+        let touching = collison::circle_intersects_line_segment(ball.position, ball.radius, a, b);
+        if !crossed && !touching {
+            return false;
+        }
+
+        if crossed {
+            // back to where it met the bar
+            let along = (from.y - rest) / (from.y - ball.position.y);
+            ball.position.x = from.x + (ball.position.x - from.x) * along;
+        }
+        ball.position.y = rest;
+        ball.bounce(Self::NORMAL, tunables);
+        true
+    }
+
+    /// Points spread along the bar, for it to shatter from
+    pub fn points(&self, count: usize) -> impl Iterator<Item = Vector2<f32>> + '_ {
+        let [a, b] = self.ends;
+        (0..count).map(move |i| a + (b - a) * ((i as f32 + 0.5) / count as f32))
+    }
+
+    pub fn push(&self, mesh: &mut MeshBuilder, palette: &Palette) {
+        let [a, b] = self.ends;
+        let [r, g, bl] = palette.color(Role::Shield);
+        // a wide faint glow around a bright core
+        mesh.push_segment(a, b, Self::THICKNESS * 3., [r, g, bl, 0.2]);
+        mesh.push_segment(a, b, Self::THICKNESS, [r, g, bl, 1.]);
+    }
+}
+
+#[test]
+fn shield_turns_back_only_a_ball_moving_into_it() {
+    let shield = Shield::new(crate::WALL_X);
+    let tunables = Tunables::default();
+    let y = shield.ends[0].y;
+    // from where it already is, so only touching counts
+    let touch = |ball: &mut Ball| {
+        let from = ball.position;
+        shield.collide(ball, from, &tunables)
+    };
+
+    // rolling along it
+    let mut ball = Ball {
+        position: Vector2::new(0., y + 0.04),
+        velocity: Vector2::new(0.01, 0.),
+        ..Ball::default()
+    };
+    assert!(!touch(&mut ball));
+
+    ball.velocity = Vector2::new(0.01, -0.02);
+    assert!(touch(&mut ball));
+    assert!(ball.velocity.y > 0.);
+    assert!(ball.position.y - ball.radius >= y);
+
+    // already on its way back out
+    assert!(!touch(&mut ball));
+}
+
+#[test]
+fn shield_turns_back_a_ball_that_crossed_it_between_checks() {
+    let shield = Shield::new(crate::WALL_X);
+    let tunables = Tunables::default();
+    let y = shield.ends[0].y;
+
+    // from well above to well below, never touching it where it was checked
+    let from = Vector2::new(0., y + 0.05);
+    let mut ball = Ball {
+        position: Vector2::new(0.1, y - 0.05),
+        velocity: Vector2::new(0.1, -0.1),
+        radius: 0.01,
+        ..Ball::default()
+    };
+    assert!(shield.collide(&mut ball, from, &tunables));
+    assert!(ball.velocity.y > 0.);
+    assert_eq!(ball.position.y, y + ball.radius);
+    // where it met the bar on its way across
+    assert!((ball.position.x - 0.04).abs() < 1e-6);
+
+    // but not one that was already below it
+    let mut below = Ball {
+        position: Vector2::new(0., y - 0.1),
+        velocity: Vector2::new(0., -0.05),
+        radius: 0.01,
+        ..Ball::default()
+    };
+    assert!(!shield.collide(&mut below, Vector2::new(0., y - 0.05), &tunables));
+}
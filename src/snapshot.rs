@@ -0,0 +1,138 @@
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use wgpu_fun::game::Game;
+use wgpu_fun::interpolation::{Frame, Pose};
+use wgpu_fun::renderer::buffer::{MeshBuilder, ParticleInstance};
+use wgpu_fun::TICK;
+
+/// Everything the renderer draws from, taken on the same tick so the mesh and camera always
+/// agree, along with the pose on the tick before so frames between ticks can be drawn partway
+#[derive(Debug)]
+pub struct Snapshot {
+    pub frame: Frame,
+    pub previous: Pose,
+    /// When the tick was published
+    pub taken: Instant,
+}
+
+impl Snapshot {
+    /// Built in `mesh` and `particles`, which can be those recycled from an earlier snapshot to
+    /// reuse their capacity
+    pub fn new(
+        game: &Game,
+        previous: Pose,
+        taken: Instant,
+        mesh: MeshBuilder,
+        particles: Vec<ParticleInstance>,
+    ) -> Self {
+        Self {
+            frame: game.frame_with(mesh, particles),
+            previous,
+            taken,
+        }
+    }
+
+    /// Builds the mesh to draw at `now` in `mesh`, returning the pose it's drawn at, which slides
+    /// from the previous tick's pose to this one's over a tick. Ticks that moved nothing, like
+    /// those paused or frozen by a hard hit, leave both poses the same and so hold still.
+    pub fn interpolate_into(&self, now: Instant, mesh: &mut MeshBuilder) -> Pose {
+        let elapsed = now.saturating_duration_since(self.taken);
+        let t = (elapsed.as_secs_f32() / TICK.as_secs_f32()).min(1.);
+        let pose = self.previous.lerp(&self.frame.pose, t);
+        self.frame.mesh_at_into(&pose, mesh);
+        pose
+    }
+}
+
+/// A triple buffer of whole values: one being built by the writer, one waiting in between and
+/// one held by the reader, so neither side waits on the other for more than a swap. Values done
+/// with go back to the writer to be built over.
+pub fn triple_buffer<T>(initial: T) -> (Writer<T>, Reader<T>) {
+    let pending = Arc::new(Mutex::new(None));
+    let returned = Arc::new(Mutex::new(None));
+    (
+        Writer {
+            pending: Arc::clone(&pending),
+            returned: Arc::clone(&returned),
+            spare: None,
+        },
+        Reader {
+            pending,
+            returned,
+            current: initial,
+        },
+    )
+}
+
+pub struct Writer<T> {
+    pending: Arc<Mutex<Option<T>>>,
+    /// The value the reader last swapped out
+    returned: Arc<Mutex<Option<T>>>,
+    /// A value published but replaced before the reader picked it up
+    spare: Option<T>,
+}
+
+impl<T> Writer<T> {
+    /// Hands over a finished value, replacing one the reader hasn't picked up yet
+    pub fn publish(&mut self, value: T) {
+        let stale = self.pending.lock().unwrap().replace(value);
+        self.spare = self.spare.take().or(stale);
+    }
+
+    /// A value no longer needed by either side, to build the next one over
+    pub fn recycle(&mut self) -> Option<T> {
+        self.spare
+            .take()
+            .or_else(|| self.returned.lock().unwrap().take())
+    }
+}
+
+pub struct Reader<T> {
+    pending: Arc<Mutex<Option<T>>>,
+    returned: Arc<Mutex<Option<T>>>,
+    current: T,
+}
+
+impl<T> Reader<T> {
+    /// The most recently published value, or the last one read if nothing new has come in
+    pub fn latest(&mut self) -> &T {
+        let pending = self.pending.lock().unwrap().take();
+        if let Some(value) = pending {
+            let old = std::mem::replace(&mut self.current, value);
+            let unclaimed = self.returned.lock().unwrap().replace(old);
+            // dropped outside the lock
+            drop(unclaimed);
+        }
+        &self.current
+    }
+}
+
+#[test]
+fn reader_sees_only_the_latest_value() {
+    let (mut writer, mut reader) = triple_buffer(0);
+    assert_eq!(*reader.latest(), 0);
+
+    writer.publish(1);
+    writer.publish(2);
+    assert_eq!(*reader.latest(), 2);
+    // nothing new keeps the last
+    assert_eq!(*reader.latest(), 2);
+
+    writer.publish(3);
+    assert_eq!(*reader.latest(), 3);
+}
+
+#[test]
+fn values_done_with_come_back_to_the_writer() {
+    let (mut writer, mut reader) = triple_buffer(vec![0]);
+    assert_eq!(writer.recycle(), None);
+
+    writer.publish(vec![1]);
+    writer.publish(vec![2]);
+    // replaced before it was read
+    assert_eq!(writer.recycle(), Some(vec![1]));
+
+    assert_eq!(*reader.latest(), vec![2]);
+    assert_eq!(writer.recycle(), Some(vec![0]));
+    assert_eq!(writer.recycle(), None);
+}
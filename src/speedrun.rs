@@ -0,0 +1,150 @@
+use crate::digits;
+use crate::renderer::buffer::MeshBuilder;
+use crate::TICK;
+use cgmath::Vector2;
+use serde::{Deserialize, Serialize};
+
+/// Times a level from its first serve to its last brick, counting ticks rather than reading the
+/// clock so a replay of the same inputs takes exactly the same time
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Speedrun {
+    /// Ticks since the first serve, or `None` before it
+    ticks: Option<u64>,
+    finished: bool,
+    /// The time each row of bricks was cleared at, in the order they were
+    splits: Vec<u64>,
+}
+
+impl Speedrun {
+    const HEIGHT: f32 = 0.08;
+    const COLOR: [f32; 4] = [1., 1., 1., 0.8];
+    const SPLIT_COLOR: [f32; 4] = [0.6, 0.9, 0.6, 0.6];
+
+    /// Starts the timer if it isn't already running
+    pub fn start(&mut self) {
+        self.ticks.get_or_insert(0);
+    }
+
+    pub fn tick(&mut self) {
+        if let (Some(ticks), false) = (&mut self.ticks, self.finished) {
+            *ticks += 1;
+        }
+    }
+
+    pub fn split(&mut self) {
+        if let Some(ticks) = self.ticks {
+            self.splits.push(ticks);
+        }
+    }
+
+    /// Stops the timer, returning the final time in ticks
+    pub fn finish(&mut self) -> Option<u64> {
+        self.finished = true;
+        self.ticks
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks.unwrap_or(0)
+    }
+
+    pub fn seconds(&self) -> f32 {
+        self.ticks() as f32 * TICK.as_secs_f32()
+    }
+
+    #[cfg(test)]
+    pub fn splits(&self) -> &[u64] {
+        &self.splits
+    }
+
+    /// Pushes the time with its top right corner at `corner`, and the last split under it
+    pub fn push(&self, mesh: &mut MeshBuilder, corner: Vector2<f32>) {
+        push_time(mesh, self.ticks(), corner, Self::HEIGHT, Self::COLOR);
+        if let Some(&split) = self.splits.last() {
+            let below = corner - Vector2::new(0., Self::HEIGHT * 1.5);
+            push_time(mesh, split, below, Self::HEIGHT * 0.6, Self::SPLIT_COLOR);
+        }
+    }
+}
+
+/// Pushes `ticks` as minutes, seconds and hundredths, right aligned with its top right corner at
+/// `corner`
+pub fn push_time(
+    mesh: &mut MeshBuilder,
+    ticks: u64,
+    corner: Vector2<f32>,
+    height: f32,
+    color: [f32; 4],
+) {
+    let hundredths = ticks * TICK.as_millis() as u64 / 10;
+    let (minutes, seconds, hundredths) =
+        (hundredths / 6000, hundredths / 100 % 60, hundredths % 100);
+    let text = format!("{minutes}:{seconds:02}.{hundredths:02}");
+
+    let (digit_width, separator_width) = (height * 0.7, height * 0.3);
+    let mut x = corner.x;
+    let y = corner.y - height / 2.;
+    for character in text.chars().rev() {
+        match character.to_digit(10) {
+            Some(digit) => {
+                x -= digit_width;
+                digits::push_digit(
+                    mesh,
+                    digit as u8,
+                    Vector2::new(x + digit_width / 2., y),
+                    height,
+                    color,
+                );
+            }
+            None => {
+                x -= separator_width;
+                let dot = Vector2::new(height, height) / 20.;
+                let center = x + separator_width / 2.;
+                let heights: &[f32] = if character == ':' {
+                    &[-0.2, 0.2]
+                } else {
+                    &[-0.45]
+                };
+                for offset in heights {
+                    let dot_center = Vector2::new(center, y + offset * height);
+                    mesh.push_rect(dot_center - dot, dot_center + dot, color);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn timer_only_runs_between_start_and_finish() {
+    let mut speedrun = Speedrun::default();
+    speedrun.tick();
+    assert_eq!(speedrun.ticks(), 0);
+
+    speedrun.start();
+    for _ in 0..150 {
+        speedrun.tick();
+    }
+    speedrun.split();
+    // starting again doesn't reset it
+    speedrun.start();
+    speedrun.tick();
+    assert_eq!(speedrun.finish(), Some(151));
+    speedrun.tick();
+
+    assert_eq!(speedrun.ticks(), 151);
+    assert_eq!(speedrun.splits(), [150]);
+    assert_eq!(speedrun.seconds(), 151. * TICK.as_secs_f32());
+}
+
+#[test]
+fn time_is_drawn_as_minutes_seconds_and_hundredths() {
+    // 1:02.34, four segments a rect, a colon and a point of three dots
+    let mut mesh = MeshBuilder::default();
+    let ticks = (62_340 / TICK.as_millis()) as u64;
+    push_time(&mut mesh, ticks, Vector2::new(0., 0.), 0.1, [1.; 4]);
+    let segments = 2 + 6 + 5 + 5 + 4;
+    assert_eq!(mesh.vertex_count(), (segments + 3) * 4);
+}
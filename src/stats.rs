@@ -0,0 +1,86 @@
+use crate::difficulty::Preset;
+use crate::menu::Mode;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Counts kept over a game, written out when it ends so balance can be analysed across many runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionStats {
+    pub seed: u64,
+    pub difficulty: Preset,
+    pub mode: String,
+    /// Player one's final score
+    pub score: u32,
+    /// Balls player one lost
+    pub lives_used: u32,
+    /// Hits by either paddle
+    pub paddle_hits: u32,
+    pub bricks_destroyed: u32,
+    /// Units per second
+    pub max_ball_speed: f32,
+    /// Ticks spent playing, not counting the menu or pauses
+    pub ticks: u64,
+    /// The names of the power-ups collected, in order
+    pub power_ups: Vec<String>,
+}
+
+impl SessionStats {
+    pub fn new(seed: u64, difficulty: Preset, mode: Mode) -> Self {
+        Self {
+            seed,
+            difficulty,
+            mode: format!("{mode:?}").to_lowercase(),
+            score: 0,
+            lives_used: 0,
+            paddle_hits: 0,
+            bricks_destroyed: 0,
+            max_ball_speed: 0.,
+            ticks: 0,
+            power_ups: Vec::new(),
+        }
+    }
+
+    /// The stats directory in the platform data directory
+    pub fn default_dir() -> Option<PathBuf> {
+        storage::data_path("stats")
+    }
+
+    /// Writes the stats to a file in `dir` named after the time, returning where
+    pub fn save(&self, dir: &Path) -> std::io::Result<PathBuf> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis());
+        let path = dir.join(format!("{timestamp}.json"));
+        storage::save(&path, self)?;
+        Ok(path)
+    }
+}
+
+#[test]
+fn unwritable_directory_is_an_error() {
+    let file = std::env::temp_dir().join(format!("wgpu-fun-{}-not-a-dir", std::process::id()));
+    std::fs::write(&file, "").unwrap();
+
+    // a directory can't be made inside a file
+    let stats = SessionStats::new(0, Preset::Normal, Mode::OnePlayer);
+    assert!(stats.save(&file.join("stats")).is_err());
+    assert!(std::fs::metadata(&file).unwrap().is_file());
+    assert_eq!(std::fs::read(&file).unwrap(), b"");
+
+    std::fs::remove_file(&file).unwrap();
+}
+
+#[test]
+fn stats_are_saved_where_they_say() {
+    let dir = std::env::temp_dir().join(format!("wgpu-fun-{}-stats-saved", std::process::id()));
+    let stats = SessionStats::new(7, Preset::Hard, Mode::OnePlayer);
+
+    let path = stats.save(&dir).unwrap();
+    assert_eq!(path.parent(), Some(dir.as_path()));
+    let saved: SessionStats = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    assert_eq!(saved, stats);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
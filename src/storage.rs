@@ -0,0 +1,53 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+fn project_dirs() -> Option<directories::ProjectDirs> {
+    directories::ProjectDirs::from("", "", "wgpu-fun")
+}
+
+/// A file in the platform data directory
+pub fn data_path(file: &str) -> Option<PathBuf> {
+    project_dirs().map(|dirs| dirs.data_dir().join(file))
+}
+
+/// A file in the platform config directory
+pub fn config_path(file: &str) -> Option<PathBuf> {
+    project_dirs().map(|dirs| dirs.config_dir().join(file))
+}
+
+/// Loads a json file, falling back to the default if it is missing or corrupt
+pub fn load<T: DeserializeOwned + Default>(path: &Path) -> T {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            if err.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("failed to read {}: {err}", path.display());
+            }
+            return T::default();
+        }
+    };
+
+    serde_json::from_str(&contents).unwrap_or_else(|err| {
+        log::warn!("ignoring corrupt {}: {err}", path.display());
+        T::default()
+    })
+}
+
+/// Writes a json file with `write`
+pub fn save<T: Serialize>(path: &Path, value: &T) -> std::io::Result<()> {
+    write(path, &serde_json::to_vec_pretty(value)?)
+}
+
+/// Writes to a temporary file then renames it over the old one, so a crash mid-save leaves the
+/// previous contents intact
+pub fn write(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    std::fs::write(&temp, contents)?;
+    std::fs::rename(&temp, path)
+}
@@ -0,0 +1,66 @@
+use crate::renderer::buffer::MeshBuilder;
+use cgmath::Vector2;
+use std::collections::VecDeque;
+
+/// A ribbon following the ball's recent positions, thinning and fading with age
+pub struct Trail {
+    positions: VecDeque<Vector2<f32>>,
+    /// Number of positions kept
+    pub length: usize,
+    /// Alpha of the newest end of the ribbon, fading linearly to 0 at the oldest
+    pub fade: f32,
+}
+
+impl Trail {
+    pub fn new(length: usize, fade: f32) -> Self {
+        Self {
+            positions: VecDeque::with_capacity(length),
+            length,
+            fade,
+        }
+    }
+
+    pub fn record(&mut self, position: Vector2<f32>) {
+        if self.length == 0 {
+            self.positions.clear();
+            return;
+        }
+
+        // the length may have been tuned down since the last tick
+        while self.positions.len() >= self.length {
+            self.positions.pop_front();
+        }
+        self.positions.push_back(position);
+    }
+
+    pub fn clear(&mut self) {
+        self.positions.clear();
+    }
+
+    pub fn push(&self, mesh: &mut MeshBuilder, width: f32, color: [f32; 3]) {
+        let positions = self.positions.iter().copied().collect::<Vec<_>>();
+        let [r, g, b] = color;
+
+        // the age is 0 at the oldest position, 1 at the newest
+        mesh.push_ribbon(&positions, width, |age| (age, [r, g, b, self.fade * age]));
+    }
+}
+
+#[test]
+fn trail_is_bounded() {
+    let mut trail = Trail::new(3, 1.);
+    for x in 0..10 {
+        trail.record(Vector2::new(x as f32, 0.));
+    }
+
+    assert_eq!(trail.positions.len(), 3);
+    assert_eq!(trail.positions[0], Vector2::new(7., 0.));
+
+    trail.length = 2;
+    trail.record(Vector2::new(10., 0.));
+    assert_eq!(trail.positions.len(), 2);
+    assert_eq!(trail.positions[0], Vector2::new(9., 0.));
+
+    trail.clear();
+    assert!(trail.positions.is_empty());
+}
@@ -0,0 +1,62 @@
+use crate::magnet::Attractor;
+use crate::zones::ForceZone;
+use cgmath::Vector2;
+
+/// Physics constants which can be adjusted while the game runs
+#[derive(Debug, Clone)]
+pub struct Tunables {
+    /// Spin given to the ball per unit of paddle velocity when it's hit
+    pub spin_transfer: f32,
+    /// Sideways acceleration per unit of spin and ball speed
+    pub magnus: f32,
+    /// Fraction of spin kept each tick
+    pub spin_decay: f32,
+    /// Velocity along a wall gained per unit of spin when bouncing off it
+    pub wall_spin_bias: f32,
+    /// Velocity the ball gains each tick, pointing whichever way the level and power-ups make down
+    pub gravity: Vector2<f32>,
+    /// The ball bounces off walls at this x position on either side
+    pub wall_x: f32,
+    /// The level's force zones, included here so predictions of the ball's flight feel them
+    pub force_zones: Vec<ForceZone>,
+    /// The pull of the player's magnet while it's held, here for the same reason
+    pub attractor: Option<Attractor>,
+    /// The fastest the ball may move along either axis per tick
+    pub max_speed: f32,
+    pub paddle_width: f32,
+    /// Fraction of the ball's speed into a paddle that it rebounds with
+    pub restitution: f32,
+    /// Fraction of the ball's speed along a paddle's face lost when it bounces
+    pub friction: f32,
+    /// Speed of a paddle's face along its normal, as if it were swung at the ball
+    pub paddle_kick: f32,
+    /// Speed per tick the ball must leave a paddle at to freeze the game for a moment
+    pub hitstop_speed: f32,
+    /// Ticks the game freezes for after a hard hit
+    pub hitstop_ticks: u32,
+    /// The chance a broken brick drops a power-up
+    pub power_up_chance: f64,
+}
+
+impl Default for Tunables {
+    fn default() -> Self {
+        Self {
+            spin_transfer: 0.3,
+            magnus: 0.04,
+            spin_decay: 0.99,
+            wall_spin_bias: 0.01,
+            gravity: Vector2::new(0., -0.001),
+            wall_x: crate::WALL_X,
+            force_zones: Vec::new(),
+            attractor: None,
+            max_speed: 0.1,
+            paddle_width: crate::Paddle::default().width,
+            restitution: 0.8,
+            friction: 0.1,
+            paddle_kick: 0.1,
+            hitstop_speed: 0.08,
+            hitstop_ticks: 3,
+            power_up_chance: 0.15,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+/// Progress through an animation lasting a fixed number of seconds
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Tween {
+    elapsed: f32,
+    duration: f32,
+}
+
+impl Tween {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            elapsed: 0.,
+            duration,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    /// In 0..=1, starting at 0 and reaching 1 once finished
+    pub fn progress(&self) -> f32 {
+        if self.duration > 0. {
+            self.elapsed / self.duration
+        } else {
+            1.
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+#[test]
+fn tween_progresses_then_finishes() {
+    let mut tween = Tween::new(0.3);
+    assert_eq!(tween.progress(), 0.);
+
+    tween.update(0.15);
+    assert!((tween.progress() - 0.5).abs() < 1e-6);
+    assert!(!tween.is_finished());
+
+    tween.update(1.);
+    assert_eq!(tween.progress(), 1.);
+    assert!(tween.is_finished());
+
+    assert!(Tween::default().is_finished());
+}
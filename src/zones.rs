@@ -0,0 +1,115 @@
+use crate::level::Level;
+use crate::renderer::buffer::MeshBuilder;
+use crate::TICK;
+use cgmath::{ElementWise, InnerSpace, Vector2};
+
+/// A rectangle pushing any ball inside it, such as an updraft or a gust along a wall
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForceZone {
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+    /// Velocity gained per tick
+    pub acceleration: Vector2<f32>,
+}
+
+impl ForceZone {
+    const COLOR: [f32; 4] = [0.5, 0.8, 1., 0.08];
+    const STREAK_COLOR: [f32; 3] = [0.7, 0.9, 1.];
+    const STREAKS: usize = 6;
+    const STREAK_LENGTH: f32 = 0.15;
+    /// Distance streaks travel per second per unit of acceleration per second squared
+    const STREAK_SPEED: f32 = 0.02;
+
+    /// The level's zones, their accelerations turned from per second squared to per tick
+    pub fn from_level(level: &Level) -> Vec<Self> {
+        let dt = TICK.as_secs_f32();
+        level
+            .force_zones
+            .iter()
+            .map(|zone| Self {
+                min: zone.min.into(),
+                max: zone.max.into(),
+                acceleration: Vector2::from(zone.acceleration) * dt * dt,
+            })
+            .collect()
+    }
+
+    pub fn contains(&self, position: Vector2<f32>) -> bool {
+        (self.min.x..=self.max.x).contains(&position.x)
+            && (self.min.y..=self.max.y).contains(&position.y)
+    }
+
+    /// The summed acceleration of every zone containing `position`
+    pub fn total(zones: &[Self], position: Vector2<f32>) -> Vector2<f32> {
+        zones
+            .iter()
+            .filter(|zone| zone.contains(position))
+            .map(|zone| zone.acceleration)
+            .sum()
+    }
+
+    /// Pushes the zone as a tinted rectangle with streaks drifting the way it pushes, `time`
+    /// seconds into the level
+    pub fn push(&self, mesh: &mut MeshBuilder, time: f32) {
+        mesh.push_rect(self.min, self.max, Self::COLOR);
+
+        let dt = TICK.as_secs_f32();
+        let acceleration = self.acceleration / (dt * dt);
+        if acceleration.magnitude2() <= f32::EPSILON {
+            return;
+        }
+        let direction = acceleration.normalize();
+        let size = self.max - self.min;
+        let travelled = acceleration * Self::STREAK_SPEED * time;
+
+        for i in 0..Self::STREAKS {
+            // spread over the zone by the golden ratio so streaks don't line up
+            let seed = Vector2::new(i as f32 * 0.618, i as f32 * 0.382 + 0.5);
+            let fraction = (seed + travelled.div_element_wise(size)).map(|x| x.rem_euclid(1.));
+            let head = self.min + fraction.mul_element_wise(size);
+            let tail = head - direction * Self::STREAK_LENGTH;
+            let clamp = |point: Vector2<f32>| {
+                Vector2::new(
+                    point.x.clamp(self.min.x, self.max.x),
+                    point.y.clamp(self.min.y, self.max.y),
+                )
+            };
+
+            let [r, g, b] = Self::STREAK_COLOR;
+            mesh.push_ribbon(&[clamp(tail), head], 0.006, |t| (t, [r, g, b, 0.5 * t]));
+        }
+    }
+}
+
+#[test]
+fn overlapping_zones_add_up() {
+    let level = Level::parse(
+        r#"
+        name = "zones"
+        [[force_zones]]
+        min = [-1.0, -1.0]
+        max = [1.0, 1.0]
+        acceleration = [0.0, 10.0]
+        [[force_zones]]
+        min = [0.0, -1.0]
+        max = [2.0, 1.0]
+        acceleration = [5.0, 0.0]
+        "#,
+    )
+    .unwrap();
+    let zones = ForceZone::from_level(&level);
+    let per_tick = |x: f32| x * TICK.as_secs_f32().powi(2);
+
+    assert_eq!(
+        ForceZone::total(&zones, Vector2::new(-0.5, 0.)),
+        Vector2::new(0., per_tick(10.))
+    );
+    assert_eq!(
+        ForceZone::total(&zones, Vector2::new(0.5, 0.)),
+        Vector2::new(per_tick(5.), per_tick(10.))
+    );
+    assert_eq!(
+        ForceZone::total(&zones, Vector2::new(0.5, 2.)),
+        Vector2::new(0., 0.)
+    );
+}